@@ -0,0 +1,23 @@
+#set document(title: "{{title|str}}", author: "{{author|str}}")
+#set page(paper: "a4", margin: 2.5cm, numbering: "1")
+#set text(size: 11pt)
+#set par(justify: true)
+#set heading(numbering: "1.1")
+
+#align(center)[
+  #text(size: 17pt, weight: "bold")[{{title|markup}}]
+  #v(0.5em)
+  {{author|markup}} · {{date}}
+]
+
+#v(1em)
+*Abstract.* #lorem(40)
+
+= Introduction
+#lorem(80)
+
+= Method
+#lorem(120)
+
+= Conclusion
+#lorem(60)
@@ -0,0 +1,22 @@
+#set page(paper: "a4", margin: (x: 2.5cm, top: 3cm, bottom: 2.5cm))
+#set text(size: 11pt)
+#set par(justify: true)
+
+{{sender|markup}}
+
+#v(2em)
+{{recipient|markup}}
+
+#v(2em)
+#align(right)[{{date}}]
+
+*{{subject|markup}}*
+
+Dear {{recipient|markup}},
+
+#lorem(90)
+
+Sincerely,
+
+#v(3em)
+{{sender|markup}}
@@ -0,0 +1,2 @@
+= Background
+#lorem(150)
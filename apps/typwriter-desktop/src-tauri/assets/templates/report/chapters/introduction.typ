@@ -0,0 +1,4 @@
+= Introduction
+#lorem(100)
+
+As shown by @knuth1984, good typography matters.
@@ -0,0 +1,12 @@
+#import "template.typ": report
+
+#show: report.with(
+  title: "{{title|str}}",
+  author: "{{author|str}}",
+  organization: "{{organization|str}}",
+)
+
+#include "chapters/introduction.typ"
+#include "chapters/background.typ"
+
+#bibliography("refs.bib")
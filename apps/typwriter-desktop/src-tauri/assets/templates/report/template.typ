@@ -0,0 +1,24 @@
+#let report(title: "", author: "", organization: "", body) = {
+  set document(title: title, author: author)
+  set page(paper: "a4", margin: (x: 2.5cm, y: 3cm))
+  set text(size: 11pt)
+  set par(justify: true)
+  set heading(numbering: "1.1")
+  show heading.where(level: 1): it => {
+    pagebreak(weak: true)
+    it
+  }
+
+  page(numbering: none, align(center + horizon)[
+    #text(size: 24pt, weight: "bold", title)
+    #v(1em)
+    #text(size: 14pt, author)
+    #v(0.5em)
+    #organization
+  ])
+
+  outline()
+  set page(numbering: "1")
+  counter(page).update(1)
+  body
+}
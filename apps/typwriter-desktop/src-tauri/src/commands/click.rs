@@ -48,7 +48,7 @@ pub fn jump_from_click(
     let zoom = *pipeline.zoom.lock() as f64;
     let point = Point::new(Abs::pt(x / zoom), Abs::pt(y / zoom));
 
-    let doc_arc = pipeline.last_document.lock().clone().ok_or_else(|| {
+    let doc_arc = pipeline.document().ok_or_else(|| {
         let e = "No compiled document available";
         error!(
             "jump_from_click: err=\"{e}\" ({:.1}ms) page={page}",
@@ -122,7 +122,7 @@ pub fn jump_from_cursor(
     let text = source.text();
     let byte_cursor = utf16_to_byte(text, cursor);
 
-    let doc_arc = pipeline.last_document.lock().clone().ok_or_else(|| {
+    let doc_arc = pipeline.document().ok_or_else(|| {
         let e = "No compiled document available";
         error!(
             "jump_from_cursor: err=\"{e}\" ({:.1}ms) path={path:?}",
@@ -25,7 +25,10 @@ use typst_layout::PagedDocument;
 use crate::{
     compiler::{CompileReason, PreviewPipeline},
     vcs::{CommitTrigger, SnapshotPolicy, VcsState},
-    workspace::WorkspaceState,
+    workspace::{
+        encoding::{self, TextFormat},
+        WorkspaceState,
+    },
     world::EditorWorld,
 };
 
@@ -133,9 +136,9 @@ pub enum JumpResponse {
     },
 }
 
-/// What the filesystem knows about a file Typwriter can't render itself.
-/// Every field is optional: metadata reads fail on broken links, revoked
-/// permissions, and (for `created`) filesystems that simply don't record it.
+/// What the filesystem knows about a file. Every field is optional: metadata
+/// reads fail on broken links, revoked permissions, and (for `created`)
+/// filesystems that simply don't record it.
 #[derive(Serialize)]
 pub struct FileMeta {
     /// Size on disk, in bytes.
@@ -146,13 +149,18 @@ pub struct FileMeta {
     pub created: Option<i64>,
     /// Whether the file is marked read-only.
     pub readonly: Option<bool>,
+    /// Encoding and line ending of a text file, which saving keeps.
+    pub format: Option<TextFormat>,
 }
 
 #[derive(Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum FileContentResponse {
+    /// `content` is UTF-8 with `\n` line breaks whatever the file on disk
+    /// uses; `meta.format` says what that is.
     Text {
         content: String,
+        meta: FileMeta,
     },
     Image {
         path: String,
@@ -175,6 +183,7 @@ fn file_meta(path: &Path) -> FileMeta {
             modified: None,
             created: None,
             readonly: None,
+            format: None,
         };
     };
     FileMeta {
@@ -182,6 +191,7 @@ fn file_meta(path: &Path) -> FileMeta {
         modified: md.modified().ok().and_then(unix_millis),
         created: md.created().ok().and_then(unix_millis),
         readonly: Some(md.permissions().readonly()),
+        format: None,
     }
 }
 
@@ -194,8 +204,9 @@ fn unix_millis(time: SystemTime) -> Option<i64> {
 // ─── Commands ─────────────────────────────────────────────────────────────────
 
 /// Read a file from disk and return its content.
-/// Text files are returned as UTF-8 strings; image files are returned as paths
-/// that the frontend can convert into Tauri asset URLs.
+/// Text files are decoded (see [`encoding`]) and returned as UTF-8 strings;
+/// image files are returned as paths that the frontend can convert into Tauri
+/// asset URLs.
 ///
 /// Reads route through the workspace's [`WorkingTreeFs`].
 #[tauri::command(async)]
@@ -293,19 +304,23 @@ pub fn read_file(
             );
             e
         })?;
-        let content = String::from_utf8(bytes).map_err(|e| {
+        let (content, format) = encoding::decode(&bytes, true).ok_or_else(|| {
+            let e = "File is not text in any supported encoding";
             error!(
-                "read_file: non-utf8 text path={path:?} err=\"{e}\" ({:.1}ms)",
+                "read_file: undecodable text path={path:?} err=\"{e}\" ({:.1}ms)",
                 t.elapsed().as_secs_f64() * 1000.0
             );
             e.to_string()
         })?;
         info!(
-            "read_file: ok text bytes={} ({:.1}ms)",
+            "read_file: ok text bytes={} format={format:?} ({:.1}ms)",
             content.len(),
             t.elapsed().as_secs_f64() * 1000.0
         );
-        return Ok(FileContentResponse::Text { content });
+        workspace.set_merge_base(abs, content.clone());
+        let mut meta = file_meta(abs);
+        meta.format = Some(format);
+        return Ok(FileContentResponse::Text { content, meta });
     }
 
     // Known binary formats: don't bother sniffing the bytes.
@@ -321,20 +336,23 @@ pub fn read_file(
 
     // Unknown extension: sniff the bytes. Plenty of text files carry no
     // recognizable extension (Makefile, LICENSE, dotfiles, niche languages) —
-    // anything reasonably sized that decodes as UTF-8 without NUL bytes opens
-    // as plain text; real binaries fail fast on the NUL check.
+    // anything reasonably sized that decodes as Unicode text opens as plain
+    // text; real binaries fail fast on their NUL bytes. Legacy encodings are
+    // left out here, since one of those would take any binary.
     const SNIFF_MAX_BYTES: usize = 8 * 1024 * 1024;
     if !is_binary {
         if let Ok(bytes) = fs.read_file(abs) {
-            let has_nul = bytes.iter().take(8192).any(|&b| b == 0);
-            if bytes.len() <= SNIFF_MAX_BYTES && !has_nul {
-                if let Ok(content) = String::from_utf8(bytes) {
+            if bytes.len() <= SNIFF_MAX_BYTES {
+                if let Some((content, format)) = encoding::decode(&bytes, false) {
                     info!(
-                        "read_file: ok sniffed text ext={ext:?} bytes={} ({:.1}ms)",
+                        "read_file: ok sniffed text ext={ext:?} bytes={} format={format:?} ({:.1}ms)",
                         content.len(),
                         t.elapsed().as_secs_f64() * 1000.0
                     );
-                    return Ok(FileContentResponse::Text { content });
+                    workspace.set_merge_base(abs, content.clone());
+                    let mut meta = file_meta(abs);
+                    meta.format = Some(format);
+                    return Ok(FileContentResponse::Text { content, meta });
                 }
             }
         }
@@ -393,12 +411,14 @@ pub fn open_file_externally(
 /// Runs off the main thread (`async`), which means two writes for the same file
 /// can be in flight simultaneously. `version` is the frontend's monotonic write
 /// counter and orders them: a write older than the last one applied is dropped.
+/// Each applied version is copied to the crash-recovery journal.
 #[tauri::command(async)]
 pub fn update_file_content(
     path: String,
     content: String,
     version: u64,
     world: State<'_, Arc<EditorWorld>>,
+    workspace: State<'_, Arc<WorkspaceState>>,
 ) -> Result<(), String> {
     let t = Instant::now();
     debug!(
@@ -419,6 +439,7 @@ pub fn update_file_content(
         debug!("update_file_content: dropped stale write version={version} path={path:?}");
         return Ok(());
     }
+    workspace.journal_buffer(abs);
     debug!(
         "update_file_content: ok ({:.1}ms)",
         t.elapsed().as_secs_f64() * 1000.0
@@ -428,6 +449,10 @@ pub fn update_file_content(
 
 /// Persist the current editor content to disk.
 /// Called explicitly by the frontend (e.g. on Ctrl+S).
+///
+/// The file keeps its encoding and line ending: they are detected from what
+/// is on disk and the editor's text is written back in them. A file that
+/// doesn't exist yet is written as UTF-8 with `\n` line breaks.
 #[tauri::command(async)]
 pub fn save_file(
     path: String,
@@ -460,21 +485,32 @@ pub fn save_file(
 
     // Write through the working-tree accessor for the current workspace root.
     let root = workspace.root.read().clone().unwrap_or_default();
-    vcs.working_tree_fs_for(&root)
-        .write_file(abs, content.as_bytes())
-        .map_err(|e| {
-            error!(
-                "save_file: io error path={path:?} err=\"{e}\" ({:.1}ms)",
-                t.elapsed().as_secs_f64() * 1000.0
-            );
-            e
-        })?;
+    let fs = vcs.working_tree_fs_for(&root);
+    let format = fs
+        .read_file(abs)
+        .map_or(TextFormat::UTF8_LF, |bytes| encoding::detect(&bytes));
+    let bytes = encoding::encode(&content, format).map_err(|e| {
+        error!(
+            "save_file: cannot encode path={path:?} format={format:?} err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        );
+        e
+    })?;
+    fs.write_file(abs, &bytes).map_err(|e| {
+        error!(
+            "save_file: io error path={path:?} err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        );
+        e
+    })?;
 
     // Disk now matches the editor content, so the shadow override is no longer
     // needed. `shadow_commit` (not `shadow_remove`) keeps the parsed tree: it
     // already holds these exact bytes, and the `request_compile` below would
     // otherwise re-read and fully reparse the file we just wrote.
     world.shadow_commit(id);
+    workspace.forget_buffer(abs);
+    workspace.set_merge_base(abs, content);
 
     if workspace.should_generate_thumbnail_for(abs) {
         workspace.generate_thumbnail();
@@ -510,7 +546,11 @@ pub fn save_file(
 /// FileId (e.g. because the workspace root has changed under us), we just log
 /// and return Ok — the shadow has nothing to do anyway.
 #[tauri::command(async)]
-pub fn discard_shadow(path: String, world: State<'_, Arc<EditorWorld>>) -> Result<(), String> {
+pub fn discard_shadow(
+    path: String,
+    world: State<'_, Arc<EditorWorld>>,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<(), String> {
     let t = Instant::now();
     info!("discard_shadow: path={path:?}");
 
@@ -523,6 +563,7 @@ pub fn discard_shadow(path: String, world: State<'_, Arc<EditorWorld>>) -> Resul
         return Ok(());
     };
     world.shadow_remove(id);
+    workspace.forget_buffer(abs);
     info!(
         "discard_shadow: ok ({:.1}ms)",
         t.elapsed().as_secs_f64() * 1000.0
@@ -596,7 +637,7 @@ pub fn get_completions(
     // Clone the Arc out of the mutex so the lock is released before the
     // (potentially long) IDE traversal — keeps concurrent click/hover
     // handlers from blocking on us.
-    let doc = pipeline.last_document.lock().clone();
+    let doc = pipeline.last_document();
     let doc_ref: Option<&PagedDocument> = doc.as_deref();
 
     let result = typst_ide::autocomplete(&**world, doc_ref, &source, byte_cursor, explicit);
@@ -670,7 +711,7 @@ pub fn get_tooltip(
 
     let byte_cursor = utf16_to_byte(source.text(), cursor);
 
-    let doc = pipeline.last_document.lock().clone();
+    let doc = pipeline.last_document();
     let doc_ref: Option<&PagedDocument> = doc.as_deref();
 
     // Try against the latest compiled document first, then document-agnostic.
@@ -741,7 +782,7 @@ pub fn get_definitions(
 
     let byte_cursor = utf16_to_byte(source.text(), cursor);
 
-    let doc = pipeline.last_document.lock().clone();
+    let doc = pipeline.last_document();
     let doc_ref: Option<&PagedDocument> = doc.as_deref();
 
     let def = typst_ide::definition(&**world, doc_ref, &source, byte_cursor, Side::Before);
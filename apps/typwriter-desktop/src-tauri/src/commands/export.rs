@@ -8,7 +8,8 @@ use log::{error, info};
 use tauri::State;
 
 use crate::compiler::{
-    HtmlExportConfig, PdfExportConfig, PngExportConfig, PreviewPipeline, SvgExportConfig,
+    DependencyGraph, FontUsageReport, HtmlExportConfig, PdfExportConfig, PngExportConfig,
+    PreviewPipeline, PrintIssue, ReportFormat, SerializedDiagnostic, SvgExportConfig,
 };
 
 #[tauri::command(async)]
@@ -103,3 +104,138 @@ pub fn export_html(
     }
     result
 }
+
+/// Check the last compiled document for accessibility problems before a
+/// PDF/UA export. Findings come back as ordinary diagnostics; an empty list
+/// means nothing was found, not that the export is guaranteed to conform.
+#[tauri::command(async)]
+pub fn preflight_accessibility(
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<Vec<SerializedDiagnostic>, String> {
+    let t = Instant::now();
+    info!("preflight_accessibility");
+    let result = pipeline.accessibility_preflight();
+    match &result {
+        Ok(findings) => info!(
+            "preflight_accessibility: ok - {} finding(s) ({:.1}ms)",
+            findings.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "preflight_accessibility: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Check the last compiled document for problems a print shop would reject:
+/// fallback fonts, missing glyphs, images under 300 dpi and content outside
+/// the page.
+#[tauri::command(async)]
+pub fn preflight_print(
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<Vec<PrintIssue>, String> {
+    let t = Instant::now();
+    info!("preflight_print");
+    let result = pipeline.print_preflight();
+    match &result {
+        Ok(issues) => info!(
+            "preflight_print: ok - {} issue(s) ({:.1}ms)",
+            issues.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "preflight_print: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Report the font faces the last compiled document uses, for licence audits
+/// and for seeing which faces make an exported PDF large.
+#[tauri::command(async)]
+pub fn get_font_usage(
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<FontUsageReport, String> {
+    let t = Instant::now();
+    info!("get_font_usage");
+    let result = pipeline.font_usage_report();
+    match &result {
+        Ok(report) => info!(
+            "get_font_usage: ok - {} face(s) ~{} bytes ({:.1}ms)",
+            report.faces.len(),
+            report.estimated_total_bytes,
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "get_font_usage: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Write the current errors and warnings to `path` as SARIF 2.1.0 or JSON,
+/// for CI annotations and review tickets.
+#[tauri::command(async)]
+pub fn export_diagnostics(
+    path: String,
+    format: ReportFormat,
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<(), String> {
+    let t = Instant::now();
+    info!("export_diagnostics: path={path:?} format={format:?}");
+    let result = pipeline.export_diagnostics(&path, format);
+    match &result {
+        Ok(bytes) => info!(
+            "export_diagnostics: ok - {bytes} bytes ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "export_diagnostics: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result.map(|_| ())
+}
+
+/// The workspace's file-level dependency graph — includes, imports, images,
+/// data files and bibliographies — with loops and missing targets flagged.
+#[tauri::command(async)]
+pub fn get_dependency_graph(pipeline: State<'_, Arc<PreviewPipeline>>) -> DependencyGraph {
+    let t = Instant::now();
+    info!("get_dependency_graph");
+    let graph = pipeline.dependency_graph();
+    info!(
+        "get_dependency_graph: ok - {} node(s), {} edge(s), {} cycle(s) ({:.1}ms)",
+        graph.nodes.len(),
+        graph.edges.len(),
+        graph.cycles.len(),
+        t.elapsed().as_secs_f64() * 1000.0
+    );
+    graph
+}
+
+/// Write the dependency graph to `path` as Graphviz DOT.
+#[tauri::command(async)]
+pub fn export_dependency_graph(
+    path: String,
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<(), String> {
+    let t = Instant::now();
+    info!("export_dependency_graph: path={path:?}");
+    let result = pipeline.export_dependency_graph(&path);
+    match &result {
+        Ok(bytes) => info!(
+            "export_dependency_graph: ok - {bytes} bytes ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "export_dependency_graph: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result.map(|_| ())
+}
@@ -59,18 +59,22 @@ pub fn formatter_config_from_settings(settings: &AppSettings) -> Config {
     }
 }
 
-/// Snapshot the shared config. Cloned rather than held as a guard so a long
-/// format never blocks a settings write.
-fn snapshot(config: &State<'_, FormatterConfig>) -> Config {
-    config.read().clone()
+/// Snapshot the shared config, with the options the open workspace pins in
+/// its `typwriter.toml` laid over it. Cloned rather than held as a guard so a
+/// long format never blocks a settings write.
+fn snapshot(config: &State<'_, FormatterConfig>, workspace: &WorkspaceState) -> Config {
+    let mut config = config.read().clone();
+    workspace.project_config().format.apply(&mut config);
+    config
 }
 
 #[tauri::command(async)]
 pub fn format_typst_source(
+    workspace: State<'_, Arc<WorkspaceState>>,
     config: State<'_, FormatterConfig>,
     source: String,
 ) -> Result<String, String> {
-    format_source_with(&snapshot(&config), source)
+    format_source_with(&snapshot(&config, &workspace), source)
 }
 
 fn format_source_with(config: &Config, source: String) -> Result<String, String> {
@@ -108,11 +112,12 @@ pub struct FormatWithCursorResponse {
 // locate where the cursor lands. See the module docs for the full strategy.
 #[tauri::command(async)]
 pub fn format_typst_cursor_virtual(
+    workspace: State<'_, Arc<WorkspaceState>>,
     config: State<'_, FormatterConfig>,
     source: String,
     cursor: u32,
 ) -> Result<FormatWithCursorResponse, String> {
-    format_cursor_virtual_with(&snapshot(&config), source, cursor)
+    format_cursor_virtual_with(&snapshot(&config, &workspace), source, cursor)
 }
 
 fn format_cursor_virtual_with(
@@ -158,10 +163,11 @@ fn format_cursor_virtual_with(
 /// any open editor view.
 #[tauri::command(async)]
 pub fn format_typst_file(
+    workspace: State<'_, Arc<WorkspaceState>>,
     config: State<'_, FormatterConfig>,
     path: String,
 ) -> Result<String, String> {
-    let config = snapshot(&config);
+    let config = snapshot(&config, &workspace);
     let t = Instant::now();
     info!("format_typst_file: path={path:?}");
 
@@ -213,7 +219,7 @@ pub fn format_workspace_typ_files(
     workspace: State<'_, Arc<WorkspaceState>>,
     config: State<'_, FormatterConfig>,
 ) -> Result<FormatWorkspaceReport, String> {
-    let config = snapshot(&config);
+    let config = snapshot(&config, &workspace);
     let t = Instant::now();
     info!("format_workspace_typ_files");
 
@@ -0,0 +1,45 @@
+// Tauri commands for read-only git awareness: the branch the workspace is
+// on, and its changes against HEAD. Per-file status rides along on the file
+// tree (see `get_file_tree`).
+
+use std::{sync::Arc, time::Instant};
+
+use log::{error, info};
+use tauri::State;
+
+use crate::git::GitInfo;
+use crate::vcs::WorkspaceDiff;
+use crate::workspace::WorkspaceState;
+
+/// Branch and HEAD commit, or `None` when the workspace isn't in a git
+/// repository.
+#[tauri::command(async)]
+pub fn get_git_info(workspace: State<'_, Arc<WorkspaceState>>) -> Result<Option<GitInfo>, String> {
+    let t = Instant::now();
+    let result = workspace.git_info();
+    match &result {
+        Ok(info) => info!(
+            "get_git_info: ok info={info:?} ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!("get_git_info: err=\"{e}\""),
+    }
+    result
+}
+
+#[tauri::command(async)]
+pub fn git_diff_vs_head(
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<WorkspaceDiff, String> {
+    let t = Instant::now();
+    let result = workspace.git_diff_vs_head();
+    match &result {
+        Ok(d) => info!(
+            "git_diff_vs_head: ok — {} file(s) ({:.1}ms)",
+            d.files.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!("git_diff_vs_head: err=\"{e}\""),
+    }
+    result
+}
@@ -0,0 +1,463 @@
+// `@local` packages: list, publish, open and delete.
+//
+// Local packages live in the package data dir under
+// `local/<name>/<version>/`, each with its own `typst.toml`. Teams use them
+// for house styles, and until now creating or updating one meant copying
+// folders around by hand. Publishing validates the manifest the way the
+// compiler will read it, and never overwrites a version: a changed house
+// style is a new version, so documents pinned to the old one keep compiling.
+//
+// For the same reason a published version is never opened for editing.
+// Opening one opens a working copy under `<app data>/package-copies/`,
+// made on first open and reopened as it was after that. Publishing the copy
+// with a bumped version in its `typst.toml` makes the next version.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use log::{error, info};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use typst::syntax::package::{PackageManifest, PackageSpec, PackageVersion};
+
+use crate::{
+    compiler::{CompileReason, PreviewPipeline},
+    vcs::fs::LocalWorkingTreeFs,
+    workspace::{
+        ignore::{walk_files, IgnoreRules, Unreadable},
+        WorkspaceState,
+    },
+    world::EditorWorld,
+};
+
+/// Namespace of packages installed on this machine only.
+const LOCAL_NAMESPACE: &str = "local";
+
+/// Manifest file name at the root of every package.
+const PACKAGE_MANIFEST: &str = "typst.toml";
+
+/// Folder under the app data dir holding the working copies of opened
+/// versions, laid out like the package data dir.
+const COPIES_DIR: &str = "package-copies";
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalPackage {
+    pub name: String,
+    pub version: String,
+    /// The import to write, `@local/name:version`.
+    pub spec: String,
+    /// Absolute path of the version's folder.
+    pub path: String,
+    pub description: Option<String>,
+    /// Whether the package ships a `[template]`.
+    pub template: bool,
+}
+
+/// Every version of every `@local` package, by name then newest version.
+#[tauri::command(async)]
+pub fn list_local_packages(world: State<'_, Arc<EditorWorld>>) -> Vec<LocalPackage> {
+    let t = Instant::now();
+    let packages = match world.package_storage_dirs().0 {
+        Some(data_dir) => list_in(&data_dir),
+        None => Vec::new(),
+    };
+    info!(
+        "list_local_packages: {} version(s) ({:.1}ms)",
+        packages.len(),
+        t.elapsed().as_secs_f64() * 1000.0
+    );
+    packages
+}
+
+/// Publish the workspace, or the workspace subfolder `folder`, as a new
+/// `@local` package version, named and versioned by its `typst.toml`.
+#[tauri::command(async)]
+pub fn publish_local_package(
+    folder: Option<String>,
+    workspace: State<'_, Arc<WorkspaceState>>,
+    world: State<'_, Arc<EditorWorld>>,
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<LocalPackage, String> {
+    let t = Instant::now();
+    info!("publish_local_package: folder={folder:?}");
+    let result = (|| {
+        let source = match folder.as_deref().filter(|f| !f.is_empty()) {
+            Some(folder) => workspace.resolve_any(folder)?,
+            None => workspace
+                .root
+                .read()
+                .clone()
+                .ok_or_else(|| "No workspace open".to_string())?,
+        };
+        let data_dir = world
+            .package_storage_dirs()
+            .0
+            .ok_or_else(|| "No package data directory on this system".to_string())?;
+        publish(&source, &data_dir)
+    })();
+    match &result {
+        Ok(package) => {
+            world.invalidate_package(LOCAL_NAMESPACE, &package.name);
+            pipeline.request_compile(CompileReason::Explicit);
+            info!(
+                "publish_local_package: ok {} ({:.1}ms)",
+                package.spec,
+                t.elapsed().as_secs_f64() * 1000.0
+            );
+        }
+        Err(e) => error!(
+            "publish_local_package: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Open the working copy of a local package version as the workspace (see
+/// the module comment). Returns the restored main file, like `open_folder`.
+#[tauri::command(async)]
+pub fn open_local_package(
+    name: String,
+    version: String,
+    app: AppHandle,
+    workspace: State<'_, Arc<WorkspaceState>>,
+    world: State<'_, Arc<EditorWorld>>,
+) -> Result<Option<String>, String> {
+    info!("open_local_package: @{LOCAL_NAMESPACE}/{name}:{version}");
+    let data_dir = world
+        .package_storage_dirs()
+        .0
+        .ok_or_else(|| "No package data directory on this system".to_string())?;
+    let copies_dir = app
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join(COPIES_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    let result = working_copy(&data_dir, &copies_dir, &name, &version)
+        .and_then(|dir| workspace.open_folder(dir));
+    if let Err(e) = &result {
+        error!("open_local_package: err=\"{e}\"");
+    }
+    result
+}
+
+#[tauri::command(async)]
+pub fn delete_local_package(
+    name: String,
+    version: String,
+    world: State<'_, Arc<EditorWorld>>,
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<(), String> {
+    info!("delete_local_package: @{LOCAL_NAMESPACE}/{name}:{version}");
+    let data_dir = world
+        .package_storage_dirs()
+        .0
+        .ok_or_else(|| "No package data directory on this system".to_string())?;
+    let result = delete(&data_dir, &name, &version);
+    match &result {
+        Ok(()) => {
+            world.invalidate_package(LOCAL_NAMESPACE, &name);
+            pipeline.request_compile(CompileReason::Explicit);
+        }
+        Err(e) => error!("delete_local_package: err=\"{e}\""),
+    }
+    result
+}
+
+fn list_in(data_dir: &Path) -> Vec<LocalPackage> {
+    let mut packages = Vec::new();
+    for name in subdirs(&data_dir.join(LOCAL_NAMESPACE)) {
+        let package_dir = data_dir.join(LOCAL_NAMESPACE).join(&name);
+        let mut versions: Vec<(PackageVersion, PathBuf)> = subdirs(&package_dir)
+            .into_iter()
+            .filter_map(|v| Some((v.parse().ok()?, package_dir.join(&v))))
+            .collect();
+        versions.sort_by_key(|(version, _)| std::cmp::Reverse(*version));
+        for (version, dir) in versions {
+            let manifest = read_manifest(&dir).ok();
+            packages.push(LocalPackage {
+                spec: format!("@{LOCAL_NAMESPACE}/{name}:{version}"),
+                name: name.clone(),
+                version: version.to_string(),
+                path: dir.to_string_lossy().into_owned(),
+                description: manifest
+                    .as_ref()
+                    .and_then(|m| m.package.description.as_ref().map(|d| d.to_string())),
+                template: manifest.is_some_and(|m| m.template.is_some()),
+            });
+        }
+    }
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages
+}
+
+/// Names of the (non-hidden) subdirectories of `dir`, sorted.
+fn subdirs(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    names.sort();
+    names
+}
+
+fn read_manifest(dir: &Path) -> Result<PackageManifest, String> {
+    let text = std::fs::read_to_string(dir.join(PACKAGE_MANIFEST))
+        .map_err(|e| format!("Failed to read {PACKAGE_MANIFEST}: {e}"))?;
+    toml::from_str(&text).map_err(|e| format!("invalid {PACKAGE_MANIFEST}: {e}"))
+}
+
+/// The folder of `@local/name:version`, refusing names and versions that are
+/// not what a package spec allows (and so could not be path components).
+fn version_dir(data_dir: &Path, name: &str, version: &str) -> Result<PathBuf, String> {
+    let spec: PackageSpec = format!("@{LOCAL_NAMESPACE}/{name}:{version}")
+        .parse()
+        .map_err(|e| format!("Invalid package @{LOCAL_NAMESPACE}/{name}:{version}: {e}"))?;
+    Ok(data_dir
+        .join(LOCAL_NAMESPACE)
+        .join(spec.name.as_str())
+        .join(spec.version.to_string()))
+}
+
+/// Check that the package in `source` is one the compiler will accept, and
+/// return its spec.
+fn validate(source: &Path, manifest: &PackageManifest) -> Result<PackageSpec, String> {
+    let info = &manifest.package;
+    let spec: PackageSpec = format!("@{LOCAL_NAMESPACE}/{}:{}", info.name, info.version)
+        .parse()
+        .map_err(|e| format!("invalid package name {:?}: {e}", info.name))?;
+    manifest
+        .validate(&spec)
+        .map_err(|e| format!("invalid {PACKAGE_MANIFEST}: {e}"))?;
+
+    let is_file = |rel: &str| {
+        let path = Path::new(rel);
+        path.is_relative() && source.join(path).is_file()
+    };
+    if !is_file(&info.entrypoint) {
+        return Err(format!("entrypoint {} does not exist", info.entrypoint));
+    }
+    if let Some(template) = &manifest.template {
+        let entrypoint = format!("{}/{}", template.path, template.entrypoint);
+        if !is_file(&entrypoint) {
+            return Err(format!("template entrypoint {entrypoint} does not exist"));
+        }
+    }
+    Ok(spec)
+}
+
+fn publish(source: &Path, data_dir: &Path) -> Result<LocalPackage, String> {
+    let manifest = read_manifest(source)?;
+    let spec = validate(source, &manifest)?;
+    let target = version_dir(data_dir, &spec.name, &spec.version.to_string())?;
+    if target.exists() {
+        return Err(format!(
+            "{spec} is already published; bump the version in {PACKAGE_MANIFEST}"
+        ));
+    }
+
+    copy_into_place(source, &target).map_err(|e| format!("Failed to publish {spec}: {e}"))?;
+
+    list_in(data_dir)
+        .into_iter()
+        .find(|p| p.spec == spec.to_string())
+        .ok_or_else(|| format!("{spec} was not found after publishing"))
+}
+
+/// The working copy of `@local/name:version` under `copies_dir`, copied from
+/// the published version in `data_dir` unless it already exists.
+fn working_copy(
+    data_dir: &Path,
+    copies_dir: &Path,
+    name: &str,
+    version: &str,
+) -> Result<PathBuf, String> {
+    let published = version_dir(data_dir, name, version)?;
+    if !published.is_dir() {
+        return Err(format!(
+            "@{LOCAL_NAMESPACE}/{name}:{version} is not installed"
+        ));
+    }
+    let copy = version_dir(copies_dir, name, version)?;
+    if !copy.is_dir() {
+        copy_into_place(&published, &copy)
+            .map_err(|e| format!("Failed to copy @{LOCAL_NAMESPACE}/{name}:{version}: {e}"))?;
+    }
+    Ok(copy)
+}
+
+/// Copy the package in `source` to the folder `target`, through a staging
+/// folder next to it renamed into place — so a failed copy never leaves a
+/// half-written version for imports to resolve to.
+fn copy_into_place(source: &Path, target: &Path) -> Result<(), String> {
+    let parent = target.parent().expect("version dir has a parent");
+    std::fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    let version = target.file_name().unwrap_or_default().to_string_lossy();
+    let staging = parent.join(format!(".{version}.partial"));
+    let _ = std::fs::remove_dir_all(&staging);
+    let copied = copy_package(source, &staging)
+        .and_then(|()| std::fs::rename(&staging, target).map_err(|e| e.to_string()));
+    if copied.is_err() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    copied
+}
+
+/// Copy the files of `source` the workspace would keep — honouring its ignore
+/// files, and never `.git` or `.typwriter` — into `dest`.
+fn copy_package(source: &Path, dest: &Path) -> Result<(), String> {
+    let fs = LocalWorkingTreeFs;
+    let rules = IgnoreRules::load(&fs, source);
+    for file in walk_files(&fs, source, &rules, Unreadable::Fail)? {
+        let target = dest.join(&file.rel);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        std::fs::copy(&file.path, &target)
+            .map_err(|e| format!("Failed to copy {}: {e}", file.rel))?;
+    }
+    Ok(())
+}
+
+fn delete(data_dir: &Path, name: &str, version: &str) -> Result<(), String> {
+    let dir = version_dir(data_dir, name, version)?;
+    if !dir.is_dir() {
+        return Err(format!(
+            "@{LOCAL_NAMESPACE}/{name}:{version} is not installed"
+        ));
+    }
+    std::fs::remove_dir_all(&dir)
+        .map_err(|e| format!("Failed to delete @{LOCAL_NAMESPACE}/{name}:{version}: {e}"))?;
+    // Leave no empty package folder behind once its last version is gone.
+    if let Some(package_dir) = dir.parent() {
+        if subdirs(package_dir).is_empty() {
+            let _ = std::fs::remove_dir(package_dir);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn house_style(version: &str) -> TempDir {
+        let dir = TempDir::new("src");
+        dir.write(
+            "typst.toml",
+            &format!(
+                "[package]\nname = \"house-style\"\nversion = \"{version}\"\nentrypoint = \"lib.typ\"\ndescription = \"Our look\"\n"
+            ),
+        );
+        dir.write("lib.typ", "#let conf(doc) = doc\n");
+        dir.write(".typwriter/history.db", "not part of the package");
+        dir.write(".gitignore", "drafts/\n");
+        dir.write("drafts/wip.typ", "unfinished");
+        dir
+    }
+
+    #[test]
+    fn publishing_copies_the_package_and_lists_it() {
+        let data = TempDir::new("data");
+        let source = house_style("1.0.0");
+
+        let published = publish(&source.0, &data.0).expect("published");
+
+        assert_eq!(published.spec, "@local/house-style:1.0.0");
+        assert_eq!(published.description.as_deref(), Some("Our look"));
+        let dir = data.0.join("local/house-style/1.0.0");
+        assert!(dir.join("lib.typ").is_file());
+        assert!(dir.join("typst.toml").is_file());
+        assert!(!dir.join(".typwriter").exists());
+        assert!(!dir.join("drafts").exists());
+        assert_eq!(list_in(&data.0), vec![published]);
+    }
+
+    #[test]
+    fn a_published_version_is_never_overwritten() {
+        let data = TempDir::new("data");
+        publish(&house_style("1.0.0").0, &data.0).expect("first publish");
+
+        let err = publish(&house_style("1.0.0").0, &data.0).unwrap_err();
+        assert!(err.contains("already published"), "{err}");
+
+        publish(&house_style("1.1.0").0, &data.0).expect("new version");
+        let versions: Vec<String> = list_in(&data.0).into_iter().map(|p| p.version).collect();
+        assert_eq!(versions, vec!["1.1.0", "1.0.0"]);
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        let data = TempDir::new("data");
+
+        let missing_entry = house_style("1.0.0");
+        std::fs::remove_file(missing_entry.0.join("lib.typ")).expect("remove");
+        let err = publish(&missing_entry.0, &data.0).unwrap_err();
+        assert!(err.contains("entrypoint lib.typ does not exist"), "{err}");
+
+        let bad_name = TempDir::new("src");
+        bad_name.write(
+            "typst.toml",
+            "[package]\nname = \"House Style\"\nversion = \"1.0.0\"\nentrypoint = \"lib.typ\"\n",
+        );
+        bad_name.write("lib.typ", "");
+        assert!(publish(&bad_name.0, &data.0).is_err());
+
+        let no_manifest = TempDir::new("src");
+        let err = publish(&no_manifest.0, &data.0).unwrap_err();
+        assert!(err.contains("typst.toml"), "{err}");
+
+        assert!(list_in(&data.0).is_empty());
+    }
+
+    #[test]
+    fn opening_a_version_edits_a_copy_and_keeps_it() {
+        let data = TempDir::new("data");
+        let copies = TempDir::new("copies");
+        publish(&house_style("1.0.0").0, &data.0).expect("published");
+
+        let copy = working_copy(&data.0, &copies.0, "house-style", "1.0.0").expect("copy");
+        assert_eq!(copy, copies.0.join("local/house-style/1.0.0"));
+        std::fs::write(copy.join("lib.typ"), "#let conf(doc) = text(red, doc)\n").expect("edit");
+
+        // Reopening finds the edit; the published version never changed.
+        let again = working_copy(&data.0, &copies.0, "house-style", "1.0.0").expect("copy");
+        assert_eq!(
+            std::fs::read_to_string(again.join("lib.typ")).expect("read"),
+            "#let conf(doc) = text(red, doc)\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(data.0.join("local/house-style/1.0.0/lib.typ")).expect("read"),
+            "#let conf(doc) = doc\n"
+        );
+
+        assert!(working_copy(&data.0, &copies.0, "house-style", "9.0.0").is_err());
+    }
+
+    #[test]
+    fn deleting_the_last_version_removes_the_package_folder() {
+        let data = TempDir::new("data");
+        publish(&house_style("1.0.0").0, &data.0).expect("published");
+        publish(&house_style("2.0.0").0, &data.0).expect("published");
+
+        delete(&data.0, "house-style", "1.0.0").expect("deleted");
+        assert!(data.0.join("local/house-style/2.0.0").is_dir());
+
+        delete(&data.0, "house-style", "2.0.0").expect("deleted");
+        assert!(!data.0.join("local/house-style").exists());
+
+        assert!(delete(&data.0, "house-style", "2.0.0").is_err());
+        assert!(delete(&data.0, "../escape", "1.0.0").is_err());
+    }
+}
@@ -6,7 +6,9 @@ pub mod click;
 pub mod editor;
 pub mod export;
 pub mod format;
+pub mod git;
 pub mod grammar;
+pub mod local_packages;
 pub mod logs;
 pub mod lsp;
 pub mod packages;
@@ -14,5 +16,6 @@ pub mod present;
 pub mod preview;
 pub mod search;
 pub mod settings;
+pub mod templates;
 pub mod vcs;
 pub mod workspace;
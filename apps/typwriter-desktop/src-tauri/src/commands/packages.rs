@@ -4,20 +4,46 @@
 // (`EditorWorld::packages`); this exposes the same data to the frontend so it
 // can be browsed and imported rather than only completed against. Nothing here
 // downloads anything the editor was not already going to download.
+//
+// The second half is the workspace's own view: which packages its sources
+// import, whether they are cached, what they could be upgraded to, and
+// clearing out downloaded versions it no longer uses.
 
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 
-use log::info;
+use ecow::EcoString;
+use log::{error, info};
 use serde::Serialize;
 use tauri::State;
+use typst::syntax::{
+    ast,
+    package::{PackageSpec, PackageVersion},
+    LinkedNode, SyntaxKind,
+};
 use typst_ide::IdeWorld;
 
-use crate::world::EditorWorld;
+use crate::{
+    compiler::{CompileReason, PreviewPipeline},
+    vcs::{fs::LocalWorkingTreeFs, WorkingTreeFs},
+    workspace::{
+        encoding,
+        ignore::{walk_files, IgnoreRules, Unreadable, WalkedFile},
+        WorkspaceState,
+    },
+    world::EditorWorld,
+};
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PackageEntry {
-    /// Registry namespace — `preview` for everything on Universe today.
+    /// Registry namespace — `preview` for Typst Universe, or the namespace
+    /// of a custom registry.
     pub namespace: String,
     pub name: String,
     /// Newest version, as `major.minor.patch`.
@@ -79,3 +105,641 @@ pub fn list_packages(world: State<'_, Arc<EditorWorld>>) -> Vec<PackageEntry> {
     );
     entries
 }
+
+// ─── Workspace packages ──────────────────────────────────────────────────────
+
+/// A package the workspace imports, one entry per imported version.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsedPackage {
+    pub namespace: String,
+    pub name: String,
+    /// The version the imports pin, as `major.minor.patch`.
+    pub version: String,
+    /// Whether this version is already in the package data or cache dir, so
+    /// compiling will not need the network.
+    pub cached: bool,
+    /// Newest version in the registry index, when the index lists the package.
+    pub latest: Option<String>,
+    /// Workspace-relative files that import this version, sorted.
+    pub importers: Vec<String>,
+    /// Versions of this package in the download cache that neither a
+    /// workspace file nor a package the workspace uses (transitively) imports,
+    /// newest first. [`remove_stale_packages`] deletes these.
+    pub stale_versions: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageUpgradeOutcome {
+    pub files_changed: usize,
+    pub imports_rewritten: usize,
+    /// Snapshot taken before writing, when history is available.
+    pub restore_point: Option<String>,
+}
+
+/// The packages the workspace's `.typ` files import, sorted by name then
+/// version.
+///
+/// Reads files from disk, like project-wide search: unsaved edits count once
+/// they are saved.
+#[tauri::command(async)]
+pub fn list_workspace_packages(
+    workspace: State<'_, Arc<WorkspaceState>>,
+    world: State<'_, Arc<EditorWorld>>,
+) -> Result<Vec<UsedPackage>, String> {
+    let t = Instant::now();
+    let root = workspace
+        .root
+        .read()
+        .clone()
+        .ok_or_else(|| "No workspace open".to_string())?;
+
+    let usage = scan_usage(typ_files(&root));
+    let (data_dir, cache_dir) = world.package_storage_dirs();
+    let storage: Vec<PathBuf> = data_dir.into_iter().chain(cache_dir.clone()).collect();
+    let latest = newest_versions(world.packages());
+    let packages = report(&usage, &storage, cache_dir.as_deref(), &latest);
+
+    info!(
+        "list_workspace_packages: {} package version(s) ({:.1}ms)",
+        packages.len(),
+        t.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(packages)
+}
+
+/// Point every import of `@namespace/name:*` in the workspace at `version`.
+///
+/// Snapshots the workspace first, for the same reason `replace_in_workspace`
+/// takes a restore point: it rewrites many files at once without asking about
+/// each. Files keep their encoding and line endings. The first write that
+/// fails stops the upgrade; the snapshot holds every file as it was. The new
+/// version is downloaded by the compile this requests, with the usual
+/// progress events.
+#[tauri::command(async)]
+pub fn upgrade_workspace_package(
+    namespace: String,
+    name: String,
+    version: String,
+    workspace: State<'_, Arc<WorkspaceState>>,
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<PackageUpgradeOutcome, String> {
+    let t = Instant::now();
+    info!("upgrade_workspace_package: @{namespace}/{name} -> {version}");
+    let root = workspace
+        .root
+        .read()
+        .clone()
+        .ok_or_else(|| "No workspace open".to_string())?;
+    let version: PackageVersion = version
+        .parse()
+        .map_err(|e| format!("Invalid version {version:?}: {e}"))?;
+    let fs = workspace.working_fs()?;
+
+    let mut rewrites = Vec::new();
+    for file in walk_typ_files(fs.as_ref(), &root) {
+        let Some((text, format)) = fs
+            .read_file(&file.path)
+            .ok()
+            .and_then(|bytes| encoding::decode(&bytes, true))
+        else {
+            continue;
+        };
+        let (updated, count) = rewrite_package_version(&text, &namespace, &name, version);
+        if count == 0 {
+            continue;
+        }
+        let bytes = encoding::encode(&updated, format).map_err(|e| {
+            error!(
+                "upgrade_workspace_package: cannot encode path={:?} err=\"{e}\"",
+                file.rel
+            );
+            format!("Cannot write {} in its encoding: {e}", file.rel)
+        })?;
+        rewrites.push((file.path, bytes, count));
+    }
+    if rewrites.is_empty() {
+        return Ok(PackageUpgradeOutcome {
+            files_changed: 0,
+            imports_rewritten: 0,
+            restore_point: None,
+        });
+    }
+
+    let restore_point = workspace.snapshot_file_op(&format!(
+        "Before upgrading @{namespace}/{name} to {version}"
+    ));
+
+    let mut written = Vec::with_capacity(rewrites.len());
+    let mut imports_rewritten = 0usize;
+    let mut result = Ok(());
+    for (path, bytes, count) in rewrites {
+        workspace.note_self_write(&path);
+        if let Err(e) = fs.write_file(&path, &bytes) {
+            error!("upgrade_workspace_package: write failed path={path:?} err=\"{e}\"");
+            result = Err(format!("Failed to write {}: {e}", path.display()));
+            break;
+        }
+        written.push(path);
+        imports_rewritten += count;
+    }
+
+    // Whatever was written is on disk either way; the editor and the preview
+    // have to see it even when a later file failed.
+    workspace.refresh_rewritten(&written);
+    pipeline.request_compile(CompileReason::Explicit);
+    result?;
+
+    info!(
+        "upgrade_workspace_package: {imports_rewritten} import(s) in {} file(s) ({:.1}ms)",
+        written.len(),
+        t.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(PackageUpgradeOutcome {
+        files_changed: written.len(),
+        imports_rewritten,
+        restore_point,
+    })
+}
+
+/// Delete the stale versions [`list_workspace_packages`] reports from the
+/// download cache. Returns the removed specs.
+///
+/// Only the cache is touched: it holds what the editor downloaded and can
+/// download again, whereas the data dir holds packages someone installed by
+/// hand. Versions the workspace's packages import themselves are kept.
+/// Another project still using a removed version simply fetches it again on
+/// its next compile.
+#[tauri::command(async)]
+pub fn remove_stale_packages(
+    workspace: State<'_, Arc<WorkspaceState>>,
+    world: State<'_, Arc<EditorWorld>>,
+) -> Result<Vec<String>, String> {
+    let t = Instant::now();
+    let root = workspace
+        .root
+        .read()
+        .clone()
+        .ok_or_else(|| "No workspace open".to_string())?;
+    let (data_dir, cache_dir) = world.package_storage_dirs();
+    let Some(cache_dir) = cache_dir else {
+        return Ok(Vec::new());
+    };
+    let storage: Vec<PathBuf> = data_dir.into_iter().chain([cache_dir.clone()]).collect();
+
+    let usage = scan_usage(typ_files(&root));
+    let result = remove_stale_versions(&cache_dir, &storage, &usage);
+    match &result {
+        Ok(removed) => info!(
+            "remove_stale_packages: removed {} version(s) ({:.1}ms)",
+            removed.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "remove_stale_packages: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// A package version as `(namespace, name, version)`.
+type PackageKey = (String, String, PackageVersion);
+
+/// Imported packages, with the files importing each.
+type Usage = BTreeMap<PackageKey, BTreeSet<String>>;
+
+/// Workspace `.typ` files as (relative path, text), skipping ignored and
+/// unreadable ones.
+fn typ_files(root: &Path) -> Vec<(String, String)> {
+    walk_typ_files(&LocalWorkingTreeFs, root)
+        .into_iter()
+        .filter_map(|file| Some((file.rel, std::fs::read_to_string(&file.path).ok()?)))
+        .collect()
+}
+
+/// The workspace `.typ` files, skipping ignored ones.
+fn walk_typ_files(fs: &dyn WorkingTreeFs, root: &Path) -> Vec<WalkedFile> {
+    let rules = IgnoreRules::load(fs, root);
+    walk_files(fs, root, &rules, Unreadable::Skip)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|file| file.rel.ends_with(".typ"))
+        .collect()
+}
+
+fn scan_usage(files: impl IntoIterator<Item = (String, String)>) -> Usage {
+    let mut usage = Usage::new();
+    for (rel, text) in files {
+        for (spec, _) in package_imports(&text) {
+            let key = (
+                spec.namespace.to_string(),
+                spec.name.to_string(),
+                spec.version,
+            );
+            usage.entry(key).or_default().insert(rel.clone());
+        }
+    }
+    usage
+}
+
+/// Package imports in `text`, with the byte range of each spec's string
+/// literal (quotes included).
+fn package_imports(text: &str) -> Vec<(PackageSpec, Range<usize>)> {
+    let root = typst::syntax::parse(text);
+    let mut imports = Vec::new();
+    collect_package_imports(&LinkedNode::new(&root), &mut imports);
+    imports
+}
+
+fn collect_package_imports(node: &LinkedNode, out: &mut Vec<(PackageSpec, Range<usize>)>) {
+    if let Some(import) = node.cast::<ast::ModuleImport>() {
+        if let ast::Expr::Str(source) = import.source() {
+            let literal = node.children().find(|c| c.kind() == SyntaxKind::Str);
+            if let (Ok(spec), Some(literal)) = (source.get().parse::<PackageSpec>(), literal) {
+                out.push((spec, literal.range()));
+            }
+        }
+        return;
+    }
+    for child in node.children() {
+        collect_package_imports(&child, out);
+    }
+}
+
+/// `text` with every import of `@namespace/name` pointed at `version`, and
+/// how many imports changed.
+fn rewrite_package_version(
+    text: &str,
+    namespace: &str,
+    name: &str,
+    version: PackageVersion,
+) -> (String, usize) {
+    let mut out = text.to_string();
+    let mut count = 0;
+    // Back to front, so earlier ranges stay valid.
+    for (spec, range) in package_imports(text).into_iter().rev() {
+        if spec.namespace.as_str() != namespace
+            || spec.name.as_str() != name
+            || spec.version == version
+        {
+            continue;
+        }
+        let upgraded = PackageSpec { version, ..spec };
+        out.replace_range(range, &format!("\"{upgraded}\""));
+        count += 1;
+    }
+    (out, count)
+}
+
+/// Newest indexed version per `(namespace, name)`.
+fn newest_versions(
+    index: &[(PackageSpec, Option<EcoString>)],
+) -> HashMap<(String, String), PackageVersion> {
+    let mut newest: HashMap<(String, String), PackageVersion> = HashMap::new();
+    for (spec, _) in index {
+        let key = (spec.namespace.to_string(), spec.name.to_string());
+        let entry = newest.entry(key).or_insert(spec.version);
+        *entry = (*entry).max(spec.version);
+    }
+    newest
+}
+
+/// Versions of `@namespace/name` present under a package directory, newest
+/// first. Directories whose name is not a version are ignored.
+fn installed_versions(dir: &Path, namespace: &str, name: &str) -> Vec<PackageVersion> {
+    let mut versions: Vec<PackageVersion> = std::fs::read_dir(dir.join(namespace).join(name))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect();
+    versions.sort_by(|a, b| b.cmp(a));
+    versions
+}
+
+/// The imported versions plus every version they import in turn, following
+/// the `.typ` files of each package found under `storage`. A package that
+/// isn't installed contributes only itself.
+fn reachable_versions(storage: &[PathBuf], usage: &Usage) -> BTreeSet<PackageKey> {
+    let mut reached: BTreeSet<PackageKey> = usage.keys().cloned().collect();
+    let mut pending: Vec<PackageKey> = reached.iter().cloned().collect();
+    while let Some((namespace, name, version)) = pending.pop() {
+        let Some(dir) = storage
+            .iter()
+            .map(|dir| dir.join(&namespace).join(&name).join(version.to_string()))
+            .find(|dir| dir.is_dir())
+        else {
+            continue;
+        };
+        for text in package_typ_texts(&dir) {
+            for (spec, _) in package_imports(&text) {
+                let key = (
+                    spec.namespace.to_string(),
+                    spec.name.to_string(),
+                    spec.version,
+                );
+                if reached.insert(key.clone()) {
+                    pending.push(key);
+                }
+            }
+        }
+    }
+    reached
+}
+
+/// Text of every `.typ` file inside an installed package.
+fn package_typ_texts(dir: &Path) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "typ") {
+                texts.extend(std::fs::read_to_string(&path).ok());
+            }
+        }
+    }
+    texts
+}
+
+/// Cached versions of each imported package that neither the workspace nor
+/// any package it (transitively) uses pins.
+fn stale_versions(
+    cache_dir: &Path,
+    storage: &[PathBuf],
+    usage: &Usage,
+) -> BTreeMap<(String, String), Vec<PackageVersion>> {
+    let mut imported: BTreeMap<(String, String), BTreeSet<PackageVersion>> = BTreeMap::new();
+    for (namespace, name, _) in usage.keys() {
+        imported
+            .entry((namespace.clone(), name.clone()))
+            .or_default();
+    }
+    for (namespace, name, version) in reachable_versions(storage, usage) {
+        if let Some(pinned) = imported.get_mut(&(namespace, name)) {
+            pinned.insert(version);
+        }
+    }
+    imported
+        .into_iter()
+        .map(|((namespace, name), pinned)| {
+            let stale = installed_versions(cache_dir, &namespace, &name)
+                .into_iter()
+                .filter(|v| !pinned.contains(v))
+                .collect();
+            ((namespace, name), stale)
+        })
+        .collect()
+}
+
+fn report(
+    usage: &Usage,
+    storage: &[PathBuf],
+    cache_dir: Option<&Path>,
+    latest: &HashMap<(String, String), PackageVersion>,
+) -> Vec<UsedPackage> {
+    let stale = cache_dir
+        .map(|dir| stale_versions(dir, storage, usage))
+        .unwrap_or_default();
+    usage
+        .iter()
+        .map(|((namespace, name, version), importers)| {
+            let key = (namespace.clone(), name.clone());
+            let cached = storage.iter().any(|dir| {
+                dir.join(namespace)
+                    .join(name)
+                    .join(version.to_string())
+                    .is_dir()
+            });
+            UsedPackage {
+                namespace: namespace.clone(),
+                name: name.clone(),
+                version: version.to_string(),
+                cached,
+                latest: latest.get(&key).map(|v| v.to_string()),
+                importers: importers.iter().cloned().collect(),
+                stale_versions: stale
+                    .get(&key)
+                    .into_iter()
+                    .flatten()
+                    .map(|v| v.to_string())
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn remove_stale_versions(
+    cache_dir: &Path,
+    storage: &[PathBuf],
+    usage: &Usage,
+) -> Result<Vec<String>, String> {
+    let mut removed = Vec::new();
+    for ((namespace, name), versions) in stale_versions(cache_dir, storage, usage) {
+        let package_dir = cache_dir.join(&namespace).join(&name);
+        for version in versions {
+            std::fs::remove_dir_all(package_dir.join(version.to_string()))
+                .map_err(|e| format!("Failed to remove @{namespace}/{name}:{version}: {e}"))?;
+            removed.push(format!("@{namespace}/{name}:{version}"));
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// Lay out an (empty) installed package under `dir`.
+    fn install(dir: &TempDir, spec: &str) {
+        let spec: PackageSpec = spec.parse().expect("valid spec");
+        let dir = dir
+            .0
+            .join(spec.namespace.as_str())
+            .join(spec.name.as_str())
+            .join(spec.version.to_string());
+        std::fs::create_dir_all(&dir).expect("mkdir");
+        std::fs::write(dir.join("typst.toml"), "").expect("write");
+    }
+
+    /// Give an installed package under `dir` an entry file with `text`.
+    fn write_entry(dir: &TempDir, spec: &str, text: &str) {
+        let spec: PackageSpec = spec.parse().expect("valid spec");
+        let path = dir
+            .0
+            .join(spec.namespace.as_str())
+            .join(spec.name.as_str())
+            .join(spec.version.to_string())
+            .join("src/lib.typ");
+        std::fs::create_dir_all(path.parent().unwrap()).expect("mkdir");
+        std::fs::write(path, text).expect("write");
+    }
+
+    fn spec(s: &str) -> PackageSpec {
+        s.parse().expect("valid spec")
+    }
+
+    fn files(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        files
+            .iter()
+            .map(|(rel, text)| (rel.to_string(), text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn imports_are_grouped_by_version_with_their_files() {
+        let usage = scan_usage(files(&[
+            (
+                "main.typ",
+                "#import \"@preview/cetz:0.3.1\": canvas\n#import \"template.typ\"\n",
+            ),
+            (
+                "ch/one.typ",
+                "#import \"@preview/cetz:0.3.1\"\n#import \"@preview/cetz:0.2.0\"\n",
+            ),
+            (
+                "notes.typ",
+                "// #import \"@preview/unused:1.0.0\"\n`#import \"@preview/code:1.0.0\"`\n",
+            ),
+        ]));
+
+        let got: Vec<(String, Vec<&str>)> = usage
+            .iter()
+            .map(|((namespace, name, version), files)| {
+                (
+                    format!("@{namespace}/{name}:{version}"),
+                    files.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("@preview/cetz:0.2.0".to_string(), vec!["ch/one.typ"]),
+                (
+                    "@preview/cetz:0.3.1".to_string(),
+                    vec!["ch/one.typ", "main.typ"]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn upgrading_rewrites_only_the_named_package() {
+        let text = "#import \"@preview/cetz:0.2.0\": canvas\n#import \"@preview/fletcher:0.5.0\"\n#{ import \"@preview/cetz:0.3.0\" }\n";
+        let (updated, count) =
+            rewrite_package_version(text, "preview", "cetz", "0.3.1".parse().unwrap());
+        assert_eq!(count, 2);
+        assert_eq!(
+            updated,
+            "#import \"@preview/cetz:0.3.1\": canvas\n#import \"@preview/fletcher:0.5.0\"\n#{ import \"@preview/cetz:0.3.1\" }\n"
+        );
+
+        let (same, count) =
+            rewrite_package_version(&updated, "preview", "cetz", "0.3.1".parse().unwrap());
+        assert_eq!(count, 0);
+        assert_eq!(same, updated);
+    }
+
+    #[test]
+    fn report_marks_cached_latest_and_stale_versions() {
+        let data = TempDir::new("data");
+        let cache = TempDir::new("cache");
+        install(&data, "@preview/cetz:0.3.1");
+        install(&cache, "@preview/cetz:0.2.0");
+        install(&cache, "@preview/cetz:0.1.0");
+        install(&cache, "@preview/other:1.0.0");
+        let usage = scan_usage(files(&[
+            ("a.typ", "#import \"@preview/cetz:0.3.1\"\n"),
+            (
+                "b.typ",
+                "#import \"@preview/cetz:0.2.0\"\n#import \"@preview/tidy:0.4.0\"\n",
+            ),
+        ]));
+        let index = vec![
+            (spec("@preview/cetz:0.3.1"), None),
+            (spec("@preview/cetz:0.4.0"), None),
+            (spec("@preview/cetz:0.2.0"), None),
+        ];
+
+        let packages = report(
+            &usage,
+            &[data.0.clone(), cache.0.clone()],
+            Some(&cache.0),
+            &newest_versions(&index),
+        );
+
+        let summary: Vec<(&str, &str, bool, Option<&str>, Vec<&str>)> = packages
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.version.as_str(),
+                    p.cached,
+                    p.latest.as_deref(),
+                    p.stale_versions.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("cetz", "0.2.0", true, Some("0.4.0"), vec!["0.1.0"]),
+                ("cetz", "0.3.1", true, Some("0.4.0"), vec!["0.1.0"]),
+                ("tidy", "0.4.0", false, None, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn removing_stale_versions_keeps_pinned_and_unrelated_ones() {
+        let cache = TempDir::new("cache");
+        install(&cache, "@preview/cetz:0.3.1");
+        install(&cache, "@preview/cetz:0.2.0");
+        install(&cache, "@preview/other:1.0.0");
+        let usage = scan_usage(files(&[("a.typ", "#import \"@preview/cetz:0.3.1\"\n")]));
+
+        let removed = remove_stale_versions(&cache.0, &[cache.0.clone()], &usage).expect("removed");
+
+        assert_eq!(removed, vec!["@preview/cetz:0.2.0"]);
+        assert!(cache.0.join("preview/cetz/0.3.1").is_dir());
+        assert!(!cache.0.join("preview/cetz/0.2.0").exists());
+        assert!(cache.0.join("preview/other/1.0.0").is_dir());
+    }
+
+    #[test]
+    fn versions_imported_by_used_packages_are_not_stale() {
+        let cache = TempDir::new("cache");
+        install(&cache, "@preview/cetz:0.3.1");
+        install(&cache, "@preview/cetz:0.2.0");
+        install(&cache, "@preview/cetz:0.1.0");
+        install(&cache, "@preview/fletcher:0.5.0");
+        install(&cache, "@preview/oxifmt:0.2.1");
+        write_entry(
+            &cache,
+            "@preview/fletcher:0.5.0",
+            "#import \"@preview/cetz:0.2.0\"\n#import \"@preview/oxifmt:0.2.1\"\n",
+        );
+        write_entry(
+            &cache,
+            "@preview/oxifmt:0.2.1",
+            "#import \"@preview/cetz:0.1.0\"\n",
+        );
+        let usage = scan_usage(files(&[(
+            "a.typ",
+            "#import \"@preview/cetz:0.3.1\"\n#import \"@preview/fletcher:0.5.0\"\n",
+        )]));
+
+        let removed = remove_stale_versions(&cache.0, &[cache.0.clone()], &usage).expect("removed");
+
+        assert!(removed.is_empty(), "removed {removed:?}");
+        assert!(cache.0.join("preview/cetz/0.2.0").is_dir());
+        assert!(cache.0.join("preview/cetz/0.1.0").is_dir());
+    }
+}
@@ -1,6 +1,7 @@
-// Tauri commands for controlling the live preview (recompile trigger, zoom).
+// Tauri commands for controlling the live preview (recompile trigger, zoom,
+// standalone chapter preview).
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use log::{error, info};
 use tauri::State;
@@ -8,6 +9,7 @@ use tauri::State;
 use crate::{
     compiler::{CompileReason, PreviewPipeline},
     workspace::WorkspaceState,
+    world::{local_file_id, EditorWorld},
 };
 
 #[tauri::command]
@@ -51,3 +53,56 @@ pub fn sync_preview(pipeline: State<'_, Arc<PreviewPipeline>>) -> Result<(), Str
     pipeline.emit_current_state();
     Ok(())
 }
+
+/// Preview `path` (workspace-relative) on its own instead of the full
+/// document, wrapped in the `standalone-wrapper` of `typwriter.toml` when
+/// the project declares one. Previewing the main file itself is the same as
+/// [`preview_full_document`].
+#[tauri::command]
+pub fn preview_standalone(
+    path: String,
+    world: State<'_, Arc<EditorWorld>>,
+    pipeline: State<'_, Arc<PreviewPipeline>>,
+) -> Result<(), String> {
+    info!("preview_standalone: path={path:?}");
+    if Path::new(&path).is_absolute() {
+        let e = "preview_standalone expects a workspace-relative path";
+        error!("preview_standalone: err=\"{e}\"");
+        return Err(e.to_string());
+    }
+    let id = local_file_id(Path::new(&path)).ok_or_else(|| {
+        let e = format!("{path} is not a valid workspace path");
+        error!("preview_standalone: err=\"{e}\"");
+        e
+    })?;
+    if !world.root().join(&path).is_file() && !world.has_shadow(id) {
+        let e = format!("{path} does not exist");
+        error!("preview_standalone: err=\"{e}\"");
+        return Err(e);
+    }
+    let chapter = (world.main_id() != Some(id)).then_some(id);
+    pipeline.set_standalone(chapter);
+    pipeline.request_compile(CompileReason::Explicit);
+    info!("preview_standalone: ok standalone={}", chapter.is_some());
+    Ok(())
+}
+
+/// Return the preview to the full document after [`preview_standalone`].
+#[tauri::command]
+pub fn preview_full_document(pipeline: State<'_, Arc<PreviewPipeline>>) -> Result<(), String> {
+    info!("preview_full_document: called");
+    if pipeline.standalone().is_some() {
+        pipeline.set_standalone(None);
+        pipeline.request_compile(CompileReason::Explicit);
+    }
+    Ok(())
+}
+
+/// Workspace-relative path of the chapter being previewed standalone, or
+/// `None` when the preview shows the full document.
+#[tauri::command]
+pub fn get_standalone_preview(pipeline: State<'_, Arc<PreviewPipeline>>) -> Option<String> {
+    pipeline
+        .standalone()
+        .map(|id| id.vpath().get_without_slash().to_string())
+}
@@ -20,8 +20,12 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use crate::{
-    vcs::VcsState,
-    workspace::{text_files::is_text_extension, WorkspaceState},
+    vcs::{fs::LocalWorkingTreeFs, VcsState},
+    workspace::{
+        ignore::{walk_files, IgnoreRules, Unreadable},
+        text_files::is_text_extension,
+        WorkspaceState,
+    },
 };
 
 /// Cap on reported hits. A query like `e` matches essentially everything; past
@@ -33,13 +37,6 @@ const MAX_HITS: usize = 2000;
 /// anyone is searching for, and reading it stalls the walk.
 const MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
 
-/// Directories never worth walking. Mirrors the file tree's own ignore list.
-const IGNORED_DIRS: &[&str] = &["node_modules", "target", "dist", "build", "out"];
-
-/// Depth cap, matching the workspace walk — a symlink cycle the OS does not
-/// resolve away would otherwise run forever.
-const MAX_DEPTH: usize = 32;
-
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchQuery {
@@ -160,52 +157,25 @@ fn is_searchable(path: &Path, extensions: &[String]) -> bool {
     is_text_extension(&ext)
 }
 
-/// Collect candidate files, applying the same ignore rules as the file tree.
+/// Collect candidate files, applying the workspace's ignore rules.
 fn collect_candidates(root: &Path, extensions: &[String]) -> Vec<PathBuf> {
-    let mut out = Vec::new();
-    let mut stack = vec![(root.to_path_buf(), 0usize)];
-
-    while let Some((dir, depth)) = stack.pop() {
-        if depth > MAX_DEPTH {
-            continue;
+    let fs = LocalWorkingTreeFs;
+    let rules = IgnoreRules::load(&fs, root);
+    let files = match walk_files(&fs, root, &rules, Unreadable::Skip) {
+        Ok(files) => files,
+        Err(err) => {
+            warn!("search: cannot walk root={root:?} err=\"{err}\"");
+            return Vec::new();
         }
-        let Ok(entries) = std::fs::read_dir(&dir) else {
-            continue;
-        };
-
-        for entry in entries.flatten() {
-            let path = entry.path();
-            let Ok(file_type) = entry.file_type() else {
-                continue;
-            };
-            // Never follow symlinks: a link back up the tree turns the walk
-            // into a loop the depth cap only papers over.
-            if file_type.is_symlink() {
-                continue;
-            }
-
-            let name = entry.file_name().to_string_lossy().to_string();
-            if file_type.is_dir() {
-                if name.starts_with('.') || IGNORED_DIRS.contains(&name.as_str()) {
-                    continue;
-                }
-                stack.push((path, depth + 1));
-                continue;
-            }
-
-            if !is_searchable(&path, extensions) {
-                continue;
-            }
-            if entry.metadata().map(|m| m.len()).unwrap_or(0) > MAX_FILE_BYTES {
-                continue;
-            }
-            out.push(path);
-        }
-    }
+    };
 
-    // Stable order, so the same search twice lists hits the same way.
-    out.sort();
-    out
+    // `walk_files` sorts, so the same search twice lists hits the same way.
+    files
+        .into_iter()
+        .map(|file| file.path)
+        .filter(|path| is_searchable(path, extensions))
+        .filter(|path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0) <= MAX_FILE_BYTES)
+        .collect()
 }
 
 fn rel_path(root: &Path, path: &Path) -> String {
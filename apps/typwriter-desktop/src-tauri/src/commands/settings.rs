@@ -17,9 +17,12 @@ use typst::text::FontFlags;
 use typstyle_core::Config as TypstyleConfig;
 
 use crate::commands::format::{formatter_config_from_settings, FormatterConfig};
+use crate::compiler::{IsolationConfig, PreviewPipeline};
 use crate::grammar::engine::GrammarConfig;
 use crate::vcs::SnapshotPolicy;
-use crate::world::EditorWorld;
+use crate::workspace::image_import::{ConvertTarget, ImageImportConfig};
+use crate::workspace::trash::TrashPolicy;
+use crate::world::{EditorWorld, PackageRegistry};
 
 const STORE_FILE: &str = "app_data.json";
 const KEY_FONT_DIRECTORIES: &str = "settings.font_directories";
@@ -45,6 +48,10 @@ const KEY_EXPORT_PRESETS: &str = "settings.export_presets";
 /// for staying out of `AppSettings`: these are edited from the settings window
 /// *and* replaceable from the editor, so they need an independent write path.
 const KEY_SNIPPETS: &str = "settings.snippets";
+/// Custom package registries (namespace → mirror directory or URL). Outside
+/// `AppSettings` because changing them has a side effect — the package index
+/// is rebuilt — that a round-trip of the whole struct shouldn't trigger.
+const KEY_PACKAGE_REGISTRIES: &str = "settings.package_registries";
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
@@ -104,6 +111,35 @@ pub struct AppSettings {
     /// Maximum age, in days, for *auto* snapshots. `0` = unlimited.
     pub snapshot_retention_max_days: u32,
 
+    // Compile isolation. See `compiler::isolation`.
+    /// Run every preview compile in a child process that is killed when it
+    /// overruns the limits below.
+    pub compile_isolation: bool,
+    /// Wall-clock limit for one compile.
+    pub compile_timeout_seconds: u32,
+    /// Resident memory limit for the compile process (Linux, macOS and
+    /// Windows; ignored elsewhere).
+    pub compile_memory_limit_mb: u32,
+
+    // Image import. See `workspace::image_import`.
+    /// Scale down, turn upright, strip GPS from and convert images copied or
+    /// dropped into the workspace, and skip ones already there.
+    pub image_import_processing: bool,
+    /// Longest side, in pixels, an imported image is scaled down to. `0`
+    /// keeps the original size.
+    pub image_import_max_dimension: u32,
+    /// What images typst can't decode are converted to.
+    pub image_import_convert_to: ConvertTarget,
+    /// JPEG quality for images re-encoded as JPEG.
+    pub image_import_jpeg_quality: u8,
+
+    // Trash. See `workspace::trash`.
+    /// Days a deleted file stays in the trash. `0` = unlimited.
+    pub trash_max_age_days: u32,
+    /// Size the trash is kept under by purging the oldest deletions first.
+    /// `0` = unlimited.
+    pub trash_max_size_mb: u32,
+
     /// Keyboard shortcut overrides, keyed by frontend command id (e.g.
     /// `editor.save` → `["Mod-s"]`). Rust only persists them; the command
     /// catalog and the chord notation live in the frontend
@@ -152,6 +188,18 @@ impl Default for AppSettings {
             snapshot_retention_max_count: 0,
             snapshot_retention_max_days: 0,
 
+            compile_isolation: false,
+            compile_timeout_seconds: 30,
+            compile_memory_limit_mb: 4096,
+
+            image_import_processing: false,
+            image_import_max_dimension: 2400,
+            image_import_convert_to: ConvertTarget::Png,
+            image_import_jpeg_quality: 85,
+
+            trash_max_age_days: 30,
+            trash_max_size_mb: 1024,
+
             keybindings: HashMap::new(),
         }
     }
@@ -276,6 +324,57 @@ pub fn set_user_snippets(handle: AppHandle, snippets: JsonValue) -> Result<(), S
     Ok(())
 }
 
+/// Load the custom package registries on startup. Entries that no longer
+/// validate (a mirror directory that went away) are kept, so the setting
+/// survives an unplugged drive; resolving from them simply fails.
+pub fn load_package_registries(handle: &AppHandle) -> Vec<PackageRegistry> {
+    let Ok(store) = handle.store(STORE_FILE) else {
+        warn!("settings: could not open {STORE_FILE}");
+        return Vec::new();
+    };
+    store
+        .get(KEY_PACKAGE_REGISTRIES)
+        .and_then(|v: JsonValue| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+#[tauri::command(async)]
+pub fn get_package_registries(handle: AppHandle) -> Vec<PackageRegistry> {
+    load_package_registries(&handle)
+}
+
+/// Validate, persist and apply the custom package registries. At most one
+/// registry per namespace.
+#[tauri::command(async)]
+pub fn set_package_registries(
+    handle: AppHandle,
+    world: State<'_, Arc<EditorWorld>>,
+    registries: Vec<PackageRegistry>,
+) -> Result<(), String> {
+    info!("set_package_registries: {} registries", registries.len());
+    for (i, registry) in registries.iter().enumerate() {
+        registry.validate()?;
+        if registries[..i]
+            .iter()
+            .any(|r| r.namespace == registry.namespace)
+        {
+            return Err(format!(
+                "more than one registry for @{}",
+                registry.namespace
+            ));
+        }
+    }
+    let store = handle
+        .store(STORE_FILE)
+        .map_err(|err| format!("could not open {STORE_FILE}: {err}"))?;
+    store.set(KEY_PACKAGE_REGISTRIES, json!(registries));
+    store
+        .save()
+        .map_err(|err| format!("failed to save package registries: {err}"))?;
+    world.set_package_registries(registries);
+    Ok(())
+}
+
 /// Load font directories from disk on startup.
 pub fn load_font_directories(handle: &AppHandle) -> Vec<PathBuf> {
     read_settings(handle)
@@ -327,6 +426,11 @@ pub fn set_app_settings(handle: AppHandle, settings: AppSettings) {
     if let Some(config) = handle.try_state::<FormatterConfig>() {
         *config.write() = formatter_config_from_settings(&settings);
     }
+    if let Some(pipeline) = handle.try_state::<Arc<PreviewPipeline>>() {
+        pipeline
+            .isolation
+            .configure(IsolationConfig::from_settings(&settings));
+    }
 }
 
 /// Build the in-memory snapshot policy from the persisted settings.
@@ -341,6 +445,24 @@ pub fn formatter_config_from_handle(handle: &AppHandle) -> TypstyleConfig {
     formatter_config_from_settings(&read_settings(handle))
 }
 
+/// Build the compile isolation limits from the persisted settings. Applied to
+/// the pipeline at startup; `set_app_settings` reapplies them thereafter.
+pub fn isolation_config_from_handle(handle: &AppHandle) -> IsolationConfig {
+    IsolationConfig::from_settings(&read_settings(handle))
+}
+
+/// Build the image import options from the persisted settings. Read at each
+/// import, so there is no in-memory copy to refresh.
+pub fn image_import_config_from_handle(handle: &AppHandle) -> ImageImportConfig {
+    ImageImportConfig::from_settings(&read_settings(handle))
+}
+
+/// Build the trash purge limits from the persisted settings. Read at each
+/// purge, so there is no in-memory copy to refresh.
+pub fn trash_policy_from_handle(handle: &AppHandle) -> TrashPolicy {
+    TrashPolicy::from_settings(&read_settings(handle))
+}
+
 #[tauri::command(async)]
 pub fn set_typst_font_directories(
     handle: AppHandle,
@@ -369,8 +491,9 @@ pub fn set_typst_font_directories(
     let world = world.inner().clone();
     let handle_clone = handle.clone();
     std::thread::spawn(move || {
-        let dirs: Vec<PathBuf> = clean.into_iter().map(PathBuf::from).collect();
-        world.reload_fonts_with(dirs);
+        // Re-reads the directories just written. The open workspace's own
+        // fonts stay layered in front of the new set.
+        world.reload_fonts();
         if let Err(err) = handle_clone.emit("app:fonts-loaded", ()) {
             error!("set_typst_font_directories: emit failed: {err}");
         }
@@ -0,0 +1,58 @@
+// Tauri commands for the project templates gallery. Creating a workspace from
+// a template goes through `create_workspace`; these list, save and delete.
+
+use std::{sync::Arc, time::Instant};
+
+use log::{error, info};
+use tauri::{AppHandle, State};
+
+use crate::workspace::{
+    templates::{self, SaveTemplateRequest, TemplateSummary},
+    WorkspaceState,
+};
+
+#[tauri::command(async)]
+pub fn list_templates(app: AppHandle) -> Result<Vec<TemplateSummary>, String> {
+    let t = Instant::now();
+    let dir = templates::user_templates_dir(&app)?;
+    let list = templates::list_templates(&dir);
+    info!(
+        "list_templates: {} template(s) ({:.1}ms)",
+        list.len(),
+        t.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(list)
+}
+
+#[tauri::command(async)]
+pub fn save_workspace_as_template(
+    request: SaveTemplateRequest,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<TemplateSummary, String> {
+    let t = Instant::now();
+    info!("save_workspace_as_template: name={:?}", request.name);
+    let result = workspace.save_as_template(request);
+    match &result {
+        Ok(summary) => info!(
+            "save_workspace_as_template: ok id={:?} ({:.1}ms)",
+            summary.id,
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "save_workspace_as_template: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+#[tauri::command(async)]
+pub fn delete_template(id: String, app: AppHandle) -> Result<(), String> {
+    info!("delete_template: id={id:?}");
+    let dir = templates::user_templates_dir(&app)?;
+    let result = templates::delete_template(&dir, &id);
+    if let Err(e) = &result {
+        error!("delete_template: err=\"{e}\"");
+    }
+    result
+}
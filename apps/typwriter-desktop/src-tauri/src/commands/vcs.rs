@@ -1,5 +1,6 @@
 // Tauri commands for the version-history pane: create restore points, list
-// history, diff commits, and restore the workspace or a single file.
+// history, diff commits, restore the workspace or a single file, and move the
+// timeline to and from git.
 
 use std::{sync::Arc, time::Instant};
 
@@ -7,6 +8,7 @@ use log::{error, info};
 use tauri::State;
 
 use crate::compiler::{PageDiffEngine, PageDiffSide};
+use crate::git::GitExport;
 use crate::vcs::{RestorePoint, VcsState, WorkspaceDiff};
 
 #[tauri::command(async)]
@@ -124,6 +126,48 @@ pub fn vcs_restore_file(
     result
 }
 
+/// Commit the restore points to a git branch — `branch`, or a default picked
+/// by [`crate::git::export_history`].
+#[tauri::command(async)]
+pub fn vcs_export_to_git(
+    branch: Option<String>,
+    vcs: State<'_, Arc<VcsState>>,
+) -> Result<GitExport, String> {
+    let t = Instant::now();
+    info!("vcs_export_to_git: branch={branch:?}");
+    let result = vcs.export_to_git(branch.as_deref());
+    match &result {
+        Ok(export) => info!(
+            "vcs_export_to_git: ok — {} commit(s) on {} ({:.1}ms)",
+            export.commits,
+            export.branch,
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!("vcs_export_to_git: err=\"{e}\""),
+    }
+    result
+}
+
+/// Seed restore points from the workspace's git log, at most `limit` of the
+/// newest commits. Returns how many restore points were added.
+#[tauri::command(async)]
+pub fn vcs_import_from_git(
+    limit: Option<usize>,
+    vcs: State<'_, Arc<VcsState>>,
+) -> Result<usize, String> {
+    let t = Instant::now();
+    info!("vcs_import_from_git: limit={limit:?}");
+    let result = vcs.import_from_git(limit);
+    match &result {
+        Ok(count) => info!(
+            "vcs_import_from_git: ok — {count} restore point(s) ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!("vcs_import_from_git: err=\"{e}\""),
+    }
+    result
+}
+
 // ─── Page-level diff ────────────────────────────────────────────────────────
 //
 // Unlike the file diffs above, this one has to *compile* the restore point
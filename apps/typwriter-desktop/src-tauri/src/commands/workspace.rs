@@ -1,10 +1,26 @@
-use std::{collections::HashMap, fs, path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 
 use log::{error, info};
 use serde::Deserialize;
-use tauri::State;
-
-use crate::workspace::{DroppedFile, FileTreeEntry, RecentWorkspaceEntry, WorkspaceState};
+use tauri::{AppHandle, State};
+use typst::syntax::package::PackageSpec;
+
+use crate::workspace::{
+    encoding::TextFormat,
+    package_templates,
+    recovery::RecoveredBuffer,
+    templates::{self, TemplateChoice},
+    trash::TrashEntry,
+    unused::UnusedFilesReport,
+    DroppedFile, FileTreeEntry, RecentWorkspaceEntry, WorkspaceState,
+};
+use crate::world::EditorWorld;
 
 #[tauri::command(async)]
 pub fn open_folder(
@@ -196,6 +212,230 @@ pub fn move_file(
     result
 }
 
+/// Report the assets no compile read and the `.typ` files no main file
+/// reaches, with their sizes.
+#[tauri::command(async)]
+pub fn find_unused_files(
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<UnusedFilesReport, String> {
+    let t = Instant::now();
+    info!("find_unused_files");
+    let result = workspace.unused_files();
+    match &result {
+        Ok(report) => info!(
+            "find_unused_files: ok - {} asset(s), {} orphan(s), {} bytes ({:.1}ms)",
+            report.assets.len(),
+            report.orphans.len(),
+            report.total_bytes,
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "find_unused_files: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Move the given files into the workspace's archive folder. Returns where
+/// each one went.
+#[tauri::command(async)]
+pub fn archive_files(
+    paths: Vec<String>,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<Vec<String>, String> {
+    let t = Instant::now();
+    info!("archive_files: count={}", paths.len());
+    let result = workspace.archive_files(&paths);
+    match &result {
+        Ok(moved) => info!(
+            "archive_files: ok - {} file(s) ({:.1}ms)",
+            moved.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "archive_files: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Rewrite a text file as UTF-8 with `\n` line breaks. Returns the encoding
+/// and line ending it had.
+#[tauri::command(async)]
+pub fn convert_to_utf8_lf(
+    path: String,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<TextFormat, String> {
+    let t = Instant::now();
+    info!("convert_to_utf8_lf: path={path:?}");
+    let result = workspace.convert_to_utf8_lf(&path);
+    match &result {
+        Ok(format) => info!(
+            "convert_to_utf8_lf: ok from={format:?} ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "convert_to_utf8_lf: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Unsaved buffers a crash left in the recovery journal that differ from
+/// disk. Also sent on `workspace:recovery-available` when a folder opens.
+#[tauri::command(async)]
+pub fn list_recovered_buffers(
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<Vec<RecoveredBuffer>, String> {
+    let t = Instant::now();
+    info!("list_recovered_buffers");
+    let result = workspace.recovered_buffers();
+    match &result {
+        Ok(buffers) => info!(
+            "list_recovered_buffers: ok - {} buffer(s) ({:.1}ms)",
+            buffers.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "list_recovered_buffers: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// The recovered text of `path`, to open as an unsaved buffer.
+#[tauri::command(async)]
+pub fn restore_recovered_buffer(
+    path: String,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<String, String> {
+    let t = Instant::now();
+    info!("restore_recovered_buffer: path={path:?}");
+    let result = workspace.restore_recovered_buffer(&path);
+    match &result {
+        Ok(text) => info!(
+            "restore_recovered_buffer: ok - {} bytes ({:.1}ms)",
+            text.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "restore_recovered_buffer: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Throw away the recovered buffer of `path`.
+#[tauri::command(async)]
+pub fn discard_recovered_buffer(
+    path: String,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<(), String> {
+    let t = Instant::now();
+    info!("discard_recovered_buffer: path={path:?}");
+    let result = workspace.discard_recovered_buffer(&path);
+    match &result {
+        Ok(()) => info!(
+            "discard_recovered_buffer: ok ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "discard_recovered_buffer: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Everything in the workspace's trash, most recently deleted first.
+#[tauri::command(async)]
+pub fn list_trash(workspace: State<'_, Arc<WorkspaceState>>) -> Result<Vec<TrashEntry>, String> {
+    let t = Instant::now();
+    info!("list_trash");
+    let result = workspace.list_trash();
+    match &result {
+        Ok(entries) => info!(
+            "list_trash: ok - {} item(s) ({:.1}ms)",
+            entries.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "list_trash: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Put a trashed file or folder back where it was deleted from. Returns the
+/// workspace-relative path it was restored to.
+#[tauri::command(async)]
+pub fn restore_from_trash(
+    id: String,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<String, String> {
+    let t = Instant::now();
+    info!("restore_from_trash: id={id:?}");
+    let result = workspace.restore_from_trash(&id);
+    match &result {
+        Ok(path) => info!(
+            "restore_from_trash: ok -> {path:?} ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "restore_from_trash: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Permanently delete one item from the trash.
+#[tauri::command(async)]
+pub fn delete_from_trash(
+    id: String,
+    workspace: State<'_, Arc<WorkspaceState>>,
+) -> Result<(), String> {
+    let t = Instant::now();
+    info!("delete_from_trash: id={id:?}");
+    let result = workspace.delete_from_trash(&id);
+    match &result {
+        Ok(()) => info!(
+            "delete_from_trash: ok ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "delete_from_trash: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Permanently delete everything in the trash. Returns how many items went.
+#[tauri::command(async)]
+pub fn empty_trash(workspace: State<'_, Arc<WorkspaceState>>) -> Result<usize, String> {
+    let t = Instant::now();
+    info!("empty_trash");
+    let result = workspace.empty_trash();
+    match &result {
+        Ok(count) => info!(
+            "empty_trash: ok - {count} item(s) ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "empty_trash: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
 #[tauri::command(async)]
 pub fn move_folder(
     src: String,
@@ -367,20 +607,101 @@ pub fn get_workspace_tabs(
 
 /// Create a new workspace folder at `parent_path/name`, initialise a
 /// `.typwriter/` metadata directory inside it, and return the absolute path to
-/// the new workspace root.
+/// the new workspace root. With a `template`, the folder is filled from the
+/// templates gallery and the template's main file is preselected.
+#[tauri::command(async)]
+pub fn create_workspace(
+    parent_path: String,
+    name: String,
+    template: Option<TemplateChoice>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let t = Instant::now();
+    info!(
+        "create_workspace: parent={parent_path:?} name={name:?} template={:?}",
+        template.as_ref().map(|choice| &choice.id)
+    );
+    let fill = template.as_ref().map(|choice| {
+        move |root: &Path| templates::create_from_template(&app, root, choice).map(drop)
+    });
+    let result = new_workspace(&parent_path, &name, fill);
+    match &result {
+        Ok(path) => info!(
+            "create_workspace: ok path={path:?} ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "create_workspace: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
+
+/// Like `create_workspace`, but starts from the template of a Typst Universe
+/// package (`@preview/name:version`), as `typst init` would. The package is
+/// downloaded first if it is not already cached.
 #[tauri::command(async)]
-pub fn create_workspace(parent_path: String, name: String) -> Result<String, String> {
+pub fn create_workspace_from_package(
+    parent_path: String,
+    name: String,
+    package: String,
+    app: AppHandle,
+    world: State<'_, Arc<EditorWorld>>,
+) -> Result<String, String> {
     let t = Instant::now();
-    info!("create_workspace: parent={parent_path:?} name={name:?}");
+    info!(
+        "create_workspace_from_package: parent={parent_path:?} name={name:?} package={package:?}"
+    );
+    let result = package
+        .parse::<PackageSpec>()
+        .map_err(|e| format!("Invalid package {package:?}: {e}"))
+        .and_then(|spec| {
+            let package_dir = world.obtain_package(&spec)?;
+            let fill = |root: &Path| {
+                package_templates::create_from_package(&app, root, &package_dir, &spec).map(drop)
+            };
+            new_workspace(&parent_path, &name, Some(fill))
+        });
+    match &result {
+        Ok(path) => info!(
+            "create_workspace_from_package: ok path={path:?} ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+        Err(e) => error!(
+            "create_workspace_from_package: err=\"{e}\" ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        ),
+    }
+    result
+}
 
+/// Create the workspace folder and its metadata, then let `fill` write the
+/// project files. A folder created here is removed again if `fill` fails.
+fn new_workspace(
+    parent_path: &str,
+    name: &str,
+    fill: Option<impl FnOnce(&Path) -> Result<(), String>>,
+) -> Result<String, String> {
     let name = name.trim().to_string();
     if name.is_empty() {
         return Err("Workspace name must not be empty".into());
     }
 
-    let workspace_path = PathBuf::from(&parent_path).join(&name);
+    let workspace_path = PathBuf::from(parent_path).join(&name);
     let meta_path = workspace_path.join(".typwriter");
 
+    // A template writes files; never over an existing project.
+    let existed = workspace_path.exists();
+    if fill.is_some()
+        && fs::read_dir(&workspace_path).is_ok_and(|mut entries| entries.next().is_some())
+    {
+        return Err(format!(
+            "{} already exists and is not empty",
+            workspace_path.display()
+        ));
+    }
+
     fs::create_dir_all(&workspace_path)
         .map_err(|e| format!("Failed to create workspace folder: {e}"))?;
     fs::create_dir_all(&meta_path)
@@ -396,12 +717,16 @@ pub fn create_workspace(parent_path: String, name: String) -> Result<String, Str
     fs::write(&meta_file, meta_json.to_string())
         .map_err(|e| format!("Failed to write workspace.json: {e}"))?;
 
-    let path_str = workspace_path.to_string_lossy().into_owned();
-    info!(
-        "create_workspace: ok path={path_str:?} ({:.1}ms)",
-        t.elapsed().as_secs_f64() * 1000.0
-    );
-    Ok(path_str)
+    if let Some(fill) = fill {
+        if let Err(e) = fill(&workspace_path) {
+            if !existed {
+                let _ = fs::remove_dir_all(&workspace_path);
+            }
+            return Err(e);
+        }
+    }
+
+    Ok(workspace_path.to_string_lossy().into_owned())
 }
 
 // ─── Project snippets ────────────────────────────────────────────────────────
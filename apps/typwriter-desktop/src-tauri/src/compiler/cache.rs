@@ -55,6 +55,14 @@ impl PageCache {
         self.0.peek(&key)
     }
 
+    /// Fingerprints of the pages held at `zoom`, without touching LRU ordering.
+    pub fn fingerprints_at(&self, zoom: ZoomBucket) -> impl Iterator<Item = PageFingerprint> + '_ {
+        self.0
+            .iter()
+            .filter(move |((_, bucket), _)| *bucket == zoom)
+            .map(|((fp, _), _)| *fp)
+    }
+
     /// Store PNG bytes in the cache.
     pub fn insert(&mut self, key: PageCacheKey, png: Vec<u8>) {
         self.0.put(key, png);
@@ -2,27 +2,72 @@
 // JSON-serialisable forms we can send over Tauri IPC.
 
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use log::warn;
 use parking_lot::Mutex;
 use rayon::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use typst::{
     diag::{FileResult, Severity, SourceDiagnostic},
     foundations::{Bytes, Datetime},
-    syntax::{FileId, Source, VirtualRoot},
+    syntax::{DiagSpan, FileId, Source, Span, VirtualRoot},
     text::{Font, FontBook},
     utils::LazyHash,
     Library, World, WorldExt,
 };
 use typst_layout::PagedDocument;
 
-use crate::world::EditorWorld;
+use super::quickfix::{QuickFix, QuickFixer, WorkspaceListing};
+use crate::vcs::fs::LocalWorkingTreeFs;
+use crate::workspace::ignore::{walk_files, IgnoreRules, Unreadable};
+use crate::world::{local_file_id, EditorWorld, HeadlessWorld};
+
+// ─── Worlds diagnostics are collected from ──────────────────────────────────
+
+/// What collecting and serialising diagnostics needs beyond `World` itself.
+/// Implemented by the live [`EditorWorld`] and by the [`HeadlessWorld`] the
+/// command-line diagnostics export compiles against.
+pub trait DiagnosticWorld: World + Sync {
+    /// Workspace root on disk.
+    fn workspace_root(&self) -> PathBuf;
+    /// The file the main compile enters at; the workspace pass skips it.
+    fn entry(&self) -> Option<FileId>;
+    /// On-disk location of a package file, so the editor can open it.
+    fn package_file_path(&self, id: FileId) -> Option<PathBuf>;
+}
+
+impl DiagnosticWorld for EditorWorld {
+    fn workspace_root(&self) -> PathBuf {
+        self.root()
+    }
+
+    fn entry(&self) -> Option<FileId> {
+        self.main_id()
+    }
+
+    fn package_file_path(&self, id: FileId) -> Option<PathBuf> {
+        self.id_to_path(id).ok()
+    }
+}
+
+impl DiagnosticWorld for HeadlessWorld<'_> {
+    fn workspace_root(&self) -> PathBuf {
+        self.root().to_path_buf()
+    }
+
+    fn entry(&self) -> Option<FileId> {
+        Some(self.main())
+    }
+
+    fn package_file_path(&self, id: FileId) -> Option<PathBuf> {
+        self.path_of(id).ok()
+    }
+}
 
 // ─── Serialisable diagnostic types ──────────────────────────────────────────
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DiagnosticRange {
     pub start_line: usize,
     pub start_col: usize,
@@ -30,7 +75,7 @@ pub struct DiagnosticRange {
     pub end_col: usize,
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SerializedDiagnostic {
     /// `"error"` or `"warning"`
     pub severity: String,
@@ -39,6 +84,26 @@ pub struct SerializedDiagnostic {
     /// Workspace-relative path, if the span resolves to a local file.
     pub file_path: Option<String>,
     pub range: Option<DiagnosticRange>,
+    /// The call chain typst recorded, innermost frame first ("error occurred
+    /// in this call of function `x`", "in this show rule"). For errors raised
+    /// deep inside a package or template the frames are often the only
+    /// locations in the user's own files.
+    pub trace: Vec<TraceEntry>,
+    /// Edits that would resolve the error, best first; see `quickfix`.
+    #[serde(default)]
+    pub fixes: Vec<QuickFix>,
+}
+
+/// One frame of a diagnostic's trace.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TraceEntry {
+    pub message: String,
+    /// Resolved like [`SerializedDiagnostic::file_path`]: workspace-relative
+    /// for project files, the on-disk path for package files.
+    pub file_path: Option<String>,
+    /// `@namespace/name:version` when the frame lies inside a package.
+    pub package: Option<String>,
+    pub range: Option<DiagnosticRange>,
 }
 
 // ─── Compile output ──────────────────────────────────────────────────────────
@@ -47,6 +112,9 @@ pub struct CompileOutput {
     pub document: Option<PagedDocument>,
     pub errors: Vec<SerializedDiagnostic>,
     pub warnings: Vec<SerializedDiagnostic>,
+    /// Every file the compile read through `World::source` or `World::file`,
+    /// assets included.
+    pub reads: HashSet<FileId>,
 }
 
 // ─── Top-level compile call ───────────────────────────────────────────────────
@@ -54,19 +122,33 @@ pub struct CompileOutput {
 /// Run a full typst compilation against the provided world and return a
 /// structured result with the optional document and serialisable diagnostics.
 pub fn compile_document(world: &EditorWorld) -> CompileOutput {
-    let result = typst::compile(world);
+    compile_in(world, world)
+}
+
+/// Compile `view` — a world entered somewhere other than the main file — and
+/// resolve its diagnostics against it. `world` supplies package paths.
+pub fn compile_in(world: &dyn DiagnosticWorld, view: &dyn World) -> CompileOutput {
+    let recording = MainOverride {
+        inner: view,
+        main_id: view.main(),
+        reads: Mutex::new(HashSet::new()),
+    };
+    let result = typst::compile(&recording);
     let raw_warnings = result.warnings;
+    let reads = recording.reads.into_inner();
 
     match result.output {
         Ok(doc) => CompileOutput {
             document: Some(doc),
             errors: vec![],
-            warnings: serialize_diags(world, &raw_warnings),
+            warnings: serialize_diags(world, view, &raw_warnings),
+            reads,
         },
         Err(errors) => CompileOutput {
             document: None,
-            errors: serialize_diags(world, &errors),
-            warnings: serialize_diags(world, &raw_warnings),
+            errors: serialize_diags(world, view, &errors),
+            warnings: serialize_diags(world, view, &raw_warnings),
+            reads,
         },
     }
 }
@@ -84,6 +166,9 @@ pub fn compile_document(world: &EditorWorld) -> CompileOutput {
 pub struct CachedFileDiags {
     /// `(file, content hash)` for the compiled file and everything it read.
     deps: Vec<(FileId, u128)>,
+    /// Everything the compile read, binary assets included — `deps` keeps
+    /// only what can be fingerprinted as source.
+    reads: Vec<FileId>,
     errors: Vec<SerializedDiagnostic>,
     warnings: Vec<SerializedDiagnostic>,
 }
@@ -105,13 +190,33 @@ impl CachedFileDiags {
 
 /// Content fingerprint of a file as the world currently sees it, or `None` if
 /// it can no longer be read.
-fn current_source_hash(world: &EditorWorld, id: FileId) -> Option<u128> {
+fn current_source_hash(world: &dyn World, id: FileId) -> Option<u128> {
     Some(typst::utils::hash128(world.source(id).ok()?.text()))
 }
 
 /// Per-file diagnostic cache, keyed by the file being compiled as an entry point.
 pub type WorkspaceDiagCache = HashMap<FileId, CachedFileDiags>;
 
+/// What the last compiles read — the ground truth for which workspace files
+/// the document uses.
+pub struct FileReads {
+    /// Read by the last full-document compile.
+    pub main: HashSet<FileId>,
+    /// Whether that compile failed, and so may have stopped short of reading
+    /// everything the document uses.
+    pub main_failed: bool,
+    /// Read by each file compiled as its own entry for workspace diagnostics.
+    pub entries: HashMap<FileId, Vec<FileId>>,
+}
+
+/// What each cached entry point read when it was last compiled.
+pub fn entry_reads(cache: &WorkspaceDiagCache) -> HashMap<FileId, Vec<FileId>> {
+    cache
+        .iter()
+        .map(|(id, entry)| (*id, entry.reads.clone()))
+        .collect()
+}
+
 /// Collect diagnostics from every `.typ` file in the workspace that is NOT the
 /// current main file. Each file is compiled as its own entry point via a thin
 /// `World` wrapper so the shared `EditorWorld` state is never mutated.
@@ -129,16 +234,16 @@ pub type WorkspaceDiagCache = HashMap<FileId, CachedFileDiags>;
 ///
 /// Output order is made deterministic by sorting on the walk order rather than
 /// on completion order, so the diagnostics pane doesn't reshuffle between runs.
-pub fn collect_workspace_diagnostics(
-    world: &EditorWorld,
+pub fn collect_workspace_diagnostics<W: DiagnosticWorld>(
+    world: &W,
     cache: &Mutex<WorkspaceDiagCache>,
 ) -> (Vec<SerializedDiagnostic>, Vec<SerializedDiagnostic>) {
-    let root = world.root();
-    let main_id = world.main_id();
+    let root = world.workspace_root();
+    let main_id = world.entry();
 
     let targets: Vec<FileId> = walk_typ_files(&root)
         .iter()
-        .filter_map(|path| world.path_to_id(path))
+        .filter_map(|path| local_file_id(path.strip_prefix(&root).ok()?))
         .filter(|id| Some(*id) != main_id)
         .collect();
 
@@ -156,11 +261,12 @@ pub fn collect_workspace_diagnostics(
             .collect()
     };
 
+    let files = WorkspaceListing::default();
     let computed: Vec<(FileId, CachedFileDiags)> = targets
         .par_iter()
         .zip(reused.par_iter())
         .filter(|(_, hit)| hit.is_none())
-        .map(|(&id, _)| (id, compile_one_for_diagnostics(world, id)))
+        .map(|(&id, _)| (id, compile_one_for_diagnostics(world, id, &files)))
         .collect();
 
     {
@@ -199,8 +305,13 @@ pub fn collect_workspace_diagnostics(
 }
 
 /// Compile one workspace file as its own entry point and record both its
-/// diagnostics and the set of files the compile read.
-fn compile_one_for_diagnostics(world: &EditorWorld, id: FileId) -> CachedFileDiags {
+/// diagnostics and the set of files the compile read. `files` is the
+/// workspace listing the whole pass shares for quick fixes.
+fn compile_one_for_diagnostics<W: DiagnosticWorld>(
+    world: &W,
+    id: FileId,
+    files: &WorkspaceListing,
+) -> CachedFileDiags {
     let override_world = MainOverride {
         inner: world,
         main_id: id,
@@ -208,23 +319,26 @@ fn compile_one_for_diagnostics(world: &EditorWorld, id: FileId) -> CachedFileDia
     };
     let result = typst::compile::<PagedDocument>(&override_world);
 
+    let fixer = QuickFixer::new(world, world, files);
     let warnings = result
         .warnings
         .iter()
-        .map(|diag| serialize_one(world, diag))
+        .map(|diag| serialize_one(world, world, &fixer, diag))
         .collect();
     let errors = match &result.output {
         Ok(_) => Vec::new(),
-        Err(errs) => errs.iter().map(|diag| serialize_one(world, diag)).collect(),
+        Err(errs) => errs
+            .iter()
+            .map(|diag| serialize_one(world, world, &fixer, diag))
+            .collect(),
     };
 
     // Fingerprint everything the compile touched. `reads` always contains `id`
     // itself, since compiling an entry point reads it.
-    let deps = override_world
-        .reads
-        .into_inner()
-        .into_iter()
-        .filter_map(|dep| {
+    let reads: Vec<FileId> = override_world.reads.into_inner().into_iter().collect();
+    let deps = reads
+        .iter()
+        .filter_map(|&dep| {
             let source = world.source(dep).ok()?;
             Some((dep, typst::utils::hash128(source.text())))
         })
@@ -232,6 +346,7 @@ fn compile_one_for_diagnostics(world: &EditorWorld, id: FileId) -> CachedFileDia
 
     CachedFileDiags {
         deps,
+        reads,
         errors,
         warnings,
     }
@@ -269,6 +384,8 @@ pub fn dedup_merge(
 /// [`collect_workspace_diagnostics`] fingerprints to decide whether a cached
 /// result is still valid — without it, a change to a shared `template.typ`
 /// would leave every importing chapter showing stale diagnostics.
+/// [`compile_in`] wraps its view with the view's own main just for the
+/// recording.
 struct MainOverride<'a> {
     inner: &'a dyn World,
     main_id: FileId,
@@ -291,8 +408,8 @@ impl World for MainOverride<'_> {
     }
     fn file(&self, id: FileId) -> FileResult<Bytes> {
         // Binary assets are recorded too: `world.source` can't fingerprint
-        // them, so they're filtered out when the dep list is built. Recording
-        // them here keeps the trait impl honest about what was touched.
+        // them, so they're filtered out of the dep list, but they are how
+        // unused assets are told apart from used ones.
         self.reads.lock().insert(id);
         self.inner.file(id)
     }
@@ -306,12 +423,26 @@ impl World for MainOverride<'_> {
 
 // ─── Helpers ─────────────────────────────────────────────────────────────────
 
-fn serialize_diags(world: &EditorWorld, diags: &[SourceDiagnostic]) -> Vec<SerializedDiagnostic> {
-    diags.iter().map(|d| serialize_one(world, d)).collect()
+fn serialize_diags(
+    world: &dyn DiagnosticWorld,
+    view: &dyn World,
+    diags: &[SourceDiagnostic],
+) -> Vec<SerializedDiagnostic> {
+    let files = WorkspaceListing::default();
+    let fixer = QuickFixer::new(world, view, &files);
+    diags
+        .iter()
+        .map(|d| serialize_one(world, view, &fixer, d))
+        .collect()
 }
 
-fn serialize_one(world: &EditorWorld, d: &SourceDiagnostic) -> SerializedDiagnostic {
-    let (file_path, range) = resolve_span(world, d);
+fn serialize_one(
+    world: &dyn DiagnosticWorld,
+    view: &dyn World,
+    fixer: &QuickFixer,
+    d: &SourceDiagnostic,
+) -> SerializedDiagnostic {
+    let (file_path, range) = resolve_span(world, view, d);
     SerializedDiagnostic {
         severity: match d.severity {
             Severity::Error => "error".into(),
@@ -322,6 +453,28 @@ fn serialize_one(world: &EditorWorld, d: &SourceDiagnostic) -> SerializedDiagnos
         hints: d.hints.iter().map(|h| h.v.to_string()).collect(),
         file_path,
         range,
+        trace: d
+            .trace
+            .iter()
+            .map(|point| {
+                let (file_path, range) = locate_span_in(world, view, point.span);
+                TraceEntry {
+                    message: point.v.to_string(),
+                    file_path,
+                    package: package_spec(point.span),
+                    range,
+                }
+            })
+            .collect(),
+        fixes: fixer.fixes(d),
+    }
+}
+
+/// The package a span points into, as `@namespace/name:version`.
+fn package_spec(span: Span) -> Option<String> {
+    match span.id()?.root() {
+        VirtualRoot::Package(spec) => Some(spec.to_string()),
+        _ => None,
     }
 }
 
@@ -331,29 +484,50 @@ fn serialize_one(world: &EditorWorld, d: &SourceDiagnostic) -> SerializedDiagnos
 /// downloaded package, the path is resolved to the absolute on-disk location
 /// in the package cache so the editor can open the source.
 fn resolve_span(
-    world: &EditorWorld,
+    world: &dyn DiagnosticWorld,
+    view: &dyn World,
     diag: &SourceDiagnostic,
 ) -> (Option<String>, Option<DiagnosticRange>) {
-    let id = match diag.span.id() {
+    locate_span_in(world, view, diag.span)
+}
+
+/// Resolve a span to the `(file_path, range)` pair diagnostics carry. Shared
+/// with the preflight checks, whose findings point at spans typst never
+/// turned into a `SourceDiagnostic`.
+pub(crate) fn locate_span(
+    world: &EditorWorld,
+    span: Span,
+) -> (Option<String>, Option<DiagnosticRange>) {
+    locate_span_in(world, world, span)
+}
+
+/// [`locate_span`] for a span produced by compiling `view`, whose sources may
+/// differ from `world`'s (the standalone preview's synthetic entry file).
+fn locate_span_in(
+    world: &dyn DiagnosticWorld,
+    view: &dyn World,
+    span: impl Into<DiagSpan>,
+) -> (Option<String>, Option<DiagnosticRange>) {
+    let span = span.into();
+    let id = match span.id() {
         Some(id) => id,
         None => return (None, None),
     };
 
-    let source = match world.source(id) {
+    let source = match view.source(id) {
         Ok(s) => s,
         Err(_) => return (None, None),
     };
 
     let file_path = if matches!(id.root(), VirtualRoot::Package(_)) {
         world
-            .id_to_path(id)
-            .ok()
+            .package_file_path(id)
             .and_then(|p| p.to_str().map(String::from))
     } else {
         Some(id.vpath().get_without_slash().to_string())
     };
 
-    let range = world.range(diag.span).and_then(|r| {
+    let range = view.range(span).and_then(|r| {
         let lines = source.lines();
         let (sl, sc) = lines.byte_to_line_column(r.start)?;
         let (el, ec) = lines.byte_to_line_column(r.end)?;
@@ -381,60 +555,23 @@ fn dedup_key(
     )
 }
 
-/// How deep the workspace walk will descend. A guard against pathological
-/// directory nesting; no real Typst project comes close.
-const MAX_WALK_DEPTH: usize = 32;
-
 /// Recursively walk a directory and yield all `.typ` file paths, in a stable
 /// (sorted) order so the diagnostics pane doesn't reshuffle between runs.
 fn walk_typ_files(root: &Path) -> Vec<std::path::PathBuf> {
-    let mut result = Vec::new();
-    walk_dir_recursive(root, 0, &mut result);
-    result.sort();
+    let mut result = walk_workspace_files(root);
+    result.retain(|path| path.extension().is_some_and(|ext| ext == "typ"));
     result
 }
 
-fn walk_dir_recursive(dir: &Path, depth: usize, out: &mut Vec<std::path::PathBuf>) {
-    if depth >= MAX_WALK_DEPTH {
-        warn!("walk_dir_recursive: depth limit reached at dir={dir:?}");
-        return;
-    }
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
+/// Every file under `root` the workspace's ignore rules let through, sorted.
+pub(crate) fn walk_workspace_files(root: &Path) -> Vec<std::path::PathBuf> {
+    let fs = LocalWorkingTreeFs;
+    let rules = IgnoreRules::load(&fs, root);
+    match walk_files(&fs, root, &rules, Unreadable::Skip) {
+        Ok(files) => files.into_iter().map(|file| file.path).collect(),
         Err(err) => {
-            warn!("walk_dir_recursive: failed to read dir={dir:?} err=\"{err}\"");
-            return;
-        }
-    };
-    for entry in entries {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(err) => {
-                warn!("walk_dir_recursive: skipped entry in dir={dir:?} err=\"{err}\"");
-                continue;
-            }
-        };
-        // `file_type` does not follow symlinks, unlike `Path::is_dir`. A
-        // symlinked directory that points at an ancestor would otherwise make
-        // this recurse until the depth cap.
-        let Ok(file_type) = entry.file_type() else {
-            continue;
-        };
-        if file_type.is_symlink() {
-            continue;
-        }
-        let path = entry.path();
-        if file_type.is_dir() {
-            // Skip hidden dirs (which covers `.typwriter` and `.git`) and the
-            // usual build-output directories.
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with('.') || name == "node_modules" || name == "target" {
-                    continue;
-                }
-            }
-            walk_dir_recursive(&path, depth + 1, out);
-        } else if path.extension().is_some_and(|ext| ext == "typ") {
-            out.push(path);
+            warn!("walk_workspace_files: cannot walk root={root:?} err=\"{err}\"");
+            Vec::new()
         }
     }
 }
@@ -464,6 +601,7 @@ mod tests {
     fn entry(deps: &[(FileId, u128)]) -> CachedFileDiags {
         CachedFileDiags {
             deps: deps.to_vec(),
+            reads: deps.iter().map(|&(id, _)| id).collect(),
             errors: Vec::new(),
             warnings: Vec::new(),
         }
@@ -598,6 +736,19 @@ mod tests {
         assert_eq!(tree.walked(), vec!["main.typ"]);
     }
 
+    #[test]
+    fn walk_honours_the_workspace_ignore_files() {
+        let tree = TempTree::new("ignorefiles");
+        tree.file("main.typ");
+        tree.file("generated/tables.typ");
+        tree.file("drafts/old.typ");
+        tree.file("drafts/keep.typ");
+        std::fs::write(tree.0.join(".gitignore"), "generated/\ndrafts/*\n").expect("write");
+        std::fs::write(tree.0.join(".typwriterignore"), "!drafts/keep.typ\n").expect("write");
+
+        assert_eq!(tree.walked(), vec!["drafts/keep.typ", "main.typ"]);
+    }
+
     #[test]
     fn walk_order_is_stable() {
         // The diagnostics pane renders in this order; an unstable walk would
@@ -622,6 +773,8 @@ mod tests {
             hints: vec!["did you mean `x`?".into()],
             file_path: Some("chapters/one.typ".into()),
             range: None,
+            trace: Vec::new(),
+            fixes: Vec::new(),
         };
         assert_eq!(super::dedup_key(&make()), super::dedup_key(&make()));
     }
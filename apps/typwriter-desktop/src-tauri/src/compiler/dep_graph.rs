@@ -0,0 +1,614 @@
+// File-level dependency graph of the workspace: who includes, imports or
+// loads what.
+//
+// Edges come from two places. The syntax tree of every `.typ` file gives the
+// dependencies written out as literal paths — `#include`, `#import`,
+// `image()`, `read()`, the data loaders and `bibliography()` — each with the
+// line it is on. A literal can't show a path computed at runtime, like
+// `image("fig/" + name)`; the compile-time access records can. A file an
+// entry point read that none of its literal edges reach becomes an `access`
+// edge from that entry, since a read doesn't say which file asked for it.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use typst::syntax::{ast, FileId, LinkedNode, Source, VirtualRoot};
+
+use super::compile::FileReads;
+use super::paths::resolve_relative;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    Include,
+    Import,
+    Image,
+    Read,
+    Json,
+    Csv,
+    Yaml,
+    Toml,
+    Xml,
+    Cbor,
+    Bibliography,
+    /// Read during a compile without a literal path leading there.
+    Access,
+}
+
+impl EdgeKind {
+    /// The kind for a call to the function `name` that takes a file path.
+    fn of_call(name: &str) -> Option<Self> {
+        Some(match name {
+            "image" => Self::Image,
+            "read" => Self::Read,
+            "json" => Self::Json,
+            "csv" => Self::Csv,
+            "yaml" => Self::Yaml,
+            "toml" => Self::Toml,
+            "xml" => Self::Xml,
+            "cbor" => Self::Cbor,
+            "bibliography" => Self::Bibliography,
+            _ => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Include => "include",
+            Self::Import => "import",
+            Self::Image => "image",
+            Self::Read => "read",
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Xml => "xml",
+            Self::Cbor => "cbor",
+            Self::Bibliography => "bibliography",
+            Self::Access => "access",
+        }
+    }
+
+    /// Edges along which a loop is a compile error.
+    fn is_module(self) -> bool {
+        matches!(self, Self::Include | Self::Import)
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    /// A `.typ` file.
+    Source,
+    /// Any other workspace file.
+    Asset,
+    /// An imported package, `@namespace/name:version`.
+    Package,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    /// Workspace-relative path, or the package spec for a package.
+    pub id: String,
+    pub kind: NodeKind,
+    /// Referenced, but not in the workspace.
+    pub missing: bool,
+    /// The current main file.
+    pub main: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// 0-based line of the reference in `from`; `None` for access edges.
+    pub line: Option<usize>,
+    /// Part of a loop of includes and imports.
+    pub in_cycle: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Each group of files that include or import one another in a loop.
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// Where a reference points.
+enum Target {
+    File(FileId),
+    Package(String),
+    /// A path that escapes the workspace, kept as written.
+    Outside(String),
+}
+
+/// Build the graph over the workspace's `.typ` `sources`. `exists` says
+/// whether a workspace file is on disk; `reads` are the access records, when
+/// a compile has produced them.
+pub fn build_graph(
+    sources: &[Source],
+    exists: impl Fn(FileId) -> bool,
+    reads: Option<&FileReads>,
+    main: Option<FileId>,
+) -> DependencyGraph {
+    let known: HashSet<FileId> = sources.iter().map(Source::id).collect();
+    let mut nodes: BTreeMap<String, GraphNode> = BTreeMap::new();
+    let mut edges: Vec<GraphEdge> = Vec::new();
+    let mut literal: HashMap<FileId, Vec<FileId>> = HashMap::new();
+
+    let add_file = |nodes: &mut BTreeMap<String, GraphNode>, id: FileId| -> String {
+        let path = rel(id);
+        nodes.entry(path.clone()).or_insert_with(|| GraphNode {
+            id: path.clone(),
+            kind: file_kind(id),
+            missing: !known.contains(&id) && !exists(id),
+            main: Some(id) == main,
+        });
+        path
+    };
+
+    for source in sources {
+        let from = add_file(&mut nodes, source.id());
+        let mut refs = Vec::new();
+        collect_refs(&LinkedNode::new(source.root()), source, &mut refs);
+        for (kind, target, line) in refs {
+            let to = match target {
+                Target::File(id) => {
+                    literal.entry(source.id()).or_default().push(id);
+                    add_file(&mut nodes, id)
+                }
+                Target::Package(spec) => {
+                    nodes.entry(spec.clone()).or_insert_with(|| GraphNode {
+                        id: spec.clone(),
+                        kind: NodeKind::Package,
+                        missing: false,
+                        main: false,
+                    });
+                    spec
+                }
+                Target::Outside(path) => {
+                    nodes.entry(path.clone()).or_insert_with(|| GraphNode {
+                        id: path.clone(),
+                        kind: NodeKind::Asset,
+                        missing: true,
+                        main: false,
+                    });
+                    path
+                }
+            };
+            edges.push(GraphEdge {
+                from: from.clone(),
+                to,
+                kind,
+                line: Some(line),
+                in_cycle: false,
+            });
+        }
+    }
+
+    if let Some(reads) = reads {
+        let entries = main
+            .map(|id| (id, reads.main.iter().copied().collect::<Vec<_>>()))
+            .into_iter()
+            .chain(reads.entries.iter().map(|(id, read)| (*id, read.clone())));
+        for (entry, read) in entries {
+            let reached = reachable(entry, &literal);
+            let mut unexplained: Vec<FileId> = read
+                .into_iter()
+                .filter(|id| matches!(id.root(), VirtualRoot::Project))
+                .filter(|id| !reached.contains(id))
+                .collect();
+            unexplained.sort_by_key(|id| rel(*id));
+            let from = add_file(&mut nodes, entry);
+            for id in unexplained {
+                let to = add_file(&mut nodes, id);
+                edges.push(GraphEdge {
+                    from: from.clone(),
+                    to,
+                    kind: EdgeKind::Access,
+                    line: None,
+                    in_cycle: false,
+                });
+            }
+        }
+    }
+
+    let cycles = module_cycles(&edges);
+    let cyclic: HashMap<&str, usize> = cycles
+        .iter()
+        .enumerate()
+        .flat_map(|(i, cycle)| cycle.iter().map(move |path| (path.as_str(), i)))
+        .collect();
+    for edge in &mut edges {
+        edge.in_cycle = edge.kind.is_module()
+            && cyclic
+                .get(edge.from.as_str())
+                .is_some_and(|group| cyclic.get(edge.to.as_str()) == Some(group));
+    }
+    edges.sort_by(|a, b| (&a.from, a.line, &a.to).cmp(&(&b.from, b.line, &b.to)));
+
+    DependencyGraph {
+        nodes: nodes.into_values().collect(),
+        edges,
+        cycles,
+    }
+}
+
+/// Every file-path reference in the tree under `node`, with its 0-based line.
+fn collect_refs(node: &LinkedNode, source: &Source, out: &mut Vec<(EdgeKind, Target, usize)>) {
+    let line = || {
+        source
+            .lines()
+            .byte_to_line_column(node.offset())
+            .map_or(0, |(line, _)| line)
+    };
+    if let Some(include) = node.cast::<ast::ModuleInclude>() {
+        if let ast::Expr::Str(path) = include.source() {
+            out.push((EdgeKind::Include, target(source.id(), &path.get()), line()));
+        }
+    } else if let Some(import) = node.cast::<ast::ModuleImport>() {
+        if let ast::Expr::Str(path) = import.source() {
+            out.push((EdgeKind::Import, target(source.id(), &path.get()), line()));
+        }
+    } else if let Some(call) = node.cast::<ast::FuncCall>() {
+        let kind = match call.callee() {
+            ast::Expr::Ident(ident) => EdgeKind::of_call(ident.as_str()),
+            _ => None,
+        };
+        // The path is the first positional argument; `bibliography` also
+        // takes an array of them.
+        let first = call.args().items().find_map(|arg| match arg {
+            ast::Arg::Pos(expr) => Some(expr),
+            _ => None,
+        });
+        if let (Some(kind), Some(first)) = (kind, first) {
+            let paths: Vec<ast::Str> = match first {
+                ast::Expr::Str(path) => vec![path],
+                ast::Expr::Array(list) if kind == EdgeKind::Bibliography => list
+                    .items()
+                    .filter_map(|item| match item {
+                        ast::ArrayItem::Pos(ast::Expr::Str(path)) => Some(path),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for path in paths {
+                out.push((kind, target(source.id(), &path.get()), line()));
+            }
+        }
+    }
+
+    for child in node.children() {
+        collect_refs(&child, source, out);
+    }
+}
+
+fn target(from: FileId, path: &str) -> Target {
+    if path.starts_with('@') {
+        return Target::Package(path.to_string());
+    }
+    match resolve_relative(from, path) {
+        Some(id) => Target::File(id),
+        None => Target::Outside(path.to_string()),
+    }
+}
+
+/// Files reachable from `entry` along literal edges, `entry` included.
+fn reachable(entry: FileId, literal: &HashMap<FileId, Vec<FileId>>) -> HashSet<FileId> {
+    let mut seen = HashSet::from([entry]);
+    let mut stack = vec![entry];
+    while let Some(id) = stack.pop() {
+        for &next in literal.get(&id).into_iter().flatten() {
+            if seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Strongly connected groups of the include/import edges that form a loop —
+/// more than one file, or a file that includes itself. Tarjan's algorithm.
+fn module_cycles(edges: &[GraphEdge]) -> Vec<Vec<String>> {
+    let mut adjacency: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for edge in edges.iter().filter(|edge| edge.kind.is_module()) {
+        adjacency
+            .entry(edge.from.as_str())
+            .or_default()
+            .push(edge.to.as_str());
+    }
+
+    struct Tarjan<'a> {
+        adjacency: &'a BTreeMap<&'a str, Vec<&'a str>>,
+        index: HashMap<&'a str, usize>,
+        low: HashMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: HashSet<&'a str>,
+        groups: Vec<Vec<String>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, node: &'a str) {
+            let index = self.index.len();
+            self.index.insert(node, index);
+            self.low.insert(node, index);
+            self.stack.push(node);
+            self.on_stack.insert(node);
+
+            let adjacency = self.adjacency;
+            for &next in adjacency.get(node).into_iter().flatten() {
+                if !self.index.contains_key(next) {
+                    self.visit(next);
+                    let low = self.low[node].min(self.low[next]);
+                    self.low.insert(node, low);
+                } else if self.on_stack.contains(next) {
+                    let low = self.low[node].min(self.index[next]);
+                    self.low.insert(node, low);
+                }
+            }
+
+            if self.low[node] == index {
+                let mut group = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(member);
+                    group.push(member.to_string());
+                    if member == node {
+                        break;
+                    }
+                }
+                let self_loop = adjacency.get(node).is_some_and(|next| next.contains(&node));
+                if group.len() > 1 || self_loop {
+                    group.sort();
+                    self.groups.push(group);
+                }
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        adjacency: &adjacency,
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        groups: Vec::new(),
+    };
+    for &node in adjacency.keys() {
+        if !tarjan.index.contains_key(node) {
+            tarjan.visit(node);
+        }
+    }
+    tarjan.groups.sort();
+    tarjan.groups
+}
+
+fn rel(id: FileId) -> String {
+    id.vpath().get_without_slash().to_string()
+}
+
+fn file_kind(id: FileId) -> NodeKind {
+    if rel(id).ends_with(".typ") {
+        NodeKind::Source
+    } else {
+        NodeKind::Asset
+    }
+}
+
+impl DependencyGraph {
+    /// The graph in Graphviz DOT. Missing targets are drawn dashed and red,
+    /// loops red, and access edges dotted.
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let mut attrs = Vec::new();
+            match node.kind {
+                NodeKind::Source => {}
+                NodeKind::Asset => attrs.push("shape=note"),
+                NodeKind::Package => attrs.push("shape=component"),
+            }
+            if node.main {
+                attrs.push("penwidth=2");
+            }
+            if node.missing {
+                attrs.push("style=dashed, color=red");
+            }
+            let _ = writeln!(dot, "    {}{};", quote(&node.id), bracket(&attrs));
+        }
+        for edge in &self.edges {
+            let label = format!("label={}", quote(edge.kind.as_str()));
+            let mut attrs = vec![label.as_str()];
+            if edge.kind == EdgeKind::Access {
+                attrs.push("style=dotted");
+            }
+            if edge.in_cycle {
+                attrs.push("color=red");
+            }
+            let _ = writeln!(
+                dot,
+                "    {} -> {}{};",
+                quote(&edge.from),
+                quote(&edge.to),
+                bracket(&attrs)
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn bracket(attrs: &[&str]) -> String {
+    if attrs.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", attrs.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::local_file_id;
+    use std::path::Path;
+
+    fn id(path: &str) -> FileId {
+        local_file_id(Path::new(path)).expect("valid virtual path")
+    }
+
+    fn sources(files: &[(&str, &str)]) -> Vec<Source> {
+        files
+            .iter()
+            .map(|(path, text)| Source::new(id(path), text.to_string()))
+            .collect()
+    }
+
+    fn edge_list(graph: &DependencyGraph) -> Vec<(&str, &str, EdgeKind)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str(), edge.kind))
+            .collect()
+    }
+
+    fn node<'a>(graph: &'a DependencyGraph, path: &str) -> &'a GraphNode {
+        graph
+            .nodes
+            .iter()
+            .find(|node| node.id == path)
+            .expect("node present")
+    }
+
+    #[test]
+    fn literal_references_become_edges() {
+        let files = sources(&[
+            (
+                "main.typ",
+                "#import \"tpl.typ\": conf\n#import \"@preview/cetz:0.3.0\"\n#include \"ch/one.typ\"\n#bibliography((\"refs.bib\", \"more.yml\"))\n",
+            ),
+            (
+                "ch/one.typ",
+                "#image(\"../fig/a.png\")\n#let data = csv(\"data.csv\")\n#json(\"/cfg.json\")\n",
+            ),
+            ("tpl.typ", "#let conf = read(\"missing.txt\")\n"),
+        ]);
+        let graph = build_graph(&files, |_| true, None, Some(id("main.typ")));
+
+        assert_eq!(
+            edge_list(&graph),
+            [
+                ("ch/one.typ", "fig/a.png", EdgeKind::Image),
+                ("ch/one.typ", "ch/data.csv", EdgeKind::Csv),
+                ("ch/one.typ", "cfg.json", EdgeKind::Json),
+                ("main.typ", "tpl.typ", EdgeKind::Import),
+                ("main.typ", "@preview/cetz:0.3.0", EdgeKind::Import),
+                ("main.typ", "ch/one.typ", EdgeKind::Include),
+                ("main.typ", "more.yml", EdgeKind::Bibliography),
+                ("main.typ", "refs.bib", EdgeKind::Bibliography),
+                ("tpl.typ", "missing.txt", EdgeKind::Read),
+            ]
+        );
+        assert_eq!(graph.edges[5].line, Some(2));
+        assert!(node(&graph, "main.typ").main);
+        assert_eq!(node(&graph, "@preview/cetz:0.3.0").kind, NodeKind::Package);
+        assert_eq!(node(&graph, "fig/a.png").kind, NodeKind::Asset);
+        assert!(graph.cycles.is_empty());
+    }
+
+    #[test]
+    fn missing_targets_are_flagged() {
+        let files = sources(&[(
+            "main.typ",
+            "#include \"gone.typ\"\n#image(\"../../out.png\")\n",
+        )]);
+        let graph = build_graph(&files, |_| false, None, None);
+        assert!(!node(&graph, "main.typ").missing);
+        assert!(node(&graph, "gone.typ").missing);
+        assert!(node(&graph, "../../out.png").missing);
+    }
+
+    #[test]
+    fn include_loops_are_reported_as_cycles() {
+        let files = sources(&[
+            ("a.typ", "#include \"b.typ\"\n"),
+            ("b.typ", "#import \"c.typ\"\n"),
+            ("c.typ", "#include \"a.typ\"\n#image(\"x.png\")\n"),
+            ("self.typ", "#include \"self.typ\"\n"),
+        ]);
+        let graph = build_graph(&files, |_| true, None, None);
+        assert_eq!(
+            graph.cycles,
+            [
+                vec!["a.typ".to_string(), "b.typ".into(), "c.typ".into()],
+                vec!["self.typ".to_string()],
+            ]
+        );
+        let in_cycle: Vec<_> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.in_cycle)
+            .map(|edge| edge.to.as_str())
+            .collect();
+        assert_eq!(in_cycle, ["b.typ", "c.typ", "a.typ", "self.typ"]);
+    }
+
+    #[test]
+    fn reads_without_a_literal_path_become_access_edges() {
+        let files = sources(&[
+            ("main.typ", "#include \"ch.typ\"\n"),
+            (
+                "ch.typ",
+                "#image(\"fig/\" + \"b.png\")\n#image(\"fig/a.png\")\n",
+            ),
+        ]);
+        let reads = FileReads {
+            main: ["main.typ", "ch.typ", "fig/a.png", "fig/b.png"]
+                .into_iter()
+                .map(id)
+                .collect(),
+            main_failed: false,
+            entries: HashMap::from([(
+                id("ch.typ"),
+                vec![id("ch.typ"), id("fig/a.png"), id("fig/b.png")],
+            )]),
+        };
+        let graph = build_graph(&files, |_| true, Some(&reads), Some(id("main.typ")));
+        let access: Vec<_> = edge_list(&graph)
+            .into_iter()
+            .filter(|(_, _, kind)| *kind == EdgeKind::Access)
+            .collect();
+        assert_eq!(
+            access,
+            [
+                ("ch.typ", "fig/b.png", EdgeKind::Access),
+                ("main.typ", "fig/b.png", EdgeKind::Access),
+            ]
+        );
+    }
+
+    #[test]
+    fn dot_output_marks_missing_nodes_and_cycles() {
+        let files = sources(&[
+            (
+                "a.typ",
+                "#include \"b.typ\"\n#image(\"gone \\\"x\\\".png\")\n",
+            ),
+            ("b.typ", "#include \"a.typ\"\n"),
+        ]);
+        let dot = build_graph(&files, |_| false, None, None).to_dot();
+        assert!(dot.starts_with("digraph dependencies {\n"));
+        assert!(dot.contains("\"a.typ\" -> \"b.typ\" [label=\"include\", color=red];"));
+        assert!(dot.contains("\"gone \\\"x\\\".png\" [shape=note, style=dashed, color=red];"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
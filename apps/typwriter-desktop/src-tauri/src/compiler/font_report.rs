@@ -0,0 +1,251 @@
+// Which font faces the compiled document actually uses.
+//
+// `list_font_families` reports what is installed; this reports what the last
+// compile rendered with, per document and per page — the input to a licence
+// audit and the first thing to look at when a PDF is bigger than expected.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+use typst::{
+    layout::{Frame, FrameItem},
+    text::{FontInstance, FontStyle},
+};
+use typst_layout::PagedDocument;
+
+use crate::world::{locate_in_dir, EditorWorld, FontOrigin};
+
+/// Rough fixed cost of a subsetted face in a PDF: the font program's
+/// required tables, the font dictionary, descriptor, widths array and the
+/// ToUnicode CMap.
+const SUBSET_OVERHEAD_BYTES: u64 = 4 * 1024;
+
+/// Licence strings from the `name` table are occasionally whole licence
+/// texts; the report only needs enough to recognise which licence it is.
+const MAX_LICENSE_CHARS: usize = 300;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFaceUsage {
+    pub family: String,
+    /// `normal`, `italic` or `oblique`.
+    pub style: String,
+    /// CSS-style weight, 100–900.
+    pub weight: u16,
+    /// `embedded`, `system`, `directory` or `workspace`.
+    pub origin: String,
+    /// Font file on disk, when it could be determined.
+    pub path: Option<String>,
+    /// OS/2 `fsType` embedding permission: `installable`, `restricted`,
+    /// `preview_and_print` or `editable`. `None` when the table is missing.
+    pub embedding: Option<String>,
+    /// Whether the font allows embedding only a subset of its glyphs.
+    pub subsetting_allowed: bool,
+    /// Licence description and URL from the `name` table.
+    pub license: Option<String>,
+    pub license_url: Option<String>,
+    /// 0-based indices of the pages this face appears on.
+    pub pages: Vec<usize>,
+    /// Distinct glyphs used — what the PDF subset will contain.
+    pub glyph_count: usize,
+    /// Rough size this face adds to an exported PDF.
+    pub estimated_subset_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageFontUsage {
+    /// 0-based page index.
+    pub page: usize,
+    /// Indices into [`FontUsageReport::faces`].
+    pub faces: Vec<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FontUsageReport {
+    pub faces: Vec<FontFaceUsage>,
+    pub pages: Vec<PageFontUsage>,
+    pub estimated_total_bytes: u64,
+}
+
+/// Per-face tallies gathered from the frames. `font` is the first instance of
+/// the face the walk met; variable-font instances of one face share a tally.
+struct FaceTally {
+    font: FontInstance,
+    pages: BTreeSet<usize>,
+    glyphs: HashSet<u16>,
+}
+
+/// Build the report for `doc`, resolving each face's origin against the
+/// fonts currently loaded into `world`.
+pub fn font_usage_report(world: &EditorWorld, doc: &PagedDocument) -> FontUsageReport {
+    let (tallies, pages) = tally(doc);
+
+    let faces: Vec<FontFaceUsage> = tallies
+        .into_iter()
+        .map(|tally| describe(world, tally))
+        .collect();
+    let estimated_total_bytes = faces.iter().map(|face| face.estimated_subset_bytes).sum();
+
+    FontUsageReport {
+        faces,
+        pages,
+        estimated_total_bytes,
+    }
+}
+
+/// Walk every page once, in order, so faces are listed in order of first use.
+fn tally(doc: &PagedDocument) -> (Vec<FaceTally>, Vec<PageFontUsage>) {
+    let mut tallies: Vec<FaceTally> = Vec::new();
+    // Keyed by the face's hash: `Font` caches that hash lazily, which makes
+    // the font itself an interior-mutable map key.
+    let mut index_of: HashMap<u128, usize> = HashMap::new();
+    let mut pages = Vec::with_capacity(doc.pages().len());
+
+    for (page, content) in doc.pages().iter().enumerate() {
+        let mut on_page = BTreeSet::new();
+        for_each_text(&content.frame, &mut |font, glyph_ids| {
+            let key = typst::utils::hash128(font.font());
+            let index = *index_of.entry(key).or_insert_with(|| {
+                tallies.push(FaceTally {
+                    font: font.clone(),
+                    pages: BTreeSet::new(),
+                    glyphs: HashSet::new(),
+                });
+                tallies.len() - 1
+            });
+            let tally = &mut tallies[index];
+            tally.pages.insert(page);
+            tally.glyphs.extend(glyph_ids);
+            on_page.insert(index);
+        });
+        pages.push(PageFontUsage {
+            page,
+            faces: on_page.into_iter().collect(),
+        });
+    }
+
+    (tallies, pages)
+}
+
+fn for_each_text(frame: &Frame, f: &mut dyn FnMut(&FontInstance, &mut dyn Iterator<Item = u16>)) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => for_each_text(&group.frame, f),
+            FrameItem::Text(text) => f(&text.font, &mut text.glyphs.iter().map(|g| g.id)),
+            _ => {}
+        }
+    }
+}
+
+fn describe(world: &EditorWorld, tally: FaceTally) -> FontFaceUsage {
+    let info = tally.font.font().info();
+    let ttf = tally.font.ttf();
+
+    let (origin, path) = match world.font_origin(info) {
+        Some(FontOrigin::Embedded) => ("embedded", None),
+        Some(FontOrigin::System) => ("system", None),
+        Some(FontOrigin::Directory(dir)) => (
+            "directory",
+            locate_in_dir(&dir, info).map(|p| p.to_string_lossy().into_owned()),
+        ),
+        Some(FontOrigin::Workspace(dir)) => (
+            "workspace",
+            locate_in_dir(&dir, info).map(|p| p.to_string_lossy().into_owned()),
+        ),
+        None => ("unknown", None),
+    };
+
+    let embedding = ttf.permissions().map(|permissions| {
+        match permissions {
+            ttf_parser::Permissions::Installable => "installable",
+            ttf_parser::Permissions::Restricted => "restricted",
+            ttf_parser::Permissions::PreviewAndPrint => "preview_and_print",
+            ttf_parser::Permissions::Editable => "editable",
+        }
+        .to_string()
+    });
+
+    let glyph_count = tally.glyphs.len();
+    FontFaceUsage {
+        family: info.family.clone(),
+        style: match info.variant.style {
+            FontStyle::Normal => "normal",
+            FontStyle::Italic => "italic",
+            FontStyle::Oblique => "oblique",
+        }
+        .to_string(),
+        weight: info.variant.weight.to_number(),
+        origin: origin.to_string(),
+        path,
+        embedding,
+        subsetting_allowed: ttf.is_subsetting_allowed(),
+        license: name_entry(ttf, ttf_parser::name_id::LICENSE),
+        license_url: name_entry(ttf, ttf_parser::name_id::LICENSE_URL),
+        pages: tally.pages.into_iter().collect(),
+        glyph_count,
+        estimated_subset_bytes: estimate_subset_bytes(
+            tally.font.font().data().len() as u64,
+            ttf.number_of_glyphs(),
+            glyph_count,
+        ),
+    }
+}
+
+/// First Unicode-decodable `name` table record with this id.
+fn name_entry(ttf: &ttf_parser::Face, id: u16) -> Option<String> {
+    ttf.names()
+        .into_iter()
+        .filter(|name| name.name_id == id)
+        .find_map(|name| name.to_string())
+        .map(|text| text.chars().take(MAX_LICENSE_CHARS).collect())
+}
+
+/// Estimate what a subset of `used_glyphs` out of `total_glyphs` costs.
+///
+/// Assumes glyphs are of average size, which overstates the cost of Latin
+/// text in a CJK-heavy font and understates it for ornate display faces —
+/// close enough to rank faces by cost, which is what the report is for.
+fn estimate_subset_bytes(font_bytes: u64, total_glyphs: u16, used_glyphs: usize) -> u64 {
+    if total_glyphs == 0 {
+        return SUBSET_OVERHEAD_BYTES;
+    }
+    let used = (used_glyphs as u64).min(u64::from(total_glyphs));
+    SUBSET_OVERHEAD_BYTES + font_bytes * used / u64::from(total_glyphs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subset_cost_scales_with_the_share_of_glyphs_used() {
+        let quarter = estimate_subset_bytes(400_000, 1000, 250);
+        assert_eq!(quarter, SUBSET_OVERHEAD_BYTES + 100_000);
+        assert!(estimate_subset_bytes(400_000, 1000, 500) > quarter);
+    }
+
+    #[test]
+    fn an_unused_face_costs_only_the_overhead() {
+        assert_eq!(
+            estimate_subset_bytes(400_000, 1000, 0),
+            SUBSET_OVERHEAD_BYTES
+        );
+    }
+
+    #[test]
+    fn glyph_counts_beyond_the_font_are_clamped() {
+        // Glyph ids come from the font, so this cannot happen in practice —
+        // but an estimate above the whole file would be plainly wrong.
+        assert_eq!(
+            estimate_subset_bytes(400_000, 1000, 5000),
+            SUBSET_OVERHEAD_BYTES + 400_000
+        );
+    }
+
+    #[test]
+    fn a_font_without_glyphs_does_not_divide_by_zero() {
+        assert_eq!(estimate_subset_bytes(1234, 0, 3), SUBSET_OVERHEAD_BYTES);
+    }
+}
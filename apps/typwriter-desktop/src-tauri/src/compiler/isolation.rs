@@ -0,0 +1,796 @@
+// Optional out-of-process compile.
+//
+// `typst::compile` cannot be interrupted. A document with runaway recursion
+// or a loop over a huge range pins the compile thread in `run_compile_worker`
+// for good, and the preview is dead until the app restarts. When isolation is
+// enabled, preview compiles run in a child process instead — this same
+// executable, started with `--compile-worker` — which the app can kill.
+//
+// The child *is* the compile. It compiles the same files (unsaved buffers are
+// sent along), renders the pages the app has no bytes for yet, and answers
+// with the diagnostics, the page fingerprints and those PNGs. The pipeline
+// feeds them through the usual page cache and emit path; nothing is compiled
+// in-process on the preview path. The child has no downloader: it lists the
+// packages it could not find locally, the app installs them and sends the
+// request again.
+//
+// The layout itself cannot cross the process boundary, so features that walk
+// it — click-to-source, exports, preflight — rebuild the document in-process
+// when they first need it. They only do so while the buffers and inputs are
+// still the ones the child finished, so that compile is known to terminate.
+//
+// Protocol: one JSON object per line on the child's stdin (a request) and
+// stdout (a response). The child keeps its font set and comemo caches across
+// requests, so after the first compile it is incremental like the main one.
+//
+// The memory cap is enforced by polling the child's resident set size
+// (`/proc` on Linux, `proc_pidinfo` on macOS, the working set on Windows).
+// On any other platform only the timeout applies.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
+    hash::{Hash, Hasher},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    time::{Duration, Instant},
+};
+
+use base64::Engine;
+use log::{error, info, warn};
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use typst::{syntax::VirtualRoot, utils::LazyHash, Library};
+use typst_kit::fonts::FontStore;
+
+use super::compile::{compile_in, CompileOutput, SerializedDiagnostic};
+use super::diff::{fingerprint_pages, PageFingerprint};
+use super::render::render_page;
+use crate::commands::settings::AppSettings;
+use crate::world::{
+    headless_fonts, headless_library, local_file_id, local_package_dirs, HeadlessWorld,
+};
+
+/// First argument that turns the executable into a compile worker.
+pub const WORKER_FLAG: &str = "--compile-worker";
+
+/// How often the supervisor wakes to check the clock and the child's memory
+/// while waiting for a response.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IsolationConfig {
+    pub enabled: bool,
+    pub timeout: Duration,
+    pub memory_limit_bytes: u64,
+}
+
+impl IsolationConfig {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            enabled: settings.compile_isolation,
+            // A zero limit would kill every compile; treat it as "at least a
+            // second" rather than as a way to switch the preview off.
+            timeout: Duration::from_secs(u64::from(settings.compile_timeout_seconds.max(1))),
+            memory_limit_bytes: u64::from(settings.compile_memory_limit_mb.max(64)) * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for IsolationConfig {
+    fn default() -> Self {
+        Self::from_settings(&AppSettings::default())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerRequest {
+    pub id: u64,
+    pub root: PathBuf,
+    /// Workspace-relative path of the main file.
+    pub main: String,
+    /// Unsaved editor buffers: workspace-relative path → content.
+    pub shadows: Vec<(String, String)>,
+    pub font_dirs: Vec<PathBuf>,
+    /// `sys.inputs` the workspace declares.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, String>,
+    /// Scale pages are rendered at, in pixels per point.
+    pub zoom: f32,
+    /// Pages the app already holds bytes for at `zoom`; the child reports
+    /// their fingerprints but does not render them again.
+    #[serde(default)]
+    pub cached: Vec<PageFingerprint>,
+}
+
+impl WorkerRequest {
+    /// Identifies what the request compiles — the workspace, entry point,
+    /// unsaved buffers and inputs — but not how it is rendered.
+    pub fn sources_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (&self.root, &self.main, &self.shadows, &self.inputs).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WorkerResponse {
+    id: u64,
+    errors: Vec<SerializedDiagnostic>,
+    warnings: Vec<SerializedDiagnostic>,
+    /// Workspace-relative paths of the project files the compile read.
+    reads: Vec<String>,
+    /// Packages (`@namespace/name:version`) the child has no local copy of.
+    #[serde(default)]
+    missing_packages: Vec<String>,
+    /// One entry per page, or `None` when the compile failed.
+    pages: Option<Vec<WorkerPage>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WorkerPage {
+    fingerprint: PageFingerprint,
+    /// Base64 PNG; absent for pages listed in the request's `cached`.
+    png: Option<String>,
+}
+
+/// What the child's compile produced, decoded from its response.
+#[derive(Debug)]
+pub struct WorkerOutput {
+    pub errors: Vec<SerializedDiagnostic>,
+    pub warnings: Vec<SerializedDiagnostic>,
+    pub reads: Vec<String>,
+    /// Packages the child could not find locally; the app fetches them and
+    /// compiles again.
+    pub missing_packages: Vec<String>,
+    /// Fingerprint and freshly rendered PNG of each page, or `None` when the
+    /// compile failed.
+    pub pages: Option<Vec<(PageFingerprint, Option<Vec<u8>>)>>,
+}
+
+impl WorkerOutput {
+    fn decode(response: WorkerResponse) -> Result<Self, String> {
+        let pages = match response.pages {
+            Some(pages) => Some(
+                pages
+                    .into_iter()
+                    .map(|page| {
+                        let png = page
+                            .png
+                            .map(|png| base64::engine::general_purpose::STANDARD.decode(png))
+                            .transpose()
+                            .map_err(|err| format!("page png: {err}"))?;
+                        Ok((page.fingerprint, png))
+                    })
+                    .collect::<Result<_, String>>()?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            errors: response.errors,
+            warnings: response.warnings,
+            reads: response.reads,
+            missing_packages: response.missing_packages,
+            pages,
+        })
+    }
+}
+
+/// Outcome of running a compile through the worker.
+#[derive(Debug)]
+pub enum Verdict {
+    /// The child finished the compile (successfully or with errors).
+    Finished(WorkerOutput),
+    TimedOut(Duration),
+    OverMemory {
+        used_bytes: u64,
+        limit_bytes: u64,
+    },
+    /// The child exited without answering.
+    Crashed,
+    /// The worker could not be started or talked to. The compile proceeds
+    /// in-process, unguarded, rather than leaving the preview empty.
+    Unavailable(String),
+}
+
+impl Verdict {
+    /// The diagnostic shown in place of the compile's own, for verdicts where
+    /// the child was stopped before it could produce one.
+    pub fn diagnostic(&self, main: Option<String>) -> Option<SerializedDiagnostic> {
+        let (message, hint) = match self {
+            Verdict::Finished(_) | Verdict::Unavailable(_) => return None,
+            Verdict::TimedOut(limit) => (
+                format!(
+                    "compile exceeded {} seconds and was stopped",
+                    limit.as_secs()
+                ),
+                "look for unbounded recursion or a loop over a very large range",
+            ),
+            Verdict::OverMemory {
+                used_bytes,
+                limit_bytes,
+            } => (
+                format!(
+                    "compile reached {} MB, over the {} MB memory limit, and was stopped",
+                    used_bytes / (1024 * 1024),
+                    limit_bytes / (1024 * 1024)
+                ),
+                "look for unbounded recursion or very large generated content",
+            ),
+            Verdict::Crashed => (
+                "compile worker exited unexpectedly".to_string(),
+                "the document may have exhausted the stack or system memory",
+            ),
+        };
+        Some(SerializedDiagnostic {
+            severity: "error".into(),
+            message,
+            hints: vec![
+                hint.to_string(),
+                "the preview shows the last document that compiled in time".to_string(),
+            ],
+            file_path: main,
+            range: None,
+            trace: Vec::new(),
+            fixes: Vec::new(),
+        })
+    }
+}
+
+/// A running worker process and the channel its stdout lines arrive on.
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    responses: Receiver<String>,
+}
+
+impl Worker {
+    fn spawn() -> Result<Self, String> {
+        let exe = std::env::current_exe().map_err(|err| format!("current_exe: {err}"))?;
+        let mut child = Command::new(exe)
+            .arg(WORKER_FLAG)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| format!("spawn: {err}"))?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("failed to capture worker stdio".to_string());
+        };
+
+        let (tx, responses) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+            // Dropping `tx` is how the supervisor learns the child is gone.
+        });
+
+        info!("compile worker: started pid={}", child.id());
+        Ok(Self {
+            child,
+            stdin,
+            responses,
+        })
+    }
+
+    fn kill(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Supervises the worker process on behalf of the preview pipeline.
+pub struct CompileGuard {
+    config: Mutex<IsolationConfig>,
+    worker: Mutex<Option<Worker>>,
+    next_id: AtomicU64,
+}
+
+impl CompileGuard {
+    pub fn new() -> Self {
+        Self {
+            config: Mutex::new(IsolationConfig::default()),
+            worker: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn configure(&self, config: IsolationConfig) {
+        *self.config.lock() = config;
+        if !config.enabled {
+            self.shutdown();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.lock().enabled
+    }
+
+    /// Kill the worker, if one is running. The next `check` respawns it.
+    pub fn shutdown(&self) {
+        if let Some(worker) = self.worker.lock().take() {
+            worker.kill();
+        }
+    }
+
+    /// Run one compile in the worker and wait for it within the configured
+    /// limits. The request's `id` is assigned here. An overrun kills the
+    /// worker; the next call starts a fresh one.
+    pub fn compile(&self, mut request: WorkerRequest) -> Verdict {
+        let config = *self.config.lock();
+        request.id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let line = match serde_json::to_string(&request) {
+            Ok(line) => line,
+            Err(err) => return Verdict::Unavailable(format!("encode request: {err}")),
+        };
+
+        let mut slot = self.worker.lock();
+        if slot.is_none() {
+            match Worker::spawn() {
+                Ok(worker) => *slot = Some(worker),
+                Err(err) => {
+                    warn!("compile worker: unavailable err=\"{err}\"");
+                    return Verdict::Unavailable(err);
+                }
+            }
+        }
+        let Some(worker) = slot.as_mut() else {
+            return Verdict::Unavailable("no worker".to_string());
+        };
+
+        if let Err(err) = writeln!(worker.stdin, "{line}").and_then(|_| worker.stdin.flush()) {
+            // Most likely the child died between compiles; start over next time.
+            warn!("compile worker: write failed err=\"{err}\"");
+            if let Some(worker) = slot.take() {
+                worker.kill();
+            }
+            return Verdict::Unavailable(format!("write request: {err}"));
+        }
+
+        let started = Instant::now();
+        let verdict = loop {
+            match worker.responses.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => match serde_json::from_str::<WorkerResponse>(&line) {
+                    // A response to an older request can only be left over
+                    // from a compile that was abandoned mid-flight; skip it.
+                    Ok(response) if response.id == request.id => {
+                        match WorkerOutput::decode(response) {
+                            Ok(output) => break Verdict::Finished(output),
+                            Err(err) => {
+                                warn!("compile worker: undecodable response err=\"{err}\"");
+                                break Verdict::Unavailable(err);
+                            }
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(err) => {
+                        warn!("compile worker: unreadable response err=\"{err}\" line={line:?}");
+                        continue;
+                    }
+                },
+                Err(RecvTimeoutError::Disconnected) => break Verdict::Crashed,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+
+            if started.elapsed() >= config.timeout {
+                break Verdict::TimedOut(config.timeout);
+            }
+            if let Some(used_bytes) = resident_bytes(worker.child.id()) {
+                if used_bytes > config.memory_limit_bytes {
+                    break Verdict::OverMemory {
+                        used_bytes,
+                        limit_bytes: config.memory_limit_bytes,
+                    };
+                }
+            }
+        };
+
+        if !matches!(verdict, Verdict::Finished(_)) {
+            error!(
+                "compile worker: {verdict:?} after {:.1}ms, killing pid={}",
+                started.elapsed().as_secs_f64() * 1000.0,
+                worker.child.id()
+            );
+            if let Some(worker) = slot.take() {
+                worker.kill();
+            }
+        }
+        verdict
+    }
+}
+
+impl Default for CompileGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for CompileGuard {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Resident set size of process `pid`, where the platform exposes it cheaply.
+#[cfg(target_os = "linux")]
+fn resident_bytes(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    parse_vm_rss(&status)
+}
+
+#[cfg(target_os = "macos")]
+fn resident_bytes(pid: u32) -> Option<u64> {
+    let mut info = std::mem::MaybeUninit::<libc::proc_taskinfo>::zeroed();
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as libc::c_int;
+    // SAFETY: the buffer is a properly sized, writable `proc_taskinfo`; the
+    // call reports how many bytes it filled and never writes past `size`.
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid as libc::c_int,
+            libc::PROC_PIDTASKINFO,
+            0,
+            info.as_mut_ptr().cast(),
+            size,
+        )
+    };
+    if written != size {
+        return None;
+    }
+    // SAFETY: the kernel filled the whole struct (checked above).
+    Some(unsafe { info.assume_init() }.pti_resident_size)
+}
+
+#[cfg(windows)]
+fn resident_bytes(pid: u32) -> Option<u64> {
+    use windows_sys::Win32::{
+        Foundation::CloseHandle,
+        System::{
+            ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS},
+            Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+        },
+    };
+
+    // SAFETY: plain handle lookup; a null result means no access or no process.
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+    if process.is_null() {
+        return None;
+    }
+    // SAFETY: a plain-old-data struct of integers; all-zero is a valid value.
+    let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+    counters.cb = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    // SAFETY: `process` is a live handle opened above and `counters` is a
+    // writable struct whose size is passed in `cb`; the handle is closed on
+    // every path after the call.
+    let ok = unsafe { GetProcessMemoryInfo(process, &mut counters, counters.cb) };
+    unsafe { CloseHandle(process) };
+    (ok != 0).then_some(counters.WorkingSetSize as u64)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn resident_bytes(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// The `VmRSS:` line of `/proc/<pid>/status`, in bytes.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let mut fields = line["VmRSS:".len()..].split_whitespace();
+    let value: u64 = fields.next()?.parse().ok()?;
+    match fields.next() {
+        Some("kB") | None => Some(value * 1024),
+        Some(_) => None,
+    }
+}
+
+// ─── Worker process ─────────────────────────────────────────────────────────
+
+/// Entry point of the child process: answer requests until stdin closes,
+/// which is also how the worker notices the app has gone away.
+pub fn run_worker_process() {
+    let packages = local_package_dirs();
+    let mut library: Option<(BTreeMap<String, String>, LazyHash<Library>)> = None;
+    let mut fonts: Option<(Vec<PathBuf>, FontStore)> = None;
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        let request: WorkerRequest = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                eprintln!("compile worker: bad request: {err}");
+                continue;
+            }
+        };
+
+        // The system scan is the slow part of a font load; only repeat it
+        // when the user's font directories actually changed.
+        if fonts.as_ref().map(|(dirs, _)| dirs) != Some(&request.font_dirs) {
+            fonts = Some((
+                request.font_dirs.clone(),
+                headless_fonts(&request.font_dirs),
+            ));
+        }
+        let Some((_, store)) = fonts.as_ref() else {
+            continue;
+        };
+        if library.as_ref().map(|(inputs, _)| inputs) != Some(&request.inputs) {
+            library = Some((request.inputs.clone(), headless_library(&request.inputs)));
+        }
+        let Some((_, library)) = library.as_ref() else {
+            continue;
+        };
+
+        // The child's view of the workspace: files from disk, overlaid with
+        // the unsaved buffers the request carried.
+        let response = match local_file_id(Path::new(&request.main)) {
+            Some(main) => {
+                let world = HeadlessWorld::new(
+                    &request.root,
+                    main,
+                    &request.shadows,
+                    library,
+                    store,
+                    &packages,
+                );
+                worker_compile(&request, &world)
+            }
+            None => WorkerResponse {
+                id: request.id,
+                errors: vec![SerializedDiagnostic {
+                    severity: "error".into(),
+                    message: format!("main file path is not valid: {}", request.main),
+                    hints: Vec::new(),
+                    file_path: None,
+                    range: None,
+                    trace: Vec::new(),
+                    fixes: Vec::new(),
+                }],
+                warnings: Vec::new(),
+                reads: Vec::new(),
+                missing_packages: Vec::new(),
+                pages: None,
+            },
+        };
+
+        let Ok(encoded) = serde_json::to_string(&response) else {
+            continue;
+        };
+        if writeln!(stdout, "{encoded}")
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Compile `world`, then render every page the request does not list as
+/// cached.
+fn worker_compile(request: &WorkerRequest, world: &HeadlessWorld) -> WorkerResponse {
+    let CompileOutput {
+        document,
+        errors,
+        warnings,
+        reads,
+    } = compile_in(world, world);
+
+    let mut reads: Vec<String> = reads
+        .into_iter()
+        .filter(|id| matches!(id.root(), VirtualRoot::Project))
+        .map(|id| id.vpath().get_without_slash().to_string())
+        .collect();
+    reads.sort();
+    let mut missing_packages: Vec<String> = world
+        .missing_packages()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    missing_packages.sort();
+
+    let pages = document.map(|doc| {
+        let cached: HashSet<PageFingerprint> = request.cached.iter().copied().collect();
+        fingerprint_pages(&doc)
+            .into_par_iter()
+            .zip(doc.pages().par_iter())
+            .map(|(fingerprint, page)| {
+                let png = if cached.contains(&fingerprint) {
+                    None
+                } else {
+                    match render_page(page, request.zoom) {
+                        Ok(png) => Some(base64::engine::general_purpose::STANDARD.encode(png)),
+                        Err(err) => {
+                            eprintln!("compile worker: render error: {err}");
+                            None
+                        }
+                    }
+                };
+                WorkerPage { fingerprint, png }
+            })
+            .collect()
+    });
+
+    WorkerResponse {
+        id: request.id,
+        errors,
+        warnings,
+        reads,
+        missing_packages,
+        pages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_round_trip_through_the_line_protocol() {
+        let request = WorkerRequest {
+            id: 7,
+            root: PathBuf::from("/ws"),
+            main: "main.typ".to_string(),
+            shadows: vec![("ch/one.typ".to_string(), "= One\nline two\n".to_string())],
+            font_dirs: vec![PathBuf::from("/fonts")],
+            inputs: BTreeMap::from([("edition".to_string(), "print".to_string())]),
+            zoom: 1.5,
+            cached: vec![u128::MAX - 1],
+        };
+        let line = serde_json::to_string(&request).unwrap();
+        // One request per line: embedded newlines must be escaped.
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<WorkerRequest>(&line).unwrap(),
+            request
+        );
+    }
+
+    #[test]
+    fn the_sources_key_ignores_how_pages_are_rendered() {
+        let request = |zoom: f32, shadow: &str| WorkerRequest {
+            id: 1,
+            root: PathBuf::from("/ws"),
+            main: "main.typ".to_string(),
+            shadows: vec![("main.typ".to_string(), shadow.to_string())],
+            font_dirs: Vec::new(),
+            inputs: BTreeMap::new(),
+            zoom,
+            cached: Vec::new(),
+        };
+        assert_eq!(
+            request(1.0, "= A").sources_key(),
+            request(3.0, "= A").sources_key()
+        );
+        assert_ne!(
+            request(1.0, "= A").sources_key(),
+            request(1.0, "= B").sources_key()
+        );
+    }
+
+    #[test]
+    fn responses_carry_diagnostics_and_rendered_pages() {
+        let response = WorkerResponse {
+            id: 3,
+            errors: Vec::new(),
+            warnings: vec![Verdict::Crashed.diagnostic(None).unwrap()],
+            reads: vec!["main.typ".to_string()],
+            missing_packages: vec!["@preview/cetz:0.4.2".to_string()],
+            pages: Some(vec![
+                WorkerPage {
+                    fingerprint: 1 << 100,
+                    png: Some(base64::engine::general_purpose::STANDARD.encode(b"\x89PNG")),
+                },
+                WorkerPage {
+                    fingerprint: 7,
+                    png: None,
+                },
+            ]),
+        };
+        let line = serde_json::to_string(&response).unwrap();
+        let output =
+            WorkerOutput::decode(serde_json::from_str::<WorkerResponse>(&line).unwrap()).unwrap();
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.reads, vec!["main.typ".to_string()]);
+        assert_eq!(
+            output.missing_packages,
+            vec!["@preview/cetz:0.4.2".to_string()]
+        );
+        assert_eq!(
+            output.pages,
+            Some(vec![(1 << 100, Some(b"\x89PNG".to_vec())), (7, None)])
+        );
+    }
+
+    #[test]
+    fn a_corrupt_page_fails_the_whole_response() {
+        let response = WorkerResponse {
+            id: 1,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            reads: Vec::new(),
+            missing_packages: Vec::new(),
+            pages: Some(vec![WorkerPage {
+                fingerprint: 1,
+                png: Some("not base64!".to_string()),
+            }]),
+        };
+        assert!(WorkerOutput::decode(response).is_err());
+    }
+
+    #[test]
+    fn resident_size_is_read_from_proc_status() {
+        let status = "Name:\ttypwriter\nVmPeak:\t  900 kB\nVmRSS:\t  2048 kB\nThreads:\t4\n";
+        assert_eq!(parse_vm_rss(status), Some(2048 * 1024));
+    }
+
+    #[test]
+    fn a_status_without_rss_reports_nothing() {
+        assert_eq!(parse_vm_rss("Name:\tzombie\n"), None);
+    }
+
+    #[test]
+    fn a_timeout_names_the_limit_in_seconds() {
+        let diag = Verdict::TimedOut(Duration::from_secs(30))
+            .diagnostic(Some("main.typ".to_string()))
+            .unwrap();
+        assert_eq!(diag.message, "compile exceeded 30 seconds and was stopped");
+        assert_eq!(diag.severity, "error");
+        assert_eq!(diag.file_path.as_deref(), Some("main.typ"));
+    }
+
+    #[test]
+    fn a_memory_overrun_names_the_limit_in_megabytes() {
+        let diag = Verdict::OverMemory {
+            used_bytes: 5 << 30,
+            limit_bytes: 4096 * 1024 * 1024,
+        }
+        .diagnostic(None)
+        .unwrap();
+        assert!(diag.message.contains("5120 MB"));
+        assert!(diag.message.contains("4096 MB"));
+    }
+
+    #[test]
+    fn only_overruns_replace_the_compile() {
+        let finished = WorkerOutput {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            reads: Vec::new(),
+            missing_packages: Vec::new(),
+            pages: None,
+        };
+        assert!(Verdict::Finished(finished).diagnostic(None).is_none());
+        assert!(Verdict::Unavailable("spawn".into())
+            .diagnostic(None)
+            .is_none());
+        assert!(Verdict::Crashed.diagnostic(None).is_some());
+    }
+
+    #[test]
+    fn zero_limits_are_raised_to_something_usable() {
+        let settings = AppSettings {
+            compile_timeout_seconds: 0,
+            compile_memory_limit_mb: 0,
+            ..AppSettings::default()
+        };
+        let config = IsolationConfig::from_settings(&settings);
+        assert_eq!(config.timeout, Duration::from_secs(1));
+        assert_eq!(config.memory_limit_bytes, 64 * 1024 * 1024);
+    }
+}
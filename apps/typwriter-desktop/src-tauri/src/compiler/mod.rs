@@ -6,22 +6,37 @@
 
 mod cache;
 mod compile;
+mod dep_graph;
 mod diff;
 mod disk_cache;
+mod font_report;
+mod isolation;
 mod page_diff;
+mod paths;
+mod preflight;
+mod quickfix;
 mod render;
+mod report;
 mod snapshot_world;
+mod standalone;
 
 pub use cache::{key_to_path, parse_key, zoom_to_bucket, PageCacheKey};
 pub use compile::{
-    collect_workspace_diagnostics, compile_document, dedup_merge, CompileOutput,
-    SerializedDiagnostic, WorkspaceDiagCache,
+    collect_workspace_diagnostics, compile_document, compile_in, dedup_merge, CompileOutput,
+    DiagnosticRange, FileReads, SerializedDiagnostic, WorkspaceDiagCache,
 };
+pub(crate) use compile::walk_workspace_files;
+pub use dep_graph::DependencyGraph;
 pub use diff::fingerprint_pages;
+pub use font_report::FontUsageReport;
+pub use isolation::{run_worker_process, IsolationConfig, WORKER_FLAG};
 pub use page_diff::{PageDiffEngine, PageDiffSide};
+pub use preflight::PrintIssue;
 pub use render::render_page;
+pub use report::{render_report, run_diagnostics_cli, ReportFormat, DIAGNOSTICS_FLAG};
 
 use std::{
+    collections::HashSet,
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering},
         mpsc::{self, Receiver, Sender},
@@ -41,9 +56,13 @@ use tauri::{AppHandle, Emitter, Manager};
 use crate::vcs::{CommitTrigger, SnapshotPolicy, VcsState};
 use crate::workspace::WorkspaceState;
 use crate::world::EditorWorld;
-use cache::PageCache;
+use cache::{PageCache, ZoomBucket};
+use diff::PageFingerprint;
 use disk_cache::DiskCache;
+use isolation::{CompileGuard, Verdict, WorkerOutput, WorkerRequest};
+use standalone::StandaloneWorld;
 use std::path::{Path, PathBuf};
+use typst::syntax::{package::PackageSpec, FileId};
 use typst_layout::PagedDocument;
 
 // IPC payloads
@@ -268,6 +287,29 @@ fn parse_pdf_standard(s: &str) -> Result<typst_pdf::PdfStandards, String> {
 /// How many pages the background render pass holds in memory at a time.
 const RENDER_BATCH: usize = 16;
 
+/// Result of a preview compile run by the isolation worker.
+enum IsolatedCompile {
+    Finished {
+        output: WorkerOutput,
+        /// [`WorkerRequest::sources_key`] of what the child compiled.
+        sources: u64,
+    },
+    /// The child overran its limits and was killed; already reported.
+    Stopped,
+}
+
+/// Where the pages of a successful compile come from.
+enum Layout {
+    /// Compiled in-process: pages are rendered from the document.
+    Document(PagedDocument),
+    /// Compiled by the isolation worker, which already rendered every page the
+    /// cache had no bytes for.
+    Worker {
+        pages: Vec<(PageFingerprint, Option<Vec<u8>>)>,
+        sources: u64,
+    },
+}
+
 #[derive(Default)]
 struct CompileQueueState {
     next_revision: u64,
@@ -311,6 +353,17 @@ pub struct PreviewPipeline {
     /// Lets a refresh recompile only the files whose inputs actually moved
     /// instead of the whole workspace.
     workspace_diag_cache: Mutex<WorkspaceDiagCache>,
+    /// Files the last full-document compile read, and whether it failed.
+    /// `None` until the main file has compiled; standalone chapter previews
+    /// leave it alone, since they don't read what the whole document reads.
+    main_reads: Mutex<Option<(HashSet<FileId>, bool)>>,
+    /// Problems with the workspace's `typwriter.toml`, set whenever it is
+    /// (re)loaded and added to every emitted set — including the empty one
+    /// sent when there is no main file, which a broken config may be why.
+    project_diags: Mutex<Vec<SerializedDiagnostic>>,
+    /// The merged diagnostics last sent on `compile:diagnostics`, kept for
+    /// [`Self::export_diagnostics`].
+    last_diagnostics: Mutex<DiagnosticsPayload>,
     page_cache: Mutex<PageCache>,
     /// Persistent on-disk mirror of `page_cache`, scoped to the open workspace.
     /// `None` until a workspace is attached via [`Self::attach_disk_cache`].
@@ -323,7 +376,13 @@ pub struct PreviewPipeline {
     workspace_root: Mutex<Option<PathBuf>>,
     // The most recently successfully compiled document.
     // Held so IDE features (hover, go-to-def, jump-from-click) can use it.
-    pub last_document: Mutex<Option<Arc<PagedDocument>>>,
+    // Read it through [`Self::last_document`], or [`Self::document`], which
+    // rebuilds it after an isolated compile.
+    last_document: Mutex<Option<Arc<PagedDocument>>>,
+    /// Key of the sources the isolation worker last compiled successfully
+    /// (see [`WorkerRequest::sources_key`]) while `last_document` does not
+    /// hold their layout yet.
+    isolated_sources: Mutex<Option<u64>>,
     app_handle: AppHandle,
     // Preview zoom: pixels per typst point. Default 2.0 (retina).
     pub zoom: Mutex<f32>,
@@ -348,6 +407,15 @@ pub struct PreviewPipeline {
     /// Version-control state. Used to auto-commit a restore point whenever
     /// a compile succeeds (the user's "good known state").
     vcs: Arc<VcsState>,
+    /// Out-of-process compile that can stop runaway documents, which the
+    /// in-process compile cannot. Off unless the user enables compile
+    /// isolation in settings.
+    pub isolation: CompileGuard,
+    /// Non-main file the preview is currently entered at, when the user asked
+    /// to preview one chapter on its own. `None` compiles the full document.
+    standalone: RwLock<Option<FileId>>,
+    /// The wrapper `typwriter.toml` declares for standalone chapters.
+    standalone_wrapper: RwLock<Option<FileId>>,
 }
 
 impl PreviewPipeline {
@@ -358,10 +426,17 @@ impl PreviewPipeline {
             last_emitted: Mutex::new(Vec::new()),
             workspace_diags: Mutex::new((Vec::new(), Vec::new())),
             workspace_diag_cache: Mutex::new(WorkspaceDiagCache::new()),
+            main_reads: Mutex::new(None),
+            project_diags: Mutex::new(Vec::new()),
+            last_diagnostics: Mutex::new(DiagnosticsPayload {
+                errors: Vec::new(),
+                warnings: Vec::new(),
+            }),
             page_cache: Mutex::new(PageCache::default()),
             disk_cache: Mutex::new(None),
             workspace_root: Mutex::new(None),
             last_document: Mutex::new(None),
+            isolated_sources: Mutex::new(None),
             app_handle,
             zoom: Mutex::new(2.0),
             visible_page: Mutex::new(0),
@@ -377,6 +452,9 @@ impl PreviewPipeline {
             }),
             last_compile_failed: AtomicBool::new(false),
             vcs,
+            isolation: CompileGuard::new(),
+            standalone: RwLock::new(None),
+            standalone_wrapper: RwLock::new(None),
         }
     }
 
@@ -397,7 +475,7 @@ impl PreviewPipeline {
         // accidental collision with stale state.
         self.page_cache.lock().clear();
         *self.last_emitted.lock() = Vec::new();
-        *self.last_document.lock() = None;
+        self.store_document(None, None);
         // The incoming workspace/main file inherits no staleness from the old one.
         self.last_compile_failed.store(false, Ordering::Release);
         // Drop cached cross-file diagnostics: they belong to the previous
@@ -405,6 +483,40 @@ impl PreviewPipeline {
         // following compile uses a non-Typing reason and repopulates them.
         *self.workspace_diags.lock() = (Vec::new(), Vec::new());
         self.workspace_diag_cache.lock().clear();
+        *self.main_reads.lock() = None;
+        // A standalone chapter belongs to the outgoing workspace/main file.
+        *self.standalone.write() = None;
+    }
+
+    /// Enter the preview at `chapter` instead of the main file, or return to
+    /// the full document with `None`. The caller requests the recompile.
+    pub fn set_standalone(&self, chapter: Option<FileId>) {
+        *self.standalone.write() = chapter;
+    }
+
+    /// The chapter the preview is entered at, if not the main file.
+    pub fn standalone(&self) -> Option<FileId> {
+        *self.standalone.read()
+    }
+
+    /// Wrap standalone chapters in `wrapper` (the project's declared file),
+    /// or in nothing with `None`.
+    pub fn set_standalone_wrapper(&self, wrapper: Option<FileId>) {
+        *self.standalone_wrapper.write() = wrapper;
+    }
+
+    /// The preview's view of the world entered at `chapter`.
+    fn standalone_world(&self, chapter: FileId) -> StandaloneWorld<'_> {
+        StandaloneWorld::new(&self.world, chapter, *self.standalone_wrapper.read())
+    }
+
+    /// Workspace-relative path of the file the preview is entered at: the
+    /// standalone chapter when one is set, else the main file.
+    fn preview_entry_rel(&self) -> Option<String> {
+        match self.standalone() {
+            Some(id) => Some(id.vpath().get_without_slash().to_string()),
+            None => self.world.main_rel(),
+        }
     }
 
     /// Bind the persistent on-disk cache to a workspace root. Subsequent
@@ -652,30 +764,207 @@ impl PreviewPipeline {
                 .emit("preview:page-removed", PageRemovedPayload { index: i });
         }
         *self.last_emitted.lock() = Vec::new();
-        *self.last_document.lock() = None;
+        self.store_document(None, None);
         // Nothing is on screen to be stale.
         self.last_compile_failed.store(false, Ordering::Release);
     }
 
+    /// Replace the `typwriter.toml` problems reported with every compile.
+    /// They reach the frontend with the next one.
+    pub fn set_project_diagnostics(&self, diagnostics: Vec<SerializedDiagnostic>) {
+        *self.project_diags.lock() = diagnostics;
+    }
+
+    /// Send the merged diagnostics of a compile to the frontend and remember
+    /// them for export.
+    fn emit_diagnostics(
+        &self,
+        mut errors: Vec<SerializedDiagnostic>,
+        mut warnings: Vec<SerializedDiagnostic>,
+    ) {
+        for diagnostic in self.project_diags.lock().iter() {
+            match diagnostic.severity.as_str() {
+                "error" => errors.push(diagnostic.clone()),
+                _ => warnings.push(diagnostic.clone()),
+            }
+        }
+        let payload = DiagnosticsPayload { errors, warnings };
+        if let Err(err) = self.app_handle.emit("compile:diagnostics", payload.clone()) {
+            error!("failed to emit compile:diagnostics err=\"{err}\"");
+        }
+        *self.last_diagnostics.lock() = payload;
+    }
+
+    /// The request that compiles the preview in the isolation worker. A
+    /// standalone wrapper travels as a shadow, so the child enters at it just
+    /// like [`StandaloneWorld`] does.
+    fn worker_request(&self, zoom: f32) -> Option<WorkerRequest> {
+        let mut shadows = self.world.shadow_entries();
+        let main = match self.standalone() {
+            Some(chapter) => match self.standalone_world(chapter).entry() {
+                Some(entry) => {
+                    let wrapper = entry.id().vpath().get_without_slash().to_string();
+                    shadows.retain(|(path, _)| *path != wrapper);
+                    shadows.push((wrapper.clone(), entry.text().to_string()));
+                    wrapper
+                }
+                None => chapter.vpath().get_without_slash().to_string(),
+            },
+            None => self.world.main_rel()?,
+        };
+        Some(WorkerRequest {
+            id: 0,
+            root: self.world.root(),
+            main,
+            shadows,
+            font_dirs: self.world.font_dirs(),
+            inputs: self.world.inputs(),
+            zoom,
+            cached: Vec::new(),
+        })
+    }
+
+    /// Fingerprints of the pages whose bytes at `zoom_bucket` are still
+    /// reachable, in memory or (for pages on screen) on disk.
+    fn cached_fingerprints(&self, zoom_bucket: ZoomBucket) -> Vec<PageFingerprint> {
+        let emitted = self.last_emitted.lock().clone();
+        let mut cached: Vec<PageFingerprint> = self
+            .page_cache
+            .lock()
+            .fingerprints_at(zoom_bucket)
+            .collect();
+        if let Some(disk) = self.disk_cache.lock().as_mut() {
+            for key in emitted.into_iter().flatten() {
+                if key.1 == zoom_bucket && !cached.contains(&key.0) && disk.contains(key) {
+                    cached.push(key.0);
+                }
+            }
+        }
+        cached
+    }
+
+    /// Run the compile in the isolation worker. `None` means it must run
+    /// in-process instead: the worker could not be started or talked to.
+    fn isolated_compile(
+        &self,
+        revision: u64,
+        reason: CompileReason,
+        zoom: f32,
+    ) -> Option<IsolatedCompile> {
+        let mut request = self.worker_request(zoom)?;
+        request.cached = self.cached_fingerprints(zoom_to_bucket(zoom));
+        let sources = request.sources_key();
+        let mut verdict = self.isolation.compile(request.clone());
+        // The child only sees locally installed packages. Fetch the ones it
+        // missed here, where the downloader and the custom registries live,
+        // and compile once more.
+        if let Verdict::Finished(output) = &verdict {
+            if self.obtain_packages(&output.missing_packages) {
+                verdict = self.isolation.compile(request);
+            }
+        }
+        let verdict = match verdict {
+            Verdict::Finished(output) => {
+                return Some(IsolatedCompile::Finished { output, sources });
+            }
+            Verdict::Unavailable(why) => {
+                warn!("compile revision={revision} runs in-process: worker unavailable: {why}");
+                return None;
+            }
+            verdict => verdict,
+        };
+        // An overrun is reported as the compile's only diagnostic and the last
+        // render stays on screen, exactly as for a compile that failed with
+        // errors.
+        let diagnostic = verdict.diagnostic(self.preview_entry_rel())?;
+        error!("compile revision={revision} reason={reason:?} stopped: {verdict:?}");
+        self.emit_diagnostics(vec![diagnostic], Vec::new());
+        self.last_compile_failed.store(true, Ordering::Release);
+        Some(IsolatedCompile::Stopped)
+    }
+
+    /// Download or install each `@namespace/name:version` in `specs`. True if
+    /// at least one of them is now available locally.
+    fn obtain_packages(&self, specs: &[String]) -> bool {
+        let mut obtained = false;
+        for spec in specs {
+            let Ok(parsed) = spec.parse::<PackageSpec>() else {
+                warn!("obtain_packages: unparsable spec {spec:?}");
+                continue;
+            };
+            match self.world.obtain_package(&parsed) {
+                Ok(_) => obtained = true,
+                Err(e) => warn!("obtain_packages: err=\"{e}\""),
+            }
+        }
+        obtained
+    }
+
+    /// Remember the layout of a successful compile for the IDE features: the
+    /// document itself, or which sources the isolation worker finished so
+    /// [`Self::document`] can rebuild it.
+    fn store_document(&self, doc: Option<PagedDocument>, sources: Option<u64>) {
+        let mut isolated = self.isolated_sources.lock();
+        *isolated = sources;
+        *self.last_document.lock() = doc.map(Arc::new);
+    }
+
+    /// The most recently compiled document, if its layout is at hand. Never
+    /// compiles, so it is what per-keystroke IDE requests (completions,
+    /// tooltips, definitions) read; after an isolated compile it is `None`
+    /// until something calls [`Self::document`].
+    pub fn last_document(&self) -> Option<Arc<PagedDocument>> {
+        self.last_document.lock().clone()
+    }
+
+    /// The most recently compiled document. After an isolated compile the
+    /// first caller lays it out in-process — but only while the buffers are
+    /// still the ones the worker finished, because that compile is then known
+    /// to terminate. Otherwise `None` until the next compile. For features
+    /// that need the layout on demand: click-to-source, exports, preflight.
+    pub fn document(&self) -> Option<Arc<PagedDocument>> {
+        if let Some(doc) = self.last_document() {
+            return Some(doc);
+        }
+        let sources = (*self.isolated_sources.lock())?;
+        if self.worker_request(*self.zoom.lock())?.sources_key() != sources {
+            return None;
+        }
+        // No lock is held here: a preview compile finishing meanwhile must not
+        // wait on this one, and simply supersedes it (checked below).
+        let t = Instant::now();
+        let output = match self.standalone() {
+            Some(chapter) => compile_in(&*self.world, &self.standalone_world(chapter)),
+            None => compile_document(&self.world),
+        };
+        let doc = Arc::new(output.document?);
+        info!(
+            "document: laid out in-process after isolated compile ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        );
+        // Same lock order as `store_document`. Keep the result only if no newer
+        // compile has replaced those sources, and prefer a layout another
+        // caller stored first.
+        let isolated = self.isolated_sources.lock();
+        if *isolated != Some(sources) {
+            return Some(doc);
+        }
+        let mut last = self.last_document.lock();
+        Some(Arc::clone(last.get_or_insert(doc)))
+    }
+
     fn compile_and_emit(&self, revision: u64, reason: CompileReason, request_mark: u64) {
         let t = Instant::now();
         info!("request_compile: starting revision={revision} reason={reason:?}");
 
         // With no main file set, typst would synthesise "cannot find main file"
         // errors on every cycle. Clear preview + diagnostics and bail.
-        if !self.world.has_main() {
+        let standalone = self.standalone();
+        if standalone.is_none() && !self.world.has_main() {
             info!("compile revision={revision} reason={reason:?} skipped — no main file");
             let old_count = self.last_emitted.lock().len();
             self.clear_preview(old_count);
-            if let Err(err) = self.app_handle.emit(
-                "compile:diagnostics",
-                DiagnosticsPayload {
-                    errors: Vec::new(),
-                    warnings: Vec::new(),
-                },
-            ) {
-                error!("failed to emit compile:diagnostics err=\"{err}\"");
-            }
+            self.emit_diagnostics(Vec::new(), Vec::new());
             return;
         }
 
@@ -688,11 +977,50 @@ impl PreviewPipeline {
         self.world.ensure_fonts_loading();
         self.world.wait_until_fonts_loaded();
 
-        let CompileOutput {
-            document,
-            mut errors,
-            mut warnings,
-        } = compile_document(&*self.world);
+        // Pages are rendered at the zoom in effect when the compile starts; a
+        // zoom change meanwhile queues another compile, making this one stale.
+        let zoom = *self.zoom.lock();
+        let zoom_bucket = zoom_to_bucket(zoom);
+
+        let isolated = if self.isolation.is_enabled() {
+            self.isolated_compile(revision, reason, zoom)
+        } else {
+            None
+        };
+        let (layout, mut errors, mut warnings, reads) = match isolated {
+            Some(IsolatedCompile::Stopped) => return,
+            Some(IsolatedCompile::Finished { output, sources }) => {
+                let reads: HashSet<FileId> = output
+                    .reads
+                    .iter()
+                    .filter_map(|path| crate::world::local_file_id(Path::new(path)))
+                    .collect();
+                let layout = output.pages.map(|pages| Layout::Worker { pages, sources });
+                (layout, output.errors, output.warnings, reads)
+            }
+            None => {
+                let CompileOutput {
+                    document,
+                    errors,
+                    warnings,
+                    reads,
+                } = match standalone {
+                    Some(chapter) => {
+                        let view = self.standalone_world(chapter);
+                        info!(
+                            "compile revision={revision} reason={reason:?} standalone chapter={chapter:?} wrapper={}",
+                            view.has_wrapper()
+                        );
+                        compile_in(&*self.world, &view)
+                    }
+                    None => compile_document(&self.world),
+                };
+                (document.map(Layout::Document), errors, warnings, reads)
+            }
+        };
+        if standalone.is_none() {
+            *self.main_reads.lock() = Some((reads, layout.is_none()));
+        }
 
         let compile_ms = t.elapsed().as_secs_f64() * 1000.0;
 
@@ -723,15 +1051,10 @@ impl PreviewPipeline {
         };
         dedup_merge(&mut errors, &mut warnings, extra_errors, extra_warnings);
 
-        if let Err(err) = self.app_handle.emit(
-            "compile:diagnostics",
-            DiagnosticsPayload { errors, warnings },
-        ) {
-            error!("failed to emit compile:diagnostics err=\"{err}\"");
-        }
+        self.emit_diagnostics(errors, warnings);
 
-        let doc = match document {
-            Some(doc) => doc,
+        let layout = match layout {
+            Some(layout) => layout,
             None => {
                 // Deliberately keep the last good render on screen instead of
                 // clearing it. Blanking costs more than the staleness: the
@@ -755,15 +1078,41 @@ impl PreviewPipeline {
         };
         self.last_compile_failed.store(false, Ordering::Release);
 
+        // The worker's pages go straight into the cache, so the emit pass
+        // below finds them there like any other hit.
+        let (new_fps, doc, sources) = match layout {
+            Layout::Document(doc) => (fingerprint_pages(&doc), Some(doc), None),
+            Layout::Worker { pages, sources } => {
+                let mut cache = self.page_cache.lock();
+                let mut disk = self.disk_cache.lock();
+                let mut fps = Vec::with_capacity(pages.len());
+                for (fp, png) in pages {
+                    if let Some(png) = png {
+                        let key: PageCacheKey = (fp, zoom_bucket);
+                        if let Some(disk) = disk.as_mut() {
+                            disk.insert(key, &png);
+                        }
+                        cache.insert(key, png);
+                    }
+                    fps.push(fp);
+                }
+                (fps, None, Some(sources))
+            }
+        };
+
         if self.is_stale_request(request_mark) {
             info!("compile revision={revision} reason={reason:?} skipped stale render");
-            *self.last_document.lock() = Some(Arc::new(doc));
+            self.store_document(doc, sources);
             return;
         }
 
-        let new_fps = fingerprint_pages(&doc);
-        let zoom = *self.zoom.lock();
-        let zoom_bucket = zoom_to_bucket(zoom);
+        // Only reachable for a worker page that was in the cache when the
+        // request went out but has been evicted since; the next compile asks
+        // the worker for it again.
+        let render = |idx: usize| match &doc {
+            Some(doc) => render_page(&doc.pages()[idx], zoom),
+            None => Err("the compile worker did not render this page".to_string()),
+        };
         let visible_page = *self.visible_page.lock();
 
         // Snapshot the previous emit state (per slot). `new_emitted` is the
@@ -893,8 +1242,7 @@ impl PreviewPipeline {
                 return;
             }
             let key: PageCacheKey = (new_fps[*idx], zoom_bucket);
-            let page = &doc.pages()[*idx];
-            match render_page(page, zoom) {
+            match render(*idx) {
                 Ok(png) => {
                     if let Some(disk) = self.disk_cache.lock().as_mut() {
                         disk.insert(key, &png);
@@ -928,8 +1276,7 @@ impl PreviewPipeline {
                 .par_iter()
                 .filter_map(|&idx| {
                     let key: PageCacheKey = (new_fps[idx], zoom_bucket);
-                    let page = &doc.pages()[idx];
-                    match render_page(page, zoom) {
+                    match render(idx) {
                         Ok(png) => Some((idx, key, png)),
                         Err(err) => {
                             error!("render error page={idx} err=\"{err}\"");
@@ -967,7 +1314,9 @@ impl PreviewPipeline {
 
         // Persist the page manifest (best effort) so the next open can paint
         // this preview from disk before the font-blocked recompile finishes.
-        if new_emitted.iter().any(Option::is_some) {
+        // A standalone chapter is a temporary view; the manifest keeps
+        // describing the full document the workspace reopens to.
+        if standalone.is_none() && new_emitted.iter().any(Option::is_some) {
             if let (Some(root), Some(main_rel)) =
                 (self.workspace_root.lock().clone(), self.world.main_rel())
             {
@@ -976,7 +1325,7 @@ impl PreviewPipeline {
         }
 
         *self.last_emitted.lock() = new_emitted;
-        *self.last_document.lock() = Some(Arc::new(doc));
+        self.store_document(doc, sources);
 
         // Generate thumbnail when the workspace is opened and the main file is compiled.
         if reason == CompileReason::MainFile {
@@ -1024,6 +1373,75 @@ impl PreviewPipeline {
         self.request_counter.load(Ordering::Acquire) != request_mark
     }
 
+    /// What the last full-document compile and the workspace diagnostics
+    /// pass read. `None` until the main file has compiled.
+    pub fn file_reads(&self) -> Option<FileReads> {
+        let (main, main_failed) = self.main_reads.lock().clone()?;
+        Some(FileReads {
+            main,
+            main_failed,
+            entries: compile::entry_reads(&self.workspace_diag_cache.lock()),
+        })
+    }
+
+    /// File-level dependency graph of the workspace: the literal references in
+    /// every `.typ` file, plus what the last compiles read beyond them.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        use typst::World;
+
+        let root = self.world.root();
+        let sources: Vec<_> = walk_workspace_files(&root)
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "typ"))
+            .filter_map(|path| crate::world::local_file_id(path.strip_prefix(&root).ok()?))
+            .filter_map(|id| self.world.source(id).ok())
+            .collect();
+        let exists = |id: FileId| root.join(id.vpath().get_without_slash()).is_file();
+        dep_graph::build_graph(
+            &sources,
+            exists,
+            self.file_reads().as_ref(),
+            self.world.main_id(),
+        )
+    }
+
+    /// Write the dependency graph to `path` as Graphviz DOT. Returns the
+    /// number of bytes written.
+    pub fn export_dependency_graph(&self, path: &str) -> Result<usize, String> {
+        let dot = self.dependency_graph().to_dot();
+        std::fs::write(path, &dot).map_err(|e| e.to_string())?;
+        Ok(dot.len())
+    }
+
+    /// Run `f` on the last compiled document. `f` runs outside the lock, so a
+    /// slow one never blocks a compile.
+    fn with_last_document<T>(&self, f: impl FnOnce(&PagedDocument) -> T) -> Result<T, String> {
+        let doc = self.document().ok_or_else(|| {
+            let e = "No compiled document available";
+            error!("with_last_document: err=\"{e}\"");
+            e.to_string()
+        })?;
+        Ok(f(&doc))
+    }
+
+    /// Accessibility findings for the last compiled document, reported as
+    /// diagnostics so they can be fixed before a PDF/UA export fails on them.
+    pub fn accessibility_preflight(&self) -> Result<Vec<SerializedDiagnostic>, String> {
+        self.with_last_document(|doc| preflight::accessibility_preflight(&self.world, doc))
+    }
+
+    /// Print-readiness report for the last compiled document: font fallback,
+    /// missing glyphs, low-resolution images and content off the page.
+    pub fn print_preflight(&self) -> Result<Vec<PrintIssue>, String> {
+        self.with_last_document(|doc| preflight::print_preflight(&self.world, doc))
+    }
+
+    /// Every font face the last compiled document renders with, per document
+    /// and per page.
+    pub fn font_usage_report(&self) -> Result<FontUsageReport, String> {
+        self.with_last_document(|doc| font_report::font_usage_report(&self.world, doc))
+    }
+
     pub fn export_pdf(&self, config: PdfExportConfig) -> Result<(), String> {
         let t = Instant::now();
         info!("export_pdf: path={:?}", config.path);
@@ -1048,19 +1466,25 @@ impl PreviewPipeline {
         Ok(())
     }
 
+    /// Write the diagnostics of the last compile — main file and workspace
+    /// pass merged, as the diagnostics panel shows them — to `path`.
+    pub fn export_diagnostics(&self, path: &str, format: ReportFormat) -> Result<usize, String> {
+        let report = {
+            let last = self.last_diagnostics.lock();
+            render_report(format, &last.errors, &last.warnings)
+        };
+        std::fs::write(path, &report).map_err(|e| e.to_string())?;
+        Ok(report.len())
+    }
+
     /// Generate the PDF bytes for the last compiled document. The destination
     /// path inside `config` is ignored — callers handle the write themselves.
     pub fn export_pdf_bytes(&self, config: PdfExportConfig) -> Result<Vec<u8>, String> {
-        let doc = self
-            .last_document
-            .lock()
-            .as_ref()
-            .ok_or_else(|| {
-                let e = "No compiled document available";
-                error!("export_pdf_bytes: err=\"{e}\"");
-                e.to_string()
-            })?
-            .clone();
+        let doc = self.document().ok_or_else(|| {
+            let e = "No compiled document available";
+            error!("export_pdf_bytes: err=\"{e}\"");
+            e.to_string()
+        })?;
 
         let standards = match &config.pdf_standard {
             Some(s) if !s.trim().is_empty() => parse_pdf_standard(s)?,
@@ -1145,16 +1569,11 @@ impl PreviewPipeline {
         &self,
         config: PngExportConfig,
     ) -> Result<Vec<(String, Vec<u8>)>, String> {
-        let doc = self
-            .last_document
-            .lock()
-            .as_ref()
-            .ok_or_else(|| {
-                let e = "No compiled document available";
-                error!("export_png_pages: err=\"{e}\"");
-                e.to_string()
-            })?
-            .clone();
+        let doc = self.document().ok_or_else(|| {
+            let e = "No compiled document available";
+            error!("export_png_pages: err=\"{e}\"");
+            e.to_string()
+        })?;
 
         let scale = config.scale.unwrap_or(2.0);
         let prefix = config.prefix.as_deref().unwrap_or("page").to_string();
@@ -1216,16 +1635,11 @@ impl PreviewPipeline {
         &self,
         config: SvgExportConfig,
     ) -> Result<Vec<(String, Vec<u8>)>, String> {
-        let doc = self
-            .last_document
-            .lock()
-            .as_ref()
-            .ok_or_else(|| {
-                let e = "No compiled document available";
-                error!("export_svg_pages: err=\"{e}\"");
-                e.to_string()
-            })?
-            .clone();
+        let doc = self.document().ok_or_else(|| {
+            let e = "No compiled document available";
+            error!("export_svg_pages: err=\"{e}\"");
+            e.to_string()
+        })?;
 
         let prefix = config.prefix.as_deref().unwrap_or("page").to_string();
 
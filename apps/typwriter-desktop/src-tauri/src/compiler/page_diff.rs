@@ -428,7 +428,7 @@ impl PageDiffEngine {
             Some(id) => Arc::new(self.compile_snapshot(id, &main_rel)?),
             // Against the working tree: reuse the document the preview is
             // already showing rather than compiling it a second time.
-            None => self.pipeline.last_document.lock().clone().ok_or(
+            None => self.pipeline.document().ok_or(
                 "The current document hasn't compiled successfully yet — fix the errors first",
             )?,
         };
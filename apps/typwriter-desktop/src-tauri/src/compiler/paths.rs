@@ -0,0 +1,58 @@
+// Path resolution shared by the passes that follow literal paths through the
+// syntax tree (preflight's include walk, the dependency graph).
+
+use typst::syntax::{FileId, VirtualRoot};
+
+use crate::world::local_file_id;
+
+/// Resolve an `#include` / `#import` path the way typst does: relative to the
+/// including file, or to the project root when it starts with `/`. Package files and
+/// paths escaping the root resolve to `None` — the scan stays in the project.
+pub(super) fn resolve_relative(from: FileId, target: &str) -> Option<FileId> {
+    if matches!(from.root(), VirtualRoot::Package(_)) {
+        return None;
+    }
+    let mut parts: Vec<&str> = Vec::new();
+    if !target.starts_with('/') {
+        let from_path = from.vpath().get_without_slash();
+        parts.extend(from_path.split('/'));
+        // Drop the including file's own name.
+        parts.pop();
+    }
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            other => parts.push(other),
+        }
+    }
+    local_file_id(std::path::Path::new(&parts.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(path: &str) -> FileId {
+        local_file_id(std::path::Path::new(path)).expect("valid path")
+    }
+
+    #[test]
+    fn include_paths_resolve_relative_to_the_including_file() {
+        assert_eq!(
+            resolve_relative(id("ch/a.typ"), "b.typ"),
+            Some(id("ch/b.typ"))
+        );
+        assert_eq!(
+            resolve_relative(id("ch/a.typ"), "../b.typ"),
+            Some(id("b.typ"))
+        );
+        assert_eq!(
+            resolve_relative(id("ch/a.typ"), "/x/b.typ"),
+            Some(id("x/b.typ"))
+        );
+        assert_eq!(resolve_relative(id("a.typ"), "../b.typ"), None);
+    }
+}
@@ -0,0 +1,1102 @@
+// Preflight checks run against the last compiled document before export.
+//
+// Typst only rejects a PDF/UA export at the very end, with a single opaque
+// error string. These checks surface the same problems up front — as ordinary
+// diagnostics with a file and range — so they can be fixed in the editor
+// before the export dialog is even opened.
+//
+// Structural checks (alt text, heading levels, table headers, captions) read
+// the syntax trees of the main file and everything it `#include`s, in
+// document order. Checks that depend on what was actually laid out (the
+// document title and language, text colours) read the compiled document.
+//
+// The print checks are about the laid-out result only — which font a glyph
+// really came from, how many pixels an image has at its placed size, whether
+// anything spills off the page — so they walk the frames and report a page
+// and position alongside the source span.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use typst::{
+    diag::Severity,
+    foundations::Smart,
+    layout::{Abs, Frame, FrameItem, Point, Transform},
+    model::Document,
+    syntax::{
+        ast::{self, AstNode},
+        FileId, LinkedNode, Source, Span, SyntaxKind, SyntaxNode,
+    },
+    text::{Lang, Locale, TextItem},
+    visualize::{ImageKind, Paint},
+    World,
+};
+use typst_layout::PagedDocument;
+
+use super::compile::{locate_span, DiagnosticRange, SerializedDiagnostic};
+use super::paths::resolve_relative;
+use crate::world::EditorWorld;
+
+/// WCAG 2.x minimum contrast for body text (success criterion 1.4.3).
+const MIN_CONTRAST_NORMAL: f64 = 4.5;
+
+/// WCAG's relaxed minimum for large text.
+const MIN_CONTRAST_LARGE: f64 = 3.0;
+
+/// Size from which text counts as "large" for WCAG: 18pt regular.
+const LARGE_TEXT_PT: f64 = 18.0;
+
+/// One preflight problem, before it is resolved to a file and range.
+#[derive(Debug)]
+pub(crate) struct Finding {
+    pub span: Span,
+    pub severity: Severity,
+    pub message: String,
+    pub hints: Vec<String>,
+}
+
+impl Finding {
+    fn new(span: Span, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            severity,
+            message: message.into(),
+            hints: Vec::new(),
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hints.push(hint.into());
+        self
+    }
+}
+
+/// Serialise findings the same way compiler diagnostics are, so the frontend
+/// renders them through the existing diagnostics list. Findings with a
+/// detached span (document-wide problems) are pinned to `fallback_path`.
+pub(crate) fn serialize_findings(
+    world: &EditorWorld,
+    findings: Vec<Finding>,
+    fallback_path: Option<&str>,
+) -> Vec<SerializedDiagnostic> {
+    findings
+        .into_iter()
+        .map(|finding| {
+            let (file_path, range) = locate_span(world, finding.span);
+            SerializedDiagnostic {
+                severity: match finding.severity {
+                    Severity::Error => "error".into(),
+                    Severity::Warning => "warning".into(),
+                },
+                message: finding.message,
+                hints: finding.hints,
+                file_path: file_path.or_else(|| fallback_path.map(String::from)),
+                range,
+                trace: Vec::new(),
+                fixes: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+// ─── Accessibility ──────────────────────────────────────────────────────────
+
+/// Everything that would make a PDF/UA export fail or be hard to use with
+/// assistive technology, for the document last compiled from `world`'s main
+/// file.
+pub fn accessibility_preflight(
+    world: &EditorWorld,
+    doc: &PagedDocument,
+) -> Vec<SerializedDiagnostic> {
+    let Some(main) = world.main_id() else {
+        return Vec::new();
+    };
+
+    let mut scan = StructureScan::new(|id| world.source(id).ok());
+    scan.run(main);
+    let mut findings = scan.findings;
+
+    // The title is read from the compiled document rather than the syntax
+    // tree: templates usually set it from inside a package, which the
+    // structural scan deliberately does not descend into.
+    if doc.info().title.is_none() {
+        findings.push(
+            Finding::new(Span::detached(), Severity::Error, "document has no title")
+                .hint("PDF/UA requires a title; add `#set document(title: [...])`"),
+        );
+    }
+    if !declares_language(doc) {
+        findings.push(
+            Finding::new(
+                Span::detached(),
+                Severity::Warning,
+                "document language is not set",
+            )
+            .hint("screen readers fall back to English; add `#set text(lang: \"..\")`"),
+        );
+    }
+
+    findings.extend(contrast_findings(doc));
+
+    let main_path = world.main_rel();
+    serialize_findings(world, findings, main_path.as_deref())
+}
+
+/// Walks the main file and its includes in document order, collecting
+/// structural accessibility findings.
+///
+/// Generic over the source loader so tests can run it against in-memory
+/// sources without an `EditorWorld`.
+struct StructureScan<L> {
+    load: L,
+    visited: HashSet<FileId>,
+    /// Depth of the previous heading in document order, across includes.
+    last_heading: Option<usize>,
+    findings: Vec<Finding>,
+}
+
+impl<L: Fn(FileId) -> Option<Source>> StructureScan<L> {
+    fn new(load: L) -> Self {
+        Self {
+            load,
+            visited: HashSet::new(),
+            last_heading: None,
+            findings: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, id: FileId) {
+        // An include cycle is a compile error anyway; the guard only keeps
+        // the scan from looping on one.
+        if !self.visited.insert(id) {
+            return;
+        }
+        let Some(source) = (self.load)(id) else {
+            return;
+        };
+        self.visit(source.root(), id);
+    }
+
+    fn visit(&mut self, node: &SyntaxNode, id: FileId) {
+        if let Some(heading) = node.cast::<ast::Heading>() {
+            self.heading(heading.depth().get(), heading.span());
+        } else if let Some(call) = node.cast::<ast::FuncCall>() {
+            self.call(call);
+        } else if let Some(include) = node.cast::<ast::ModuleInclude>() {
+            // Scanned in place: the included file's headings sit at the
+            // include's position in document order.
+            if let ast::Expr::Str(path) = include.source() {
+                if let Some(target) = resolve_relative(id, path.get().as_str()) {
+                    self.run(target);
+                }
+            }
+        }
+
+        for child in node.children() {
+            self.visit(child, id);
+        }
+    }
+
+    fn heading(&mut self, depth: usize, span: Span) {
+        let expected_max = self.last_heading.map_or(1, |last| last + 1);
+        if depth > expected_max {
+            let message = match self.last_heading {
+                Some(last) => format!("heading level skips from {last} to {depth}"),
+                None => format!("first heading is level {depth}, not 1"),
+            };
+            self.findings.push(
+                Finding::new(span, Severity::Warning, message)
+                    .hint("assistive technology navigates by heading level; do not skip levels"),
+            );
+        }
+        self.last_heading = Some(depth);
+    }
+
+    fn call(&mut self, call: ast::FuncCall) {
+        let Some(name) = callee_name(call) else {
+            return;
+        };
+        let args = call.args();
+        match name.as_str() {
+            "image" if !has_named(args, "alt") => self.findings.push(
+                Finding::new(
+                    call.span(),
+                    Severity::Error,
+                    "image has no alternative text",
+                )
+                .hint("add `alt: \"...\"` describing the image"),
+            ),
+            "figure" if !has_named(args, "caption") => self.findings.push(
+                Finding::new(call.span(), Severity::Warning, "figure has no caption")
+                    .hint("add `caption: [...]` so the figure can be referenced and described"),
+            ),
+            "table" if !has_header(args) => self.findings.push(
+                Finding::new(call.span(), Severity::Warning, "table has no header row")
+                    .hint("wrap the first row in `table.header(...)`"),
+            ),
+            "heading" => {
+                let depth = named_int(args, "level").unwrap_or(1);
+                self.heading(depth.max(1) as usize, call.span());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `image` for `image(..)`, `table.header` for `table.header(..)`.
+fn callee_name(call: ast::FuncCall) -> Option<String> {
+    match call.callee() {
+        ast::Expr::Ident(ident) => Some(ident.as_str().to_string()),
+        ast::Expr::FieldAccess(access) => match access.target() {
+            ast::Expr::Ident(target) => {
+                Some(format!("{}.{}", target.as_str(), access.field().as_str()))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn has_named(args: ast::Args, name: &str) -> bool {
+    args.items()
+        .any(|arg| matches!(arg, ast::Arg::Named(named) if named.name().as_str() == name))
+}
+
+fn named_int(args: ast::Args, name: &str) -> Option<i64> {
+    args.items().find_map(|arg| match arg {
+        ast::Arg::Named(named) if named.name().as_str() == name => match named.expr() {
+            ast::Expr::Int(int) => Some(int.get()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Whether a `table(..)` call has a `table.header(..)` among its cells.
+fn has_header(args: ast::Args) -> bool {
+    args.items().any(|arg| match arg {
+        ast::Arg::Pos(ast::Expr::FuncCall(cell)) => {
+            callee_name(cell).as_deref() == Some("table.header")
+        }
+        _ => false,
+    })
+}
+
+/// Text whose colour contrasts too little with the page background. One
+/// finding per source span, however many glyph runs it produced.
+/// Whether the document says what language it is in: a top-level
+/// `set text(lang: ..)`, wherever it comes from (a template in a package
+/// counts), or text laid out in anything but typst's default English. Only
+/// the compiled document can tell — the rule may sit in a package, or be
+/// applied through a `show` rule the syntax doesn't reveal.
+fn declares_language(doc: &PagedDocument) -> bool {
+    let mut langs = Vec::new();
+    for page in doc.pages() {
+        for_each_text(&page.frame, &mut |text| langs.push(text.lang));
+    }
+    language_is_set(&doc.info().locale, langs)
+}
+
+fn language_is_set(locale: &Smart<Locale>, runs: impl IntoIterator<Item = Lang>) -> bool {
+    locale.is_custom() || runs.into_iter().any(|lang| lang != Lang::ENGLISH)
+}
+
+fn contrast_findings(doc: &PagedDocument) -> Vec<Finding> {
+    let mut seen = HashSet::new();
+    let mut findings = Vec::new();
+
+    for page in doc.pages() {
+        // Coloured shapes under the text are not considered; the page fill
+        // is the background in the overwhelming majority of documents.
+        let background = match &page.fill {
+            Smart::Custom(Some(Paint::Solid(color))) => rgb_of(color.to_vec4_u8()),
+            _ => [255, 255, 255],
+        };
+
+        for_each_text(&page.frame, &mut |text| {
+            let Paint::Solid(color) = &text.fill else {
+                // Gradients and tilings have no single colour to rate.
+                return;
+            };
+            let Some(span) = text.glyphs.first().map(|g| g.span.0) else {
+                return;
+            };
+            if span.is_detached() || !seen.insert(span) {
+                return;
+            }
+
+            let ratio = contrast_ratio(rgb_of(color.to_vec4_u8()), background);
+            let minimum = if text.size.to_pt() >= LARGE_TEXT_PT {
+                MIN_CONTRAST_LARGE
+            } else {
+                MIN_CONTRAST_NORMAL
+            };
+            if ratio < minimum {
+                findings.push(
+                    Finding::new(
+                        span,
+                        Severity::Warning,
+                        format!("low text contrast {ratio:.1}:1 (minimum {minimum}:1)"),
+                    )
+                    .hint("darken the text colour or lighten the background"),
+                );
+            }
+        });
+    }
+
+    findings
+}
+
+fn for_each_text(frame: &Frame, f: &mut dyn FnMut(&TextItem)) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => for_each_text(&group.frame, f),
+            FrameItem::Text(text) => f(text),
+            _ => {}
+        }
+    }
+}
+
+fn rgb_of([r, g, b, _]: [u8; 4]) -> [u8; 3] {
+    [r, g, b]
+}
+
+/// WCAG relative luminance of an sRGB colour.
+fn relative_luminance([r, g, b]: [u8; 3]) -> f64 {
+    let channel = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// WCAG contrast ratio, from 1.0 (identical) to 21.0 (black on white).
+fn contrast_ratio(a: [u8; 3], b: [u8; 3]) -> f64 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    let (light, dark) = if la >= lb { (la, lb) } else { (lb, la) };
+    (light + 0.05) / (dark + 0.05)
+}
+
+// ─── Print ──────────────────────────────────────────────────────────────────
+
+/// Print shops reject raster images below this resolution at placed size.
+const MIN_PRINT_DPI: f64 = 300.0;
+
+/// Slack before content counts as outside the page. Glyph outlines routinely
+/// overshoot their advance box by a hair; that is not what this check is for.
+const BOUNDS_TOLERANCE_PT: f64 = 0.5;
+
+/// The faces typst gives body text, equations and raw text when nothing
+/// overrides them. Equations and raw text get theirs from built-in show rules,
+/// so a plain `set text(font: ..)` does not reach them.
+const BODY_FAMILY: &str = "libertinus serif";
+const MATH_FAMILY: &str = "new computer modern math";
+const RAW_FAMILY: &str = "dejavu sans mono";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PrintIssueKind {
+    /// Text rendered in a face the document never asked for.
+    FontFallback,
+    /// A character no available font has a glyph for (rendered as tofu).
+    MissingGlyph,
+    /// A raster image below [`MIN_PRINT_DPI`] at its placed size.
+    LowResolutionImage,
+    /// Content extending past the page edge.
+    OutsidePage,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrintIssue {
+    pub kind: PrintIssueKind,
+    /// 0-based page index.
+    pub page: usize,
+    /// Position on the page in typst points, top-left origin.
+    pub x: f64,
+    pub y: f64,
+    pub message: String,
+    /// Workspace-relative source file, if the span resolves to one.
+    pub file_path: Option<String>,
+    pub range: Option<DiagnosticRange>,
+    /// Family that actually rendered the text, for font issues.
+    pub font_family: Option<String>,
+    /// Effective resolution, for image issues.
+    pub effective_dpi: Option<f64>,
+}
+
+/// Print-readiness report for the document last compiled from `world`'s main
+/// file, in page order.
+pub fn print_preflight(world: &EditorWorld, doc: &PagedDocument) -> Vec<PrintIssue> {
+    let mut requests = FontRequests::new(world.main_id(), |id| world.source(id).ok());
+
+    let mut issues = Vec::new();
+    for (index, page) in doc.pages().iter().enumerate() {
+        check_page(index, &page.frame, &mut requests, &mut issues);
+    }
+
+    issues
+        .into_iter()
+        .map(|raw| {
+            let (file_path, range) = locate_span(world, raw.span);
+            PrintIssue {
+                kind: raw.kind,
+                page: raw.page,
+                x: raw.at.x.to_pt(),
+                y: raw.at.y.to_pt(),
+                message: raw.message,
+                file_path,
+                range,
+                font_family: raw.font_family,
+                effective_dpi: raw.effective_dpi,
+            }
+        })
+        .collect()
+}
+
+/// A print issue before its span is resolved to a file and range.
+struct RawPrintIssue {
+    kind: PrintIssueKind,
+    page: usize,
+    at: Point,
+    span: Span,
+    message: String,
+    font_family: Option<String>,
+    effective_dpi: Option<f64>,
+}
+
+fn check_page<L: Fn(FileId) -> Option<Source>>(
+    page: usize,
+    frame: &Frame,
+    requests: &mut FontRequests<L>,
+    out: &mut Vec<RawPrintIssue>,
+) {
+    let bounds = frame.size();
+    // One report per (kind, span): a paragraph in a fallback font is one
+    // problem, not one per line it wraps onto.
+    let mut seen: HashSet<(PrintIssueKind, Span)> = HashSet::new();
+    let mut push = |issue: RawPrintIssue, out: &mut Vec<RawPrintIssue>| {
+        if seen.insert((issue.kind, issue.span)) {
+            out.push(issue);
+        }
+    };
+
+    for_each_placed(frame, Transform::identity(), &mut |item, ts| match item {
+        FrameItem::Text(text) => {
+            let Some(span) = text.glyphs.first().map(|g| g.span.0) else {
+                return;
+            };
+            let at = Point::zero().transform(ts);
+            let family = text.font.info().family.clone();
+
+            if !requests.at(span).contains(&family.to_lowercase()) {
+                push(
+                    RawPrintIssue {
+                        kind: PrintIssueKind::FontFallback,
+                        page,
+                        at,
+                        span,
+                        message: format!("text fell back to “{family}”"),
+                        font_family: Some(family.clone()),
+                        effective_dpi: None,
+                    },
+                    out,
+                );
+            }
+
+            let mut x = Abs::zero();
+            for glyph in &text.glyphs {
+                // Glyph 0 is `.notdef`: the shaper found no face with this
+                // character and drew the placeholder box.
+                if glyph.id == 0 {
+                    let chars = &text.text[glyph.range()];
+                    push(
+                        RawPrintIssue {
+                            kind: PrintIssueKind::MissingGlyph,
+                            page,
+                            at: Point::new(x, Abs::zero()).transform(ts),
+                            span: glyph.span.0,
+                            message: format!("no glyph for {}", describe_chars(chars)),
+                            font_family: Some(family.clone()),
+                            effective_dpi: None,
+                        },
+                        out,
+                    );
+                }
+                x += glyph.x_advance.at(text.size);
+            }
+
+            let top_left = Point::new(Abs::zero(), -text.size).transform(ts);
+            let bottom_right = Point::new(text.width(), Abs::zero()).transform(ts);
+            if outside_page(top_left, bottom_right, bounds) {
+                push(outside_issue(page, at, span), out);
+            }
+        }
+        FrameItem::Image(image, size, span) => {
+            let top_left = Point::zero().transform(ts);
+            let bottom_right = Point::new(size.x, size.y).transform(ts);
+
+            if let ImageKind::Raster(raster) = image.kind() {
+                let placed_width = (bottom_right.x - top_left.x).abs();
+                let placed_height = (bottom_right.y - top_left.y).abs();
+                let dpi = effective_dpi(raster.width(), placed_width.to_pt())
+                    .min(effective_dpi(raster.height(), placed_height.to_pt()));
+                if dpi < MIN_PRINT_DPI {
+                    push(
+                        RawPrintIssue {
+                            kind: PrintIssueKind::LowResolutionImage,
+                            page,
+                            at: top_left,
+                            span: *span,
+                            message: format!(
+                                "image is {dpi:.0} dpi at its placed size (minimum {MIN_PRINT_DPI:.0})"
+                            ),
+                            font_family: None,
+                            effective_dpi: Some(dpi),
+                        },
+                        out,
+                    );
+                }
+            }
+
+            if outside_page(top_left, bottom_right, bounds) {
+                push(outside_issue(page, top_left, *span), out);
+            }
+        }
+        FrameItem::Shape(shape, span) => {
+            let bbox = shape.bbox(false);
+            let top_left = bbox.min.transform(ts);
+            let bottom_right = bbox.max.transform(ts);
+            if outside_page(top_left, bottom_right, bounds) {
+                push(outside_issue(page, top_left, *span), out);
+            }
+        }
+        _ => {}
+    });
+}
+
+fn outside_issue(page: usize, at: Point, span: Span) -> RawPrintIssue {
+    RawPrintIssue {
+        kind: PrintIssueKind::OutsidePage,
+        page,
+        at,
+        span,
+        message: "content extends beyond the page edge".to_string(),
+        font_family: None,
+        effective_dpi: None,
+    }
+}
+
+/// Visit every non-group item with the transform from its own coordinate
+/// space to the page's. Unlike the glyph walk in `commands::click`, this keeps
+/// the full transform rather than just the offset, because a `scale`d image's
+/// resolution depends on it.
+fn for_each_placed(frame: &Frame, ts: Transform, f: &mut dyn FnMut(&FrameItem, Transform)) {
+    for (pos, item) in frame.items() {
+        let ts = ts.pre_concat(Transform::translate(pos.x, pos.y));
+        match item {
+            FrameItem::Group(group) => {
+                for_each_placed(&group.frame, ts.pre_concat(group.transform), f)
+            }
+            other => f(other, ts),
+        }
+    }
+}
+
+/// Pixels per inch of `pixels` spread over `placed_pt` points.
+fn effective_dpi(pixels: u32, placed_pt: f64) -> f64 {
+    if placed_pt <= 0.0 {
+        return f64::INFINITY;
+    }
+    f64::from(pixels) / (placed_pt / 72.0)
+}
+
+/// Whether the box spanned by two (possibly flipped) corners leaves the page.
+fn outside_page(a: Point, b: Point, page: typst::layout::Size) -> bool {
+    let tolerance = Abs::pt(BOUNDS_TOLERANCE_PT);
+    let (left, right) = (a.x.min(b.x), a.x.max(b.x));
+    let (top, bottom) = (a.y.min(b.y), a.y.max(b.y));
+    left < -tolerance
+        || top < -tolerance
+        || right > page.x + tolerance
+        || bottom > page.y + tolerance
+}
+
+/// `“→” (U+2192)` — the code point matters when the character itself is
+/// exactly what will not render.
+fn describe_chars(chars: &str) -> String {
+    let points: Vec<String> = chars
+        .chars()
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect();
+    format!("“{chars}” ({})", points.join(" "))
+}
+
+/// Which font families each text run asked for, as far as the project's
+/// sources tell.
+///
+/// A run asks for the list of the innermost `set text(font: ..)` or
+/// `text(font: ..)[..]` enclosing it, looking through `#include` sites into
+/// the including file. Equations and raw text ask for their own default face.
+/// When no such rule is in sight — the font comes from a template function
+/// applied with `show: ..`, or from a package — the run may be in any family
+/// the project names anywhere, or in typst's default.
+struct FontRequests<L> {
+    load: L,
+    /// Every family named anywhere in the project, lowercased.
+    anywhere: HashSet<String>,
+    /// Where each included file is included from: file and byte offset.
+    included_at: HashMap<FileId, (FileId, usize)>,
+    cache: HashMap<Span, HashSet<String>>,
+}
+
+/// What kind of text a run is, by the markup around it.
+#[derive(Clone, Copy)]
+enum TextContext {
+    Body,
+    Math,
+    Raw,
+}
+
+impl<L: Fn(FileId) -> Option<Source>> FontRequests<L> {
+    fn new(main: Option<FileId>, load: L) -> Self {
+        let mut anywhere = HashSet::new();
+        let mut included_at = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<FileId> = main.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let Some(source) = load(id) else {
+                continue;
+            };
+            collect_font_args(
+                &LinkedNode::new(source.root()),
+                id,
+                &mut anywhere,
+                &mut included_at,
+                &mut stack,
+            );
+        }
+        Self {
+            load,
+            anywhere,
+            included_at,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// The families the run at `span` may legitimately be set in, lowercased.
+    fn at(&mut self, span: Span) -> &HashSet<String> {
+        if !self.cache.contains_key(&span) {
+            let families = self.resolve(span);
+            self.cache.insert(span, families);
+        }
+        &self.cache[&span]
+    }
+
+    fn resolve(&self, span: Span) -> HashSet<String> {
+        let located = span.id().and_then(|id| {
+            let source = (self.load)(id)?;
+            let node = source.find(span)?;
+            Some((id, node.offset(), text_context(&node)))
+        });
+        let Some((id, offset, context)) = located else {
+            // Text from a package, or generated: the project can't tell.
+            let mut families = self.anywhere.clone();
+            families.extend([BODY_FAMILY, MATH_FAMILY, RAW_FAMILY].map(String::from));
+            return families;
+        };
+
+        let mut families = match context {
+            TextContext::Math => HashSet::from([MATH_FAMILY.to_string()]),
+            TextContext::Raw => HashSet::from([RAW_FAMILY.to_string()]),
+            TextContext::Body => match self.enclosing_font(id, offset) {
+                Some(list) => return list.into_iter().collect(),
+                None => HashSet::from([BODY_FAMILY.to_string()]),
+            },
+        };
+        // Overridden by a show rule or a template we can't evaluate.
+        families.extend(self.anywhere.iter().cloned());
+        families
+    }
+
+    /// The font list of the innermost font rule in effect at `offset` of
+    /// file `id`, following the file's `#include` site when it has none.
+    fn enclosing_font(&self, mut id: FileId, mut offset: usize) -> Option<Vec<String>> {
+        let mut visited = HashSet::new();
+        while visited.insert(id) {
+            let source = (self.load)(id)?;
+            let root = LinkedNode::new(source.root());
+            if let Some(list) = root
+                .leaf_at(offset, typst::syntax::Side::After)
+                .and_then(|leaf| font_rule_above(&leaf))
+            {
+                return Some(list);
+            }
+            (id, offset) = *self.included_at.get(&id)?;
+        }
+        None
+    }
+}
+
+/// Whether `node` sits in an equation or in raw text.
+fn text_context(node: &LinkedNode) -> TextContext {
+    let mut current = Some(node.clone());
+    while let Some(node) = current {
+        match node.kind() {
+            SyntaxKind::Raw => return TextContext::Raw,
+            SyntaxKind::Equation => return TextContext::Math,
+            _ => {}
+        }
+        current = node.parent().cloned();
+    }
+    TextContext::Body
+}
+
+/// Walking outwards from `node`: the nearest `set text(font: ..)` before it
+/// in each enclosing block, or an enclosing `text(font: ..)` call.
+fn font_rule_above(node: &LinkedNode) -> Option<Vec<String>> {
+    let mut current = node.clone();
+    loop {
+        let mut sibling = current.prev_sibling();
+        while let Some(node) = sibling {
+            if let Some(set) = node.cast::<ast::SetRule>() {
+                if is_text(set.target()) {
+                    if let Some(list) = font_arg(set.args()) {
+                        return Some(list);
+                    }
+                }
+            }
+            sibling = node.prev_sibling();
+        }
+        let parent = current.parent()?.clone();
+        if let Some(call) = parent.cast::<ast::FuncCall>() {
+            if is_text(call.callee()) {
+                if let Some(list) = font_arg(call.args()) {
+                    return Some(list);
+                }
+            }
+        }
+        current = parent;
+    }
+}
+
+fn is_text(expr: ast::Expr) -> bool {
+    matches!(expr, ast::Expr::Ident(ident) if ident.as_str() == "text")
+}
+
+/// The `font:` argument of a call or set rule, lowercased.
+fn font_arg(args: ast::Args) -> Option<Vec<String>> {
+    args.items().find_map(|arg| match arg {
+        ast::Arg::Named(named) if named.name().as_str() == "font" => font_list(named.expr()),
+        _ => None,
+    })
+}
+
+/// A font family or list of families written as string literals, lowercased.
+fn font_list(expr: ast::Expr) -> Option<Vec<String>> {
+    match expr {
+        ast::Expr::Str(family) => Some(vec![family.get().as_str().to_lowercase()]),
+        ast::Expr::Array(list) => Some(
+            list.items()
+                .filter_map(|item| match item {
+                    ast::ArrayItem::Pos(ast::Expr::Str(family)) => {
+                        Some(family.get().as_str().to_lowercase())
+                    }
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Every `font:` argument under `node`, and the files it includes or imports
+/// (recording where each include sits).
+fn collect_font_args(
+    node: &LinkedNode,
+    id: FileId,
+    families: &mut HashSet<String>,
+    included_at: &mut HashMap<FileId, (FileId, usize)>,
+    stack: &mut Vec<FileId>,
+) {
+    if let Some(named) = node.cast::<ast::Named>() {
+        if named.name().as_str() == "font" {
+            families.extend(font_list(named.expr()).into_iter().flatten());
+        }
+    } else if let Some(include) = node.cast::<ast::ModuleInclude>() {
+        if let ast::Expr::Str(path) = include.source() {
+            if let Some(target) = resolve_relative(id, path.get().as_str()) {
+                included_at.entry(target).or_insert((id, node.offset()));
+                stack.push(target);
+            }
+        }
+    } else if let Some(import) = node.cast::<ast::ModuleImport>() {
+        if let ast::Expr::Str(path) = import.source() {
+            stack.extend(resolve_relative(id, path.get().as_str()));
+        }
+    }
+
+    for child in node.children() {
+        collect_font_args(&child, id, families, included_at, stack);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::local_file_id;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn id(path: &str) -> FileId {
+        local_file_id(Path::new(path)).expect("valid virtual path")
+    }
+
+    fn scan(files: &[(&str, &str)]) -> Vec<String> {
+        let sources: HashMap<FileId, Source> = files
+            .iter()
+            .map(|(path, text)| (id(path), Source::new(id(path), text.to_string())))
+            .collect();
+        let mut scan = StructureScan::new(|id| sources.get(&id).cloned());
+        scan.run(id(files[0].0));
+        scan.findings.iter().map(|f| f.message.clone()).collect()
+    }
+
+    // ─── Structure ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn an_image_without_alt_text_is_flagged() {
+        let messages = scan(&[("main.typ", "#image(\"a.png\")\n")]);
+        assert_eq!(messages, vec!["image has no alternative text"]);
+    }
+
+    #[test]
+    fn an_image_with_alt_text_passes() {
+        let messages = scan(&[("main.typ", "#image(\"a.png\", alt: \"A cat\")\n")]);
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn skipped_heading_levels_are_flagged() {
+        let messages = scan(&[("main.typ", "= One\n=== Three\n")]);
+        assert_eq!(messages, vec!["heading level skips from 1 to 3"]);
+    }
+
+    #[test]
+    fn going_back_up_any_number_of_levels_is_fine() {
+        let messages = scan(&[("main.typ", "= A\n== B\n=== C\n= D\n")]);
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn heading_order_follows_includes() {
+        // The chapter's `===` follows main's `=` in document order, even
+        // though the chapter file on its own starts at level 3.
+        let messages = scan(&[
+            ("main.typ", "= Intro\n#include \"ch/one.typ\"\n"),
+            ("ch/one.typ", "=== Deep\n"),
+        ]);
+        assert_eq!(messages, vec!["heading level skips from 1 to 3"]);
+    }
+
+    #[test]
+    fn tables_need_a_header_row() {
+        let missing = scan(&[("main.typ", "#table(columns: 2, [a], [b])\n")]);
+        assert_eq!(missing, vec!["table has no header row"]);
+
+        let present = scan(&[(
+            "main.typ",
+            "#table(columns: 2, table.header([A], [B]), [a], [b])\n",
+        )]);
+        assert!(present.is_empty());
+    }
+
+    #[test]
+    fn figures_need_a_caption() {
+        let messages = scan(&[("main.typ", "#figure(rect())\n")]);
+        assert_eq!(messages, vec!["figure has no caption"]);
+    }
+
+    #[test]
+    fn text_language_is_detected_from_the_compiled_document() {
+        let english = || [Lang::ENGLISH, Lang::ENGLISH];
+        assert!(!language_is_set(&Smart::Auto, english()));
+        // An explicit `lang: "en"` only shows in the document's locale.
+        assert!(language_is_set(&Smart::Custom(Locale::DEFAULT), english()));
+        // A template in a package that sets German only shows in the text.
+        assert!(language_is_set(&Smart::Auto, [Lang::GERMAN]));
+    }
+
+    #[test]
+    fn include_cycles_do_not_loop() {
+        let messages = scan(&[
+            ("a.typ", "#include \"b.typ\"\n"),
+            ("b.typ", "#include \"a.typ\"\n"),
+        ]);
+        assert!(messages.is_empty());
+    }
+
+    // ─── Contrast ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn black_on_white_is_the_maximum_contrast() {
+        let ratio = contrast_ratio([0, 0, 0], [255, 255, 255]);
+        assert!((ratio - 21.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn contrast_is_symmetric() {
+        let a = contrast_ratio([120, 30, 200], [250, 250, 210]);
+        let b = contrast_ratio([250, 250, 210], [120, 30, 200]);
+        assert!((a - b).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn light_grey_on_white_fails_the_body_text_minimum() {
+        // #aaaaaa on white is ~2.3:1.
+        assert!(contrast_ratio([0xaa, 0xaa, 0xaa], [255, 255, 255]) < MIN_CONTRAST_NORMAL);
+        // #595959 on white is ~7:1.
+        assert!(contrast_ratio([0x59, 0x59, 0x59], [255, 255, 255]) > MIN_CONTRAST_NORMAL);
+    }
+
+    // ─── Print ──────────────────────────────────────────────────────────────
+
+    fn requests(files: &[(&str, &str)]) -> FontRequests<impl Fn(FileId) -> Option<Source>> {
+        let sources: HashMap<FileId, Source> = files
+            .iter()
+            .map(|(path, text)| (id(path), Source::new(id(path), text.to_string())))
+            .collect();
+        FontRequests::new(Some(id(files[0].0)), move |id| sources.get(&id).cloned())
+    }
+
+    /// The families requested for the first run of `needle` in `path`.
+    fn requested_at(files: &[(&str, &str)], path: &str, needle: &str) -> Vec<String> {
+        let requests = requests(files);
+        let source = (requests.load)(id(path)).expect("file exists");
+        let offset = source.text().find(needle).expect("needle in file");
+        let span = LinkedNode::new(source.root())
+            .leaf_at(offset + 1, typst::syntax::Side::Before)
+            .expect("leaf at needle")
+            .span();
+        let mut families: Vec<String> = requests.resolve(span).into_iter().collect();
+        families.sort();
+        families
+    }
+
+    #[test]
+    fn without_font_rules_text_asks_for_the_typst_defaults() {
+        let files = [("main.typ", "Hello $x$ `code`\n")];
+        assert_eq!(
+            requested_at(&files, "main.typ", "Hello"),
+            ["libertinus serif"]
+        );
+        assert_eq!(
+            requested_at(&files, "main.typ", "x$"),
+            ["new computer modern math"]
+        );
+        assert_eq!(
+            requested_at(&files, "main.typ", "code"),
+            ["dejavu sans mono"]
+        );
+    }
+
+    #[test]
+    fn a_set_font_replaces_the_default_for_the_text_after_it() {
+        let files = [(
+            "main.typ",
+            "Before\n#set text(font: (\"Inter\", \"Noto Emoji\"))\nAfter\n",
+        )];
+        // No rule in sight yet, so any family the project names may apply.
+        assert_eq!(
+            requested_at(&files, "main.typ", "Before"),
+            ["inter", "libertinus serif", "noto emoji"]
+        );
+        assert_eq!(
+            requested_at(&files, "main.typ", "After"),
+            ["inter", "noto emoji"]
+        );
+    }
+
+    #[test]
+    fn set_rules_end_with_their_block_and_text_calls_apply_inside() {
+        let files = [(
+            "main.typ",
+            "#[#set text(font: \"Inter\")\nInside]\nOutside #text(font: \"Fira Sans\")[Called]\n",
+        )];
+        assert_eq!(requested_at(&files, "main.typ", "Inside"), ["inter"]);
+        assert_eq!(
+            requested_at(&files, "main.typ", "Outside"),
+            ["fira sans", "inter", "libertinus serif"]
+        );
+        assert_eq!(requested_at(&files, "main.typ", "Called"), ["fira sans"]);
+    }
+
+    #[test]
+    fn included_files_inherit_the_font_at_their_include_site() {
+        let files = [
+            (
+                "main.typ",
+                "#set text(font: \"Inter\")\n#include \"chapters/one.typ\"\n",
+            ),
+            ("chapters/one.typ", "Chapter text\n"),
+        ];
+        assert_eq!(
+            requested_at(&files, "chapters/one.typ", "Chapter"),
+            ["inter"]
+        );
+    }
+
+    #[test]
+    fn fonts_set_by_templates_are_accepted_where_no_rule_is_in_sight() {
+        let files = [
+            (
+                "main.typ",
+                "#import \"tpl/base.typ\": conf\n#show: conf\nBody\n",
+            ),
+            (
+                "tpl/base.typ",
+                "#let conf(body) = { set text(font: \"Fira Sans\"); body }\n",
+            ),
+        ];
+        assert_eq!(
+            requested_at(&files, "main.typ", "Body"),
+            ["fira sans", "libertinus serif"]
+        );
+    }
+
+    #[test]
+    fn effective_dpi_is_pixels_per_placed_inch() {
+        // 600 px across 2 inches (144pt) is 300 dpi.
+        assert!((effective_dpi(600, 144.0) - 300.0).abs() < 1e-9);
+        assert!(effective_dpi(600, 288.0) < MIN_PRINT_DPI);
+    }
+
+    #[test]
+    fn a_zero_size_image_never_reports_low_resolution() {
+        assert!(effective_dpi(10, 0.0) >= MIN_PRINT_DPI);
+    }
+
+    #[test]
+    fn content_inside_the_page_is_not_flagged() {
+        let page = typst::layout::Size::new(Abs::pt(100.0), Abs::pt(100.0));
+        let p = |x, y| Point::new(Abs::pt(x), Abs::pt(y));
+        assert!(!outside_page(p(0.0, 0.0), p(100.0, 100.0), page));
+        // Within the tolerance.
+        assert!(!outside_page(p(-0.2, 0.0), p(100.3, 100.0), page));
+        assert!(outside_page(p(90.0, 10.0), p(120.0, 20.0), page));
+        // Corners given flipped, as a rotation produces them.
+        assert!(outside_page(p(10.0, 10.0), p(-5.0, 20.0), page));
+    }
+
+    #[test]
+    fn missing_characters_are_described_by_code_point() {
+        assert_eq!(describe_chars("→"), "“→” (U+2192)");
+    }
+}
@@ -0,0 +1,853 @@
+// Quick fixes for common compile errors.
+//
+// `SerializedDiagnostic` tells the user what went wrong; this turns the most
+// common mistakes into edits the editor can apply with one click:
+//
+// - an identifier nothing binds → import it from the workspace file or the
+//   already used package that defines it
+// - a path literal naming a file that doesn't exist → point it at the closest
+//   existing file
+// - a named argument the called function has no parameter for → rename it to
+//   the closest parameter the function actually takes
+// - a citation in a workspace without any `#bibliography` → add one for a
+//   `.bib`/`.yml` file in the workspace to the main file
+//
+// Errors are recognised by the syntax node their span covers. Where one node
+// can carry unrelated errors — a citation whose key is merely unknown, a
+// string that is a colour rather than a path — the fixed start of typst's
+// message decides. Fixes are attached to each error as diagnostics are
+// serialised; the classification is cheap and rules out almost every error
+// before any fixer reads the workspace, and all errors of a compile (or of a
+// workspace diagnostics pass) share one listing of the workspace files.
+// Edit offsets are UTF-16, like every other offset crossing IPC.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use typst::{
+    diag::{Severity, SourceDiagnostic},
+    foundations::{Scope, Value},
+    syntax::{
+        ast,
+        package::{PackageManifest, PackageSpec},
+        FileId, LinkedNode, RootedPath, Side, Source, SyntaxKind, SyntaxNode, VirtualPath,
+        VirtualRoot,
+    },
+    World, WorldExt,
+};
+
+use super::compile::{walk_workspace_files, DiagnosticWorld};
+use crate::commands::editor::byte_to_utf16;
+use crate::world::local_file_id;
+
+/// How many alternatives to offer for a path or argument name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Extensions the bibliography fix offers, as typst accepts them.
+const BIBLIOGRAPHY_EXTENSIONS: &[&str] = &["bib", "yml", "yaml"];
+
+/// Start of the error a citation gets when no bibliography exists at all.
+/// A reference to an unknown label reads "label `<x>` does not exist", and
+/// a bibliography would not fix it.
+const NO_BIBLIOGRAPHY: &str = "the document does not contain a bibliography";
+
+/// Start of typst's message for `FileError::NotFound`.
+const FILE_NOT_FOUND: &str = "file not found";
+
+/// One replacement in one file. Offsets are UTF-16 code units.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TextEdit {
+    /// Absolute path of the file to edit.
+    pub path: String,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuickFix {
+    /// Menu label, e.g. "Import `title` from \"lib.typ\"".
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+/// The errors this module knows how to fix, recognised by syntax.
+#[derive(Debug, PartialEq)]
+enum Problem {
+    /// An identifier neither the standard library nor the file binds.
+    UnknownVariable(String),
+    /// A path literal naming no file; byte range of its contents.
+    FileNotFound(Range<usize>),
+    /// A named argument the callee doesn't take.
+    UnexpectedArgument {
+        /// Byte range of the argument name.
+        name: Range<usize>,
+        /// Named parameters the callee does take.
+        params: Vec<String>,
+    },
+    /// A citation in a workspace without a bibliography.
+    MissingBibliography,
+}
+
+/// Everything a fixer needs about the file the error is in.
+struct Context<'a> {
+    world: &'a dyn DiagnosticWorld,
+    view: &'a dyn World,
+    root: PathBuf,
+    id: FileId,
+    source: Source,
+    files: &'a WorkspaceListing,
+}
+
+impl Context<'_> {
+    /// Edit `range` (bytes) of `source`, converting to UTF-16 for the wire.
+    fn edit(&self, source: &Source, start: usize, end: usize, text: String) -> TextEdit {
+        let id = source.id();
+        TextEdit {
+            path: self
+                .root
+                .join(id.vpath().get_without_slash())
+                .to_string_lossy()
+                .into_owned(),
+            start: byte_to_utf16(source.text(), start),
+            end: byte_to_utf16(source.text(), end),
+            text,
+        }
+    }
+
+    fn rel(&self) -> &str {
+        self.id.vpath().get_without_slash()
+    }
+
+    /// Workspace-relative paths of every file in the workspace, walked the
+    /// first time any error of the compile needs them.
+    fn workspace_files(&self) -> &[String] {
+        self.files.0.get_or_init(|| {
+            walk_workspace_files(&self.root)
+                .into_iter()
+                .filter_map(|path| {
+                    let rel = path.strip_prefix(&self.root).ok()?;
+                    Some(rel.to_string_lossy().replace('\\', "/"))
+                })
+                .collect()
+        })
+    }
+
+    /// The `.typ` sources of the workspace, as the compile sees them.
+    fn workspace_sources(&self) -> Vec<Source> {
+        self.workspace_files()
+            .iter()
+            .filter(|rel| rel.ends_with(".typ"))
+            .filter_map(|rel| self.view.source(local_file_id(Path::new(rel))?).ok())
+            .collect()
+    }
+}
+
+/// The workspace's files, listed at most once for all the errors of a
+/// compile — or of a whole workspace diagnostics pass — that need them.
+#[derive(Default)]
+pub(crate) struct WorkspaceListing(OnceLock<Vec<String>>);
+
+/// Quick fixes for the errors of one compile, read against the world it ran
+/// in.
+pub(crate) struct QuickFixer<'a> {
+    world: &'a dyn DiagnosticWorld,
+    view: &'a dyn World,
+    files: &'a WorkspaceListing,
+}
+
+impl<'a> QuickFixer<'a> {
+    pub(crate) fn new(
+        world: &'a dyn DiagnosticWorld,
+        view: &'a dyn World,
+        files: &'a WorkspaceListing,
+    ) -> Self {
+        Self { world, view, files }
+    }
+
+    /// Fixes for the error `diag`. Warnings and errors outside the
+    /// workspace's own files get none.
+    pub(crate) fn fixes(&self, diag: &SourceDiagnostic) -> Vec<QuickFix> {
+        if diag.severity != Severity::Error {
+            return Vec::new();
+        }
+        let Some(id) = diag
+            .span
+            .id()
+            .filter(|id| matches!(id.root(), VirtualRoot::Project))
+        else {
+            return Vec::new();
+        };
+        let (Some(range), Ok(source)) = (self.view.range(diag.span), self.view.source(id)) else {
+            return Vec::new();
+        };
+
+        let ctx = Context {
+            world: self.world,
+            view: self.view,
+            root: self.world.workspace_root(),
+            id,
+            source,
+            files: self.files,
+        };
+        let root = LinkedNode::new(ctx.source.root());
+        let Some(problem) =
+            node_at(&root, range).and_then(|node| classify(&ctx, &node, &diag.message))
+        else {
+            return Vec::new();
+        };
+        match problem {
+            Problem::UnknownVariable(name) => import_fixes(&ctx, &name),
+            Problem::FileNotFound(range) => path_fixes(&ctx, range),
+            Problem::UnexpectedArgument { name, params } => argument_fixes(&ctx, name, params),
+            Problem::MissingBibliography => bibliography_fixes(&ctx),
+        }
+    }
+}
+
+/// The innermost node covering exactly `range`, or the leaf it starts at when
+/// no node matches (a range typst narrowed inside one token).
+fn node_at<'a>(root: &LinkedNode<'a>, range: Range<usize>) -> Option<LinkedNode<'a>> {
+    let leaf = root.leaf_at(range.start, Side::After)?;
+    let exact = std::iter::successors(Some(leaf.clone()), |node| node.parent().cloned())
+        .take_while(|node| node.range().end <= range.end)
+        .find(|node| node.range() == range);
+    Some(exact.unwrap_or(leaf))
+}
+
+fn classify(ctx: &Context, node: &LinkedNode, message: &str) -> Option<Problem> {
+    match node.kind() {
+        SyntaxKind::Named => unexpected_argument(ctx, node),
+        SyntaxKind::Ident if is_argument_name(node) => unexpected_argument(ctx, node.parent()?),
+        SyntaxKind::Ident | SyntaxKind::MathIdent => unknown_variable(ctx, node),
+        SyntaxKind::Ref if message.starts_with(NO_BIBLIOGRAPHY) => {
+            citation_without_bibliography(ctx)
+        }
+        SyntaxKind::FuncCall
+            if callee_name(node).as_deref() == Some("cite")
+                && message.starts_with(NO_BIBLIOGRAPHY) =>
+        {
+            citation_without_bibliography(ctx)
+        }
+        _ if message.starts_with(FILE_NOT_FOUND) => missing_file(ctx, node),
+        _ => None,
+    }
+}
+
+/// Whether `node` is the name (not the value) of a named argument.
+fn is_argument_name(node: &LinkedNode) -> bool {
+    node.parent().is_some_and(|parent| {
+        parent.kind() == SyntaxKind::Named && parent.offset() == node.offset()
+    })
+}
+
+/// The name of the identifier a call is made through, if it is one.
+fn callee_name(call: &LinkedNode) -> Option<String> {
+    match call.cast::<ast::FuncCall>()?.callee() {
+        ast::Expr::Ident(ident) => Some(ident.as_str().to_string()),
+        _ => None,
+    }
+}
+
+fn unknown_variable(ctx: &Context, node: &LinkedNode) -> Option<Problem> {
+    let name = node.leaf_text().as_str();
+    let library = ctx.view.library();
+    let in_std = library.global.scope().get(name).is_some()
+        || (node.kind() == SyntaxKind::MathIdent && library.math.scope().get(name).is_some());
+    if in_std || bindings_in(ctx.source.root()).contains(name) {
+        return None;
+    }
+    Some(Problem::UnknownVariable(name.to_string()))
+}
+
+fn unexpected_argument(ctx: &Context, named: &LinkedNode) -> Option<Problem> {
+    let args = named
+        .parent()
+        .filter(|node| node.kind() == SyntaxKind::Args)?;
+    let call = args.parent()?.cast::<ast::FuncCall>()?;
+    let ident = named
+        .children()
+        .find(|child| child.kind() == SyntaxKind::Ident)?;
+
+    let bound = bindings_in(ctx.source.root());
+    let Value::Func(func) = std_value(ctx.view, &bound, call.callee())? else {
+        return None;
+    };
+    let params: Vec<String> = func
+        .params()
+        .filter_map(|param| param.to_native())
+        .filter(|info| info.named)
+        .map(|info| info.name.to_string())
+        .collect();
+    if params.is_empty()
+        || params
+            .iter()
+            .any(|param| param == ident.leaf_text().as_str())
+    {
+        return None;
+    }
+    Some(Problem::UnexpectedArgument {
+        name: ident.range(),
+        params,
+    })
+}
+
+fn citation_without_bibliography(ctx: &Context) -> Option<Problem> {
+    let has_bibliography = ctx
+        .workspace_sources()
+        .iter()
+        .any(|source| calls_bibliography(source.root()));
+    (!has_bibliography).then_some(Problem::MissingBibliography)
+}
+
+fn missing_file(ctx: &Context, node: &LinkedNode) -> Option<Problem> {
+    let range = string_at(&ctx.source, node.offset(), node.offset() + node.len())?;
+    let written = &ctx.source.text()[range.clone()];
+    if written.is_empty() || written.starts_with('@') || written.contains("://") {
+        return None;
+    }
+    let target = match written.strip_prefix('/') {
+        Some(rooted) => ctx.root.join(rooted),
+        None => {
+            let dir = ctx.rel().rsplit_once('/').map_or("", |(dir, _)| dir);
+            ctx.root.join(dir).join(written)
+        }
+    };
+    (!target.exists()).then_some(Problem::FileNotFound(range))
+}
+
+/// The standard library value `expr` names: an identifier the file doesn't
+/// rebind, or a field of one (`calc.round`, `table.cell`). User-defined
+/// closures carry no parameter metadata, so they aren't followed.
+fn std_value(view: &dyn World, bound: &BTreeSet<String>, expr: ast::Expr) -> Option<Value> {
+    match expr {
+        ast::Expr::Ident(ident) if !bound.contains(ident.as_str()) => view
+            .library()
+            .global
+            .scope()
+            .get(ident.as_str())
+            .map(|binding| binding.read().clone()),
+        ast::Expr::MathIdent(ident) if !bound.contains(ident.as_str()) => view
+            .library()
+            .math
+            .scope()
+            .get(ident.as_str())
+            .map(|binding| binding.read().clone()),
+        ast::Expr::FieldAccess(access) => {
+            let target = std_value(view, bound, access.target())?;
+            let scope: &Scope = match &target {
+                Value::Module(module) => module.scope(),
+                Value::Func(func) => func.scope()?,
+                _ => return None,
+            };
+            scope
+                .get(access.field().as_str())
+                .map(|binding| binding.read().clone())
+        }
+        _ => None,
+    }
+}
+
+/// Every name the file binds anywhere: lets, parameters, loop variables and
+/// imported items. Scoping is ignored, which errs towards offering no fix.
+fn bindings_in(root: &SyntaxNode) -> BTreeSet<String> {
+    fn walk(node: &SyntaxNode, names: &mut BTreeSet<String>) {
+        let mut idents = Vec::new();
+        if let Some(binding) = node.cast::<ast::LetBinding>() {
+            idents.extend(binding.kind().bindings());
+        } else if let Some(closure) = node.cast::<ast::Closure>() {
+            idents.extend(closure.name());
+            for param in closure.params().children() {
+                match param {
+                    ast::Param::Pos(pattern) => idents.extend(pattern.bindings()),
+                    ast::Param::Named(named) => idents.push(named.name()),
+                    ast::Param::Spread(spread) => idents.extend(spread.sink_ident()),
+                }
+            }
+        } else if let Some(for_loop) = node.cast::<ast::ForLoop>() {
+            idents.extend(for_loop.pattern().bindings());
+        } else if let Some(import) = node.cast::<ast::ModuleImport>() {
+            idents.extend(import.new_name());
+            if let Some(ast::Imports::Items(items)) = import.imports() {
+                idents.extend(items.iter().map(|item| item.bound_name()));
+            }
+        }
+        names.extend(idents.into_iter().map(|ident| ident.as_str().to_string()));
+        for child in node.children() {
+            walk(child, names);
+        }
+    }
+    let mut names = BTreeSet::new();
+    walk(root, &mut names);
+    names
+}
+
+/// Whether anything under `node` calls `bibliography`.
+fn calls_bibliography(node: &SyntaxNode) -> bool {
+    let call = node.cast::<ast::FuncCall>().is_some_and(
+        |call| matches!(call.callee(), ast::Expr::Ident(ident) if ident.as_str() == "bibliography"),
+    );
+    call || node.children().any(calls_bibliography)
+}
+
+// ─── Unknown variable ───────────────────────────────────────────────────────
+
+fn import_fixes(ctx: &Context, name: &str) -> Vec<QuickFix> {
+    let at = import_insertion_point(&ctx.source);
+    let mut fixes = Vec::new();
+
+    for source in ctx.workspace_sources() {
+        let rel = source.id().vpath().get_without_slash().to_string();
+        if rel == ctx.rel() {
+            continue;
+        }
+        if top_level_bindings(source.root()).contains(name) {
+            let target = import_path(ctx.rel(), &rel);
+            fixes.push(import_fix(ctx, at, &target, name));
+        }
+    }
+
+    for spec in imported_packages(ctx) {
+        let Some(entry) = package_entrypoint(ctx.view, &spec) else {
+            continue;
+        };
+        if top_level_bindings(entry.root()).contains(name) {
+            fixes.push(import_fix(ctx, at, &spec.to_string(), name));
+        }
+    }
+
+    fixes
+}
+
+fn import_fix(ctx: &Context, at: usize, target: &str, name: &str) -> QuickFix {
+    QuickFix {
+        title: format!("Import `{name}` from \"{target}\""),
+        edits: vec![ctx.edit(
+            &ctx.source,
+            at,
+            at,
+            format!("#import \"{target}\": {name}\n"),
+        )],
+    }
+}
+
+/// Byte offset at which a new import line goes: the start of the line after
+/// the last top-level import, or the top of the file.
+fn import_insertion_point(source: &Source) -> usize {
+    let text = source.text();
+    let last_import_end = LinkedNode::new(source.root())
+        .children()
+        .filter(|node| node.kind() == SyntaxKind::ModuleImport)
+        .map(|node| node.offset() + node.len())
+        .max();
+    match last_import_end {
+        Some(end) => text[end..].find('\n').map_or(text.len(), |nl| end + nl + 1),
+        None => 0,
+    }
+}
+
+/// Names a file defines at its top level, i.e. what `import "file": *` brings
+/// into scope (re-exports through nested imports are not followed).
+fn top_level_bindings(root: &SyntaxNode) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let Some(markup) = root.cast::<ast::Markup>() else {
+        return names;
+    };
+    for expr in markup.exprs() {
+        if let ast::Expr::LetBinding(binding) = expr {
+            names.extend(
+                binding
+                    .kind()
+                    .bindings()
+                    .into_iter()
+                    .map(|ident| ident.as_str().to_string()),
+            );
+        }
+    }
+    names
+}
+
+/// Path `to` as written in an import inside `from` (both workspace-relative):
+/// relative when `to` sits in `from`'s directory or below it, root-relative
+/// otherwise, which stays correct wherever either file moves.
+fn import_path(from: &str, to: &str) -> String {
+    let dir = from.rsplit_once('/').map_or("", |(dir, _)| dir);
+    if dir.is_empty() {
+        return to.to_string();
+    }
+    match to.strip_prefix(dir).and_then(|rest| rest.strip_prefix('/')) {
+        Some(below) => below.to_string(),
+        None => format!("/{to}"),
+    }
+}
+
+/// Packages any workspace file already imports. Offering only these keeps
+/// the suggestion list to packages the user has chosen, and ones that are
+/// installed locally.
+fn imported_packages(ctx: &Context) -> Vec<PackageSpec> {
+    let mut specs = BTreeMap::new();
+    for source in ctx.workspace_sources() {
+        collect_package_imports(source.root(), &mut specs);
+    }
+    specs.into_values().collect()
+}
+
+/// Package imports under `node`, keyed by their spec string for a stable order.
+fn collect_package_imports(node: &SyntaxNode, out: &mut BTreeMap<String, PackageSpec>) {
+    if let Some(import) = node.cast::<ast::ModuleImport>() {
+        if let ast::Expr::Str(path) = import.source() {
+            if let Ok(spec) = path.get().parse::<PackageSpec>() {
+                out.insert(path.get().to_string(), spec);
+            }
+        }
+    }
+    for child in node.children() {
+        collect_package_imports(child, out);
+    }
+}
+
+/// The entrypoint source of an installed package, per its `typst.toml`.
+fn package_entrypoint(world: &dyn World, spec: &PackageSpec) -> Option<Source> {
+    let file_in = |path: &str| {
+        let vpath = VirtualPath::new(path).ok()?;
+        Some(RootedPath::new(VirtualRoot::Package(spec.clone()), vpath).intern())
+    };
+    let manifest = world.file(file_in("typst.toml")?).ok()?;
+    let manifest: PackageManifest = toml::from_str(std::str::from_utf8(&manifest).ok()?).ok()?;
+    world
+        .source(file_in(manifest.package.entrypoint.as_str())?)
+        .ok()
+}
+
+// ─── File not found ─────────────────────────────────────────────────────────
+
+fn path_fixes(ctx: &Context, range: Range<usize>) -> Vec<QuickFix> {
+    let written = &ctx.source.text()[range.clone()];
+    let extension = Path::new(written)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+
+    let candidates: Vec<String> = ctx
+        .workspace_files()
+        .iter()
+        .filter(|rel| *rel != ctx.rel())
+        .filter(|rel| {
+            extension.as_deref().is_none_or(|ext| {
+                Path::new(rel)
+                    .extension()
+                    .is_some_and(|e| e.to_string_lossy().to_lowercase() == ext)
+            })
+        })
+        .map(|rel| {
+            if written.starts_with('/') {
+                format!("/{rel}")
+            } else {
+                relative_path(ctx.rel(), rel)
+            }
+        })
+        .collect();
+
+    closest(written, candidates)
+        .into_iter()
+        .map(|path| QuickFix {
+            title: format!("Change path to \"{path}\""),
+            edits: vec![ctx.edit(&ctx.source, range.start, range.end, path)],
+        })
+        .collect()
+}
+
+/// Byte range of the contents (without quotes) of the first string literal
+/// within `start..end`.
+fn string_at(source: &Source, start: usize, end: usize) -> Option<Range<usize>> {
+    let root = LinkedNode::new(source.root());
+    let mut node = root.leaf_at(start + 1, Side::Before)?;
+    while node.kind() != SyntaxKind::Str {
+        // An error spanning the whole call (`image("x.png")`) still starts
+        // before the literal; look for it inside the range.
+        let next = node.next_leaf()?;
+        if next.offset() >= end.max(start + 1) {
+            return None;
+        }
+        node = next;
+    }
+    let (from, to) = (node.offset(), node.offset() + node.len());
+    (to >= from + 2).then_some(from + 1..to - 1)
+}
+
+/// Path to workspace-relative `to` as written inside workspace-relative
+/// `from`, climbing with `..` where needed.
+fn relative_path(from: &str, to: &str) -> String {
+    let mut from_dir: Vec<&str> = from.split('/').collect();
+    from_dir.pop();
+    let to_parts: Vec<&str> = to.split('/').collect();
+    let common = from_dir
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts: Vec<&str> = vec![".."; from_dir.len() - common];
+    parts.extend(&to_parts[common..]);
+    parts.join("/")
+}
+
+// ─── Unexpected argument ────────────────────────────────────────────────────
+
+fn argument_fixes(ctx: &Context, name: Range<usize>, params: Vec<String>) -> Vec<QuickFix> {
+    let written = &ctx.source.text()[name.clone()];
+    closest(written, params)
+        .into_iter()
+        .map(|param| QuickFix {
+            title: format!("Change `{written}` to `{param}`"),
+            edits: vec![ctx.edit(&ctx.source, name.start, name.end, param)],
+        })
+        .collect()
+}
+
+// ─── Missing bibliography ───────────────────────────────────────────────────
+
+fn bibliography_fixes(ctx: &Context) -> Vec<QuickFix> {
+    // A bibliography belongs to the document, so it goes at the end of the
+    // main file rather than into whichever chapter cited something.
+    let Some(main) = ctx.world.entry().and_then(|id| ctx.view.source(id).ok()) else {
+        return Vec::new();
+    };
+    let main_rel = main.id().vpath().get_without_slash().to_string();
+    let end = main.text().len();
+    let separator = if main.text().is_empty() || main.text().ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+
+    ctx.workspace_files()
+        .iter()
+        .filter(|rel| {
+            Path::new(rel).extension().is_some_and(|ext| {
+                BIBLIOGRAPHY_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+            })
+        })
+        .map(|rel| {
+            let path = relative_path(&main_rel, rel);
+            QuickFix {
+                title: format!("Add #bibliography(\"{path}\") to {main_rel}"),
+                edits: vec![ctx.edit(
+                    &main,
+                    end,
+                    end,
+                    format!("{separator}\n#bibliography(\"{path}\")\n"),
+                )],
+            }
+        })
+        .collect()
+}
+
+// ─── Ranking ────────────────────────────────────────────────────────────────
+
+/// The candidates closest to `written`, best first. A candidate further away
+/// than half its own length is a different word, not a typo, and is dropped.
+fn closest(written: &str, candidates: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut ranked: Vec<(usize, String)> = candidates
+        .into_iter()
+        .filter(|candidate| candidate != written)
+        .map(|candidate| (edit_distance(written, &candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.chars().count() / 2).max(2))
+        .collect();
+    ranked.sort();
+    ranked.dedup();
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Levenshtein distance, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use typst_kit::fonts::FontStore;
+    use typst_layout::PagedDocument;
+
+    use super::*;
+    use crate::world::{headless_library, HeadlessWorld};
+
+    fn source(text: &str) -> Source {
+        Source::detached(text)
+    }
+
+    /// Compile `main.typ` holding `text` in a workspace with no files on
+    /// disk, and return the first error's classification.
+    fn problem_in(text: &str) -> Option<Problem> {
+        let root = std::env::temp_dir().join("typwriter-quickfix-no-such-workspace");
+        let main = local_file_id(Path::new("main.typ")).expect("valid id");
+        let shadows = [("main.typ".to_string(), text.to_string())];
+        let library = headless_library(&BTreeMap::new());
+        let fonts = FontStore::new();
+        let world = HeadlessWorld::new(&root, main, &shadows, &library, &fonts, &[]);
+
+        let errors = typst::compile::<PagedDocument>(&world)
+            .output
+            .expect_err("the document has an error");
+        let diag = errors.first().expect("one error");
+        let ctx = Context {
+            world: &world,
+            view: &world,
+            root: root.clone(),
+            id: main,
+            source: world.source(main).expect("main is shadowed"),
+            files: &WorkspaceListing::default(),
+        };
+        let range = world.range(diag.span).expect("error is in main.typ");
+        let tree = LinkedNode::new(ctx.source.root());
+        classify(
+            &ctx,
+            &node_at(&tree, range).expect("node at span"),
+            &diag.message,
+        )
+    }
+
+    #[test]
+    fn an_unbound_identifier_is_an_unknown_variable() {
+        assert_eq!(
+            problem_in("#intro\n"),
+            Some(Problem::UnknownVariable("intro".into()))
+        );
+    }
+
+    #[test]
+    fn a_misused_bound_identifier_is_not_an_unknown_variable() {
+        // `x` is bound, so the error ("expected function") has no import fix.
+        assert_eq!(problem_in("#let x = 1\n#x()\n"), None);
+    }
+
+    #[test]
+    fn an_unmatched_named_argument_lists_the_callee_parameters() {
+        let text = "#text(sise: 12pt)[Hi]\n";
+        let Some(Problem::UnexpectedArgument { name, params }) = problem_in(text) else {
+            panic!("expected an unexpected argument");
+        };
+        assert_eq!(&text[name], "sise");
+        assert_eq!(closest("sise", params), vec!["size"]);
+    }
+
+    #[test]
+    fn a_missing_path_literal_is_a_file_not_found() {
+        let text = "#image(\"fig/plot.pgn\")\n";
+        let Some(Problem::FileNotFound(range)) = problem_in(text) else {
+            panic!("expected a missing file");
+        };
+        assert_eq!(&text[range], "fig/plot.pgn");
+    }
+
+    #[test]
+    fn a_string_with_a_type_error_is_not_a_missing_file() {
+        assert_eq!(problem_in("#text(fill: \"red\")[Hi]\n"), None);
+    }
+
+    #[test]
+    fn a_citation_without_any_bibliography_needs_one() {
+        assert_eq!(
+            problem_in("#cite(<knuth>)\n"),
+            Some(Problem::MissingBibliography)
+        );
+    }
+
+    #[test]
+    fn a_reference_to_an_unknown_label_does_not_offer_a_bibliography() {
+        assert_eq!(problem_in("See @intro.\n"), None);
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("sise", "size"), 1);
+        assert_eq!(edit_distance("fil", "fill"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn closest_ranks_typos_and_drops_unrelated_names() {
+        let params = ["size", "fill", "font", "weight"].map(String::from);
+        assert_eq!(closest("sise", params.clone()), vec!["size"]);
+        assert!(closest("baseline", params).is_empty());
+    }
+
+    #[test]
+    fn top_level_lets_are_exports_but_nested_ones_are_not() {
+        let src = source(
+            "#let title = [T]\n#let (a, b) = (1, 2)\n#let f(x) = { let inner = x; inner }\n",
+        );
+        let names = top_level_bindings(src.root());
+        assert_eq!(
+            names.into_iter().collect::<Vec<_>>(),
+            vec!["a", "b", "f", "title"]
+        );
+    }
+
+    #[test]
+    fn every_binding_site_counts_as_binding_a_name() {
+        let src = source(
+            "#import \"lib.typ\": helper\n#let f(x, y: 1, ..rest) = x\n#for item in () {}\n",
+        );
+        let names = bindings_in(src.root());
+        for name in ["helper", "f", "x", "y", "rest", "item"] {
+            assert!(names.contains(name), "{name} should be bound");
+        }
+    }
+
+    #[test]
+    fn imports_go_after_the_last_top_level_import() {
+        let src = source("#import \"a.typ\": x\n#import \"b.typ\": y\n= Heading\n");
+        let at = import_insertion_point(&src);
+        assert_eq!(&src.text()[at..], "= Heading\n");
+    }
+
+    #[test]
+    fn without_imports_the_new_one_goes_first() {
+        assert_eq!(import_insertion_point(&source("= Heading\n")), 0);
+    }
+
+    #[test]
+    fn import_paths_are_relative_below_the_importer_and_rooted_elsewhere() {
+        assert_eq!(import_path("main.typ", "lib/util.typ"), "lib/util.typ");
+        assert_eq!(
+            import_path("ch/one.typ", "ch/parts/two.typ"),
+            "parts/two.typ"
+        );
+        assert_eq!(import_path("ch/one.typ", "lib/util.typ"), "/lib/util.typ");
+    }
+
+    #[test]
+    fn relative_paths_climb_out_of_the_file_directory() {
+        assert_eq!(relative_path("main.typ", "img/a.png"), "img/a.png");
+        assert_eq!(relative_path("ch/one.typ", "img/a.png"), "../img/a.png");
+        assert_eq!(relative_path("ch/one.typ", "ch/a.png"), "a.png");
+    }
+
+    #[test]
+    fn the_string_literal_is_found_inside_a_call_range() {
+        let src = source("#image(\"fig/plot.pgn\")\n");
+        let range = string_at(&src, 1, 21).unwrap();
+        assert_eq!(&src.text()[range], "fig/plot.pgn");
+    }
+}
@@ -0,0 +1,551 @@
+// Machine-readable diagnostics reports.
+//
+// The diagnostics panel is the only consumer of the merged error/warning list
+// inside the app. CI wants the same list as a file it can annotate a pull
+// request from, and reviewers want something they can attach to a ticket, so
+// the list can be written as SARIF 2.1.0 (what code-scanning upload actions
+// understand) or as plain JSON (the `SerializedDiagnostic` shape the frontend
+// already receives).
+//
+// Paths are the workspace-relative paths diagnostics already carry, so a
+// report produced on a developer machine and one produced in CI agree.
+// Package files keep their absolute on-disk path; they are not part of the
+// repository and SARIF consumers ignore locations they cannot map.
+//
+// The same report can be produced without the app:
+//
+//     typwriter --export-diagnostics <workspace> [--main main.typ]
+//         [--format sarif|json] [--output report.sarif] [--font-dir dir]...
+//         [--registry namespace=location]...
+//
+// compiles the workspace from disk with a `HeadlessWorld`, exactly as the
+// preview and the workspace diagnostics pass would, and exits non-zero when
+// any error was found. Missing packages are downloaded like in the app: from
+// Typst Universe, or from the custom registry given for their namespace. The
+// workspace's `typwriter.toml` applies as it does in the app — its main file,
+// inputs and font directories, and its problems are part of the report — with
+// `--main` and `--font-dir` taking precedence.
+
+use std::path::{Path, PathBuf};
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::compile::{
+    collect_workspace_diagnostics, compile_in, dedup_merge, DiagnosticRange, SerializedDiagnostic,
+    WorkspaceDiagCache,
+};
+use crate::vcs::fs::LocalWorkingTreeFs;
+use crate::workspace::project_config;
+use crate::world::{
+    headless_fonts, headless_library, local_file_id, local_package_dirs, HeadlessPackages,
+    HeadlessWorld, PackageRegistry,
+};
+
+/// First argument that turns the executable into the headless exporter.
+pub const DIAGNOSTICS_FLAG: &str = "--export-diagnostics";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Sarif,
+    Json,
+}
+
+impl ReportFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "sarif" => Some(Self::Sarif),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Render `errors` and `warnings` as a pretty-printed report.
+pub fn render_report(
+    format: ReportFormat,
+    errors: &[SerializedDiagnostic],
+    warnings: &[SerializedDiagnostic],
+) -> String {
+    let value = match format {
+        ReportFormat::Sarif => sarif_report(errors, warnings),
+        ReportFormat::Json => json_report(errors, warnings),
+    };
+    // Serialising a `Value` cannot fail.
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+fn json_report(errors: &[SerializedDiagnostic], warnings: &[SerializedDiagnostic]) -> Value {
+    json!({
+        "tool": "typwriter",
+        "version": env!("CARGO_PKG_VERSION"),
+        "errors": errors.len(),
+        "warnings": warnings.len(),
+        "diagnostics": errors.iter().chain(warnings).collect::<Vec<_>>(),
+    })
+}
+
+fn sarif_report(errors: &[SerializedDiagnostic], warnings: &[SerializedDiagnostic]) -> Value {
+    let results: Vec<Value> = errors.iter().chain(warnings).map(sarif_result).collect();
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "typwriter",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://typst.app/docs",
+                }
+            },
+            // Diagnostic columns count characters, not UTF-16 units.
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+fn sarif_result(diag: &SerializedDiagnostic) -> Value {
+    let level = if diag.severity == "error" {
+        "error"
+    } else {
+        "warning"
+    };
+    // Most viewers only show `message.text`, so hints go there too.
+    let mut text = diag.message.clone();
+    for hint in &diag.hints {
+        text.push_str("\nhint: ");
+        text.push_str(hint);
+    }
+
+    let mut result = json!({
+        "level": level,
+        "message": { "text": text },
+        "properties": { "hints": diag.hints },
+    });
+    if let Some(location) = sarif_location(diag.file_path.as_deref(), diag.range.as_ref()) {
+        result["locations"] = json!([location]);
+    }
+    // Trace frames become related locations, innermost first, so a viewer
+    // can walk from the package internals back to the user's call site.
+    let related: Vec<Value> = diag
+        .trace
+        .iter()
+        .enumerate()
+        .filter_map(|(i, frame)| {
+            let mut location = sarif_location(frame.file_path.as_deref(), frame.range.as_ref())?;
+            location["id"] = json!(i);
+            location["message"] = json!({ "text": frame.message });
+            if let Some(package) = &frame.package {
+                location["properties"] = json!({ "package": package });
+            }
+            Some(location)
+        })
+        .collect();
+    if !related.is_empty() {
+        result["relatedLocations"] = json!(related);
+    }
+    result
+}
+
+fn sarif_location(path: Option<&str>, range: Option<&DiagnosticRange>) -> Option<Value> {
+    let path = path?;
+    let mut artifact = json!({ "uri": path.replace('\\', "/") });
+    if !Path::new(path).is_absolute() {
+        artifact["uriBaseId"] = json!("%SRCROOT%");
+    }
+    let mut physical = json!({ "artifactLocation": artifact });
+    // SARIF lines and columns are 1-based; ours are 0-based.
+    if let Some(range) = range {
+        physical["region"] = json!({
+            "startLine": range.start_line + 1,
+            "startColumn": range.start_col + 1,
+            "endLine": range.end_line + 1,
+            "endColumn": range.end_col + 1,
+        });
+    }
+    Some(json!({ "physicalLocation": physical }))
+}
+
+// ─── Headless export ────────────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq)]
+struct CliArgs {
+    root: PathBuf,
+    main: Option<String>,
+    format: ReportFormat,
+    output: Option<PathBuf>,
+    font_dirs: Vec<PathBuf>,
+    registries: Vec<PackageRegistry>,
+}
+
+const USAGE: &str = "usage: typwriter --export-diagnostics <workspace> [--main <file>] \
+                     [--format sarif|json] [--output <file>] [--font-dir <dir>]... \
+                     [--registry <namespace>=<dir or url>]...";
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliArgs, String> {
+    let mut root = None;
+    let mut main = None;
+    let mut format = ReportFormat::Sarif;
+    let mut output = None;
+    let mut font_dirs = Vec::new();
+    let mut registries = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| format!("{flag} needs a value"));
+        match arg.as_str() {
+            "--main" => main = Some(value("--main")?),
+            "--format" => {
+                let v = value("--format")?;
+                format = ReportFormat::parse(&v).ok_or_else(|| format!("unknown format '{v}'"))?;
+            }
+            "--output" => output = Some(PathBuf::from(value("--output")?)),
+            "--font-dir" => font_dirs.push(PathBuf::from(value("--font-dir")?)),
+            "--registry" => {
+                let v = value("--registry")?;
+                let (namespace, location) = v
+                    .split_once('=')
+                    .ok_or_else(|| format!("--registry needs namespace=location, got '{v}'"))?;
+                let registry = PackageRegistry {
+                    namespace: namespace.trim().to_string(),
+                    location: location.trim().to_string(),
+                };
+                registry.validate()?;
+                registries.push(registry);
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{flag}'")),
+            _ if root.is_none() => root = Some(PathBuf::from(&arg)),
+            _ => return Err(format!("unexpected argument '{arg}'")),
+        }
+    }
+
+    Ok(CliArgs {
+        root: root.ok_or("missing workspace directory")?,
+        main,
+        format,
+        output,
+        font_dirs,
+        registries,
+    })
+}
+
+/// Entry point for `typwriter --export-diagnostics`. `args` are the
+/// arguments after the flag. Returns the process exit code: 0 when the
+/// workspace has no errors, 1 when it has, 2 for usage or I/O failures.
+pub fn run_diagnostics_cli(args: impl IntoIterator<Item = String>) -> i32 {
+    #[cfg(windows)]
+    attach_parent_console();
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return 2;
+        }
+    };
+    let root = match args.root.canonicalize() {
+        Ok(root) if root.is_dir() => root,
+        _ => {
+            eprintln!("not a directory: {}", args.root.display());
+            return 2;
+        }
+    };
+
+    let packages = HeadlessPackages::new(args.registries);
+    let (errors, warnings) = match collect(&root, args.main.as_deref(), &args.font_dirs, &packages)
+    {
+        Ok(diags) => diags,
+        Err(e) => {
+            eprintln!("{e}");
+            return 2;
+        }
+    };
+    let report = render_report(args.format, &errors, &warnings);
+
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, report) {
+                eprintln!("cannot write {}: {e}", path.display());
+                return 2;
+            }
+        }
+        None => println!("{report}"),
+    }
+    eprintln!("{} error(s), {} warning(s)", errors.len(), warnings.len());
+    i32::from(!errors.is_empty())
+}
+
+/// Release builds use the Windows GUI subsystem, so the process starts with no
+/// console and `println!`/`eprintln!` go nowhere. Borrow the console of the
+/// shell that ran us — unless the output was redirected to a file or pipe,
+/// which the GUI subsystem keeps and which must win.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{
+        AttachConsole, GetStdHandle, ATTACH_PARENT_PROCESS, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+    };
+
+    // SAFETY: both calls take plain integers; a null handle means "no stream".
+    let redirected = unsafe {
+        !GetStdHandle(STD_OUTPUT_HANDLE).is_null() || !GetStdHandle(STD_ERROR_HANDLE).is_null()
+    };
+    if !redirected {
+        // SAFETY: as above. Fails harmlessly when there is no parent console
+        // (started from Explorer); output is then lost, as before.
+        unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+    }
+}
+
+/// Compile `main` (when given) and diagnose every other `.typ` file in the
+/// workspace, merged the way the app merges them.
+fn collect(
+    root: &Path,
+    main: Option<&str>,
+    font_dirs: &[PathBuf],
+    downloads: &HeadlessPackages,
+) -> Result<(Vec<SerializedDiagnostic>, Vec<SerializedDiagnostic>), String> {
+    let (project, problems) = project_config::load(&LocalWorkingTreeFs, root);
+    let main = main.or(project.choose_main(None));
+    let font_dirs: Vec<PathBuf> = font_dirs
+        .iter()
+        .cloned()
+        .chain(project.font_dirs_in(root))
+        .collect();
+
+    let main_id = match main {
+        Some(rel) => Some(
+            local_file_id(Path::new(rel)).ok_or_else(|| format!("invalid main file '{rel}'"))?,
+        ),
+        None => None,
+    };
+    // Without a main file every `.typ` file is diagnosed on its own; the
+    // world still needs an entry, and one that names no file is never walked.
+    let entry = main_id
+        .or_else(|| local_file_id(Path::new(".typwriter/no-main.typ")))
+        .ok_or("cannot create an entry file id")?;
+
+    let library = headless_library(&project.inputs);
+    let fonts = headless_fonts(&font_dirs);
+    let packages = local_package_dirs();
+    let diagnose = || {
+        let world = HeadlessWorld::new(root, entry, &[], &library, &fonts, &packages);
+        let (mut errors, mut warnings) = match main_id {
+            Some(_) => {
+                let output = compile_in(&world, &world);
+                (output.errors, output.warnings)
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+        let cache: Mutex<WorkspaceDiagCache> = Mutex::new(WorkspaceDiagCache::new());
+        let (extra_errors, extra_warnings) = collect_workspace_diagnostics(&world, &cache);
+        dedup_merge(&mut errors, &mut warnings, extra_errors, extra_warnings);
+        (errors, warnings, world.missing_packages())
+    };
+
+    // The world only reads installed packages; fetch what the first pass
+    // missed and diagnose again.
+    let (mut errors, mut warnings, missing) = diagnose();
+    if !missing.is_empty() && downloads.obtain(&missing) {
+        (errors, warnings, _) = diagnose();
+    }
+    for problem in problems {
+        match problem.severity.as_str() {
+            "error" => errors.push(problem),
+            _ => warnings.push(problem),
+        }
+    }
+    Ok((errors, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::compile::TraceEntry;
+
+    fn diag(severity: &str, path: Option<&str>) -> SerializedDiagnostic {
+        SerializedDiagnostic {
+            severity: severity.into(),
+            message: "unknown variable: foo".into(),
+            hints: vec!["did you mean `for`?".into()],
+            file_path: path.map(String::from),
+            range: Some(DiagnosticRange {
+                start_line: 2,
+                start_col: 4,
+                end_line: 2,
+                end_col: 7,
+            }),
+            trace: Vec::new(),
+            fixes: Vec::new(),
+        }
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn sarif_regions_are_one_based_and_relative_to_the_source_root() {
+        let report: Value = serde_json::from_str(&render_report(
+            ReportFormat::Sarif,
+            &[diag("error", Some("chapters/a.typ"))],
+            &[],
+        ))
+        .unwrap();
+        assert_eq!(report["version"], "2.1.0");
+        let result = &report["runs"][0]["results"][0];
+        assert_eq!(result["level"], "error");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "chapters/a.typ");
+        assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["startColumn"], 5);
+        assert_eq!(location["region"]["endColumn"], 8);
+    }
+
+    #[test]
+    fn sarif_messages_carry_the_hints() {
+        let report: Value = serde_json::from_str(&render_report(
+            ReportFormat::Sarif,
+            &[],
+            &[diag("warning", None)],
+        ))
+        .unwrap();
+        let result = &report["runs"][0]["results"][0];
+        assert_eq!(result["level"], "warning");
+        assert_eq!(
+            result["message"]["text"],
+            "unknown variable: foo\nhint: did you mean `for`?"
+        );
+        assert!(result.get("locations").is_none());
+    }
+
+    #[test]
+    fn package_files_keep_their_absolute_path() {
+        let report: Value = serde_json::from_str(&render_report(
+            ReportFormat::Sarif,
+            &[diag("error", Some("/cache/preview/pkg/0.1.0/lib.typ"))],
+            &[],
+        ))
+        .unwrap();
+        let artifact = &report["runs"][0]["results"][0]["locations"][0]["physicalLocation"]
+            ["artifactLocation"];
+        assert_eq!(artifact["uri"], "/cache/preview/pkg/0.1.0/lib.typ");
+        assert!(artifact.get("uriBaseId").is_none());
+    }
+
+    #[test]
+    fn trace_frames_become_related_locations() {
+        let mut error = diag("error", Some("/cache/preview/pkg/0.1.0/lib.typ"));
+        error.trace = vec![
+            TraceEntry {
+                message: "error occurred in this call of function `inner`".into(),
+                file_path: Some("/cache/preview/pkg/0.1.0/lib.typ".into()),
+                package: Some("@preview/pkg:0.1.0".into()),
+                range: None,
+            },
+            TraceEntry {
+                message: "error occurred in this call of function `template`".into(),
+                file_path: Some("main.typ".into()),
+                package: None,
+                range: Some(DiagnosticRange {
+                    start_line: 0,
+                    start_col: 7,
+                    end_line: 0,
+                    end_col: 15,
+                }),
+            },
+        ];
+        let report: Value =
+            serde_json::from_str(&render_report(ReportFormat::Sarif, &[error], &[])).unwrap();
+        let related = &report["runs"][0]["results"][0]["relatedLocations"];
+        assert_eq!(related.as_array().unwrap().len(), 2);
+        assert_eq!(related[0]["properties"]["package"], "@preview/pkg:0.1.0");
+        assert_eq!(
+            related[1]["message"]["text"],
+            "error occurred in this call of function `template`"
+        );
+        assert_eq!(related[1]["physicalLocation"]["region"]["startColumn"], 8);
+        assert_eq!(
+            related[1]["physicalLocation"]["artifactLocation"]["uriBaseId"],
+            "%SRCROOT%"
+        );
+    }
+
+    #[test]
+    fn json_lists_errors_before_warnings() {
+        let report: Value = serde_json::from_str(&render_report(
+            ReportFormat::Json,
+            &[diag("error", Some("a.typ"))],
+            &[diag("warning", Some("b.typ"))],
+        ))
+        .unwrap();
+        assert_eq!(report["errors"], 1);
+        assert_eq!(report["warnings"], 1);
+        assert_eq!(report["diagnostics"][0]["file_path"], "a.typ");
+        assert_eq!(report["diagnostics"][1]["severity"], "warning");
+        assert_eq!(report["diagnostics"][1]["range"]["start_line"], 2);
+    }
+
+    #[test]
+    fn arguments_are_parsed() {
+        let parsed = parse_args(args(&[
+            "book",
+            "--main",
+            "main.typ",
+            "--format",
+            "JSON",
+            "--font-dir",
+            "fonts",
+            "--output",
+            "out.json",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed,
+            CliArgs {
+                root: "book".into(),
+                main: Some("main.typ".into()),
+                format: ReportFormat::Json,
+                output: Some("out.json".into()),
+                font_dirs: vec!["fonts".into()],
+                registries: Vec::new(),
+            }
+        );
+        assert_eq!(
+            parse_args(args(&["book"])).unwrap().format,
+            ReportFormat::Sarif
+        );
+    }
+
+    #[test]
+    fn registries_map_a_namespace_to_a_location() {
+        let parsed = parse_args(args(&[
+            "book",
+            "--registry",
+            "corp = https://pkg.example.com/",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed.registries,
+            vec![PackageRegistry {
+                namespace: "corp".into(),
+                location: "https://pkg.example.com/".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        assert!(parse_args(args(&[])).is_err());
+        assert!(parse_args(args(&["a", "b"])).is_err());
+        assert!(parse_args(args(&["a", "--format", "xml"])).is_err());
+        assert!(parse_args(args(&["a", "--main"])).is_err());
+        assert!(parse_args(args(&["a", "--verbose"])).is_err());
+        assert!(parse_args(args(&["a", "--registry", "corp"])).is_err());
+        assert!(parse_args(args(&["a", "--registry", "local=https://example.com"])).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+// Standalone preview: compile one included chapter as if it were the main file.
+//
+// In a book of twenty `#include`d chapters, previewing a change to one of them
+// otherwise means recompiling (and laying out) all twenty. `StandaloneWorld`
+// enters the document at the chapter instead — the same main-override trick
+// `compile::MainOverride` plays for workspace diagnostics — so only that
+// chapter is evaluated and laid out.
+//
+// A chapter on its own usually lacks the book's template: its page setup,
+// fonts and show rules live in the main file. A workspace can supply them in
+// a wrapper file holding the template import and set/show rules, declared as
+// `standalone-wrapper` in `typwriter.toml` so it travels with the project.
+// When there is one, the wrapper becomes the entry point and the chapter is
+// appended to it as an `#include`, so
+//
+//     #import "/template.typ": book
+//     #show: book.with(title: "Draft")
+//
+// renders the chapter exactly as the book would. Paths in the wrapper resolve
+// relative to its own folder, as in any other file.
+//
+// Everything else delegates to the live [`EditorWorld`], so unsaved buffers,
+// fonts and packages behave as in the full compile.
+
+use typst::{
+    diag::FileResult,
+    foundations::{Bytes, Datetime, Duration},
+    syntax::{FileId, Source},
+    text::{Font, FontBook},
+    utils::LazyHash,
+    Library, World,
+};
+
+use crate::world::EditorWorld;
+
+pub struct StandaloneWorld<'a> {
+    base: &'a EditorWorld,
+    /// The chapter being previewed.
+    chapter: FileId,
+    /// The wrapper's text with the chapter appended, parsed under the
+    /// wrapper's id. `None` when the workspace declares no wrapper, in which
+    /// case the chapter itself is the entry point.
+    entry: Option<Source>,
+}
+
+impl<'a> StandaloneWorld<'a> {
+    /// Enter `base` at `chapter`, inside `wrapper` when the workspace
+    /// declares one.
+    pub fn new(base: &'a EditorWorld, chapter: FileId, wrapper: Option<FileId>) -> Self {
+        let entry = wrapper.filter(|&id| id != chapter).and_then(|id| {
+            let wrapper = base.source(id).ok()?;
+            let chapter_rel = chapter.vpath().get_without_slash();
+            Some(Source::new(id, wrap(wrapper.text(), chapter_rel)))
+        });
+        Self {
+            base,
+            chapter,
+            entry,
+        }
+    }
+
+    /// Whether a workspace wrapper is being applied.
+    pub fn has_wrapper(&self) -> bool {
+        self.entry.is_some()
+    }
+
+    /// The wrapper with the chapter appended, when there is a wrapper.
+    pub fn entry(&self) -> Option<&Source> {
+        self.entry.as_ref()
+    }
+}
+
+/// The wrapper's text followed by an include of `chapter_rel`, a
+/// workspace-relative forward-slash path.
+fn wrap(wrapper: &str, chapter_rel: &str) -> String {
+    let escaped = chapter_rel.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut text = wrapper.to_string();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&format!("#include \"/{escaped}\"\n"));
+    text
+}
+
+impl World for StandaloneWorld<'_> {
+    fn library(&self) -> &LazyHash<Library> {
+        self.base.library()
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        self.base.book()
+    }
+
+    fn main(&self) -> FileId {
+        self.entry.as_ref().map_or(self.chapter, Source::id)
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        match &self.entry {
+            Some(entry) if entry.id() == id => Ok(entry.clone()),
+            _ => self.base.source(id),
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.base.file(id)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.base.font(index)
+    }
+
+    fn today(&self, offset: Option<Duration>) -> Option<Datetime> {
+        self.base.today(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_chapter_is_included_after_the_wrapper() {
+        let text = wrap(
+            "#import \"/template.typ\": book\n#show: book\n",
+            "ch/12.typ",
+        );
+        assert_eq!(
+            text,
+            "#import \"/template.typ\": book\n#show: book\n#include \"/ch/12.typ\"\n"
+        );
+    }
+
+    #[test]
+    fn a_wrapper_without_a_trailing_newline_is_terminated() {
+        assert_eq!(
+            wrap("#set page(\"a5\")", "a.typ"),
+            "#set page(\"a5\")\n#include \"/a.typ\"\n"
+        );
+    }
+
+    #[test]
+    fn quotes_in_the_chapter_path_are_escaped() {
+        assert_eq!(
+            wrap("", "say \"hi\".typ"),
+            "#include \"/say \\\"hi\\\".typ\"\n"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+// Which untracked files git ignores, when the workspace walk alone can't
+// say: the walk reads the workspace's own ignore files, but a workspace deep
+// inside a repository also inherits the `.gitignore` files above it, and any
+// folder may have one of its own.
+//
+// Patterns come from the same places, with the same precedence, as in git:
+// `info/exclude` first, then each `.gitignore` from the work-tree root down
+// to the file's folder, a deeper file overriding a shallower one. Each
+// `.gitignore` is read relative to its own folder. A file in an ignored
+// folder stays ignored whatever is said about the file itself.
+// `core.excludesFile` is not read.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::vcs::WorkingTreeFs;
+use crate::workspace::ignore::{IgnoreRules, GITIGNORE};
+
+pub(super) struct Excludes<'a> {
+    fs: &'a dyn WorkingTreeFs,
+    work_tree: PathBuf,
+    info_exclude: IgnoreRules,
+    /// The `.gitignore` of each folder looked at so far, keyed by
+    /// work-tree-relative folder path with a trailing `/` (`""` at the top).
+    gitignores: HashMap<String, IgnoreRules>,
+    /// Folders already judged, by the same key minus the slash.
+    dirs: HashMap<String, bool>,
+}
+
+impl<'a> Excludes<'a> {
+    pub fn new(fs: &'a dyn WorkingTreeFs, work_tree: &Path, common_dir: &Path) -> Self {
+        Self {
+            fs,
+            work_tree: work_tree.to_path_buf(),
+            info_exclude: read_rules(fs, &common_dir.join("info/exclude")),
+            gitignores: HashMap::new(),
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Whether git ignores the file at the work-tree-relative path `path`.
+    pub fn is_ignored(&mut self, path: &str) -> bool {
+        let mut end = 0;
+        while let Some(slash) = path[end..].find('/') {
+            end += slash;
+            let dir = &path[..end];
+            let ignored = match self.dirs.get(dir) {
+                Some(&ignored) => ignored,
+                None => {
+                    let ignored = self.matches(dir, true);
+                    self.dirs.insert(dir.to_string(), ignored);
+                    ignored
+                }
+            };
+            if ignored {
+                return true;
+            }
+            end += 1;
+        }
+        self.matches(path, false)
+    }
+
+    /// Whether the patterns say `path` is ignored, judging the entry alone.
+    fn matches(&mut self, path: &str, is_dir: bool) -> bool {
+        // The folders whose `.gitignore` applies, deepest first.
+        let mut bases: Vec<usize> = path.match_indices('/').map(|(at, _)| at + 1).collect();
+        bases.reverse();
+        bases.push(0);
+        for base in bases {
+            let folder = &path[..base];
+            if !self.gitignores.contains_key(folder) {
+                let rules = read_rules(self.fs, &self.work_tree.join(folder).join(GITIGNORE));
+                self.gitignores.insert(folder.to_string(), rules);
+            }
+            if let Some(ignored) = self.gitignores[folder].verdict(&path[base..], is_dir) {
+                return ignored;
+            }
+        }
+        self.info_exclude.verdict(path, is_dir).unwrap_or(false)
+    }
+}
+
+/// The patterns of the ignore file at `path`; none when it can't be read.
+fn read_rules(fs: &dyn WorkingTreeFs, path: &Path) -> IgnoreRules {
+    let source = fs
+        .read_file(path)
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .unwrap_or_default();
+    IgnoreRules::parse_patterns(&source)
+}
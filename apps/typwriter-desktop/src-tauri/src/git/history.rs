@@ -0,0 +1,765 @@
+// Moving the restore-point timeline between `.typwriter/history` and git,
+// in both directions.
+//
+// Export replays the snapshots, each after its parent, as a line of commits
+// on a branch: the snapshot's files become the commit's tree at the
+// workspace's folder (everything else in the tree stays as the branch had
+// it), its message the subject, and `created_at_ms` the author date.
+// Trailers record the trigger and the snapshot id, so exporting again only
+// adds what's new and an import recognizes commits that came from here.
+//
+// Exporting to the checked-out branch moves it under the index, so that is
+// only done when nothing is staged, and the index is then rewritten to match
+// the new tip — the working tree is never touched.
+//
+// Import walks the checked-out branch's first-parent log, oldest first, and
+// records each commit that changes the workspace's files as a restore point.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use chrono::{Local, Offset, TimeZone};
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::vcs::store::{
+    build_manifest, read_all_snapshots, read_blob, read_head, write_blob_if_missing, write_head,
+    write_snapshot, SnapshotManifest,
+};
+use crate::vcs::{CommitTrigger, WorkingTreeFs};
+use crate::workspace::ignore::IgnoreRules;
+
+use super::object::{
+    format_commit, format_tree, parse_commit, parse_tree, ObjectKind, ObjectStore, Oid, Signature,
+    TreeEntry, MODE_FILE, MODE_TREE,
+};
+use super::{collect_tree, index, refs, GitRepo, Head};
+
+/// Branch of a newly created repository.
+const INITIAL_BRANCH: &str = "main";
+/// Branch exported to when the checked-out one has commits that weren't
+/// exported.
+const EXPORT_BRANCH: &str = "typwriter-history";
+
+const TRIGGER_TRAILER: &str = "Typwriter-Trigger";
+const SNAPSHOT_TRAILER: &str = "Typwriter-Snapshot";
+
+/// Used when git has no `user.name` / `user.email` configured.
+const FALLBACK_NAME: &str = "Typwriter";
+const FALLBACK_EMAIL: &str = "typwriter@localhost";
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GitExport {
+    pub branch: String,
+    /// The commit the branch points at afterwards.
+    pub head: Option<String>,
+    /// Restore points committed by this export; ones an earlier export
+    /// already committed are skipped.
+    pub commits: usize,
+    /// Whether the workspace had no repository and got a new one.
+    pub created_repository: bool,
+}
+
+/// Commit every restore point not yet on `branch` to the workspace's git
+/// repository, creating one at the workspace root if there is none.
+/// `branch` defaults to the checked-out one when it has no commits yet or
+/// an export made its last, and to `typwriter-history` otherwise.
+pub fn export_history(
+    workspace_root: &Path,
+    fs: &impl WorkingTreeFs,
+    branch: Option<&str>,
+) -> Result<GitExport, String> {
+    if let Some(branch) = branch.filter(|branch| !valid_branch_name(branch)) {
+        return Err(format!("{branch:?} is not a valid branch name"));
+    }
+    let snapshots = read_all_snapshots(fs, workspace_root)?;
+    if snapshots.is_empty() {
+        return Err("There are no restore points to export".into());
+    }
+
+    let (repo, created_repository) = match GitRepo::discover(workspace_root) {
+        Some(repo) => (repo, false),
+        None => (
+            GitRepo::init(workspace_root, branch.unwrap_or(INITIAL_BRANCH))?,
+            true,
+        ),
+    };
+    let head = repo.head()?;
+    let objects = repo.objects();
+    let branch = match (branch, &head) {
+        (Some(branch), _) => branch.to_string(),
+        (None, Head::Branch { name, oid })
+            if oid.is_none_or(|oid| made_by_export(&objects, oid)) =>
+        {
+            name.clone()
+        }
+        (None, _) => EXPORT_BRANCH.to_string(),
+    };
+    let checked_out = matches!(&head, Head::Branch { name, .. } if *name == branch);
+    if checked_out && !nothing_staged(&repo, &objects, head.oid())? {
+        return Err(format!(
+            "{branch} is checked out and has staged changes; commit or unstage them, \
+             or export to another branch"
+        ));
+    }
+
+    let ref_name = format!("refs/heads/{branch}");
+    let mut tip = refs::resolve_ref(&repo.common_dir, &ref_name)?;
+    let exported = exported_snapshots(&objects, tip)?;
+    // The workspace's folder within the work tree, where its files go.
+    let prefix = repo.prefix(workspace_root)?;
+    let folders: Vec<&str> = prefix.split('/').filter(|dir| !dir.is_empty()).collect();
+    let (name, email) = identity(&repo);
+
+    // The tree the next commit starts from.
+    let mut base = match tip {
+        Some(tip) => Some(parse_commit(&objects.read_kind(tip, ObjectKind::Commit)?)?.tree),
+        None => None,
+    };
+    // Store blob hash → git blob id.
+    let mut blobs: HashMap<&str, Oid> = HashMap::new();
+    let mut commits = 0;
+    for manifest in parent_order(&snapshots) {
+        if exported.contains(&manifest.id) {
+            continue;
+        }
+        let mut files = Vec::with_capacity(manifest.files.len());
+        for (path, hash) in &manifest.files {
+            let blob = match blobs.get(hash.as_str()) {
+                Some(blob) => *blob,
+                None => {
+                    let bytes = read_blob(fs, workspace_root, hash)?;
+                    let blob = objects.write(ObjectKind::Blob, &bytes)?;
+                    blobs.insert(hash, blob);
+                    blob
+                }
+            };
+            files.push((path.as_str(), blob));
+        }
+        let workspace_tree = write_tree(&objects, files)?;
+        let tree = graft(&objects, base, &folders, workspace_tree)?;
+        base = Some(tree);
+        let time = manifest.created_at_ms.div_euclid(1000);
+        let signature = Signature {
+            name: &name,
+            email: &email,
+            time,
+            offset_minutes: local_offset_minutes(time),
+        };
+        let commit = format_commit(tree, tip.as_slice(), &signature, &commit_message(manifest));
+        tip = Some(objects.write(ObjectKind::Commit, &commit)?);
+        commits += 1;
+    }
+
+    if let Some(tip) = tip.filter(|_| commits > 0) {
+        refs::update_ref(&repo.common_dir, &ref_name, tip)?;
+        if let Some(tree) = base.filter(|_| checked_out) {
+            let mut entries = Vec::new();
+            flatten_tree(&objects, tree, "", &mut entries)?;
+            index::write_index(&repo.git_dir.join("index"), &entries)?;
+        }
+    }
+    info!("git::export_history: {commits} commit(s) on {branch}");
+    Ok(GitExport {
+        branch,
+        head: tip.map(Oid::to_hex),
+        commits,
+        created_repository,
+    })
+}
+
+/// Record the checked-out branch's first-parent log — at most `limit` of
+/// its newest commits — as restore points, oldest first. Commits that leave
+/// the workspace's files as they were, and commits an export made from a
+/// restore point that still exists, add nothing. Returns how many restore
+/// points were added.
+///
+/// The timeline's HEAD stays where it is; it only starts at the newest
+/// commit when there was no history at all.
+pub fn import_history(
+    workspace_root: &Path,
+    fs: &impl WorkingTreeFs,
+    limit: Option<usize>,
+) -> Result<usize, String> {
+    let repo =
+        GitRepo::discover(workspace_root).ok_or("The workspace isn't in a git repository")?;
+    let Some(mut oid) = repo.head()?.oid() else {
+        return Ok(0);
+    };
+    let objects = repo.objects();
+    let mut log = vec![parse_commit(&objects.read_kind(oid, ObjectKind::Commit)?)?];
+    while limit.is_none_or(|limit| log.len() < limit) {
+        let Some(&parent) = log.last().and_then(|commit| commit.parents.first()) else {
+            break;
+        };
+        oid = parent;
+        match objects.read_kind(oid, ObjectKind::Commit) {
+            Ok(data) => log.push(parse_commit(&data)?),
+            // A shallow clone's log ends at a commit whose parents it lacks.
+            Err(e) => {
+                warn!("git::import_history: log ends at {oid}: {e}");
+                break;
+            }
+        }
+    }
+
+    let snapshots: HashMap<String, SnapshotManifest> = read_all_snapshots(fs, workspace_root)?
+        .into_iter()
+        .map(|(_, manifest)| (manifest.id.clone(), manifest))
+        .collect();
+    let prefix = repo.prefix(workspace_root)?;
+    let rules = IgnoreRules::load(fs, workspace_root);
+
+    let mut blobs: HashMap<Oid, String> = HashMap::new();
+    let mut previous: Option<SnapshotManifest> = None;
+    let mut imported = 0;
+    for commit in log.into_iter().rev() {
+        let exported = trailer(&commit.message, SNAPSHOT_TRAILER).and_then(|id| snapshots.get(id));
+        if let Some(manifest) = exported {
+            previous = Some(manifest.clone());
+            continue;
+        }
+
+        let mut tree = BTreeMap::new();
+        collect_tree(&objects, commit.tree, "", &prefix, &mut tree)?;
+        let mut files = BTreeMap::new();
+        for (path, oid) in tree {
+            let rel = &path[prefix.len()..];
+            if rules.is_ignored_path(Path::new(rel), false) {
+                continue;
+            }
+            let hash = match blobs.get(&oid) {
+                Some(hash) => hash.clone(),
+                None => {
+                    let bytes = objects.read_kind(oid, ObjectKind::Blob)?;
+                    let hash = write_blob_if_missing(fs, workspace_root, &bytes)?;
+                    blobs.insert(oid, hash.clone());
+                    hash
+                }
+            };
+            files.insert(rel.to_string(), hash);
+        }
+        if previous.as_ref().is_some_and(|p| p.files == files) {
+            continue;
+        }
+
+        let trigger = trailer(&commit.message, TRIGGER_TRAILER)
+            .and_then(CommitTrigger::from_tag)
+            .unwrap_or(CommitTrigger::Manual);
+        let manifest = build_manifest(
+            previous.as_ref().map(|p| p.id.clone()),
+            commit.author_time * 1000,
+            trigger,
+            &restore_point_message(&commit.message),
+            files,
+        );
+        if !snapshots.contains_key(&manifest.id) {
+            write_snapshot(fs, workspace_root, &manifest)?;
+            imported += 1;
+        }
+        previous = Some(manifest);
+    }
+
+    if read_head(fs, workspace_root)?.is_none() {
+        if let Some(last) = &previous {
+            write_head(fs, workspace_root, &last.id)?;
+        }
+    }
+    info!("git::import_history: {imported} restore point(s) added");
+    Ok(imported)
+}
+
+/// Snapshots oldest first, each after its parent. A parent that retention
+/// pruned doesn't hold anything up.
+fn parent_order(snapshots: &[(std::path::PathBuf, SnapshotManifest)]) -> Vec<&SnapshotManifest> {
+    let by_id: HashMap<&str, &SnapshotManifest> = snapshots
+        .iter()
+        .map(|(_, manifest)| (manifest.id.as_str(), manifest))
+        .collect();
+    let mut placed = HashSet::new();
+    let mut order = Vec::with_capacity(snapshots.len());
+    for (_, manifest) in snapshots {
+        // The snapshot and whichever of its ancestors aren't placed yet,
+        // newest first.
+        let mut pending = Vec::new();
+        let mut next = Some(manifest);
+        while let Some(manifest) = next.filter(|m| !placed.contains(m.id.as_str())) {
+            placed.insert(manifest.id.as_str());
+            pending.push(manifest);
+            next = manifest
+                .parent
+                .as_deref()
+                .and_then(|id| by_id.get(id).copied());
+        }
+        order.extend(pending.into_iter().rev());
+    }
+    order
+}
+
+/// Whether an export made commit `oid`.
+fn made_by_export(objects: &ObjectStore, oid: Oid) -> bool {
+    objects
+        .read_kind(oid, ObjectKind::Commit)
+        .and_then(|data| parse_commit(&data))
+        .is_ok_and(|commit| trailer(&commit.message, SNAPSHOT_TRAILER).is_some())
+}
+
+/// Whether the index holds exactly the tree of `head`, the commit checked
+/// out — that is, nothing is staged and nothing is left unmerged.
+fn nothing_staged(
+    repo: &GitRepo,
+    objects: &ObjectStore,
+    head: Option<Oid>,
+) -> Result<bool, String> {
+    let mut committed = Vec::new();
+    if let Some(head) = head {
+        let tree = parse_commit(&objects.read_kind(head, ObjectKind::Commit)?)?.tree;
+        flatten_tree(objects, tree, "", &mut committed)?;
+    }
+    let index = repo.index("")?;
+    Ok(index.len() == committed.len()
+        && index.iter().zip(&committed).all(|(entry, (path, oid, _))| {
+            entry.stage == 0 && entry.path == *path && entry.oid == *oid
+        }))
+}
+
+/// Every entry of tree `oid`, sitting at `base`, but its subtrees — as
+/// path, id and mode, in index order.
+fn flatten_tree(
+    objects: &ObjectStore,
+    oid: Oid,
+    base: &str,
+    out: &mut Vec<(String, Oid, u32)>,
+) -> Result<(), String> {
+    for entry in parse_tree(&objects.read_kind(oid, ObjectKind::Tree)?)? {
+        let path = format!("{base}{}", entry.name);
+        if entry.mode == MODE_TREE {
+            flatten_tree(objects, entry.oid, &format!("{path}/"), out)?;
+        } else {
+            out.push((path, entry.oid, entry.mode));
+        }
+    }
+    if base.is_empty() {
+        out.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+    }
+    Ok(())
+}
+
+/// Tree `base` with `subtree` in place of the folder at `folders` — or
+/// just `subtree` when that's the top. Folders on the way that `base`
+/// doesn't have are created.
+fn graft(
+    objects: &ObjectStore,
+    base: Option<Oid>,
+    folders: &[&str],
+    subtree: Oid,
+) -> Result<Oid, String> {
+    let Some((folder, inner)) = folders.split_first() else {
+        return Ok(subtree);
+    };
+    let mut entries = match base {
+        Some(base) => parse_tree(&objects.read_kind(base, ObjectKind::Tree)?)?,
+        None => Vec::new(),
+    };
+    let existing = entries
+        .iter()
+        .position(|entry| entry.name == *folder)
+        .map(|at| entries.remove(at))
+        .filter(|entry| entry.mode == MODE_TREE);
+    entries.push(TreeEntry {
+        mode: MODE_TREE,
+        name: folder.to_string(),
+        oid: graft(objects, existing.map(|entry| entry.oid), inner, subtree)?,
+    });
+    objects.write(ObjectKind::Tree, &format_tree(&mut entries))
+}
+
+/// Ids of the snapshots the commits on the first-parent line from `tip`
+/// were exported from.
+fn exported_snapshots(objects: &ObjectStore, tip: Option<Oid>) -> Result<HashSet<String>, String> {
+    let mut ids = HashSet::new();
+    let mut next = tip;
+    while let Some(oid) = next {
+        let commit = parse_commit(&objects.read_kind(oid, ObjectKind::Commit)?)?;
+        if let Some(id) = trailer(&commit.message, SNAPSHOT_TRAILER) {
+            ids.insert(id.to_string());
+        }
+        next = commit.parents.first().copied();
+    }
+    Ok(ids)
+}
+
+/// Write the tree holding `files` — path → blob — and the subtrees under
+/// it.
+fn write_tree(objects: &ObjectStore, files: Vec<(&str, Oid)>) -> Result<Oid, String> {
+    let mut entries = Vec::new();
+    let mut dirs: BTreeMap<&str, Vec<(&str, Oid)>> = BTreeMap::new();
+    for (path, oid) in files {
+        match path.split_once('/') {
+            Some((dir, rest)) => dirs.entry(dir).or_default().push((rest, oid)),
+            None => entries.push(TreeEntry {
+                mode: MODE_FILE,
+                name: path.to_string(),
+                oid,
+            }),
+        }
+    }
+    for (name, files) in dirs {
+        entries.push(TreeEntry {
+            mode: MODE_TREE,
+            name: name.to_string(),
+            oid: write_tree(objects, files)?,
+        });
+    }
+    objects.write(ObjectKind::Tree, &format_tree(&mut entries))
+}
+
+fn commit_message(manifest: &SnapshotManifest) -> String {
+    format!(
+        "{}\n\n{TRIGGER_TRAILER}: {}\n{SNAPSHOT_TRAILER}: {}\n",
+        manifest.message.trim(),
+        manifest.trigger.tag(),
+        manifest.id
+    )
+}
+
+/// The message a restore point gets for a commit: its first paragraph,
+/// which leaves out the body and the trailers an export adds.
+fn restore_point_message(message: &str) -> String {
+    let subject = message.split("\n\n").next().unwrap_or("").trim();
+    if subject.is_empty() {
+        "Imported from git".to_string()
+    } else {
+        subject.to_string()
+    }
+}
+
+/// The value of the last `key: value` trailer line in `message`.
+fn trailer<'a>(message: &'a str, key: &str) -> Option<&'a str> {
+    message
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(": "))
+        .map(str::trim)
+}
+
+/// Whether git would accept `name` as a branch (a simplified
+/// `git check-ref-format --branch`).
+fn valid_branch_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && !name.ends_with(".lock")
+        && !name.contains("..")
+        && !name.contains("@{")
+        && name
+            .split('/')
+            .all(|part| !part.is_empty() && !part.starts_with('.') && !part.ends_with('.'))
+        && !name
+            .chars()
+            .any(|c| c.is_control() || c.is_whitespace() || "~^:?*[\\".contains(c))
+}
+
+/// `user.name` and `user.email` from the repository's config, else the
+/// user's global one.
+fn identity(repo: &GitRepo) -> (String, String) {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    let xdg = std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| Path::new(home).join(".config")));
+    let configs = [
+        Some(repo.common_dir.join("config")),
+        home.map(|home| Path::new(&home).join(".gitconfig")),
+        xdg.map(|xdg| xdg.join("git").join("config")),
+    ];
+    let (mut name, mut email) = (None, None);
+    for path in configs.into_iter().flatten() {
+        let Ok(config) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let (found_name, found_email) = config_user(&config);
+        name = name.or(found_name);
+        email = email.or(found_email);
+    }
+    (
+        name.unwrap_or_else(|| FALLBACK_NAME.to_string()),
+        email.unwrap_or_else(|| FALLBACK_EMAIL.to_string()),
+    )
+}
+
+/// `name` and `email` from the `[user]` section of a git config file.
+fn config_user(config: &str) -> (Option<String>, Option<String>) {
+    let (mut name, mut email) = (None, None);
+    let mut in_user = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[') {
+            in_user = section
+                .trim_end_matches(']')
+                .trim()
+                .eq_ignore_ascii_case("user");
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=').filter(|_| in_user) else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "name" => name = Some(value),
+            "email" => email = Some(value),
+            _ => {}
+        }
+    }
+    (name, email)
+}
+
+/// The local UTC offset at `time`, in minutes.
+fn local_offset_minutes(time: i64) -> i32 {
+    Local
+        .timestamp_opt(time, 0)
+        .single()
+        .map_or(0, |local| local.offset().fix().local_minus_utc() / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::object::Commit;
+    use crate::git::GitFileStatus;
+    use crate::test_support::TempDir;
+    use crate::vcs::fs::LocalWorkingTreeFs;
+
+    fn write(root: &Path, path: &str, text: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    /// Record a restore point of `files` on top of the timeline's HEAD.
+    fn snapshot(
+        root: &Path,
+        created_at_ms: i64,
+        trigger: CommitTrigger,
+        message: &str,
+        files: &[(&str, &str)],
+    ) -> String {
+        let fs = LocalWorkingTreeFs;
+        let files = files
+            .iter()
+            .map(|(path, text)| {
+                let hash = write_blob_if_missing(&fs, root, text.as_bytes()).unwrap();
+                (path.to_string(), hash)
+            })
+            .collect();
+        let parent = read_head(&fs, root).unwrap();
+        let manifest = build_manifest(parent, created_at_ms, trigger, message, files);
+        write_snapshot(&fs, root, &manifest).unwrap();
+        write_head(&fs, root, &manifest.id).unwrap();
+        manifest.id
+    }
+
+    fn commit(repo: &GitRepo, hex: Option<&str>) -> (Oid, Commit) {
+        let oid = Oid::from_hex(hex.expect("a commit")).expect("a commit id");
+        let data = repo.objects().read_kind(oid, ObjectKind::Commit).unwrap();
+        (oid, parse_commit(&data).unwrap())
+    }
+
+    fn paths(repo: &GitRepo, tree: Oid) -> Vec<String> {
+        let mut entries = Vec::new();
+        flatten_tree(&repo.objects(), tree, "", &mut entries).unwrap();
+        entries.into_iter().map(|(path, ..)| path).collect()
+    }
+
+    #[test]
+    fn export_creates_a_repository_and_later_adds_only_new_points() {
+        let dir = TempDir::new("git-history");
+        let root = dir.0.as_path();
+        let fs = LocalWorkingTreeFs;
+        let first = snapshot(
+            root,
+            1_700_000_000_000,
+            CommitTrigger::Initial,
+            "Initial restore point",
+            &[("main.typ", "= One\n")],
+        );
+        write(root, "main.typ", "= Two\n");
+        write(root, "chapters/a.typ", "A\n");
+        let second = snapshot(
+            root,
+            1_700_000_060_500,
+            CommitTrigger::Save,
+            "Saved main.typ",
+            &[("main.typ", "= Two\n"), ("chapters/a.typ", "A\n")],
+        );
+
+        let export = export_history(root, &fs, None).unwrap();
+        assert_eq!(export.branch, "main");
+        assert_eq!((export.commits, export.created_repository), (2, true));
+        let repo = GitRepo::discover(root).expect("a repository");
+        assert_eq!(repo.info().unwrap().head, export.head);
+        // The index was written to match, so the working tree is clean.
+        assert!(repo.status(root, &fs).unwrap().is_empty());
+
+        let (tip, commit_two) = commit(&repo, export.head.as_deref());
+        assert_eq!(commit_two.author_time, 1_700_000_060);
+        assert_eq!(
+            commit_two.message,
+            format!("Saved main.typ\n\nTypwriter-Trigger: save\nTypwriter-Snapshot: {second}\n")
+        );
+        assert_eq!(
+            paths(&repo, commit_two.tree),
+            ["chapters/a.typ", "main.typ"]
+        );
+        let (_, commit_one) = commit(&repo, Some(&commit_two.parents[0].to_hex()));
+        assert!(commit_one.parents.is_empty());
+        assert_eq!(
+            trailer(&commit_one.message, SNAPSHOT_TRAILER),
+            Some(first.as_str())
+        );
+
+        // The branch an export made takes the next one, which adds only the
+        // new restore point.
+        write(root, "main.typ", "= Three\n");
+        snapshot(
+            root,
+            1_700_000_120_000,
+            CommitTrigger::Manual,
+            "Before the rewrite",
+            &[("main.typ", "= Three\n"), ("chapters/a.typ", "A\n")],
+        );
+        let again = export_history(root, &fs, None).unwrap();
+        assert_eq!(again.branch, "main");
+        assert_eq!((again.commits, again.created_repository), (1, false));
+        assert_eq!(commit(&repo, again.head.as_deref()).1.parents, [tip]);
+        assert!(repo.status(root, &fs).unwrap().is_empty());
+
+        // Every commit came from a restore point that's still here.
+        assert_eq!(import_history(root, &fs, None).unwrap(), 0);
+    }
+
+    #[test]
+    fn export_keeps_off_a_checked_out_branch_with_its_own_commits() {
+        let dir = TempDir::fixture("loose");
+        let root = dir.0.as_path();
+        let fs = LocalWorkingTreeFs;
+        snapshot(
+            root,
+            1_700_100_000_000,
+            CommitTrigger::Manual,
+            "Draft",
+            &[("main.typ", "= Thesis\n")],
+        );
+
+        let export = export_history(root, &fs, None).unwrap();
+        assert_eq!(export.branch, EXPORT_BRANCH);
+        assert_eq!((export.commits, export.created_repository), (1, false));
+        let repo = GitRepo::discover(root).expect("a repository");
+        assert_eq!(repo.info().unwrap().branch.as_deref(), Some("main"));
+        let (_, exported) = commit(&repo, export.head.as_deref());
+        assert!(exported.parents.is_empty());
+        assert_eq!(paths(&repo, exported.tree), ["main.typ"]);
+
+        // `main` has staged changes.
+        let err = export_history(root, &fs, Some("main")).unwrap_err();
+        assert!(err.contains("staged"), "{err}");
+        assert!(export_history(root, &fs, Some("no..dots")).is_err());
+    }
+
+    #[test]
+    fn export_from_a_folder_grafts_it_into_the_branch() {
+        let dir = TempDir::fixture("packed");
+        let root = dir.0.join("notes");
+        let fs = LocalWorkingTreeFs;
+        write(&root, "idea.typ", "= Idea\n");
+        snapshot(
+            &root,
+            1_700_200_000_000,
+            CommitTrigger::Manual,
+            "Idea",
+            &[("idea.typ", "= Idea\n")],
+        );
+
+        let export = export_history(&root, &fs, Some("feature/long")).unwrap();
+        assert_eq!(export.commits, 1);
+        let repo = GitRepo::discover(&dir.0).expect("a repository");
+        assert_eq!(repo.info().unwrap().head, export.head);
+        let (_, exported) = commit(&repo, export.head.as_deref());
+        assert_eq!(
+            exported.parents[0].to_hex(),
+            "8e865030e5f7ee10e36c2116bf81a5d11041b89f"
+        );
+        assert_eq!(
+            paths(&repo, exported.tree),
+            ["long.typ", "main.typ", "notes/idea.typ"]
+        );
+        // The index follows the branch; the working tree's own change stays.
+        let status: Vec<_> = repo.status(&dir.0, &fs).unwrap().into_iter().collect();
+        assert_eq!(status, [("long.typ".to_string(), GitFileStatus::Modified)]);
+    }
+
+    #[test]
+    fn import_seeds_restore_points_from_the_log() {
+        let dir = TempDir::fixture("loose");
+        let root = dir.0.as_path();
+        let fs = LocalWorkingTreeFs;
+        assert_eq!(import_history(root, &fs, None).unwrap(), 2);
+
+        let snapshots: Vec<SnapshotManifest> = read_all_snapshots(&fs, root)
+            .unwrap()
+            .into_iter()
+            .map(|(_, manifest)| manifest)
+            .collect();
+        let [first, second] = snapshots.as_slice() else {
+            panic!("expected two restore points");
+        };
+        assert_eq!(first.message, "Start the thesis");
+        assert_eq!(first.created_at_ms, 1_700_000_000_000);
+        assert_eq!(first.trigger, CommitTrigger::Manual);
+        assert_eq!(second.message, "Add chapter two");
+        assert_eq!(second.parent.as_ref(), Some(&first.id));
+        assert_eq!(
+            second.files.keys().collect::<Vec<_>>(),
+            [
+                ".gitignore",
+                "chapters/intro.typ",
+                "gone.typ",
+                "main.typ",
+                "notes.txt"
+            ]
+        );
+        assert_eq!(
+            read_blob(&fs, root, &second.files["main.typ"]).unwrap(),
+            b"= Thesis\n\n#include \"chapters/intro.typ\"\n#include \"chapters/two.typ\"\n"
+        );
+        assert_eq!(read_head(&fs, root).unwrap(), Some(second.id.clone()));
+        assert_eq!(import_history(root, &fs, None).unwrap(), 0);
+
+        // In a folder, only commits that change it count.
+        let chapters = dir.0.join("chapters");
+        assert_eq!(import_history(&chapters, &fs, None).unwrap(), 1);
+        let (_, only) = read_all_snapshots(&fs, &chapters).unwrap().remove(0);
+        assert_eq!(only.files.keys().collect::<Vec<_>>(), ["intro.typ"]);
+
+        let packed = TempDir::fixture("packed");
+        assert_eq!(import_history(&packed.0, &fs, Some(1)).unwrap(), 1);
+        let (_, newest) = read_all_snapshots(&fs, &packed.0).unwrap().remove(0);
+        assert_eq!(newest.message, "Spell out one hundred fifty");
+        assert_eq!(newest.parent, None);
+    }
+
+    #[test]
+    fn config_and_branch_names_are_read_like_git() {
+        let config = "[core]\n\tname = not me\n[user]\n\tname = Ada Writer\n\t# email = no\n\temail = \"ada@example.com\"\n";
+        assert_eq!(
+            config_user(config),
+            (Some("Ada Writer".into()), Some("ada@example.com".into()))
+        );
+
+        assert!(valid_branch_name("feature/long"));
+        for name in ["", "-x", "a..b", "a b", "x.lock", "a//b", ".hidden", "a:b"] {
+            assert!(!valid_branch_name(name), "{name:?}");
+        }
+    }
+}
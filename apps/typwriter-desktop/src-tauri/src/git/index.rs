@@ -0,0 +1,179 @@
+// The index (`.git/index`): what the next commit would contain, plus the
+// stat data git uses to tell whether a working file changed without
+// rehashing it.
+//
+// Versions 2 to 4 are read. An entry is fixed-size stat fields, the blob id
+// and flags, then the path — NUL-terminated and padded to eight bytes in
+// versions 2 and 3, prefix-compressed against the previous path in version
+// 4. Extensions after the entries (cached trees, untracked caches, …) are
+// only optimizations and are skipped.
+//
+// Written indexes are version 2 without stat data, as `git read-tree`
+// leaves them: git hashes each file on its next look and records the stat
+// data then.
+
+use std::{io::Write, path::Path};
+
+use sha1::{Digest, Sha1};
+
+use super::object::{be_u32, read_offset_varint, Oid, MODE_TREE};
+
+/// Extended flag: the path is outside a sparse checkout, so whatever is in
+/// the working tree says nothing about it.
+const SKIP_WORKTREE: u16 = 0x4000;
+
+pub struct IndexEntry {
+    /// Relative to the work tree, forward-slash.
+    pub path: String,
+    pub oid: Oid,
+    /// Modification time the file had when it was last hashed, as seconds
+    /// and nanoseconds.
+    pub mtime: (u32, u32),
+    /// File size, truncated to 32 bits.
+    pub size: u32,
+    /// 0 normally; 1 to 3 for the base, ours and theirs of a merge conflict.
+    pub stage: u8,
+    pub skip_worktree: bool,
+    pub mode: u32,
+}
+
+impl IndexEntry {
+    /// Whether the entry is a regular file, rather than a symlink (`120000`)
+    /// or a submodule's commit (`160000`).
+    pub fn is_file(&self) -> bool {
+        self.mode & 0o170000 == 0o100000
+    }
+}
+
+/// Entries of the index at `path`, in index order (sorted by path). A
+/// repository without an index yet has no entries.
+pub fn read_index(path: &Path) -> Result<Vec<IndexEntry>, String> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("read {path:?}: {e}")),
+    };
+    parse_index(&data)
+}
+
+fn parse_index(data: &[u8]) -> Result<Vec<IndexEntry>, String> {
+    if data.len() < 12 || &data[..4] != b"DIRC" {
+        return Err("not a git index".into());
+    }
+    let version = be_u32(&data[4..]);
+    if !(2..=4).contains(&version) {
+        return Err(format!("unsupported index version {version}"));
+    }
+    let count = be_u32(&data[8..]) as usize;
+
+    let truncated = || "index is truncated".to_string();
+    let mut entries = Vec::with_capacity(count);
+    let mut at = 12;
+    let mut previous = String::new();
+    for _ in 0..count {
+        let start = at;
+        let fixed = data.get(at..at + 62).ok_or_else(truncated)?;
+        let mtime = (be_u32(&fixed[8..]), be_u32(&fixed[12..]));
+        let mode = be_u32(&fixed[24..]);
+        let size = be_u32(&fixed[36..]);
+        let oid = Oid::from_bytes(&fixed[40..60]).ok_or_else(truncated)?;
+        let flags = u16::from_be_bytes([fixed[60], fixed[61]]);
+        at += 62;
+        let mut extended = 0u16;
+        if flags & 0x4000 != 0 && version >= 3 {
+            let bytes = data.get(at..at + 2).ok_or_else(truncated)?;
+            extended = u16::from_be_bytes([bytes[0], bytes[1]]);
+            at += 2;
+        }
+
+        let path = if version == 4 {
+            let mut reader = data.get(at..).ok_or_else(truncated)?;
+            let strip = read_offset_varint(&mut reader)? as usize;
+            at = data.len() - reader.len();
+            let nul = data[at..]
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or_else(truncated)?;
+            let keep = previous
+                .len()
+                .checked_sub(strip)
+                .ok_or("index path strips more than the previous path")?;
+            let mut path = previous.as_bytes()[..keep].to_vec();
+            path.extend_from_slice(&data[at..at + nul]);
+            at += nul + 1;
+            String::from_utf8_lossy(&path).into_owned()
+        } else {
+            let nul = data[at..]
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or_else(truncated)?;
+            let path = String::from_utf8_lossy(&data[at..at + nul]).into_owned();
+            // Entry, path and at least one NUL, padded to a multiple of 8.
+            at = start + ((at - start + nul + 8) & !7);
+            path
+        };
+        previous.clone_from(&path);
+
+        // A sparse index folds whole directories outside the checkout into
+        // one entry; there is nothing beneath them to report on.
+        if mode == MODE_TREE {
+            continue;
+        }
+        entries.push(IndexEntry {
+            path,
+            oid,
+            mtime,
+            size,
+            stage: ((flags >> 12) & 0b11) as u8,
+            skip_worktree: extended & SKIP_WORKTREE != 0,
+            mode,
+        });
+    }
+    Ok(entries)
+}
+
+/// Replace the index at `path` with one holding `files`: work-tree-relative
+/// path, id and mode of every entry of a tree but its subtrees. Written to
+/// `index.lock` and renamed over the index, failing while someone else holds
+/// the lock.
+pub fn write_index(path: &Path, files: &[(String, Oid, u32)]) -> Result<(), String> {
+    let mut files: Vec<&(String, Oid, u32)> = files.iter().collect();
+    files.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+
+    let mut data = Vec::new();
+    data.extend_from_slice(b"DIRC");
+    data.extend_from_slice(&2u32.to_be_bytes());
+    data.extend_from_slice(&(files.len() as u32).to_be_bytes());
+    for (name, oid, mode) in files {
+        let start = data.len();
+        // ctime, mtime, dev, ino.
+        data.extend_from_slice(&[0; 24]);
+        data.extend_from_slice(&mode.to_be_bytes());
+        // uid, gid, and a size of 0, which git reads as "never looked at".
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&oid.0);
+        // Names too long for the 12-bit length field store 0xFFF.
+        data.extend_from_slice(&(name.len().min(0xFFF) as u16).to_be_bytes());
+        data.extend_from_slice(name.as_bytes());
+        let len = data.len() - start;
+        data.resize(start + ((len + 8) & !7), 0);
+    }
+    let checksum = Sha1::digest(&data);
+    data.extend_from_slice(&checksum);
+
+    let lock = path.with_file_name("index.lock");
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock)
+        .map_err(|e| format!("lock index: {e}"))?;
+    let written = file
+        .write_all(&data)
+        .and_then(|()| file.sync_all())
+        .and_then(|()| std::fs::rename(&lock, path));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&lock);
+        return Err(format!("write index: {e}"));
+    }
+    Ok(())
+}
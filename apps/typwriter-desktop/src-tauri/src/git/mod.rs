@@ -0,0 +1,614 @@
+// Awareness of the git repository a workspace lives in: the current branch,
+// each file's status, and a diff of the working tree against HEAD.
+// Everything is read straight from the on-disk format — there's no git
+// binary to find and no library to link. The only writes are the restore
+// point timeline going out as commits (see `history`), which never touch the
+// working tree.
+//
+// The workspace may be the repository's work tree or any folder inside it;
+// paths handed out are workspace-relative either way, and files outside the
+// workspace are left out.
+//
+// Status follows `git status`: a file is *staged* when the index differs
+// from HEAD and *modified* when the working file differs from the index.
+// Working files are compared by the index's stat data first and only hashed
+// when that doesn't settle it, like git does. Untracked files are the ones the
+// workspace walk finds that the index doesn't know and git doesn't ignore
+// (see `exclude`). Symlinks and submodules are left out on both sides: HEAD
+// and the index hold a link target or a commit for them, which says nothing
+// about the working file.
+
+mod exclude;
+mod history;
+mod index;
+mod object;
+mod refs;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::Serialize;
+
+use crate::vcs::{build_diff, WorkingTreeFs, WorkspaceDiff};
+use crate::workspace::ignore::{walk_files, IgnoreRules, Unreadable};
+use exclude::Excludes;
+pub use history::{export_history, import_history, GitExport};
+use index::IndexEntry;
+use object::{parse_commit, parse_tree, ObjectKind, ObjectStore, Oid, MODE_GITLINK, MODE_TREE};
+pub use refs::Head;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileStatus {
+    /// Neither in the index nor ignored.
+    Untracked,
+    /// The working file differs from the index, or is gone.
+    Modified,
+    /// The index differs from HEAD, and the working file matches the index.
+    Staged,
+    /// Staged, then changed again.
+    StagedModified,
+    /// A merge left the file unmerged.
+    Conflicted,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitInfo {
+    /// The checked-out branch; `None` when HEAD is detached.
+    pub branch: Option<String>,
+    /// The commit HEAD points at; `None` before the first commit.
+    pub head: Option<String>,
+}
+
+pub struct GitRepo {
+    /// `.git` — or, for a linked worktree, its private directory under the
+    /// main repository's `.git/worktrees/`.
+    git_dir: PathBuf,
+    /// Where objects and refs live: `git_dir` except in a linked worktree.
+    common_dir: PathBuf,
+    work_tree: PathBuf,
+}
+
+impl GitRepo {
+    /// The repository `path` belongs to: the nearest folder at or above it
+    /// with a `.git` directory, or a `.git` file pointing at one (as linked
+    /// worktrees and submodules have).
+    pub fn discover(path: &Path) -> Option<Self> {
+        for dir in path.ancestors() {
+            let dot_git = dir.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                dot_git
+            } else if dot_git.is_file() {
+                let contents = std::fs::read_to_string(&dot_git).ok()?;
+                dir.join(contents.trim().strip_prefix("gitdir: ")?)
+            } else {
+                continue;
+            };
+            if !git_dir.join("HEAD").is_file() {
+                continue;
+            }
+            let common_dir = match std::fs::read_to_string(git_dir.join("commondir")) {
+                Ok(common) => git_dir.join(common.trim()),
+                Err(_) => git_dir.clone(),
+            };
+            return Some(Self {
+                git_dir,
+                common_dir,
+                work_tree: dir.to_path_buf(),
+            });
+        }
+        None
+    }
+
+    /// Create an empty repository with `dir` as its work tree, on `branch`
+    /// with no commits yet. The workspace's own `.typwriter/` is excluded
+    /// locally rather than through a `.gitignore` in the work tree.
+    fn init(dir: &Path, branch: &str) -> Result<Self, String> {
+        let git_dir = dir.join(".git");
+        for sub in ["info", "objects", "refs/heads", "refs/tags"] {
+            let path = git_dir.join(sub);
+            std::fs::create_dir_all(&path).map_err(|e| format!("create {path:?}: {e}"))?;
+        }
+        let files = [
+            ("HEAD", format!("ref: refs/heads/{branch}\n")),
+            (
+                "config",
+                "[core]\n\trepositoryformatversion = 0\n\tbare = false\n".to_string(),
+            ),
+            ("info/exclude", "/.typwriter/\n".to_string()),
+        ];
+        for (name, contents) in files {
+            let path = git_dir.join(name);
+            std::fs::write(&path, contents).map_err(|e| format!("write {path:?}: {e}"))?;
+        }
+        Ok(Self {
+            git_dir: git_dir.clone(),
+            common_dir: git_dir,
+            work_tree: dir.to_path_buf(),
+        })
+    }
+
+    pub fn head(&self) -> Result<Head, String> {
+        refs::read_head(&self.git_dir, &self.common_dir)
+    }
+
+    pub fn info(&self) -> Result<GitInfo, String> {
+        let head = self.head()?;
+        Ok(GitInfo {
+            branch: match &head {
+                Head::Branch { name, .. } => Some(name.clone()),
+                Head::Detached(_) => None,
+            },
+            head: head.oid().map(Oid::to_hex),
+        })
+    }
+
+    /// Status of every file under `workspace_root` that isn't clean, keyed
+    /// by workspace-relative path. Files deleted from the working tree are
+    /// included.
+    pub fn status(
+        &self,
+        workspace_root: &Path,
+        fs: &dyn WorkingTreeFs,
+    ) -> Result<BTreeMap<String, GitFileStatus>, String> {
+        let prefix = self.prefix(workspace_root)?;
+        let objects = self.objects();
+        let head = self.head_files(&objects, &prefix)?;
+        let index = self.index(&prefix)?;
+        let index_mtime = self.index_mtime();
+
+        let mut status = BTreeMap::new();
+        let mut tracked = HashSet::new();
+        let mut submodules = Vec::new();
+        for entry in &index {
+            tracked.insert(entry.path.as_str());
+            if !entry.is_file() {
+                if entry.mode == MODE_GITLINK {
+                    submodules.push(format!("{}/", entry.path));
+                }
+                continue;
+            }
+            let rel = entry.path[prefix.len()..].to_string();
+            if entry.stage != 0 {
+                status.insert(rel, GitFileStatus::Conflicted);
+                continue;
+            }
+            let staged = head.get(&entry.path) != Some(&entry.oid);
+            let modified = !entry.skip_worktree && self.worktree_differs(entry, index_mtime, fs);
+            let file_status = match (staged, modified) {
+                (false, false) => continue,
+                (false, true) => GitFileStatus::Modified,
+                (true, false) => GitFileStatus::Staged,
+                (true, true) => GitFileStatus::StagedModified,
+            };
+            status.insert(rel, file_status);
+        }
+        // Removed from the index, and so staged for deletion.
+        for path in head.keys() {
+            if !tracked.contains(path.as_str()) {
+                status.insert(path[prefix.len()..].to_string(), GitFileStatus::Staged);
+            }
+        }
+
+        let rules = IgnoreRules::load(fs, workspace_root);
+        let mut excludes = Excludes::new(fs, &self.work_tree, &self.common_dir);
+        for file in walk_files(fs, workspace_root, &rules, Unreadable::Skip)? {
+            let path = format!("{prefix}{}", file.rel);
+            if tracked.contains(path.as_str())
+                || submodules.iter().any(|dir| path.starts_with(dir.as_str()))
+                || excludes.is_ignored(&path)
+            {
+                continue;
+            }
+            status.entry(file.rel).or_insert(GitFileStatus::Untracked);
+        }
+        Ok(status)
+    }
+
+    /// Diff HEAD against the working tree, in the shape restore-point diffs
+    /// have. Like `git diff HEAD`, it covers the files git tracks — in HEAD
+    /// or in the index — and leaves untracked ones out.
+    pub fn diff_vs_head(
+        &self,
+        workspace_root: &Path,
+        fs: &dyn WorkingTreeFs,
+    ) -> Result<WorkspaceDiff, String> {
+        let prefix = self.prefix(workspace_root)?;
+        let objects = self.objects();
+        let head = self.head_files(&objects, &prefix)?;
+        let index: BTreeMap<String, IndexEntry> = self
+            .index(&prefix)?
+            .into_iter()
+            .filter(|entry| entry.stage == 0 && entry.is_file())
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+        let index_mtime = self.index_mtime();
+
+        let paths: BTreeSet<&String> = head.keys().chain(index.keys()).collect();
+        let mut before = BTreeMap::new();
+        let mut after = BTreeMap::new();
+        for path in paths {
+            let head_oid = head.get(path).copied();
+            // Unchanged since HEAD by the cheap test: staged as in HEAD, and
+            // the stat data says the file wasn't touched since.
+            if let Some(entry) = index.get(path) {
+                if entry.skip_worktree
+                    || head_oid == Some(entry.oid) && !self.worktree_differs(entry, index_mtime, fs)
+                {
+                    continue;
+                }
+            }
+            let working = fs.read_file(&self.work_tree.join(path)).ok();
+            if let (Some(oid), Some(bytes)) = (head_oid, &working) {
+                if blob_matches(oid, bytes) {
+                    continue;
+                }
+            }
+            let rel = path[prefix.len()..].to_string();
+            if let Some(oid) = head_oid {
+                before.insert(rel.clone(), objects.read_kind(oid, ObjectKind::Blob)?);
+            }
+            if let Some(bytes) = working {
+                after.insert(rel, bytes);
+            }
+        }
+        Ok(build_diff(before, after))
+    }
+
+    /// Where `workspace_root` sits in the work tree, as a path prefix:
+    /// `""` at the top, `"sub/dir/"` further down.
+    fn prefix(&self, workspace_root: &Path) -> Result<String, String> {
+        let rel = workspace_root
+            .strip_prefix(&self.work_tree)
+            .map_err(|_| format!("{} is not inside the repository", workspace_root.display()))?;
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        Ok(if rel.is_empty() {
+            rel
+        } else {
+            format!("{rel}/")
+        })
+    }
+
+    fn objects(&self) -> ObjectStore {
+        ObjectStore::open(&self.common_dir.join("objects"))
+    }
+
+    /// Index entries under `prefix`.
+    fn index(&self, prefix: &str) -> Result<Vec<IndexEntry>, String> {
+        let mut entries = index::read_index(&self.git_dir.join("index"))?;
+        entries.retain(|entry| entry.path.starts_with(prefix));
+        Ok(entries)
+    }
+
+    fn index_mtime(&self) -> Option<(u32, u32)> {
+        std::fs::metadata(self.git_dir.join("index"))
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(mtime_parts)
+    }
+
+    /// The files HEAD's tree holds under `prefix`, by work-tree-relative
+    /// path. Empty before the first commit.
+    fn head_files(
+        &self,
+        objects: &ObjectStore,
+        prefix: &str,
+    ) -> Result<BTreeMap<String, Oid>, String> {
+        let mut files = BTreeMap::new();
+        let Some(commit) = self.head()?.oid() else {
+            return Ok(files);
+        };
+        let commit = parse_commit(&objects.read_kind(commit, ObjectKind::Commit)?)?;
+        collect_tree(objects, commit.tree, "", prefix, &mut files)?;
+        Ok(files)
+    }
+
+    /// Whether the working file of `entry` differs from what the index holds.
+    ///
+    /// The stat data is read with `std::fs` rather than through `fs`: the
+    /// index recorded it from the local disk, and `WorkingTreeFs` has no
+    /// metadata to compare against anyway.
+    fn worktree_differs(
+        &self,
+        entry: &IndexEntry,
+        index_mtime: Option<(u32, u32)>,
+        fs: &dyn WorkingTreeFs,
+    ) -> bool {
+        let path = self.work_tree.join(&entry.path);
+        let Ok(meta) = std::fs::symlink_metadata(&path) else {
+            return true;
+        };
+        if !meta.is_file() {
+            return true;
+        }
+        let mtime = meta.modified().ok().and_then(mtime_parts);
+        // A file written in the same second the index was can change again
+        // without its stat data showing it ("racy git"), so it's hashed.
+        let racy = index_mtime.is_none_or(|index| entry.mtime.0 >= index.0);
+        if !racy
+            && meta.len() as u32 == entry.size
+            && mtime.is_some_and(|m| same_mtime(m, entry.mtime))
+        {
+            return false;
+        }
+        match fs.read_file(&path) {
+            Ok(bytes) => !blob_matches(entry.oid, &bytes),
+            Err(_) => true,
+        }
+    }
+}
+
+/// Add the files of tree `oid`, sitting at `base` in the work tree, to
+/// `files` — only those under `prefix`, and without reading subtrees that
+/// can't hold any.
+fn collect_tree(
+    objects: &ObjectStore,
+    oid: Oid,
+    base: &str,
+    prefix: &str,
+    files: &mut BTreeMap<String, Oid>,
+) -> Result<(), String> {
+    for entry in parse_tree(&objects.read_kind(oid, ObjectKind::Tree)?)? {
+        let path = format!("{base}{}", entry.name);
+        if entry.mode == MODE_TREE {
+            let dir = format!("{path}/");
+            if dir.starts_with(prefix) || prefix.starts_with(&dir) {
+                collect_tree(objects, entry.oid, &dir, prefix, files)?;
+            }
+        } else if entry.is_file() && path.starts_with(prefix) {
+            files.insert(path, entry.oid);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `bytes` are the blob `oid`. A checkout with `core.autocrlf`
+/// writes `\r\n` line breaks for a blob stored with `\n`, so that counts as
+/// a match too.
+fn blob_matches(oid: Oid, bytes: &[u8]) -> bool {
+    if Oid::hash_object(ObjectKind::Blob, bytes) == oid {
+        return true;
+    }
+    if !bytes.windows(2).any(|pair| pair == b"\r\n") {
+        return false;
+    }
+    let mut normalized = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().peekable();
+    while let Some(&byte) = iter.next() {
+        if byte == b'\r' && iter.peek() == Some(&&b'\n') {
+            continue;
+        }
+        normalized.push(byte);
+    }
+    Oid::hash_object(ObjectKind::Blob, &normalized) == oid
+}
+
+fn mtime_parts(time: SystemTime) -> Option<(u32, u32)> {
+    let since = time.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+    Some((since.as_secs() as u32, since.subsec_nanos()))
+}
+
+/// Git built without nanosecond support records zero nanoseconds; seconds
+/// alone decide then.
+fn same_mtime(disk: (u32, u32), index: (u32, u32)) -> bool {
+    disk.0 == index.0 && (index.1 == 0 || disk.1 == index.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use crate::vcs::fs::LocalWorkingTreeFs;
+    use crate::vcs::FileDiffStatus;
+
+    fn status_of(repo: &GitRepo, root: &Path) -> Vec<(String, GitFileStatus)> {
+        repo.status(root, &LocalWorkingTreeFs)
+            .expect("status")
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn loose_repository_reports_branch_and_status() {
+        let dir = TempDir::fixture("loose");
+        let repo = GitRepo::discover(&dir.0).expect("a repository");
+        assert_eq!(
+            repo.info().expect("info"),
+            GitInfo {
+                branch: Some("main".into()),
+                head: Some("8f085f3affdba49895e8a670321e7c9b7fb326d1".into()),
+            }
+        );
+        // `build/` is ignored, so its file isn't untracked.
+        assert_eq!(
+            status_of(&repo, &dir.0),
+            [
+                ("chapters/intro.typ".to_string(), GitFileStatus::Modified),
+                ("draft.typ".to_string(), GitFileStatus::Untracked),
+                ("gone.typ".to_string(), GitFileStatus::Modified),
+                ("new.typ".to_string(), GitFileStatus::Staged),
+                ("notes.txt".to_string(), GitFileStatus::StagedModified),
+            ]
+        );
+    }
+
+    #[test]
+    fn loose_repository_diffs_against_head() {
+        let dir = TempDir::fixture("loose");
+        let repo = GitRepo::discover(&dir.0).expect("a repository");
+        let diff = repo
+            .diff_vs_head(&dir.0, &LocalWorkingTreeFs)
+            .expect("diff");
+        let files: Vec<(&str, FileDiffStatus, Option<&str>, Option<&str>)> = diff
+            .files
+            .iter()
+            .map(|f| {
+                let status = f.status.clone();
+                (
+                    f.path.as_str(),
+                    status,
+                    f.before.as_deref(),
+                    f.after.as_deref(),
+                )
+            })
+            .collect();
+        // Untracked `draft.typ` is left out; staged `new.typ` is in.
+        assert_eq!(
+            files,
+            [
+                (
+                    "chapters/intro.typ",
+                    FileDiffStatus::Modified,
+                    Some("== Intro\n\nHello.\n"),
+                    Some("== Intro\n\nHello, world.\n"),
+                ),
+                ("gone.typ", FileDiffStatus::Removed, Some("old\n"), None),
+                ("new.typ", FileDiffStatus::Added, None, Some("= New\n")),
+                (
+                    "notes.txt",
+                    FileDiffStatus::Modified,
+                    Some("todo\n"),
+                    Some("todo\nstaged\nand not\n"),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn packed_repository_resolves_packed_refs_and_deltas() {
+        let dir = TempDir::fixture("packed");
+        let repo = GitRepo::discover(&dir.0).expect("a repository");
+        let info = repo.info().expect("info");
+        assert_eq!(info.branch.as_deref(), Some("feature/long"));
+        assert_eq!(
+            status_of(&repo, &dir.0),
+            [("long.typ".to_string(), GitFileStatus::Modified)]
+        );
+
+        let diff = repo
+            .diff_vs_head(&dir.0, &LocalWorkingTreeFs)
+            .expect("diff");
+        assert_eq!(diff.files.len(), 1);
+        let before = diff.files[0].before.as_deref().expect("text");
+        assert!(before.contains("Line 1 of") && before.contains("Line one hundred fifty of"));
+        assert!(diff.files[0]
+            .after
+            .as_deref()
+            .expect("text")
+            .contains("Line one of"));
+
+        // The first commit's version of the chapter (`HEAD~2:long.typ`) is
+        // stored as a delta of a delta.
+        let first = Oid::from_hex("d165d9961ee584192498349f255a35efa7b62a3f").expect("oid");
+        let first = repo
+            .objects()
+            .read_kind(first, ObjectKind::Blob)
+            .expect("blob");
+        let first = String::from_utf8(first).expect("utf-8");
+        assert!(first.contains("Line 50 of") && first.contains("Line 150 of"));
+        assert_eq!(first.lines().count(), 200);
+    }
+
+    #[test]
+    fn a_workspace_inside_the_repository_sees_its_own_files() {
+        let dir = TempDir::fixture("loose");
+        let workspace = dir.0.join("chapters");
+        let repo = GitRepo::discover(&workspace).expect("a repository");
+        assert_eq!(
+            status_of(&repo, &workspace),
+            [("intro.typ".to_string(), GitFileStatus::Modified)]
+        );
+        let diff = repo
+            .diff_vs_head(&workspace, &LocalWorkingTreeFs)
+            .expect("diff");
+        let paths: Vec<&str> = diff.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["intro.typ"]);
+    }
+
+    #[test]
+    fn a_detached_head_has_no_branch() {
+        let dir = TempDir::fixture("loose");
+        let commit = "d87068905e06576884f17a43b5906e532c47d410";
+        std::fs::write(dir.0.join(".git/HEAD"), format!("{commit}\n")).expect("write HEAD");
+        let repo = GitRepo::discover(&dir.0).expect("a repository");
+        assert_eq!(
+            repo.info().expect("info"),
+            GitInfo {
+                branch: None,
+                head: Some(commit.into()),
+            }
+        );
+        // Against the first commit, `main.typ` now counts as staged.
+        assert_eq!(
+            repo.status(&dir.0, &LocalWorkingTreeFs).expect("status")["main.typ"],
+            GitFileStatus::Staged
+        );
+    }
+
+    #[test]
+    fn symlinks_and_submodules_are_left_out() {
+        let dir = TempDir::fixture("loose");
+        let repo = GitRepo::discover(&dir.0).expect("a repository");
+        let before = status_of(&repo, &dir.0);
+
+        let index_path = dir.0.join(".git/index");
+        let mut files: Vec<(String, Oid, u32)> = index::read_index(&index_path)
+            .expect("index")
+            .into_iter()
+            .map(|entry| (entry.path, entry.oid, entry.mode))
+            .collect();
+        let target = Oid::hash_object(ObjectKind::Blob, b"main.typ");
+        files.push(("link.typ".into(), target, 0o120000));
+        files.push(("vendor/lib".into(), Oid([7; 20]), MODE_GITLINK));
+        index::write_index(&index_path, &files).expect("write index");
+        std::fs::create_dir_all(dir.0.join("vendor/lib")).expect("mkdir");
+        std::fs::write(dir.0.join("vendor/lib/lib.typ"), "lib\n").expect("write");
+
+        assert_eq!(status_of(&repo, &dir.0), before);
+        let diff = repo
+            .diff_vs_head(&dir.0, &LocalWorkingTreeFs)
+            .expect("diff");
+        assert!(diff.files.iter().all(|f| f.path != "link.typ"));
+    }
+
+    #[test]
+    fn untracked_files_honour_every_ignore_file_git_reads() {
+        let dir = TempDir::fixture("loose");
+        let workspace = dir.0.join("chapters");
+        std::fs::write(dir.0.join(".gitignore"), "build/\n*.log\n").expect("write");
+        std::fs::create_dir_all(dir.0.join(".git/info")).expect("mkdir");
+        std::fs::write(dir.0.join(".git/info/exclude"), "private.typ\n").expect("write");
+        std::fs::create_dir_all(workspace.join("sub")).expect("mkdir");
+        std::fs::write(workspace.join("sub/.gitignore"), "scratch.typ\n").expect("write");
+        for name in [
+            "build.log",
+            "private.typ",
+            "sub/scratch.typ",
+            "sub/kept.typ",
+        ] {
+            std::fs::write(workspace.join(name), "x\n").expect("write");
+        }
+
+        let repo = GitRepo::discover(&workspace).expect("a repository");
+        assert_eq!(
+            status_of(&repo, &workspace),
+            [
+                ("intro.typ".to_string(), GitFileStatus::Modified),
+                ("sub/.gitignore".to_string(), GitFileStatus::Untracked),
+                ("sub/kept.typ".to_string(), GitFileStatus::Untracked),
+            ]
+        );
+    }
+
+    #[test]
+    fn crlf_checkouts_match_their_blobs() {
+        let oid = Oid::hash_object(ObjectKind::Blob, b"a\nb\n");
+        assert!(blob_matches(oid, b"a\nb\n"));
+        assert!(blob_matches(oid, b"a\r\nb\r\n"));
+        assert!(!blob_matches(oid, b"a\r\nc\r\n"));
+    }
+}
@@ -0,0 +1,746 @@
+// The object database: loose objects under `objects/xx/…` and packfiles
+// under `objects/pack/`.
+//
+// Loose objects are zlib-compressed `<kind> <len>\0<data>`. A pack is a run
+// of zlib-compressed entries, some stored whole and some as deltas against
+// another entry (by offset within the same pack, or by id); its `.idx` maps
+// ids to offsets. Only version 2 indexes are read — git has written nothing
+// else since 2008.
+//
+// Objects are only ever written loose; packing them is left to git's own
+// `gc`.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use log::warn;
+use sha1::{Digest, Sha1};
+
+/// Deepest delta chain followed before giving up on a corrupt pack. Git's
+/// own default is 50; `--aggressive` repacks go to 250.
+const MAX_DELTA_DEPTH: usize = 1000;
+
+/// A git object id.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Oid(pub [u8; 20]);
+
+impl Oid {
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.as_bytes();
+        if hex.len() != 40 {
+            return None;
+        }
+        let mut bytes = [0u8; 20];
+        for (i, pair) in hex.chunks(2).enumerate() {
+            let pair = std::str::from_utf8(pair).ok()?;
+            bytes[i] = u8::from_str_radix(pair, 16).ok()?;
+        }
+        Some(Self(bytes))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(Self(bytes.try_into().ok()?))
+    }
+
+    pub fn to_hex(self) -> String {
+        self.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// The id git gives `data` stored as a `kind` object.
+    pub fn hash_object(kind: ObjectKind, data: &[u8]) -> Self {
+        let mut hasher = Sha1::new();
+        hasher.update(format!("{} {}\0", kind.name(), data.len()));
+        hasher.update(data);
+        Self(hasher.finalize().into())
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Oid({self})")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectKind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl ObjectKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ObjectKind::Commit => "commit",
+            ObjectKind::Tree => "tree",
+            ObjectKind::Blob => "blob",
+            ObjectKind::Tag => "tag",
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"commit" => Some(ObjectKind::Commit),
+            b"tree" => Some(ObjectKind::Tree),
+            b"blob" => Some(ObjectKind::Blob),
+            b"tag" => Some(ObjectKind::Tag),
+            _ => None,
+        }
+    }
+}
+
+pub struct Object {
+    pub kind: ObjectKind,
+    pub data: Vec<u8>,
+}
+
+/// Reads objects by id out of one repository's object directory.
+pub struct ObjectStore {
+    dir: PathBuf,
+    packs: Vec<Pack>,
+}
+
+impl ObjectStore {
+    /// Open `objects_dir`, loading the index of every pack in it. A pack
+    /// whose index can't be read is skipped with a warning.
+    pub fn open(objects_dir: &Path) -> Self {
+        let mut packs = Vec::new();
+        if let Ok(dir) = std::fs::read_dir(objects_dir.join("pack")) {
+            for entry in dir.flatten() {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != "idx") {
+                    continue;
+                }
+                match Pack::open(&path) {
+                    Ok(pack) => packs.push(pack),
+                    Err(e) => warn!("git::ObjectStore::open: skipping pack {path:?} err=\"{e}\""),
+                }
+            }
+        }
+        Self {
+            dir: objects_dir.to_path_buf(),
+            packs,
+        }
+    }
+
+    pub fn read(&self, oid: Oid) -> Result<Object, String> {
+        self.read_at_depth(oid, 0)
+    }
+
+    /// Read an object that has to be of `kind`.
+    pub fn read_kind(&self, oid: Oid, kind: ObjectKind) -> Result<Vec<u8>, String> {
+        let object = self.read(oid)?;
+        if object.kind != kind {
+            return Err(format!(
+                "object {oid} is a {}, not a {}",
+                object.kind.name(),
+                kind.name()
+            ));
+        }
+        Ok(object.data)
+    }
+
+    /// Store `data` as a `kind` object unless the repository already has it,
+    /// and return its id. The object goes to a temporary file first and is
+    /// renamed into place, so a reader never sees it half-written.
+    pub fn write(&self, kind: ObjectKind, data: &[u8]) -> Result<Oid, String> {
+        let oid = Oid::hash_object(kind, data);
+        let path = self.loose_path(oid);
+        if path.exists() {
+            return Ok(oid);
+        }
+        for pack in &self.packs {
+            if pack.find(oid)?.is_some() {
+                return Ok(oid);
+            }
+        }
+        let dir = path.parent().expect("loose objects sit in a fanout dir");
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {dir:?}: {e}"))?;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        let compressed = encoder
+            .write_all(format!("{} {}\0", kind.name(), data.len()).as_bytes())
+            .and_then(|()| encoder.write_all(data))
+            .and_then(|()| encoder.finish())
+            .map_err(|e| format!("deflate object {oid}: {e}"))?;
+        let tmp = dir.join(format!("tmp_obj_{oid}"));
+        std::fs::write(&tmp, compressed).map_err(|e| format!("write {tmp:?}: {e}"))?;
+        std::fs::rename(&tmp, &path).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            format!("rename {tmp:?} to {path:?}: {e}")
+        })?;
+        Ok(oid)
+    }
+
+    fn loose_path(&self, oid: Oid) -> PathBuf {
+        let hex = oid.to_hex();
+        self.dir.join(&hex[..2]).join(&hex[2..])
+    }
+
+    fn read_at_depth(&self, oid: Oid, depth: usize) -> Result<Object, String> {
+        if let Some(object) = self.read_loose(oid)? {
+            return Ok(object);
+        }
+        for pack in &self.packs {
+            if let Some(offset) = pack.find(oid)? {
+                return pack.read_at(self, offset, depth);
+            }
+        }
+        Err(format!("object {oid} not found"))
+    }
+
+    fn read_loose(&self, oid: Oid) -> Result<Option<Object>, String> {
+        let path = self.loose_path(oid);
+        let compressed = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("read {path:?}: {e}")),
+        };
+        let mut raw = Vec::new();
+        ZlibDecoder::new(compressed.as_slice())
+            .read_to_end(&mut raw)
+            .map_err(|e| format!("inflate {path:?}: {e}"))?;
+        let nul = raw
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| format!("object {oid} has no header"))?;
+        let header = &raw[..nul];
+        let space = header
+            .iter()
+            .position(|&b| b == b' ')
+            .ok_or_else(|| format!("object {oid} has a malformed header"))?;
+        let kind = ObjectKind::from_name(&header[..space])
+            .ok_or_else(|| format!("object {oid} has an unknown kind"))?;
+        let data = raw.split_off(nul + 1);
+        Ok(Some(Object { kind, data }))
+    }
+}
+
+/// One packfile and its index.
+struct Pack {
+    path: PathBuf,
+    /// `fanout[b]` is how many ids start with a byte `<= b`.
+    fanout: [u32; 256],
+    /// Sorted.
+    oids: Vec<Oid>,
+    offsets: Vec<u64>,
+}
+
+/// A pack entry as stored, before any delta is applied.
+enum PackEntry {
+    Whole(Object),
+    OfsDelta { base_offset: u64, delta: Vec<u8> },
+    RefDelta { base: Oid, delta: Vec<u8> },
+}
+
+impl Pack {
+    fn open(idx_path: &Path) -> Result<Self, String> {
+        let idx = std::fs::read(idx_path).map_err(|e| e.to_string())?;
+        if idx.len() < 8 + 256 * 4 || idx[..4] != [0xff, b't', b'O', b'c'] {
+            return Err("not a version 2 pack index".into());
+        }
+        if be_u32(&idx[4..8]) != 2 {
+            return Err(format!(
+                "unsupported pack index version {}",
+                be_u32(&idx[4..8])
+            ));
+        }
+        let mut fanout = [0u32; 256];
+        for (i, slot) in fanout.iter_mut().enumerate() {
+            *slot = be_u32(&idx[8 + i * 4..]);
+        }
+        let count = fanout[255] as usize;
+        let oids_at = 8 + 256 * 4;
+        let offsets_at = oids_at + count * 20 + count * 4;
+        let large_at = offsets_at + count * 4;
+        if idx.len() < large_at {
+            return Err("pack index is truncated".into());
+        }
+
+        let oids = (0..count)
+            .map(|i| Oid::from_bytes(&idx[oids_at + i * 20..oids_at + (i + 1) * 20]))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("pack index is truncated")?;
+        let mut offsets = Vec::with_capacity(count);
+        for i in 0..count {
+            let offset = be_u32(&idx[offsets_at + i * 4..]);
+            if offset & 0x8000_0000 == 0 {
+                offsets.push(offset as u64);
+            } else {
+                // Packs past 2 GiB keep the high offsets in a second table.
+                let at = large_at + (offset & 0x7fff_ffff) as usize * 8;
+                let bytes = idx.get(at..at + 8).ok_or("pack index is truncated")?;
+                offsets.push(u64::from_be_bytes(bytes.try_into().unwrap()));
+            }
+        }
+
+        Ok(Self {
+            path: idx_path.with_extension("pack"),
+            fanout,
+            oids,
+            offsets,
+        })
+    }
+
+    /// Offset of `oid` in the pack, or `None` when the pack doesn't hold it.
+    /// A fanout table that doesn't fit the index is an error, not a panic.
+    fn find(&self, oid: Oid) -> Result<Option<u64>, String> {
+        let first = oid.0[0] as usize;
+        let lo = if first == 0 {
+            0
+        } else {
+            self.fanout[first - 1] as usize
+        };
+        let hi = self.fanout[first] as usize;
+        if lo > hi || hi > self.oids.len() {
+            return Err(format!("corrupt fanout table in {:?}", self.path));
+        }
+        Ok(self.oids[lo..hi]
+            .binary_search(&oid)
+            .ok()
+            .map(|i| self.offsets[lo + i]))
+    }
+
+    fn read_at(&self, store: &ObjectStore, offset: u64, depth: usize) -> Result<Object, String> {
+        if depth > MAX_DELTA_DEPTH {
+            return Err(format!("delta chain too deep in {:?}", self.path));
+        }
+        match self.read_entry(offset)? {
+            PackEntry::Whole(object) => Ok(object),
+            PackEntry::OfsDelta { base_offset, delta } => {
+                let base = self.read_at(store, base_offset, depth + 1)?;
+                Ok(Object {
+                    kind: base.kind,
+                    data: apply_delta(&base.data, &delta)?,
+                })
+            }
+            PackEntry::RefDelta { base, delta } => {
+                let base = store.read_at_depth(base, depth + 1)?;
+                Ok(Object {
+                    kind: base.kind,
+                    data: apply_delta(&base.data, &delta)?,
+                })
+            }
+        }
+    }
+
+    fn read_entry(&self, offset: u64) -> Result<PackEntry, String> {
+        let file = File::open(&self.path).map_err(|e| format!("open {:?}: {e}", self.path))?;
+        let mut reader = BufReader::new(file);
+        reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| e.to_string())?;
+
+        // Type in bits 4-6 of the first byte, size in little-endian groups of
+        // seven bits after that.
+        let mut byte = read_u8(&mut reader)?;
+        let kind = (byte >> 4) & 0b111;
+        let mut size = (byte & 0x0f) as u64;
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = read_u8(&mut reader)?;
+            size |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+        }
+
+        let whole = |kind| {
+            PackEntry::Whole(Object {
+                kind,
+                data: Vec::new(),
+            })
+        };
+        let mut entry = match kind {
+            1 => whole(ObjectKind::Commit),
+            2 => whole(ObjectKind::Tree),
+            3 => whole(ObjectKind::Blob),
+            4 => whole(ObjectKind::Tag),
+            6 => {
+                let distance = read_offset_varint(&mut reader)?;
+                let base_offset = offset
+                    .checked_sub(distance)
+                    .ok_or("delta base before the start of the pack")?;
+                PackEntry::OfsDelta {
+                    base_offset,
+                    delta: Vec::new(),
+                }
+            }
+            7 => {
+                let mut base = [0u8; 20];
+                reader.read_exact(&mut base).map_err(|e| e.to_string())?;
+                PackEntry::RefDelta {
+                    base: Oid(base),
+                    delta: Vec::new(),
+                }
+            }
+            other => return Err(format!("unknown pack entry type {other} at {offset}")),
+        };
+
+        let mut data = Vec::with_capacity(size as usize);
+        ZlibDecoder::new(reader)
+            .take(size)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("inflate pack entry at {offset}: {e}"))?;
+        if data.len() as u64 != size {
+            return Err(format!("pack entry at {offset} is truncated"));
+        }
+        match &mut entry {
+            PackEntry::Whole(object) => object.data = data,
+            PackEntry::OfsDelta { delta, .. } | PackEntry::RefDelta { delta, .. } => *delta = data,
+        }
+        Ok(entry)
+    }
+}
+
+/// Rebuild an object from its delta base: two sizes, then instructions that
+/// either copy a range of the base or insert literal bytes.
+fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, String> {
+    let mut at = 0;
+    let base_len = read_size_varint(delta, &mut at)?;
+    if base_len != base.len() {
+        return Err("delta base has the wrong size".into());
+    }
+    let out_len = read_size_varint(delta, &mut at)?;
+    let mut out = Vec::with_capacity(out_len);
+
+    while at < delta.len() {
+        let op = delta[at];
+        at += 1;
+        if op & 0x80 != 0 {
+            // Copy: bits 0-3 say which offset bytes follow, bits 4-6 which
+            // size bytes.
+            let mut copy_offset = 0usize;
+            let mut copy_len = 0usize;
+            for i in 0..4 {
+                if op & (1 << i) != 0 {
+                    copy_offset |=
+                        (*delta.get(at).ok_or("delta is truncated")? as usize) << (i * 8);
+                    at += 1;
+                }
+            }
+            for i in 0..3 {
+                if op & (0x10 << i) != 0 {
+                    copy_len |= (*delta.get(at).ok_or("delta is truncated")? as usize) << (i * 8);
+                    at += 1;
+                }
+            }
+            if copy_len == 0 {
+                copy_len = 0x10000;
+            }
+            let range = base
+                .get(copy_offset..copy_offset + copy_len)
+                .ok_or("delta copies past the end of its base")?;
+            out.extend_from_slice(range);
+        } else if op != 0 {
+            let insert = delta
+                .get(at..at + op as usize)
+                .ok_or("delta is truncated")?;
+            out.extend_from_slice(insert);
+            at += op as usize;
+        } else {
+            return Err("delta has a reserved instruction".into());
+        }
+    }
+
+    if out.len() != out_len {
+        return Err("delta result has the wrong size".into());
+    }
+    Ok(out)
+}
+
+/// Little-endian groups of seven bits, as in delta headers.
+fn read_size_varint(bytes: &[u8], at: &mut usize) -> Result<usize, String> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*at).ok_or("delta is truncated")?;
+        *at += 1;
+        value |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Git's offset encoding: big-endian groups of seven bits, each continuation
+/// adding one so that no value has two encodings. Used for delta base
+/// distances and index v4 path prefixes.
+pub(super) fn read_offset_varint(reader: &mut impl Read) -> Result<u64, String> {
+    let mut byte = read_u8(reader)?;
+    let mut value = (byte & 0x7f) as u64;
+    while byte & 0x80 != 0 {
+        byte = read_u8(reader)?;
+        value = ((value + 1) << 7) | (byte & 0x7f) as u64;
+    }
+    Ok(value)
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8, String> {
+    let mut byte = [0u8; 1];
+    reader
+        .read_exact(&mut byte)
+        .map_err(|e| format!("unexpected end of data: {e}"))?;
+    Ok(byte[0])
+}
+
+pub(super) fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes(bytes[..4].try_into().unwrap())
+}
+
+// ─── Trees and commits ──────────────────────────────────────────────────────
+
+/// File mode of a subtree entry.
+pub const MODE_TREE: u32 = 0o040000;
+/// File mode of a regular, non-executable file.
+pub const MODE_FILE: u32 = 0o100644;
+/// Mode of a submodule: the entry names a commit of another repository.
+pub const MODE_GITLINK: u32 = 0o160000;
+
+pub struct TreeEntry {
+    pub mode: u32,
+    pub name: String,
+    pub oid: Oid,
+}
+
+impl TreeEntry {
+    /// A regular file, executable or not — not a symlink or a submodule.
+    pub fn is_file(&self) -> bool {
+        self.mode & 0o170000 == 0o100000
+    }
+}
+
+/// Entries of a tree object: `<octal mode> <name>\0<20-byte id>`, repeated.
+pub fn parse_tree(data: &[u8]) -> Result<Vec<TreeEntry>, String> {
+    let mut entries = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let space = rest
+            .iter()
+            .position(|&b| b == b' ')
+            .ok_or("malformed tree entry")?;
+        let nul = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("malformed tree entry")?;
+        let mode = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+            .ok_or("malformed tree entry mode")?;
+        let name = String::from_utf8_lossy(&rest[space + 1..nul]).into_owned();
+        let oid = rest
+            .get(nul + 1..nul + 21)
+            .and_then(Oid::from_bytes)
+            .ok_or("truncated tree entry")?;
+        entries.push(TreeEntry { mode, name, oid });
+        rest = &rest[nul + 21..];
+    }
+    Ok(entries)
+}
+
+/// Serialize `entries` as a tree object. Git orders entries by name, with a
+/// subtree sorting as if its name ended in `/`.
+pub fn format_tree(entries: &mut [TreeEntry]) -> Vec<u8> {
+    entries.sort_by_cached_key(|entry| {
+        let mut key = entry.name.as_bytes().to_vec();
+        if entry.mode == MODE_TREE {
+            key.push(b'/');
+        }
+        key
+    });
+    let mut out = Vec::new();
+    for entry in entries.iter() {
+        out.extend_from_slice(format!("{:o} {}\0", entry.mode, entry.name).as_bytes());
+        out.extend_from_slice(&entry.oid.0);
+    }
+    out
+}
+
+pub struct Commit {
+    pub tree: Oid,
+    /// The first parent is the commit this one was made on top of; merges
+    /// have more.
+    pub parents: Vec<Oid>,
+    /// Author date, in seconds since the Unix epoch.
+    pub author_time: i64,
+    pub message: String,
+}
+
+/// The parts of a commit object this module needs.
+pub fn parse_commit(data: &[u8]) -> Result<Commit, String> {
+    let text = String::from_utf8_lossy(data);
+    let (headers, message) = text.split_once("\n\n").unwrap_or((&text, ""));
+    let mut tree = None;
+    let mut parents = Vec::new();
+    let mut author_time = 0;
+    for line in headers.lines() {
+        if let Some(hex) = line.strip_prefix("tree ") {
+            tree = Oid::from_hex(hex);
+        } else if let Some(hex) = line.strip_prefix("parent ") {
+            parents.push(Oid::from_hex(hex).ok_or("malformed commit parent")?);
+        } else if let Some(author) = line.strip_prefix("author ") {
+            // `Name <email> <seconds> <zone>`; the name can hold spaces.
+            author_time = author
+                .rsplit(' ')
+                .nth(1)
+                .and_then(|seconds| seconds.parse().ok())
+                .ok_or("malformed commit author")?;
+        }
+    }
+    Ok(Commit {
+        tree: tree.ok_or("commit has no tree")?,
+        parents,
+        author_time,
+        message: message.to_string(),
+    })
+}
+
+/// Who made a commit, and when: seconds since the Unix epoch and the UTC
+/// offset in minutes.
+pub struct Signature<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub time: i64,
+    pub offset_minutes: i32,
+}
+
+impl fmt::Display for Signature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.offset_minutes < 0 { '-' } else { '+' };
+        let offset = self.offset_minutes.unsigned_abs();
+        write!(
+            f,
+            "{} <{}> {} {sign}{:02}{:02}",
+            self.name,
+            self.email,
+            self.time,
+            offset / 60,
+            offset % 60
+        )
+    }
+}
+
+/// Serialize a commit object; `signature` is both author and committer.
+pub fn format_commit(tree: Oid, parents: &[Oid], signature: &Signature, message: &str) -> Vec<u8> {
+    let mut out = format!("tree {tree}\n");
+    for parent in parents {
+        out.push_str(&format!("parent {parent}\n"));
+    }
+    out.push_str(&format!(
+        "author {signature}\ncommitter {signature}\n\n{message}"
+    ));
+    if !message.ends_with('\n') {
+        out.push('\n');
+    }
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blob_ids_match_git() {
+        // `printf 'hello\n' | git hash-object --stdin`
+        assert_eq!(
+            Oid::hash_object(ObjectKind::Blob, b"hello\n").to_hex(),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+        assert_eq!(
+            Oid::from_hex("ce013625030ba8dba906f756967f9e9ca394464a"),
+            Some(Oid::hash_object(ObjectKind::Blob, b"hello\n"))
+        );
+        assert_eq!(Oid::from_hex("ce01"), None);
+    }
+
+    #[test]
+    fn deltas_copy_and_insert() {
+        let base = b"The quick brown fox";
+        // base and result are both 19 bytes; copy 0..10 ("The quick "), insert
+        // "red", copy 15..19 (" fox"), insert "es".
+        let delta = [
+            19, 19, 0x90, 10, 3, b'r', b'e', b'd', 0x91, 15, 4, 2, b'e', b's',
+        ];
+        assert_eq!(apply_delta(base, &delta).unwrap(), b"The quick red foxes");
+        assert!(apply_delta(b"short", &delta).is_err());
+    }
+
+    #[test]
+    fn written_trees_and_commits_parse_back() {
+        let blob = Oid::hash_object(ObjectKind::Blob, b"hello\n");
+        let entry = |mode, name: &str| TreeEntry {
+            mode,
+            name: name.into(),
+            oid: blob,
+        };
+        let mut entries = [
+            entry(MODE_FILE, "a.typ"),
+            entry(MODE_TREE, "a"),
+            entry(MODE_FILE, "a-b"),
+        ];
+        let tree = format_tree(&mut entries);
+        // '-' < '.' < '/', and the subtree `a` sorts as `a/`.
+        let names: Vec<String> = parse_tree(&tree)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(names, ["a-b", "a.typ", "a"]);
+
+        let tree = Oid::hash_object(ObjectKind::Tree, &tree);
+        let signature = Signature {
+            name: "Ada Writer",
+            email: "ada@example.com",
+            time: 1_700_000_000,
+            offset_minutes: -90,
+        };
+        let data = format_commit(tree, &[blob], &signature, "Saved main.typ");
+        assert!(String::from_utf8_lossy(&data)
+            .contains("author Ada Writer <ada@example.com> 1700000000 -0130\n"));
+        let commit = parse_commit(&data).unwrap();
+        assert_eq!(commit.tree, tree);
+        assert_eq!(commit.parents, [blob]);
+        assert_eq!(commit.author_time, 1_700_000_000);
+        assert_eq!(commit.message, "Saved main.typ\n");
+    }
+
+    #[test]
+    fn offset_varints_use_gits_encoding() {
+        assert_eq!(read_offset_varint(&mut &[0x05][..]).unwrap(), 5);
+        // 0x81 0x00 is (1 + 1) << 7 = 256, not 128.
+        assert_eq!(read_offset_varint(&mut &[0x81, 0x00][..]).unwrap(), 256);
+    }
+
+    #[test]
+    fn a_fanout_past_the_index_is_an_error_not_a_panic() {
+        let oid = Oid::hash_object(ObjectKind::Blob, b"hello\n");
+        let mut fanout = [0u32; 256];
+        // Every bucket from the object's first byte on claims five entries,
+        // but the index only lists one.
+        fanout[oid.0[0] as usize..].fill(5);
+        let pack = Pack {
+            path: PathBuf::from("pack-corrupt.pack"),
+            fanout,
+            oids: vec![oid],
+            offsets: vec![12],
+        };
+        assert!(pack.find(oid).is_err());
+
+        // Decreasing bounds are just as corrupt.
+        let mut fanout = [1u32; 256];
+        fanout[oid.0[0] as usize] = 0;
+        let pack = Pack { fanout, ..pack };
+        assert!(pack.find(oid).is_err());
+    }
+}
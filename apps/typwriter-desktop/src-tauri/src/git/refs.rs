@@ -0,0 +1,111 @@
+// References: `HEAD`, loose refs under `refs/`, and `packed-refs`. Updates
+// only ever write loose refs.
+
+use std::{io::Write, path::Path};
+
+use super::object::Oid;
+
+/// Symbolic refs pointing at symbolic refs are followed this far.
+const MAX_SYMREF_DEPTH: usize = 5;
+
+/// What `HEAD` points at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Head {
+    /// On a branch. `oid` is `None` until the branch's first commit.
+    Branch { name: String, oid: Option<Oid> },
+    /// Checked out at a commit directly.
+    Detached(Oid),
+}
+
+impl Head {
+    pub fn oid(&self) -> Option<Oid> {
+        match self {
+            Head::Branch { oid, .. } => *oid,
+            Head::Detached(oid) => Some(*oid),
+        }
+    }
+}
+
+/// Read `HEAD` from `git_dir`, resolving refs in `common_dir` (the same
+/// directory, except in a linked worktree).
+pub fn read_head(git_dir: &Path, common_dir: &Path) -> Result<Head, String> {
+    let head =
+        std::fs::read_to_string(git_dir.join("HEAD")).map_err(|e| format!("read HEAD: {e}"))?;
+    let head = head.trim();
+    if let Some(target) = head.strip_prefix("ref: ") {
+        let name = target
+            .strip_prefix("refs/heads/")
+            .unwrap_or(target)
+            .to_string();
+        let oid = resolve_ref(common_dir, target)?;
+        return Ok(Head::Branch { name, oid });
+    }
+    Oid::from_hex(head)
+        .map(Head::Detached)
+        .ok_or_else(|| format!("malformed HEAD {head:?}"))
+}
+
+/// The commit `name` (e.g. `refs/heads/main`) points at, or `None` when the
+/// ref doesn't exist.
+pub fn resolve_ref(common_dir: &Path, name: &str) -> Result<Option<Oid>, String> {
+    let mut name = name.to_string();
+    for _ in 0..MAX_SYMREF_DEPTH {
+        match std::fs::read_to_string(common_dir.join(&name)) {
+            Ok(contents) => {
+                let contents = contents.trim();
+                if let Some(target) = contents.strip_prefix("ref: ") {
+                    name = target.to_string();
+                    continue;
+                }
+                return Oid::from_hex(contents)
+                    .map(Some)
+                    .ok_or_else(|| format!("malformed ref {name}"));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(packed_ref(common_dir, &name));
+            }
+            Err(e) => return Err(format!("read ref {name}: {e}")),
+        }
+    }
+    Err(format!("symbolic ref {name} nests too deep"))
+}
+
+/// Point `name` at `oid` by writing a loose ref, which takes precedence
+/// over any entry in `packed-refs`. Like git, the new value goes to
+/// `<name>.lock` first — creating that file fails while someone else holds
+/// the ref — and is renamed over the ref.
+pub fn update_ref(common_dir: &Path, name: &str, oid: Oid) -> Result<(), String> {
+    let path = common_dir.join(name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("create {parent:?}: {e}"))?;
+    }
+    let lock = common_dir.join(format!("{name}.lock"));
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock)
+        .map_err(|e| format!("lock ref {name}: {e}"))?;
+    let written = file
+        .write_all(format!("{oid}\n").as_bytes())
+        .and_then(|()| file.sync_all())
+        .and_then(|()| std::fs::rename(&lock, &path));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&lock);
+        return Err(format!("write ref {name}: {e}"));
+    }
+    Ok(())
+}
+
+/// Look `name` up in `packed-refs`: `<hex> <name>` lines, with `#` headers
+/// and `^<hex>` lines for the commits annotated tags peel to.
+fn packed_ref(common_dir: &Path, name: &str) -> Option<Oid> {
+    let packed = std::fs::read_to_string(common_dir.join("packed-refs")).ok()?;
+    packed.lines().find_map(|line| {
+        let (hex, ref_name) = line.split_once(' ')?;
+        if ref_name == name {
+            Oid::from_hex(hex)
+        } else {
+            None
+        }
+    })
+}
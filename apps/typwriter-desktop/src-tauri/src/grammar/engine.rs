@@ -170,6 +170,9 @@ pub struct GrammarEngine {
 
 struct State {
     config: GrammarConfig,
+    /// The open workspace's dialect (`typwriter.toml`), which wins over the
+    /// one in `config` without being saved into it.
+    dialect_override: Option<GrammarDialect>,
     /// Built on first use and dropped whenever the dialect or user dictionary
     /// changes. Construction loads the curated dictionary and instantiates
     /// several hundred linters — far too slow to sit on the startup path, and
@@ -187,6 +190,7 @@ impl GrammarEngine {
         Self {
             state: Mutex::new(State {
                 config,
+                dialect_override: None,
                 built: None,
             }),
         }
@@ -208,13 +212,23 @@ impl GrammarEngine {
         if needs_rebuild {
             state.built = None;
         } else {
-            let State { config, built } = &mut *state;
+            let State { config, built, .. } = &mut *state;
             if let Some(built) = built {
                 apply_rules(&mut built.group, config);
             }
         }
     }
 
+    /// Check against `dialect` instead of the configured one, or go back to
+    /// the configured one with `None`.
+    pub fn set_dialect_override(&self, dialect: Option<GrammarDialect>) {
+        let mut state = self.state.lock();
+        if state.dialect_override != dialect {
+            state.dialect_override = dialect;
+            state.built = None;
+        }
+    }
+
     /// Add a word to the user dictionary, returning the updated config so the
     /// caller can persist it. No-op if the word is already there.
     pub fn add_dictionary_word(&self, word: &str) -> GrammarConfig {
@@ -320,14 +334,15 @@ impl GrammarEngine {
 impl State {
     fn ensure_built(&mut self) -> &mut Built {
         if self.built.is_none() {
-            self.built = Some(Built::new(&self.config));
+            let dialect = self.dialect_override.unwrap_or(self.config.dialect);
+            self.built = Some(Built::new(&self.config, dialect));
         }
         self.built.as_mut().expect("just built")
     }
 }
 
 impl Built {
-    fn new(config: &GrammarConfig) -> Self {
+    fn new(config: &GrammarConfig, dialect: GrammarDialect) -> Self {
         let mut dictionary = MergedDictionary::new();
         dictionary.add_dictionary(FstDictionary::curated());
 
@@ -344,7 +359,7 @@ impl Built {
 
         let dictionary = Arc::new(dictionary);
         let mut group =
-            harper_core::linting::LintGroup::new_curated(dictionary.clone(), config.dialect.into());
+            harper_core::linting::LintGroup::new_curated(dictionary.clone(), dialect.into());
         apply_rules(&mut group, config);
 
         Self { dictionary, group }
@@ -2,8 +2,11 @@
 
 mod commands;
 mod compiler;
+mod git;
 mod grammar;
 mod lsp;
+#[cfg(test)]
+pub(crate) mod test_support;
 mod vcs;
 mod workspace;
 mod world;
@@ -11,6 +14,10 @@ mod world;
 use std::sync::Arc;
 
 use compiler::{parse_key, PageDiffEngine, PreviewPipeline};
+pub use compiler::{
+    run_diagnostics_cli, run_worker_process as run_compile_worker, DIAGNOSTICS_FLAG,
+    WORKER_FLAG as COMPILE_WORKER_FLAG,
+};
 use parking_lot::RwLock;
 use tauri::Manager;
 use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
@@ -25,37 +32,53 @@ use commands::{
         discard_shadow, get_completions, get_definitions, get_tooltip, open_file_externally,
         read_file, reveal_file_in_manager, save_file, update_file_content,
     },
-    export::{export_html, export_pdf, export_png, export_svg},
+    export::{
+        export_dependency_graph, export_diagnostics, export_html, export_pdf, export_png,
+        export_svg, get_dependency_graph, get_font_usage, preflight_accessibility, preflight_print,
+    },
     format::{
         format_typst_cursor_virtual, format_typst_file, format_typst_source,
         format_workspace_typ_files,
     },
+    git::{get_git_info, git_diff_vs_head},
     grammar::{
         add_grammar_dictionary_word, check_grammar, get_grammar_config, get_grammar_rules,
         set_grammar_config, set_grammar_file_enabled,
     },
+    local_packages::{
+        delete_local_package, list_local_packages, open_local_package, publish_local_package,
+    },
     logs::get_log_file_path,
     lsp::{lsp_probe, lsp_send, lsp_start, lsp_stop},
-    packages::list_packages,
+    packages::{
+        list_packages, list_workspace_packages, remove_stale_packages, upgrade_workspace_package,
+    },
     present::{enter_presentation, exit_presentation, list_displays},
-    preview::{get_zoom, set_visible_page, set_zoom, sync_preview, trigger_preview},
+    preview::{
+        get_standalone_preview, get_zoom, preview_full_document, preview_standalone,
+        set_visible_page, set_zoom, sync_preview, trigger_preview,
+    },
     search::{replace_in_workspace, search_workspace},
     settings::{
-        get_app_settings, get_export_presets, get_onboarding_completed, get_user_snippets,
-        list_font_families, list_system_font_families, set_app_settings, set_export_presets,
-        set_onboarding_completed, set_typst_font_directories, set_user_snippets,
+        get_app_settings, get_export_presets, get_onboarding_completed, get_package_registries,
+        get_user_snippets, list_font_families, list_system_font_families, set_app_settings,
+        set_export_presets, set_onboarding_completed, set_package_registries,
+        set_typst_font_directories, set_user_snippets,
     },
+    templates::{delete_template, list_templates, save_workspace_as_template},
     vcs::{
         vcs_create_restore_point, vcs_current_id, vcs_diff_between, vcs_diff_vs_current,
-        vcs_list_history, vcs_page_diff_cancel, vcs_page_diff_render_page,
-        vcs_page_diff_request, vcs_restore_file, vcs_restore_workspace,
+        vcs_export_to_git, vcs_import_from_git, vcs_list_history, vcs_page_diff_cancel,
+        vcs_page_diff_render_page, vcs_page_diff_request, vcs_restore_file, vcs_restore_workspace,
     },
     workspace::{
-        clear_recent_workspaces, create_file, create_folder, create_workspace, delete_file,
-        delete_folder, get_file_tree, get_project_snippets, get_recent_workspaces,
-        get_workspace_tabs, import_dropped, import_files, move_file, move_folder, open_folder,
-        remove_recent_workspace, rename_file, save_workspace_tabs, set_main_file,
-        set_project_snippets,
+        archive_files, clear_recent_workspaces, convert_to_utf8_lf, create_file, create_folder,
+        create_workspace, create_workspace_from_package, delete_file, delete_folder,
+        delete_from_trash, discard_recovered_buffer, empty_trash, find_unused_files, get_file_tree,
+        get_project_snippets, get_recent_workspaces, get_workspace_tabs, import_dropped,
+        import_files, list_recovered_buffers, list_trash, move_file, move_folder, open_folder,
+        remove_recent_workspace, rename_file, restore_from_trash, restore_recovered_buffer,
+        save_workspace_tabs, set_main_file, set_project_snippets,
     },
 };
 
@@ -196,6 +219,10 @@ pub fn run() {
                 if let Some(lsp) = window.app_handle().try_state::<lsp::LspState>() {
                     lsp.stop();
                 }
+                // Same for the compile isolation worker.
+                if let Some(pipeline) = window.app_handle().try_state::<Arc<PreviewPipeline>>() {
+                    pipeline.isolation.shutdown();
+                }
             }
         })
         .setup(|app| {
@@ -209,11 +236,15 @@ pub fn run() {
             // the world reads source files through.
             let vcs = Arc::new(VcsState::new(handle.clone()));
             let world = Arc::new(EditorWorld::new(root, handle.clone(), vcs.clone()));
+            world.set_package_registries(commands::settings::load_package_registries(&handle));
             let pipeline = Arc::new(PreviewPipeline::new(
                 world.clone(),
                 handle.clone(),
                 vcs.clone(),
             ));
+            pipeline
+                .isolation
+                .configure(commands::settings::isolation_config_from_handle(&handle));
             pipeline.start_worker();
             let workspace = Arc::new(WorkspaceState::new(
                 world.clone(),
@@ -221,6 +252,7 @@ pub fn run() {
                 vcs.clone(),
                 handle.clone(),
             ));
+            workspace.start_recovery_journal();
             // Historical compiles run on their own worker so a page
             // comparison never contends with (or blocks) the live preview.
             let page_diff = Arc::new(PageDiffEngine::new(
@@ -273,6 +305,10 @@ pub fn run() {
             // workspace / file-system
             open_folder,
             create_workspace,
+            create_workspace_from_package,
+            list_templates,
+            save_workspace_as_template,
+            delete_template,
             set_main_file,
             get_file_tree,
             get_recent_workspaces,
@@ -289,6 +325,16 @@ pub fn run() {
             move_folder,
             import_files,
             import_dropped,
+            find_unused_files,
+            archive_files,
+            list_trash,
+            restore_from_trash,
+            delete_from_trash,
+            empty_trash,
+            list_recovered_buffers,
+            restore_recovered_buffer,
+            discard_recovered_buffer,
+            convert_to_utf8_lf,
             // editor buffer + IDE features
             read_file,
             update_file_content,
@@ -305,6 +351,9 @@ pub fn run() {
             set_zoom,
             get_zoom,
             set_visible_page,
+            preview_standalone,
+            preview_full_document,
+            get_standalone_preview,
             // presentation mode
             list_displays,
             enter_presentation,
@@ -322,6 +371,13 @@ pub fn run() {
             // logs
             get_log_file_path,
             list_packages,
+            list_workspace_packages,
+            upgrade_workspace_package,
+            remove_stale_packages,
+            list_local_packages,
+            publish_local_package,
+            open_local_package,
+            delete_local_package,
             search_workspace,
             replace_in_workspace,
             // language server (tinymist) bridge
@@ -343,11 +399,19 @@ pub fn run() {
             list_font_families,
             list_system_font_families,
             set_typst_font_directories,
+            get_package_registries,
+            set_package_registries,
             // export
             export_pdf,
             export_png,
             export_svg,
+            export_diagnostics,
             export_html,
+            preflight_accessibility,
+            preflight_print,
+            get_font_usage,
+            get_dependency_graph,
+            export_dependency_graph,
             // format
             format_typst_source,
             format_typst_cursor_virtual,
@@ -361,9 +425,14 @@ pub fn run() {
             vcs_diff_between,
             vcs_restore_workspace,
             vcs_restore_file,
+            vcs_export_to_git,
+            vcs_import_from_git,
             vcs_page_diff_request,
             vcs_page_diff_cancel,
             vcs_page_diff_render_page,
+            // git
+            get_git_info,
+            git_diff_vs_head,
         ])
         .run(tauri::generate_context!())
         .unwrap_or_else(|err| {
@@ -2,5 +2,14 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // The same binary doubles as the compile isolation worker.
+    if std::env::args().nth(1).as_deref() == Some(desktop_lib::COMPILE_WORKER_FLAG) {
+        desktop_lib::run_compile_worker();
+        return;
+    }
+    // ...and as the headless diagnostics exporter for CI.
+    if std::env::args().nth(1).as_deref() == Some(desktop_lib::DIAGNOSTICS_FLAG) {
+        std::process::exit(desktop_lib::run_diagnostics_cli(std::env::args().skip(2)));
+    }
     desktop_lib::run()
 }
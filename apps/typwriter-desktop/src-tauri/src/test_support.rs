@@ -0,0 +1,59 @@
+// Fixtures shared by the unit tests.
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+/// A scratch directory under the system temp dir, removed on drop.
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    /// A fresh, empty directory. `tag` only makes it recognisable on disk.
+    pub fn new(tag: &str) -> Self {
+        // Tests run in parallel; the counter keeps two directories created
+        // within the same clock tick apart.
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("clock after epoch")
+            .as_nanos();
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("typwriter-{tag}-{nanos}-{n}"));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        Self(dir)
+    }
+
+    /// A fresh directory holding the fixture repository `name` (see
+    /// `tests/fixtures/git/make-fixtures.sh`).
+    pub fn fixture(name: &str) -> Self {
+        let archive: &[u8] = match name {
+            "loose" => include_bytes!("../tests/fixtures/git/loose.tar.gz"),
+            "packed" => include_bytes!("../tests/fixtures/git/packed.tar.gz"),
+            _ => panic!("no fixture {name}"),
+        };
+        let dir = Self::new(&format!("git-{name}"));
+        tar::Archive::new(flate2::read::GzDecoder::new(archive))
+            .unpack(&dir.0)
+            .expect("unpack fixture");
+        dir
+    }
+
+    /// Write `contents` to `rel`, creating parent directories.
+    pub fn write(&self, rel: &str, contents: impl AsRef<[u8]>) {
+        let path = self.0.join(rel);
+        std::fs::create_dir_all(path.parent().expect("has parent")).expect("mkdir");
+        std::fs::write(path, contents).expect("write");
+    }
+
+    pub fn read(&self, rel: &str) -> String {
+        std::fs::read_to_string(self.0.join(rel)).expect("read")
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
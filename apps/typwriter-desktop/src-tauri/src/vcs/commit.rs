@@ -8,8 +8,9 @@ use std::path::Path;
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
 
+use crate::workspace::ignore::{walk_files, IgnoreRules, Unreadable};
+
 use super::fs::WorkingTreeFs;
-use super::paths::IGNORED_TOP_LEVEL;
 use super::retention::{prune_snapshots, RetentionPolicy};
 use super::store::{
     build_manifest, find_snapshot_by_id, read_head, write_blob_if_missing,
@@ -49,6 +50,20 @@ impl CommitTrigger {
         }
     }
 
+    /// The trigger whose [`Self::tag`] is `tag`.
+    pub(crate) fn from_tag(tag: &str) -> Option<Self> {
+        [
+            CommitTrigger::Initial,
+            CommitTrigger::Manual,
+            CommitTrigger::Save,
+            CommitTrigger::Compile,
+            CommitTrigger::PreRestore,
+            CommitTrigger::FileOp,
+        ]
+        .into_iter()
+        .find(|trigger| trigger.tag() == tag)
+    }
+
     /// Manual / Initial / PreRestore / FileOp snapshots are user-driven or
     /// safety-critical — they never get pruned by retention rules. FileOp
     /// points record structural changes (delete / rename / move) and are the
@@ -117,44 +132,21 @@ pub fn commit_if_changed(
     Ok(Some(manifest.id))
 }
 
-/// Recursively walk `workspace_root`, skipping the ignored top-level dirs,
+/// Walk `workspace_root`, skipping whatever the workspace ignores,
 /// and build a flat path → blob-hash map. Blob bytes are stored as a
 /// side-effect (zstd-compressed, deduped by content).
 fn build_tree(
     workspace_root: &Path,
     fs: &impl WorkingTreeFs,
 ) -> Result<BTreeMap<String, String>, String> {
+    let rules = IgnoreRules::load(fs, workspace_root);
     let mut out = BTreeMap::new();
-    walk(workspace_root, workspace_root, fs, &mut out)?;
-    Ok(out)
-}
-
-fn walk(
-    workspace_root: &Path,
-    dir: &Path,
-    fs: &impl WorkingTreeFs,
-    out: &mut BTreeMap<String, String>,
-) -> Result<(), String> {
-    let entries = fs.read_dir(dir)?;
-    for entry in entries {
-        if dir == workspace_root && IGNORED_TOP_LEVEL.contains(&entry.name.as_str()) {
-            continue;
-        }
-        if entry.is_dir {
-            walk(workspace_root, &entry.path, fs, out)?;
-        } else if entry.is_file {
-            let bytes = fs.read_file(&entry.path)?;
-            let hash = write_blob_if_missing(fs, workspace_root, &bytes)?;
-            let rel = match entry.path.strip_prefix(workspace_root) {
-                Ok(p) => p.to_path_buf(),
-                Err(_) => continue,
-            };
-            let rel_str = rel.to_string_lossy().replace('\\', "/");
-            out.insert(rel_str, hash);
-        }
-        // Symlinks etc. silently dropped — not relevant for our use case.
+    for file in walk_files(fs, workspace_root, &rules, Unreadable::Fail)? {
+        let bytes = fs.read_file(&file.path)?;
+        let hash = write_blob_if_missing(fs, workspace_root, &bytes)?;
+        out.insert(file.rel, hash);
     }
-    Ok(())
+    Ok(out)
 }
 
 pub(crate) fn now_ms() -> i64 {
@@ -10,15 +10,16 @@ use std::path::Path;
 
 use serde::Serialize;
 
+use crate::workspace::ignore::{walk_files, IgnoreRules, Unreadable};
+
 use super::fs::WorkingTreeFs;
-use super::paths::IGNORED_TOP_LEVEL;
 use super::store::{find_snapshot_by_id, read_blob};
 
 /// Anything bigger than this we treat as "too large to diff inline". Keeps
 /// the IPC payload reasonable and avoids choking the renderer on huge logs.
 const MAX_TEXT_BYTES: usize = 256 * 1024;
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum FileDiffStatus {
     Added,
@@ -88,7 +89,7 @@ fn load_snapshot_bytes(
     Ok(out)
 }
 
-fn build_diff(
+pub(crate) fn build_diff(
     from: BTreeMap<String, Vec<u8>>,
     to: BTreeMap<String, Vec<u8>>,
 ) -> WorkspaceDiff {
@@ -161,33 +162,10 @@ fn collect_working_files(
     workspace_root: &Path,
     fs: &impl WorkingTreeFs,
 ) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let rules = IgnoreRules::load(fs, workspace_root);
     let mut out = BTreeMap::new();
-    collect_into(workspace_root, workspace_root, fs, &mut out)?;
-    Ok(out)
-}
-
-fn collect_into(
-    workspace_root: &Path,
-    dir: &Path,
-    fs: &impl WorkingTreeFs,
-    out: &mut BTreeMap<String, Vec<u8>>,
-) -> Result<(), String> {
-    let entries = fs.read_dir(dir)?;
-    for entry in entries {
-        if dir == workspace_root && IGNORED_TOP_LEVEL.contains(&entry.name.as_str()) {
-            continue;
-        }
-        if entry.is_dir {
-            collect_into(workspace_root, &entry.path, fs, out)?;
-        } else if entry.is_file {
-            let bytes = fs.read_file(&entry.path)?;
-            let rel = match entry.path.strip_prefix(workspace_root) {
-                Ok(p) => p.to_path_buf(),
-                Err(_) => continue,
-            };
-            let rel_str = rel.to_string_lossy().replace('\\', "/");
-            out.insert(rel_str, bytes);
-        }
+    for file in walk_files(fs, workspace_root, &rules, Unreadable::Fail)? {
+        out.insert(file.rel, fs.read_file(&file.path)?);
     }
-    Ok(())
+    Ok(out)
 }
@@ -14,7 +14,8 @@
 //
 //   * `.typwriter/` and `.git/` are skipped when walking — the preview
 //     cache, history itself, and any external git metadata don't belong
-//     in a snapshot.
+//     in a snapshot — and so is everything the workspace's `.gitignore` /
+//     `.typwriterignore` excludes (see `workspace::ignore`).
 //
 //   * "Changed files" for timeline coloring comes from set-diffing each
 //     snapshot's file map against its parent's. The frontend hashes paths
@@ -26,6 +27,10 @@
 //   * Retention: Save and Compile snapshots are subject to the user's
 //     configured count / age caps. Manual / Initial / PreRestore are
 //     always preserved.
+//
+//   * The timeline can be exported to a git repository as commits, and
+//     seeded from a git log (`git::history`). That is a one-off copy each
+//     way; the store itself stays git-free.
 
 mod commit;
 mod diff;
@@ -34,9 +39,11 @@ mod history;
 mod paths;
 mod restore;
 mod retention;
-mod store;
+pub(crate) mod store;
 
+pub(crate) use commit::now_ms;
 pub use commit::CommitTrigger;
+pub(crate) use diff::build_diff;
 #[allow(unused_imports)]
 pub use diff::{FileDiff, FileDiffStatus, WorkspaceDiff};
 pub use history::RestorePoint;
@@ -52,6 +59,7 @@ use std::time::Instant;
 use log::{info, warn};
 use parking_lot::{Mutex, RwLock};
 
+use crate::git::GitExport;
 use fs::LocalWorkingTreeFs;
 pub use fs::WorkingTreeFs;
 
@@ -291,4 +299,20 @@ impl VcsState {
         let fs = LocalWorkingTreeFs;
         restore::restore_file(&root, &fs, commit_id, path)
     }
+
+    /// Commit the restore points to a branch of the workspace's git
+    /// repository, creating the repository if there is none.
+    pub fn export_to_git(&self, branch: Option<&str>) -> Result<GitExport, String> {
+        let root = self.workspace_root().ok_or("No workspace open")?;
+        let fs = LocalWorkingTreeFs;
+        crate::git::export_history(&root, &fs, branch)
+    }
+
+    /// Add the workspace's git log as restore points. Returns how many were
+    /// added.
+    pub fn import_from_git(&self, limit: Option<usize>) -> Result<usize, String> {
+        let root = self.workspace_root().ok_or("No workspace open")?;
+        let fs = LocalWorkingTreeFs;
+        crate::git::import_history(&root, &fs, limit)
+    }
 }
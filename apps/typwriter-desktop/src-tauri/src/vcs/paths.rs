@@ -5,12 +5,6 @@
 
 use std::path::{Path, PathBuf};
 
-/// Top-level directories ignored when walking the workspace tree to build a
-/// snapshot. `.git` is preserved in case the user runs git externally on
-/// desktop; `.typwriter` is *our* metadata (preview cache + history itself)
-/// and must never recurse into a commit.
-pub const IGNORED_TOP_LEVEL: &[&str] = &[".git", ".typwriter"];
-
 /// `<root>/.typwriter/history/`
 pub fn history_root(workspace_root: &Path) -> PathBuf {
     workspace_root.join(".typwriter").join("history")
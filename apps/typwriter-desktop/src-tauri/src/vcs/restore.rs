@@ -7,9 +7,10 @@ use std::path::{Component, Path, PathBuf};
 
 use log::{error, info};
 
+use crate::workspace::ignore::{walk_files, IgnoreRules, Unreadable};
+
 use super::commit::{commit_if_changed, CommitTrigger};
 use super::fs::WorkingTreeFs;
-use super::paths::IGNORED_TOP_LEVEL;
 use super::retention::RetentionPolicy;
 use super::store::{find_snapshot_by_id, read_blob};
 
@@ -41,8 +42,12 @@ pub fn restore_workspace(
 
     // Enumerate current working files so we know what to delete after the
     // target's files are written.
-    let mut current_files: Vec<PathBuf> = Vec::new();
-    walk_working(workspace_root, workspace_root, fs, &mut current_files)?;
+    // Ignored files are not part of any snapshot and are left alone.
+    let rules = IgnoreRules::load(fs, workspace_root);
+    let current_files: Vec<PathBuf> = walk_files(fs, workspace_root, &rules, Unreadable::Fail)?
+        .into_iter()
+        .map(|file| PathBuf::from(file.rel))
+        .collect();
 
     // Write target files. Directories are created on demand; existing files
     // are overwritten in place.
@@ -204,28 +209,6 @@ fn ensure_existing_ancestor_within_workspace(
     }
 }
 
-fn walk_working(
-    workspace_root: &Path,
-    dir: &Path,
-    fs: &impl WorkingTreeFs,
-    out: &mut Vec<PathBuf>,
-) -> Result<(), String> {
-    let entries = fs.read_dir(dir)?;
-    for entry in entries {
-        if dir == workspace_root && IGNORED_TOP_LEVEL.contains(&entry.name.as_str()) {
-            continue;
-        }
-        if entry.is_dir {
-            walk_working(workspace_root, &entry.path, fs, out)?;
-        } else if entry.is_file {
-            if let Ok(rel) = entry.path.strip_prefix(workspace_root) {
-                out.push(rel.to_path_buf());
-            }
-        }
-    }
-    Ok(())
-}
-
 fn prune_empty_parents(fs: &impl WorkingTreeFs, workspace_root: &Path, file: &Path) {
     let mut cur = file.parent();
     while let Some(dir) = cur {
@@ -0,0 +1,340 @@
+// Text encodings and line endings, detected on read and kept on save.
+//
+// The editor works in UTF-8 with `\n` line breaks. Files that arrive in
+// another shape — CRLF from Windows, a UTF-8 BOM, UTF-16 from Excel's "Unicode
+// text" export, Latin-1 bibliographies — are decoded and normalized for the
+// editor, and the shape is recorded in a [`TextFormat`]. Saving re-detects
+// the format from the file on disk and writes the editor's text back in it,
+// so opening and saving a file never changes more than the edited lines.
+//
+// Legacy single-byte files are read as Windows-1252, the superset of
+// ISO-8859-1 browsers use for "Latin-1": every byte maps to a character, so
+// the round trip is exact.
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+}
+
+impl Encoding {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Windows1252 => "Windows-1252",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    /// Classic Mac OS.
+    Cr,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+            Self::Cr => "\r",
+        }
+    }
+}
+
+/// How a text file is stored on disk.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: Encoding,
+    /// Whether the file starts with a byte order mark.
+    pub bom: bool,
+    /// The most common line break. A file that mixes them is written back
+    /// with this one throughout.
+    pub line_ending: LineEnding,
+}
+
+impl TextFormat {
+    /// Plain UTF-8 with `\n` line breaks, what new files are written in.
+    pub const UTF8_LF: Self = Self {
+        encoding: Encoding::Utf8,
+        bom: false,
+        line_ending: LineEnding::Lf,
+    };
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Decode `bytes` into editor text with `\n` line breaks, and the format it
+/// was stored in. With `legacy` off, only Unicode encodings are accepted —
+/// for callers sniffing whether an unknown file is text at all, where
+/// Windows-1252 would take any binary. `None` when the bytes aren't text.
+pub fn decode(bytes: &[u8], legacy: bool) -> Option<(String, TextFormat)> {
+    let (encoding, bom, body) = if let Some(body) = bytes.strip_prefix(UTF8_BOM) {
+        (Encoding::Utf8, true, body)
+    } else if let Some(body) = bytes.strip_prefix(UTF16LE_BOM) {
+        (Encoding::Utf16Le, true, body)
+    } else if let Some(body) = bytes.strip_prefix(UTF16BE_BOM) {
+        (Encoding::Utf16Be, true, body)
+    } else if std::str::from_utf8(bytes).is_ok() && !bytes.contains(&0) {
+        (Encoding::Utf8, false, bytes)
+    } else if let Some(encoding) = sniff_utf16(bytes) {
+        (encoding, false, bytes)
+    } else if legacy && !bytes.contains(&0) {
+        (Encoding::Windows1252, false, bytes)
+    } else {
+        return None;
+    };
+
+    let raw = match encoding {
+        Encoding::Utf8 => String::from_utf8(body.to_vec()).ok()?,
+        Encoding::Utf16Le => decode_utf16(body, u16::from_le_bytes)?,
+        Encoding::Utf16Be => decode_utf16(body, u16::from_be_bytes)?,
+        Encoding::Windows1252 => body.iter().map(|&b| cp1252_char(b)).collect(),
+    };
+    let line_ending = detect_line_ending(&raw);
+    let text = if raw.contains('\r') {
+        raw.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        raw
+    };
+    Some((
+        text,
+        TextFormat {
+            encoding,
+            bom,
+            line_ending,
+        },
+    ))
+}
+
+/// The format of the text file `bytes`, or plain UTF-8 / LF when they don't
+/// decode.
+pub fn detect(bytes: &[u8]) -> TextFormat {
+    decode(bytes, true).map_or(TextFormat::UTF8_LF, |(_, format)| format)
+}
+
+/// Encode editor text for disk in `format`. Fails when the text has
+/// characters a legacy encoding can't store.
+pub fn encode(text: &str, format: TextFormat) -> Result<Vec<u8>, String> {
+    let text = if format.line_ending == LineEnding::Lf && !text.contains('\r') {
+        std::borrow::Cow::Borrowed(text)
+    } else {
+        let lf = text.replace("\r\n", "\n");
+        std::borrow::Cow::Owned(lf.replace('\n', format.line_ending.as_str()))
+    };
+
+    let mut out = Vec::with_capacity(text.len() + 3);
+    match format.encoding {
+        Encoding::Utf8 => {
+            if format.bom {
+                out.extend_from_slice(UTF8_BOM);
+            }
+            out.extend_from_slice(text.as_bytes());
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let le = format.encoding == Encoding::Utf16Le;
+            if format.bom {
+                out.extend_from_slice(if le { UTF16LE_BOM } else { UTF16BE_BOM });
+            }
+            for unit in text.encode_utf16() {
+                out.extend_from_slice(&if le {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                });
+            }
+        }
+        Encoding::Windows1252 => {
+            for c in text.chars() {
+                let byte = cp1252_byte(c).ok_or_else(|| {
+                    format!(
+                        "'{c}' can't be saved in {}; convert the file to UTF-8 first",
+                        format.encoding.label()
+                    )
+                })?;
+                out.push(byte);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// UTF-16 without a byte order mark: mostly-ASCII text leaves every other
+/// byte zero, on the odd side for little-endian and the even for big.
+fn sniff_utf16(bytes: &[u8]) -> Option<Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let probe = &bytes[..bytes.len().min(4096)];
+    let pairs = probe.len() / 2;
+    let zero_even = probe.iter().step_by(2).filter(|&&b| b == 0).count();
+    let zero_odd = probe.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let encoding = if zero_odd * 2 > pairs && zero_even == 0 {
+        Encoding::Utf16Le
+    } else if zero_even * 2 > pairs && zero_odd == 0 {
+        Encoding::Utf16Be
+    } else {
+        return None;
+    };
+    let read = if encoding == Encoding::Utf16Le {
+        u16::from_le_bytes
+    } else {
+        u16::from_be_bytes
+    };
+    decode_utf16(bytes, read).map(|_| encoding)
+}
+
+fn decode_utf16(bytes: &[u8], read: fn([u8; 2]) -> u16) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = bytes.chunks_exact(2).map(|pair| read([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .ok()
+}
+
+fn detect_line_ending(text: &str) -> LineEnding {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    if crlf > lf && crlf >= cr {
+        LineEnding::Crlf
+    } else if cr > lf && cr > crlf {
+        LineEnding::Cr
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Windows-1252 bytes 0x80–0x9F; the rest match Unicode. The five unassigned
+/// bytes map to the C1 controls, as in the WHATWG table, so every byte
+/// survives the round trip.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+fn cp1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => CP1252_HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+fn cp1252_byte(c: char) -> Option<u8> {
+    match u32::from(c) {
+        code @ (0..=0x7F | 0xA0..=0xFF) => Some(code as u8),
+        _ => CP1252_HIGH
+            .iter()
+            .position(|&high| high == c)
+            .map(|i| 0x80 + i as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(bytes: &[u8]) -> (String, TextFormat) {
+        let (text, format) = decode(bytes, true).expect("decodes");
+        assert_eq!(encode(&text, format).expect("encodes"), bytes);
+        (text, format)
+    }
+
+    #[test]
+    fn crlf_is_normalized_for_the_editor_and_restored_on_save() {
+        let (text, format) = round_trip(b"@book{a,\r\n  title={A}\r\n}\r\n");
+        assert_eq!(text, "@book{a,\n  title={A}\n}\n");
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+        assert_eq!(format.encoding, Encoding::Utf8);
+        assert!(!format.bom);
+
+        // New lines typed in the editor get the file's line ending too.
+        let saved = encode("a\nb\n", format).expect("encodes");
+        assert_eq!(saved, b"a\r\nb\r\n");
+    }
+
+    #[test]
+    fn byte_order_marks_are_kept() {
+        let (text, format) = round_trip(b"\xEF\xBB\xBFname,city\n");
+        assert_eq!(text, "name,city\n");
+        assert_eq!((format.encoding, format.bom), (Encoding::Utf8, true));
+
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("é,ü\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+        let (text, format) = round_trip(&utf16);
+        assert_eq!(text, "é,ü\n");
+        assert_eq!(format.encoding, Encoding::Utf16Le);
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+    }
+
+    #[test]
+    fn utf16_without_a_bom_is_recognized() {
+        let bytes: Vec<u8> = "a,b\n1,2\n"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        let (text, format) = round_trip(&bytes);
+        assert_eq!(text, "a,b\n1,2\n");
+        assert_eq!((format.encoding, format.bom), (Encoding::Utf16Be, false));
+    }
+
+    #[test]
+    fn legacy_single_byte_text_round_trips() {
+        // "Müller – 5 €" in Windows-1252.
+        let bytes = b"M\xFCller \x96 5 \x80\n";
+        let (text, format) = round_trip(bytes);
+        assert_eq!(text, "Müller – 5 €\n");
+        assert_eq!(format.encoding, Encoding::Windows1252);
+
+        let err = encode("emoji 🙂", format).expect_err("not representable");
+        assert!(err.contains("Windows-1252"));
+    }
+
+    #[test]
+    fn sniffing_without_legacy_rejects_binary() {
+        assert!(decode(b"\x89PNG\r\n\x1a\n\x00\x00", false).is_none());
+        assert!(decode(b"caf\xE9", false).is_none());
+        assert!(decode(b"caf\xE9", true).is_some());
+    }
+
+    #[test]
+    fn mixed_line_endings_settle_on_the_most_common() {
+        let format = detect(b"a\r\nb\r\nc\nd");
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+        assert_eq!(detect(b"no breaks").line_ending, LineEnding::Lf);
+        assert_eq!(detect(b"a\rb\r").line_ending, LineEnding::Cr);
+    }
+}
@@ -0,0 +1,445 @@
+// Which workspace paths the app leaves alone.
+//
+// The file tree, project search, restore points, workspace diagnostics and
+// the file watcher all walk the same folder, and each used to carry its own
+// list of directories to skip. The lists drifted — a folder could be hidden
+// from the tree yet searched, or ignored by the watcher yet snapshotted — so
+// they are replaced by one rule set, loaded from the workspace:
+//
+//   * `.gitignore` at the workspace root, so a project that is also a git
+//     repository keeps its build output and secrets out of history and
+//     search without repeating itself;
+//   * `.typwriterignore` at the workspace root, same syntax, read after
+//     `.gitignore` so it can re-include (`!pattern`) what git ignores;
+//   * the `ignore` list of `typwriter.toml` (see [`super::project_config`]),
+//     one pattern per entry, read last.
+//
+// Patterns follow gitignore semantics: `*`, `?`, `[...]` and `**` globs, a
+// trailing `/` matches directories only, a pattern containing a `/` is
+// anchored at the root while one without matches at any depth, `!` negates,
+// and the last matching pattern wins. As in git, a file inside an ignored
+// directory cannot be re-included — the directory is never entered.
+// Ignore files in subdirectories are not read.
+//
+// `.git` and `.typwriter` (our own preview cache and history) are excluded
+// before any pattern is consulted and cannot be re-included.
+
+use std::path::{Component, Path, PathBuf};
+
+use log::warn;
+use regex::Regex;
+
+use super::project_config::{self, PROJECT_CONFIG_FILE};
+use crate::vcs::WorkingTreeFs;
+
+/// Ignore file shared with git.
+pub const GITIGNORE: &str = ".gitignore";
+/// The app's own ignore file, applied after [`GITIGNORE`].
+pub const TYPWRITERIGNORE: &str = ".typwriterignore";
+
+/// Never walked, whatever the ignore files say.
+const ALWAYS_IGNORED: &[&str] = &[".git", ".typwriter"];
+
+/// Generated and dependency folders no Typst project wants walked. Ordinary
+/// patterns, so an ignore file can re-include one with `!dist/`.
+const DEFAULT_PATTERNS: &[&str] = &[
+    "node_modules/",
+    "target/",
+    "dist/",
+    ".svelte-kit/",
+];
+
+/// How deep a walk will descend. A guard against pathological nesting and
+/// symlink-free loops (bind mounts); no real project comes close.
+const MAX_DEPTH: usize = 32;
+
+struct Rule {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// The parsed ignore rules of one workspace.
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// The built-in defaults followed by the workspace's ignore files and
+    /// the project's ignore patterns.
+    pub fn load(fs: &dyn WorkingTreeFs, root: &Path) -> Self {
+        let mut sources: Vec<String> = [GITIGNORE, TYPWRITERIGNORE]
+            .iter()
+            .filter_map(|name| fs.read_file(&root.join(name)).ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .collect();
+        sources.push(project_config::ignore_patterns(fs, root).join("\n"));
+        Self::parse(sources.iter().map(String::as_str))
+    }
+
+    /// The built-in defaults followed by the given ignore-file contents.
+    pub fn parse<'a>(sources: impl IntoIterator<Item = &'a str>) -> Self {
+        let defaults = DEFAULT_PATTERNS.iter().copied();
+        let lines = sources.into_iter().flat_map(str::lines);
+        Self {
+            rules: defaults.chain(lines).filter_map(parse_line).collect(),
+        }
+    }
+
+    /// Exactly the patterns of `source`, without the built-in defaults — one
+    /// of git's own ignore files, read as git reads it.
+    pub fn parse_patterns(source: &str) -> Self {
+        Self {
+            rules: source.lines().filter_map(parse_line).collect(),
+        }
+    }
+
+    /// Whether `rel` names one of the files the rules are read from, whose
+    /// change means the rules have to be reloaded.
+    pub fn is_ignore_file(rel: &Path) -> bool {
+        [GITIGNORE, TYPWRITERIGNORE, PROJECT_CONFIG_FILE]
+            .iter()
+            .any(|name| rel == Path::new(name))
+    }
+
+    /// Whether the entry at the workspace-relative, forward-slash path `rel`
+    /// is ignored, judging the entry alone. Walks consult this per entry and
+    /// never descend into an ignored directory, which covers its contents.
+    pub fn is_ignored(&self, rel: &str, is_dir: bool) -> bool {
+        let name = rel.rsplit('/').next().unwrap_or(rel);
+        if ALWAYS_IGNORED
+            .iter()
+            .any(|ignored| name.eq_ignore_ascii_case(ignored))
+        {
+            return true;
+        }
+        self.verdict(rel, is_dir).unwrap_or(false)
+    }
+
+    /// What the last pattern matching `rel` says: ignored (`Some(true)`),
+    /// re-included (`Some(false)`), or nothing when none matches — so rules
+    /// read from several files can be consulted in turn.
+    pub fn verdict(&self, rel: &str, is_dir: bool) -> Option<bool> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.regex.is_match(rel))
+            .map(|rule| !rule.negated)
+    }
+
+    /// Whether `rel` or any directory above it is ignored — for a path that
+    /// arrives on its own (a watcher event) rather than through a walk.
+    pub fn is_ignored_path(&self, rel: &Path, is_dir: bool) -> bool {
+        let mut prefix = String::new();
+        let mut components = rel.components().peekable();
+        while let Some(component) = components.next() {
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(&name.to_string_lossy());
+            let last = components.peek().is_none();
+            if self.is_ignored(&prefix, !last || is_dir) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// One gitignore line as a rule, or `None` for blanks and comments.
+fn parse_line(line: &str) -> Option<Rule> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    // Trailing spaces are insignificant unless escaped.
+    let line = match line.trim_end_matches(' ') {
+        trimmed if trimmed.ends_with('\\') && trimmed.len() < line.len() => &line[..=trimmed.len()],
+        trimmed => trimmed,
+    };
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    // A slash anywhere but at the end anchors the pattern at the root.
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    if pattern.is_empty() {
+        return None;
+    }
+
+    let body = glob_to_regex(pattern);
+    let regex = if anchored {
+        format!("^{body}$")
+    } else {
+        format!("^(?:.*/)?{body}$")
+    };
+    match Regex::new(&regex) {
+        Ok(regex) => Some(Rule {
+            regex,
+            negated,
+            dir_only,
+        }),
+        Err(err) => {
+            warn!("ignore: skipped pattern {line:?} err=\"{err}\"");
+            None
+        }
+    }
+}
+
+/// Translate a gitignore glob into the body of a regex over forward-slash
+/// paths.
+fn glob_to_regex(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let at_start = i == 0 || chars[i - 1] == '/';
+                let slash_after = chars.get(i + 2) == Some(&'/');
+                if at_start && slash_after {
+                    // `**/` — zero or more leading directories.
+                    out.push_str("(?:.*/)?");
+                    i += 3;
+                } else if at_start && i + 2 == chars.len() {
+                    // Trailing `/**` — everything inside.
+                    out.push_str(".*");
+                    i += 2;
+                } else {
+                    // `**` mid-name degrades to `*`, as in git.
+                    out.push_str("[^/]*");
+                    i += 2;
+                }
+            }
+            '*' => {
+                out.push_str("[^/]*");
+                i += 1;
+            }
+            '?' => {
+                out.push_str("[^/]");
+                i += 1;
+            }
+            '[' => match chars[i + 1..].iter().position(|&c| c == ']') {
+                // An empty class (`[]`) is not a class; take `]` literally.
+                Some(len) if len > 0 => {
+                    let class: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let class = match class.strip_prefix('!') {
+                        Some(rest) => format!("^{rest}"),
+                        None => class,
+                    };
+                    out.push('[');
+                    out.push_str(&class.replace('\\', "\\\\"));
+                    out.push(']');
+                    i += len + 2;
+                }
+                _ => {
+                    out.push_str("\\[");
+                    i += 1;
+                }
+            },
+            '\\' if i + 1 < chars.len() => {
+                out.push_str(&regex::escape(&chars[i + 1].to_string()));
+                i += 2;
+            }
+            c => {
+                out.push_str(&regex::escape(&c.to_string()));
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// A file found by [`walk_files`].
+pub struct WalkedFile {
+    pub path: PathBuf,
+    /// Workspace-relative, forward-slash.
+    pub rel: String,
+}
+
+/// What a walk does with a directory it cannot read.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Unreadable {
+    /// Abort the walk — a snapshot that silently missed a folder would
+    /// record its files as deleted.
+    Fail,
+    /// Log and carry on, for best-effort consumers like search.
+    Skip,
+}
+
+/// Every file under `root` that the rules do not ignore, sorted by relative
+/// path. Symlinks are never followed.
+pub fn walk_files(
+    fs: &dyn WorkingTreeFs,
+    root: &Path,
+    rules: &IgnoreRules,
+    unreadable: Unreadable,
+) -> Result<Vec<WalkedFile>, String> {
+    let mut out = Vec::new();
+    let mut stack = vec![(root.to_path_buf(), String::new(), 0usize)];
+
+    while let Some((dir, dir_rel, depth)) = stack.pop() {
+        if depth >= MAX_DEPTH {
+            warn!("walk_files: depth limit reached at dir={dir:?}");
+            continue;
+        }
+        let entries = match fs.read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if unreadable == Unreadable::Skip && depth > 0 => {
+                warn!("walk_files: failed to read dir={dir:?} err=\"{err}\"");
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        for entry in entries {
+            let rel = if dir_rel.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{dir_rel}/{}", entry.name)
+            };
+            if !(entry.is_dir || entry.is_file) || rules.is_ignored(&rel, entry.is_dir) {
+                continue;
+            }
+            if entry.is_dir {
+                stack.push((entry.path, rel, depth + 1));
+            } else {
+                out.push(WalkedFile {
+                    path: entry.path,
+                    rel,
+                });
+            }
+        }
+    }
+
+    out.sort_by(|a, b| a.rel.cmp(&b.rel));
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(source: &str) -> IgnoreRules {
+        IgnoreRules::parse([source])
+    }
+
+    #[test]
+    fn defaults_and_metadata_dirs_are_ignored() {
+        let rules = rules("");
+        assert!(rules.is_ignored("node_modules", true));
+        assert!(rules.is_ignored("chapters/target", true));
+        assert!(rules.is_ignored(".git", true));
+        assert!(rules.is_ignored("sub/.typwriter", true));
+        assert!(!rules.is_ignored("chapters", true));
+        // Directory-only defaults leave a file of the same name alone.
+        assert!(!rules.is_ignored("target", false));
+        // Common chapter and figure folder names are not defaults.
+        assert!(!rules.is_ignored("build", true));
+        assert!(!rules.is_ignored("out", true));
+    }
+
+    #[test]
+    fn unanchored_patterns_match_at_any_depth() {
+        let rules = rules("*.log\nsecrets.toml\n");
+        assert!(rules.is_ignored("build.log", false));
+        assert!(rules.is_ignored("a/b/c.log", false));
+        assert!(rules.is_ignored("config/secrets.toml", false));
+        assert!(!rules.is_ignored("log.typ", false));
+    }
+
+    #[test]
+    fn a_slash_anchors_the_pattern_at_the_root() {
+        let rules = rules("/drafts\nimg/raw\n");
+        assert!(rules.is_ignored("drafts", true));
+        assert!(!rules.is_ignored("chapters/drafts", true));
+        assert!(rules.is_ignored("img/raw", true));
+        assert!(!rules.is_ignored("assets/img/raw", true));
+    }
+
+    #[test]
+    fn double_star_spans_directories() {
+        let rules = rules("**/cache\nlogs/**\na/**/z.txt\n");
+        assert!(rules.is_ignored("cache", true));
+        assert!(rules.is_ignored("x/y/cache", true));
+        assert!(rules.is_ignored("logs/2024/jan.txt", false));
+        assert!(rules.is_ignored("a/z.txt", false));
+        assert!(rules.is_ignored("a/b/c/z.txt", false));
+        assert!(!rules.is_ignored("b/z.txt", false));
+    }
+
+    #[test]
+    fn the_last_matching_pattern_wins() {
+        let rules = rules("*.pdf\n!cover.pdf\n");
+        assert!(rules.is_ignored("main.pdf", false));
+        assert!(!rules.is_ignored("cover.pdf", false));
+        assert!(!rules.is_ignored("sub/cover.pdf", false));
+
+        let rules = IgnoreRules::parse(["!cover.pdf\n", "*.pdf\n"]);
+        assert!(rules.is_ignored("cover.pdf", false));
+    }
+
+    #[test]
+    fn a_default_can_be_reincluded() {
+        assert!(!rules("!dist/\n").is_ignored("dist", true));
+    }
+
+    #[test]
+    fn metadata_dirs_cannot_be_reincluded() {
+        assert!(rules("!.git/\n!.typwriter\n").is_ignored(".typwriter", true));
+    }
+
+    #[test]
+    fn character_classes_and_single_wildcards() {
+        let rules = rules("fig[0-9].png\nv?.typ\n[!a]*.tmp\n");
+        assert!(rules.is_ignored("fig3.png", false));
+        assert!(!rules.is_ignored("figx.png", false));
+        assert!(rules.is_ignored("v1.typ", false));
+        assert!(!rules.is_ignored("v10.typ", false));
+        assert!(rules.is_ignored("b.tmp", false));
+        assert!(!rules.is_ignored("a.tmp", false));
+    }
+
+    #[test]
+    fn comments_blanks_and_escapes() {
+        let rules = rules("# a comment\n\n\\#hash.txt\n\\!bang.txt\nspaced.txt   \n");
+        assert!(rules.is_ignored("#hash.txt", false));
+        assert!(rules.is_ignored("!bang.txt", false));
+        assert!(rules.is_ignored("spaced.txt", false));
+        assert!(!rules.is_ignored("# a comment", false));
+    }
+
+    #[test]
+    fn literal_regex_characters_are_escaped() {
+        let rules = rules("notes (old).md\na+b.txt\n");
+        assert!(rules.is_ignored("notes (old).md", false));
+        assert!(rules.is_ignored("a+b.txt", false));
+        assert!(!rules.is_ignored("aab.txt", false));
+    }
+
+    #[test]
+    fn paths_inside_an_ignored_directory_are_ignored() {
+        let rules = rules("generated/\n!generated/keep.typ\n");
+        // As in git, the directory is excluded, so its contents cannot be
+        // re-included.
+        assert!(rules.is_ignored_path(Path::new("generated/keep.typ"), false));
+        assert!(rules.is_ignored_path(Path::new("node_modules/x/y.js"), false));
+        assert!(!rules.is_ignored_path(Path::new("chapters/one.typ"), false));
+    }
+
+    #[test]
+    fn ignore_files_are_recognised() {
+        assert!(IgnoreRules::is_ignore_file(Path::new(".gitignore")));
+        assert!(IgnoreRules::is_ignore_file(Path::new(".typwriterignore")));
+        assert!(IgnoreRules::is_ignore_file(Path::new("typwriter.toml")));
+        assert!(!IgnoreRules::is_ignore_file(Path::new("sub/.gitignore")));
+    }
+}
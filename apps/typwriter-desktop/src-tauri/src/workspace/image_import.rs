@@ -0,0 +1,560 @@
+// Image processing on import.
+//
+// `import_files` and `import_dropped` would otherwise copy bytes as-is: a
+// 12 MB phone photo stays 12 MB in every exported PDF and every restore point,
+// and a BMP lands in the workspace only for `image()` to reject it later. When
+// enabled in settings, each imported image is
+//
+//   * scaled down so its longer side fits the configured maximum,
+//   * turned upright, baking its EXIF orientation into the pixels,
+//   * stripped of its GPS location, and
+//   * converted to PNG or JPEG when it is a format typst can't decode.
+//
+// An image is only re-encoded when one of those applies; otherwise its bytes
+// are kept untouched, so importing an already-small PNG never costs quality.
+// Re-encoding drops all metadata, not just GPS — that is the encoder's
+// behaviour and, for a privacy fix, the safe direction to err in — except the
+// ICC colour profile, without which colours would shift.
+//
+// The only WebP encoder here is lossless, so a lossy WebP (a photo, almost
+// always) that needs re-encoding becomes a JPEG, or a PNG if it has
+// transparency, rather than a lossless WebP many times its size.
+//
+// Formats typst can't decode and this can't convert either (HEIC, TIFF) are
+// imported unchanged, with a note saying so instead of a silent surprise.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    DynamicImage, ImageDecoder, ImageEncoder, ImageFormat,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::ignore::{walk_files, IgnoreRules, Unreadable};
+use crate::commands::settings::AppSettings;
+use crate::vcs::WorkingTreeFs;
+
+/// Raster formats typst decodes itself, which are processed in place.
+const TYPST_RASTER: &[&str] = &["png", "jpg", "jpeg", "webp"];
+/// Formats typst rejects but that can be decoded here and converted.
+const CONVERTIBLE: &[&str] = &["bmp", "ico", "pbm", "pgm", "ppm", "pnm", "tga"];
+/// Image formats nothing here can decode.
+const UNSUPPORTED: &[&str] = &["tif", "tiff", "heic", "heif", "avif", "psd"];
+/// Images passed through as-is but still de-duplicated. GIFs are left alone
+/// because re-encoding one would drop all but its first frame.
+const KEPT_AS_IS: &[&str] = &["gif", "svg", "pdf"];
+
+/// Format an image typst can't decode is converted to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConvertTarget {
+    #[default]
+    Png,
+    /// Falls back to PNG for images with transparency, which JPEG can't hold.
+    Jpeg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageImportConfig {
+    pub enabled: bool,
+    /// Longest side in pixels; `0` keeps the original size.
+    pub max_dimension: u32,
+    pub convert_to: ConvertTarget,
+    /// JPEG quality, 1–100, for images that are re-encoded as JPEG.
+    pub jpeg_quality: u8,
+}
+
+impl ImageImportConfig {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            enabled: settings.image_import_processing,
+            max_dimension: settings.image_import_max_dimension,
+            convert_to: settings.image_import_convert_to,
+            jpeg_quality: settings.image_import_jpeg_quality.clamp(1, 100),
+        }
+    }
+}
+
+/// What import processing made of one file.
+pub struct Processed<'a> {
+    /// Name to write the file under; the extension changes with the format.
+    pub name: String,
+    pub bytes: Cow<'a, [u8]>,
+    /// Whether the file is an image, and so subject to de-duplication.
+    pub is_image: bool,
+    /// What was changed, or why an image was left as it is, for the user.
+    pub notes: Vec<String>,
+}
+
+/// Process one imported file named `name`. Anything that isn't an image, and
+/// any image when processing is off, comes back unchanged.
+pub fn process<'a>(name: &str, bytes: &'a [u8], config: &ImageImportConfig) -> Processed<'a> {
+    let ext = extension(name);
+    let mut processed = Processed {
+        name: name.to_string(),
+        bytes: Cow::Borrowed(bytes),
+        is_image: is_image_extension(&ext),
+        notes: Vec::new(),
+    };
+
+    if UNSUPPORTED.contains(&ext.as_str()) {
+        processed.notes.push(format!(
+            "typst can't display {} images; convert it to PNG or JPEG before using it",
+            ext.to_uppercase()
+        ));
+        return processed;
+    }
+    let convert = CONVERTIBLE.contains(&ext.as_str());
+    if !config.enabled || !(convert || TYPST_RASTER.contains(&ext.as_str())) {
+        return processed;
+    }
+
+    let format = image::guess_format(bytes)
+        .ok()
+        .or_else(|| ImageFormat::from_extension(&ext));
+    let Some(format) = format else {
+        processed
+            .notes
+            .push("not a readable image; imported as-is".into());
+        return processed;
+    };
+    match reencode(bytes, format, convert, config) {
+        Ok(Some((output, target, notes))) => {
+            if target != format {
+                processed.name = with_extension(name, target);
+            }
+            processed.bytes = Cow::Owned(output);
+            processed.notes = notes;
+        }
+        Ok(None) => {}
+        Err(err) => processed
+            .notes
+            .push(format!("could not be processed ({err}); imported as-is")),
+    }
+    processed
+}
+
+/// The re-encoded bytes, the format they are in and notes on what changed.
+type Reencoded = (Vec<u8>, ImageFormat, Vec<String>);
+
+/// Decode, fix up and encode an image, or `None` when nothing needs doing.
+fn reencode(
+    bytes: &[u8],
+    format: ImageFormat,
+    convert: bool,
+    config: &ImageImportConfig,
+) -> Result<Option<Reencoded>, String> {
+    let metadata = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(bytes))
+        .ok();
+    let orientation = metadata
+        .as_ref()
+        .and_then(|exif| exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY))
+        .and_then(|field| field.value.get_uint(0))
+        .and_then(|value| image::metadata::Orientation::from_exif(value.try_into().ok()?))
+        .filter(|orientation| *orientation != image::metadata::Orientation::NoTransforms);
+    let has_gps = metadata.as_ref().is_some_and(|exif| {
+        exif.fields()
+            .any(|field| field.tag.context() == exif::Context::Gps)
+    });
+    let (width, height) = image::ImageReader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|e| e.to_string())?;
+    let oversized = config.max_dimension > 0 && width.max(height) > config.max_dimension;
+
+    if !(convert || oversized || orientation.is_some() || has_gps) {
+        return Ok(None);
+    }
+
+    let mut decoder = image::ImageReader::with_format(Cursor::new(bytes), format)
+        .into_decoder()
+        .map_err(|e| e.to_string())?;
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    let mut notes = Vec::new();
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+        notes.push("turned upright per its EXIF orientation".to_string());
+    }
+    if oversized {
+        // Measured after turning upright, so the note reads the way round the
+        // user sees the picture.
+        let (width, height) = (image.width(), image.height());
+        image = image.resize(
+            config.max_dimension,
+            config.max_dimension,
+            FilterType::Lanczos3,
+        );
+        notes.push(format!(
+            "scaled down from {width}×{height} to {}×{}",
+            image.width(),
+            image.height()
+        ));
+    }
+    if has_gps {
+        notes.push("GPS location removed".to_string());
+    }
+
+    let target = match format {
+        ImageFormat::WebP if is_lossy_webp(bytes) && image.color().has_alpha() => ImageFormat::Png,
+        ImageFormat::WebP if is_lossy_webp(bytes) => ImageFormat::Jpeg,
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP => format,
+        _ => match config.convert_to {
+            ConvertTarget::Jpeg if !image.color().has_alpha() => ImageFormat::Jpeg,
+            _ => ImageFormat::Png,
+        },
+    };
+    if target != format {
+        notes.push(format!(
+            "converted from {} to {}",
+            format_name(format),
+            format_name(target)
+        ));
+    }
+    let output =
+        encode(&image, target, config.jpeg_quality, icc_profile).map_err(|e| e.to_string())?;
+    Ok(Some((output, target, notes)))
+}
+
+fn encode(
+    image: &DynamicImage,
+    format: ImageFormat,
+    quality: u8,
+    icc_profile: Option<Vec<u8>>,
+) -> image::ImageResult<Vec<u8>> {
+    let mut out = Vec::new();
+    match format {
+        ImageFormat::Jpeg => {
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(with_profile(
+                JpegEncoder::new_with_quality(&mut out, quality),
+                icc_profile,
+            ))?
+        }
+        ImageFormat::Png => {
+            image.write_with_encoder(with_profile(PngEncoder::new(&mut out), icc_profile))?
+        }
+        // The encoder only takes 8-bit RGBA, whatever the source decoded to.
+        ImageFormat::WebP => DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(
+            with_profile(WebPEncoder::new_lossless(&mut out), icc_profile),
+        )?,
+        ImageFormat::Gif => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_to(&mut Cursor::new(&mut out), format)?,
+        _ => image.write_to(&mut Cursor::new(&mut out), format)?,
+    }
+    Ok(out)
+}
+
+/// `encoder`, set to embed `icc_profile`. An encoder that can't embed one
+/// writes the image without it.
+fn with_profile<E: ImageEncoder>(mut encoder: E, icc_profile: Option<Vec<u8>>) -> E {
+    if let Some(profile) = icc_profile {
+        let _ = encoder.set_icc_profile(profile);
+    }
+    encoder
+}
+
+/// Whether a WebP file is VP8-compressed (lossy) rather than VP8L (lossless),
+/// judged by its first image chunk.
+fn is_lossy_webp(bytes: &[u8]) -> bool {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WEBP" {
+        return false;
+    }
+    let mut at = 12;
+    while let Some(header) = bytes.get(at..at + 8) {
+        match &header[..4] {
+            b"VP8 " => return true,
+            b"VP8L" => return false,
+            _ => {}
+        }
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        // Chunks are padded to an even length.
+        at += 8 + size + (size & 1);
+    }
+    false
+}
+
+fn format_name(format: ImageFormat) -> &'static str {
+    format.extensions_str().first().copied().unwrap_or("image")
+}
+
+/// Lower-cased extension of `name`, or `""`.
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+fn is_image_extension(ext: &str) -> bool {
+    [TYPST_RASTER, CONVERTIBLE, UNSUPPORTED, KEPT_AS_IS]
+        .iter()
+        .any(|list| list.contains(&ext))
+}
+
+/// `name` with its extension swapped for `format`'s.
+fn with_extension(name: &str, format: ImageFormat) -> String {
+    let stem = Path::new(name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(name);
+    format!("{stem}.{}", format_name(format))
+}
+
+// ─── De-duplication ─────────────────────────────────────────────────────────
+
+/// Content hashes of the images already in a workspace, for spotting an
+/// import that would only add a second copy of one. Files are hashed lazily,
+/// and only when an import has the same length.
+pub struct ImageIndex {
+    root: PathBuf,
+    unhashed: HashMap<u64, Vec<PathBuf>>,
+    hashed: HashMap<[u8; 32], PathBuf>,
+}
+
+impl ImageIndex {
+    pub fn scan(fs: &dyn WorkingTreeFs, root: &Path) -> Self {
+        let rules = IgnoreRules::load(fs, root);
+        let mut unhashed: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        for file in walk_files(fs, root, &rules, Unreadable::Skip).unwrap_or_default() {
+            if !is_image_extension(&extension(&file.rel)) {
+                continue;
+            }
+            if let Ok(meta) = std::fs::metadata(&file.path) {
+                unhashed.entry(meta.len()).or_default().push(file.path);
+            }
+        }
+        Self {
+            root: root.to_path_buf(),
+            unhashed,
+            hashed: HashMap::new(),
+        }
+    }
+
+    /// Record an image just written to `path`, so the rest of the import
+    /// finds it like one that was already there.
+    pub fn add(&mut self, bytes: &[u8], path: PathBuf) {
+        self.hashed
+            .entry(Sha256::digest(bytes).into())
+            .or_insert(path);
+    }
+
+    /// Workspace-relative path of an image that already has exactly `bytes`.
+    pub fn find(&mut self, bytes: &[u8]) -> Option<String> {
+        let hash: [u8; 32] = Sha256::digest(bytes).into();
+        if !self.hashed.contains_key(&hash) {
+            for path in self
+                .unhashed
+                .remove(&(bytes.len() as u64))
+                .unwrap_or_default()
+            {
+                if let Ok(existing) = std::fs::read(&path) {
+                    self.hashed
+                        .entry(Sha256::digest(&existing).into())
+                        .or_insert(path);
+                }
+            }
+        }
+        let path = self.hashed.get(&hash)?;
+        let rel = path.strip_prefix(&self.root).unwrap_or(path);
+        Some(rel.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use crate::vcs::fs::LocalWorkingTreeFs;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn enabled() -> ImageImportConfig {
+        ImageImportConfig {
+            enabled: true,
+            max_dimension: 100,
+            convert_to: ConvertTarget::Png,
+            jpeg_quality: 85,
+        }
+    }
+
+    fn encoded(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut out = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut out), format)
+            .expect("encode");
+        out
+    }
+
+    fn photo(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([200, 120, 40])))
+    }
+
+    fn decode(bytes: &[u8]) -> DynamicImage {
+        image::load_from_memory(bytes).expect("decode")
+    }
+
+    #[test]
+    fn small_supported_images_are_kept_byte_for_byte() {
+        let png = encoded(photo(40, 20), ImageFormat::Png);
+        let processed = process("figure.png", &png, &enabled());
+        assert!(matches!(processed.bytes, Cow::Borrowed(_)));
+        assert_eq!(processed.name, "figure.png");
+        assert!(processed.is_image);
+        assert!(processed.notes.is_empty());
+    }
+
+    #[test]
+    fn oversized_images_are_scaled_to_fit() {
+        let png = encoded(photo(400, 200), ImageFormat::Png);
+        let processed = process("wide.png", &png, &enabled());
+        let image = decode(&processed.bytes);
+        assert_eq!((image.width(), image.height()), (100, 50));
+        assert_eq!(processed.notes, ["scaled down from 400×200 to 100×50"]);
+    }
+
+    #[test]
+    fn formats_typst_reads_keep_their_format_when_processed() {
+        let webp = encoded(photo(400, 200), ImageFormat::WebP);
+        let processed = process("wide.webp", &webp, &enabled());
+        assert_eq!(processed.name, "wide.webp");
+        assert_eq!(
+            image::guess_format(&processed.bytes).ok(),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(processed.notes, ["scaled down from 400×200 to 100×50"]);
+
+        // A GIF saved under another image extension is still a GIF.
+        let gif = encoded(photo(400, 200), ImageFormat::Gif);
+        let processed = process("wide.png", &gif, &enabled());
+        assert_eq!(processed.name, "wide.png");
+        assert_eq!(
+            image::guess_format(&processed.bytes).ok(),
+            Some(ImageFormat::Gif)
+        );
+    }
+
+    #[test]
+    fn unsupported_formats_are_converted() {
+        let bmp = encoded(photo(10, 10), ImageFormat::Bmp);
+        let processed = process("scan.bmp", &bmp, &enabled());
+        assert_eq!(processed.name, "scan.png");
+        assert_eq!(
+            image::guess_format(&processed.bytes).ok(),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(processed.notes, ["converted from bmp to png"]);
+
+        let jpeg = ImageImportConfig {
+            convert_to: ConvertTarget::Jpeg,
+            ..enabled()
+        };
+        assert_eq!(process("scan.bmp", &bmp, &jpeg).name, "scan.jpg");
+
+        // Transparency can't survive JPEG, so such images stay PNG.
+        let clear = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0])));
+        let ico = encoded(clear, ImageFormat::Ico);
+        assert_eq!(process("icon.ico", &ico, &jpeg).name, "icon.png");
+    }
+
+    #[test]
+    fn the_colour_profile_survives_reencoding() {
+        let profile = b"not really an ICC profile".to_vec();
+        let mut png = Vec::new();
+        let mut encoder = PngEncoder::new(&mut png);
+        encoder
+            .set_icc_profile(profile.clone())
+            .expect("PNG takes a profile");
+        photo(400, 200).write_with_encoder(encoder).expect("encode");
+
+        let processed = process("wide.png", &png, &enabled());
+        assert!(matches!(processed.bytes, Cow::Owned(_)));
+        let mut decoder = image::ImageReader::new(Cursor::new(&*processed.bytes))
+            .with_guessed_format()
+            .expect("format")
+            .into_decoder()
+            .expect("decoder");
+        assert_eq!(decoder.icc_profile().expect("profile"), Some(profile));
+    }
+
+    #[test]
+    fn lossy_webp_is_told_from_lossless_by_its_image_chunk() {
+        let webp = |chunks: &[(&[u8; 4], &[u8])]| {
+            let mut body = b"WEBP".to_vec();
+            for (fourcc, data) in chunks {
+                body.extend_from_slice(*fourcc);
+                body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                body.extend_from_slice(data);
+                if data.len() % 2 == 1 {
+                    body.push(0);
+                }
+            }
+            let mut file = b"RIFF".to_vec();
+            file.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            file.extend(body);
+            file
+        };
+        assert!(is_lossy_webp(&webp(&[(b"VP8 ", b"frame")])));
+        assert!(is_lossy_webp(&webp(&[
+            (b"VP8X", b"flags"),
+            (b"ICCP", b"icc"),
+            (b"VP8 ", b"")
+        ])));
+        assert!(!is_lossy_webp(&webp(&[
+            (b"VP8X", b"flags"),
+            (b"VP8L", b"frame")
+        ])));
+        assert!(!is_lossy_webp(&encoded(photo(4, 4), ImageFormat::WebP)));
+        assert!(!is_lossy_webp(b"RIFF"));
+    }
+
+    #[test]
+    fn formats_nothing_can_read_are_flagged() {
+        let processed = process("IMG_0001.HEIC", b"not decoded", &enabled());
+        assert_eq!(&*processed.bytes, b"not decoded");
+        assert_eq!(processed.name, "IMG_0001.HEIC");
+        assert!(processed.notes[0].contains("HEIC"), "{:?}", processed.notes);
+    }
+
+    #[test]
+    fn nothing_is_processed_when_disabled() {
+        let config = ImageImportConfig {
+            enabled: false,
+            ..enabled()
+        };
+        let bmp = encoded(photo(400, 400), ImageFormat::Bmp);
+        let processed = process("scan.bmp", &bmp, &config);
+        assert_eq!(processed.name, "scan.bmp");
+        assert!(matches!(processed.bytes, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn non_images_pass_through() {
+        let processed = process("chapter.typ", b"= Intro", &enabled());
+        assert!(!processed.is_image);
+        assert_eq!(&*processed.bytes, b"= Intro");
+    }
+
+    #[test]
+    fn identical_images_are_found_by_content() {
+        let dir = TempDir::new("dedup");
+        std::fs::create_dir_all(dir.0.join("fig")).expect("mkdir");
+        std::fs::write(dir.0.join("fig/logo.png"), b"logo bytes").expect("write");
+        std::fs::write(dir.0.join("fig/other.png"), b"logo BYTES").expect("write");
+        std::fs::write(dir.0.join("notes.txt"), b"logo bytes").expect("write");
+
+        let mut index = ImageIndex::scan(&LocalWorkingTreeFs, &dir.0);
+        assert_eq!(index.find(b"logo bytes").as_deref(), Some("fig/logo.png"));
+        assert_eq!(index.find(b"logo bytes").as_deref(), Some("fig/logo.png"));
+        assert_eq!(index.find(b"new image"), None);
+
+        // An image written earlier in the same import counts too.
+        index.add(b"new image", dir.0.join("fig/new.png"));
+        assert_eq!(index.find(b"new image").as_deref(), Some("fig/new.png"));
+    }
+}
@@ -0,0 +1,381 @@
+// Three-way merge of an unsaved buffer with an external change to its file.
+//
+// When a file with unsaved edits changes on disk — a cloud drive syncing
+// another machine's save, most often — the last text the buffer and disk
+// agreed on (the base) says which side changed what. Lines only one side
+// touched take that side's version; where both changed the same lines
+// differently, the region is a conflict for the user to settle.
+//
+// The merge is line-based, diff3-style: each side is diffed against the base,
+// the changed base ranges ("hunks") of the two sides are laid over one
+// another, and overlapping or touching hunks from both sides form one region.
+
+use serde::Serialize;
+
+/// Sent on `workspace:external-merge` when a file with unsaved edits changed
+/// on disk.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalMerge {
+    /// Absolute path, as in `workspace:files-changed`.
+    pub path: String,
+    pub outcome: MergeOutcome,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum MergeOutcome {
+    /// Both sides' changes combined without overlap.
+    Clean { text: String },
+    /// The file in order, as merged text and the conflicts between it.
+    Conflicted { regions: Vec<MergeRegion> },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MergeRegion {
+    /// Text the merge settled: unchanged, or changed by one side only, or
+    /// changed the same way by both.
+    Merged { text: String },
+    /// Lines both sides changed differently.
+    Conflict {
+        base: String,
+        buffer: String,
+        disk: String,
+    },
+}
+
+/// Merge the `buffer`'s and `disk`'s changes to `base`.
+pub fn merge(base: &str, buffer: &str, disk: &str) -> MergeOutcome {
+    if buffer == disk || disk == base {
+        return MergeOutcome::Clean {
+            text: buffer.to_string(),
+        };
+    }
+    if buffer == base {
+        return MergeOutcome::Clean {
+            text: disk.to_string(),
+        };
+    }
+
+    let base_lines = lines(base);
+    let ours = lines(buffer);
+    let theirs = lines(disk);
+
+    let mut hunks: Vec<(Side, Hunk)> = diff(&base_lines, &ours)
+        .into_iter()
+        .map(|hunk| (Side::Buffer, hunk))
+        .chain(
+            diff(&base_lines, &theirs)
+                .into_iter()
+                .map(|hunk| (Side::Disk, hunk)),
+        )
+        .collect();
+    hunks.sort_by_key(|(side, hunk)| (hunk.base_start, *side as u8));
+
+    let mut regions: Vec<MergeRegion> = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < hunks.len() {
+        // Gather every hunk overlapping or touching the region so far.
+        let lo = hunks[i].1.base_start;
+        let mut hi = hunks[i].1.base_end();
+        let mut j = i + 1;
+        while j < hunks.len() && hunks[j].1.base_start <= hi {
+            hi = hi.max(hunks[j].1.base_end());
+            j += 1;
+        }
+        let group = &hunks[i..j];
+        i = j;
+
+        push_merged(&mut regions, &base_lines[copied..lo]);
+        copied = hi;
+
+        let side_text = |side: Side, other: &[&str]| -> Option<String> {
+            let mine: Vec<&Hunk> = group
+                .iter()
+                .filter(|(s, _)| *s == side)
+                .map(|(_, hunk)| hunk)
+                .collect();
+            let (first, last) = (mine.first()?, mine.last()?);
+            let start = first.side_start - (first.base_start - lo);
+            let end = last.side_start + last.side_len + (hi - last.base_end());
+            Some(other[start..end].concat())
+        };
+        let base_text = base_lines[lo..hi].concat();
+        let buffer_text = side_text(Side::Buffer, &ours);
+        let disk_text = side_text(Side::Disk, &theirs);
+        match (buffer_text, disk_text) {
+            (Some(buffer), Some(disk)) if buffer != disk => {
+                regions.push(MergeRegion::Conflict {
+                    base: base_text,
+                    buffer,
+                    disk,
+                });
+            }
+            (Some(text), _) | (None, Some(text)) => {
+                push_merged_text(&mut regions, text);
+            }
+            (None, None) => unreachable!("a group holds at least one hunk"),
+        }
+    }
+    push_merged(&mut regions, &base_lines[copied..]);
+
+    if regions
+        .iter()
+        .any(|region| matches!(region, MergeRegion::Conflict { .. }))
+    {
+        MergeOutcome::Conflicted { regions }
+    } else {
+        let text = regions
+            .into_iter()
+            .map(|region| match region {
+                MergeRegion::Merged { text } => text,
+                MergeRegion::Conflict { .. } => unreachable!(),
+            })
+            .collect();
+        MergeOutcome::Clean { text }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Buffer,
+    Disk,
+}
+
+/// Base lines `base_start..base_start + base_len` became side lines
+/// `side_start..side_start + side_len`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Hunk {
+    base_start: usize,
+    base_len: usize,
+    side_start: usize,
+    side_len: usize,
+}
+
+impl Hunk {
+    fn base_end(&self) -> usize {
+        self.base_start + self.base_len
+    }
+}
+
+/// Lines with their terminators, so concatenating them gives the text back.
+fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+fn push_merged(regions: &mut Vec<MergeRegion>, lines: &[&str]) {
+    if !lines.is_empty() {
+        push_merged_text(regions, lines.concat());
+    }
+}
+
+/// Append settled text, joining it onto a settled region just before.
+fn push_merged_text(regions: &mut Vec<MergeRegion>, text: String) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(MergeRegion::Merged { text: last }) = regions.last_mut() {
+        last.push_str(&text);
+    } else {
+        regions.push(MergeRegion::Merged { text });
+    }
+}
+
+/// The hunks turning `a` into `b`, in order: Myers' diff over lines, with
+/// the common prefix and suffix trimmed first since most edits are local.
+fn diff(a: &[&str], b: &[&str]) -> Vec<Hunk> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    // Every gap between consecutive shared lines is a hunk; the end of both
+    // texts closes the last one.
+    let mut hunks = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    for (mx, my) in matches(a_mid, b_mid)
+        .into_iter()
+        .chain([(a_mid.len(), b_mid.len())])
+    {
+        if mx > x || my > y {
+            hunks.push(Hunk {
+                base_start: prefix + x,
+                base_len: mx - x,
+                side_start: prefix + y,
+                side_len: my - y,
+            });
+        }
+        (x, y) = (mx + 1, my + 1);
+    }
+    hunks
+}
+
+/// Index pairs of the lines `a` and `b` share, in order, along a shortest
+/// edit script.
+fn matches(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    if max == 0 {
+        return Vec::new();
+    }
+    let offset = max as isize;
+    let mut v = vec![0isize; 2 * max + 2];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    // Walk the trace back from the end, collecting the diagonal moves.
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..trace.len() as isize).rev() {
+        let v = &trace[d as usize];
+        let k = x - y;
+        let prev_k =
+            if k == -d || (k != d && v[(k - 1 + offset) as usize] < v[(k + 1 + offset) as usize]) {
+                k + 1
+            } else {
+                k - 1
+            };
+        let prev_x = if d == 0 {
+            0
+        } else {
+            v[(prev_k + offset) as usize]
+        };
+        let prev_y = prev_x - prev_k;
+        while x > prev_x
+            && y > prev_y
+            && x > 0
+            && y > 0
+            && a[(x - 1) as usize] == b[(y - 1) as usize]
+        {
+            x -= 1;
+            y -= 1;
+            pairs.push((x as usize, y as usize));
+        }
+        if d > 0 {
+            (x, y) = (prev_x, prev_y);
+        }
+    }
+    pairs.reverse();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(outcome: MergeOutcome) -> String {
+        match outcome {
+            MergeOutcome::Clean { text } => text,
+            other => panic!("expected a clean merge, got {other:?}"),
+        }
+    }
+
+    const BASE: &str = "= Title\n\nIntro.\n\n== One\n\nFirst.\n\n== Two\n\nSecond.\n";
+
+    #[test]
+    fn changes_to_different_lines_merge_cleanly() {
+        let buffer = BASE.replace("Intro.", "Intro, revised.");
+        let disk = BASE.replace("Second.", "Second, synced.");
+        assert_eq!(
+            clean(merge(BASE, &buffer, &disk)),
+            "= Title\n\nIntro, revised.\n\n== One\n\nFirst.\n\n== Two\n\nSecond, synced.\n"
+        );
+    }
+
+    #[test]
+    fn insertions_and_deletions_on_both_sides_merge() {
+        let buffer = BASE.replace("== One\n\nFirst.\n\n", "");
+        let disk = format!("{BASE}\n== Three\n\nThird.\n");
+        assert_eq!(
+            clean(merge(BASE, &buffer, &disk)),
+            "= Title\n\nIntro.\n\n== Two\n\nSecond.\n\n== Three\n\nThird.\n"
+        );
+    }
+
+    #[test]
+    fn the_same_change_on_both_sides_is_not_a_conflict() {
+        let both = BASE.replace("First.", "First!");
+        let buffer = both.replace("Intro.", "Intro!");
+        assert_eq!(
+            clean(merge(BASE, &buffer, &both)),
+            BASE.replace("First.", "First!").replace("Intro.", "Intro!")
+        );
+    }
+
+    #[test]
+    fn overlapping_changes_become_a_conflict_region() {
+        let buffer = BASE.replace("First.", "First, mine.");
+        let disk = BASE.replace("First.", "First, theirs.");
+        let outcome = merge(BASE, &buffer, &disk);
+        assert_eq!(
+            outcome,
+            MergeOutcome::Conflicted {
+                regions: vec![
+                    MergeRegion::Merged {
+                        text: "= Title\n\nIntro.\n\n== One\n\n".into()
+                    },
+                    MergeRegion::Conflict {
+                        base: "First.\n".into(),
+                        buffer: "First, mine.\n".into(),
+                        disk: "First, theirs.\n".into(),
+                    },
+                    MergeRegion::Merged {
+                        text: "\n== Two\n\nSecond.\n".into()
+                    },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn one_sided_changes_take_that_side() {
+        let disk = BASE.replace("Second.", "Second, synced.");
+        assert_eq!(clean(merge(BASE, BASE, &disk)), disk);
+        assert_eq!(clean(merge(BASE, &disk, BASE)), disk);
+    }
+
+    #[test]
+    fn a_missing_final_newline_survives() {
+        let base = "a\nb\nc";
+        assert_eq!(clean(merge(base, "A\nb\nc", "a\nb\nC")), "A\nb\nC");
+    }
+
+    #[test]
+    fn myers_finds_the_shared_lines() {
+        let a = ["a", "b", "c", "a", "b", "b", "a"];
+        let b = ["c", "b", "a", "b", "a", "c"];
+        let pairs = matches(&a, &b);
+        // Myers' example: an edit script of length 5 keeps four lines.
+        assert_eq!(pairs.len(), 4);
+        assert!(pairs.iter().all(|&(x, y)| a[x] == b[y]));
+        assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+    }
+}
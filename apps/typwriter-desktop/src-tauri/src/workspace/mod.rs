@@ -2,17 +2,27 @@
 // live FS watcher. All file-system operations funnel through here so the
 // EditorWorld caches stay consistent.
 
+pub mod ignore;
 pub mod text_files;
 mod error;
+pub mod encoding;
+pub mod image_import;
+pub mod merge;
+pub mod package_templates;
 mod path;
+pub mod project_config;
+pub mod recovery;
 mod self_writes;
 mod store;
+pub mod templates;
+pub mod trash;
+pub mod unused;
 mod watcher;
 
 use log::{error, info, warn};
 use parking_lot::{Mutex, RwLock};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, Instant},
@@ -21,14 +31,23 @@ use std::{
 use base64::Engine;
 use notify::RecommendedWatcher;
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 
 use crate::{
     compiler::{render_page, CompileReason, PreviewPipeline},
-    vcs::{CommitTrigger, VcsState, WorkingTreeFs},
+    git::{GitFileStatus, GitInfo, GitRepo},
+    vcs::{CommitTrigger, VcsState, WorkingTreeFs, WorkspaceDiff},
     world::{local_file_id, EditorWorld},
 };
+use encoding::TextFormat;
+use ignore::IgnoreRules;
+use image_import::{ImageImportConfig, ImageIndex};
+use merge::{ExternalMerge, MergeOutcome};
 use path::{ExternalPath, WorkspacePath};
+use project_config::{ProjectConfig, PROJECT_CONFIG_FILE};
+use recovery::{RecoveredBuffer, RecoveryJournal};
+use trash::{Trash, TrashEntry};
+use unused::{UnusedFilesReport, ARCHIVE_DIR};
 
 // ─── Recent workspace entry (returned to the frontend) ────────────────────────
 
@@ -51,6 +70,10 @@ pub struct FileTreeEntry {
     pub path: String,
     pub is_dir: bool,
     pub children: Vec<FileTreeEntry>,
+    /// The file's git status when the workspace is in a git repository.
+    /// `None` for clean files, for directories, and outside git.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_status: Option<GitFileStatus>,
 }
 
 // ─── External drag-and-drop ──────────────────────────────────────────────────
@@ -65,12 +88,47 @@ pub struct DroppedFile {
     pub len: usize,
 }
 
+/// What import processing did beyond writing files, sent on
+/// `workspace:import-report` when there is anything to say. See
+/// [`image_import`].
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    /// Images identical to one already in the workspace, which were not
+    /// written again.
+    pub duplicates: Vec<DuplicateImport>,
+    /// What processing changed in an image, or why it left one alone.
+    pub notes: Vec<ImportNote>,
+}
+
+/// An imported image that was already in the workspace. The frontend offers
+/// to rename `existing` to `name`, so the file carries the name the user just
+/// gave it without a second copy.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateImport {
+    /// Workspace-relative path the import would have written.
+    pub name: String,
+    /// Workspace-relative path of the identical file already there.
+    pub existing: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportNote {
+    /// Workspace-relative path the file was written to.
+    pub path: String,
+    pub message: String,
+}
+
 // ─── WorkspaceState ──────────────────────────────────────────────────────────
 
 pub struct WorkspaceState {
     pub root: RwLock<Option<PathBuf>>,
     pub main_file: RwLock<Option<PathBuf>>,
     pub zoom: Mutex<f32>,
+    /// The workspace's `typwriter.toml`, as last loaded. See [`project_config`].
+    project: RwLock<ProjectConfig>,
     /// Keeps the watcher alive for the process lifetime.
     _watcher: Mutex<Option<RecommendedWatcher>>,
     last_thumbnail_at: Mutex<Option<Instant>>,
@@ -82,6 +140,13 @@ pub struct WorkspaceState {
     /// Writes the editor performed itself, so the watcher can ignore the
     /// filesystem events they generate. Shared with the watcher thread.
     self_writes: Arc<self_writes::SelfWriteLog>,
+    /// Copies of the unsaved buffers, for recovery after a crash. See
+    /// [`recovery`].
+    recovery: Arc<RecoveryJournal>,
+    /// Per workspace-relative path, the text the file had on disk when the
+    /// editor last read or saved it: the common ancestor when an external
+    /// change meets unsaved edits. See [`merge`].
+    merge_bases: Mutex<HashMap<String, String>>,
     pub app_handle: AppHandle,
 }
 
@@ -96,8 +161,11 @@ impl WorkspaceState {
             root: RwLock::new(None),
             main_file: RwLock::new(None),
             zoom: Mutex::new(1.0),
+            project: RwLock::new(ProjectConfig::default()),
             _watcher: Mutex::new(None),
             last_thumbnail_at: Mutex::new(None),
+            recovery: Arc::new(RecoveryJournal::new(world.clone())),
+            merge_bases: Mutex::new(HashMap::new()),
             world,
             pipeline,
             vcs,
@@ -120,6 +188,11 @@ impl WorkspaceState {
         self.self_writes.note(path);
     }
 
+    /// Start sweeping the unsaved buffers into the recovery journal.
+    pub fn start_recovery_journal(&self) {
+        self.recovery.start();
+    }
+
     // ─── Workspace open ────────────────────────────────────────────────────
 
     /// Open a directory as the workspace root.
@@ -145,6 +218,8 @@ impl WorkspaceState {
         // contents survive across opens (per workspace), so re-opening shows
         // the existing preview without recompiling.
         self.world.set_root(path.clone());
+        self.recovery.attach(&path);
+        self.merge_bases.lock().clear();
         self.pipeline.invalidate_cache();
         self.pipeline.attach_disk_cache(&path);
         // Page-diff thumbnails were rendered from the *previous* workspace's
@@ -156,6 +231,10 @@ impl WorkspaceState {
             engine.invalidate();
         }
 
+        // The project's `sys.inputs`, fonts and the rest, before anything
+        // compiles.
+        let project_problems = self.load_project_config(&path);
+
         // Kick the (lazy) font search off now so the system scan overlaps the
         // rest of the open path — watcher start, cache attach, frontend
         // round-trips — and is usually finished by the time the first compile
@@ -188,15 +267,28 @@ impl WorkspaceState {
         // 1. Add to the recent-workspaces list.
         store::add_recent_workspace(&self.app_handle, &path);
 
-        // 2. Ensure the .typwriter metadata directory exists.
+        // 2. Ensure the .typwriter metadata directory exists, and let the
+        //    trash shed what has outlived its limits since the last open.
         let _ = store::ensure_typwriter_dir(&path);
-
-        // 3. Restore the previously-set main file (if it still exists).
+        self.purge_trash(self.vcs.working_tree_fs_for(&path).as_ref(), &path);
+
+        // 3. Restore the main file: the one `typwriter.toml` names, or else the
+        //    one the user last chose (if it still exists).
+        let stored = store::get_workspace_main_file(&self.app_handle, &path).map(PathBuf::from);
+        let stored_rel = stored
+            .as_deref()
+            .and_then(|main| main.strip_prefix(&path).ok())
+            .and_then(|rel| rel.to_str())
+            .map(|rel| rel.replace('\\', "/"));
+        let declared = self
+            .project
+            .read()
+            .choose_main(stored_rel.as_deref())
+            .map(|rel| path.join(rel));
         let mut restored_main: Option<String> = None;
-        if let Some(main) = store::get_workspace_main_file(&self.app_handle, &path) {
-            let main_path = PathBuf::from(&main);
+        if let Some(main_path) = declared.or(stored) {
             if main_path.exists() {
-                info!("WorkspaceState::open_folder: restoring main file main={main:?}");
+                info!("WorkspaceState::open_folder: restoring main file main={main_path:?}");
                 let _ = self.set_main_file(main_path.clone());
                 // Compute the workspace-relative path for the frontend (forward slashes).
                 restored_main = main_path
@@ -213,10 +305,26 @@ impl WorkspaceState {
                 self.pipeline.request_compile(CompileReason::MainFile);
             } else {
                 warn!(
-                    "WorkspaceState::open_folder: persisted main file no longer exists main={main:?}"
+                    "WorkspaceState::open_folder: persisted main file no longer exists main={main_path:?}"
                 );
             }
         }
+        // Without a main file nothing else compiles, and the compile is what
+        // reports a broken `typwriter.toml`.
+        if restored_main.is_none() && project_problems > 0 {
+            self.pipeline.request_compile(CompileReason::Explicit);
+        }
+
+        // 4. Offer back the unsaved edits a crash left in the journal.
+        let recovered = recovery::pending(&path);
+        if !recovered.is_empty() {
+            if let Err(err) = self
+                .app_handle
+                .emit("workspace:recovery-available", recovered)
+            {
+                warn!("WorkspaceState::open_folder: failed to emit workspace:recovery-available err=\"{err}\"");
+            }
+        }
 
         info!(
             "WorkspaceState::open_folder: ok restored_main={restored_main:?} ({:.1}ms)",
@@ -328,6 +436,100 @@ impl WorkspaceState {
         }
     }
 
+    // ─── Project config ────────────────────────────────────────────────────
+
+    /// The open workspace's `typwriter.toml`, as last loaded.
+    pub fn project_config(&self) -> ProjectConfig {
+        self.project.read().clone()
+    }
+
+    /// Re-read `typwriter.toml` after it changed on disk, and switch the main
+    /// file if the current one is no longer among those it names. Like
+    /// [`Self::reconcile_main_file`], this is the backend's own bookkeeping.
+    pub(crate) fn reload_project_config(&self) {
+        let Some(root) = self.root.read().clone() else {
+            return;
+        };
+        self.load_project_config(&root);
+
+        let current = self
+            .main_file
+            .read()
+            .as_deref()
+            .and_then(|main| main.strip_prefix(&root).ok())
+            .and_then(|rel| rel.to_str())
+            .map(|rel| rel.replace('\\', "/"));
+        let chosen = self
+            .project
+            .read()
+            .choose_main(current.as_deref())
+            .map(str::to_string);
+        if let Some(chosen) = chosen.filter(|chosen| current.as_ref() != Some(chosen)) {
+            info!("WorkspaceState::reload_project_config: main file -> {chosen:?}");
+            if let Err(e) = self.set_main_file(root.join(&chosen)) {
+                warn!("WorkspaceState::reload_project_config: cannot set main file err=\"{e}\"");
+            }
+        }
+        self.pipeline.request_compile(CompileReason::Explicit);
+    }
+
+    /// Load `typwriter.toml` from `root` and apply all of it but the main
+    /// file, which the callers reconcile with the one currently set. Returns
+    /// the number of problems found.
+    fn load_project_config(&self, root: &Path) -> usize {
+        let fs = self.vcs.working_tree_fs_for(root);
+        let (config, problems) = project_config::load(fs.as_ref(), root);
+        let count = problems.len();
+        if count > 0 {
+            warn!(
+                "WorkspaceState::load_project_config: {count} problem(s) in {PROJECT_CONFIG_FILE}"
+            );
+        }
+        self.pipeline.set_project_diagnostics(problems);
+
+        self.world.set_inputs(config.inputs.clone());
+        self.pipeline.set_standalone_wrapper(
+            config
+                .standalone_wrapper
+                .as_deref()
+                .and_then(|rel| local_file_id(Path::new(rel))),
+        );
+        if let Some(engine) = self
+            .app_handle
+            .try_state::<Arc<crate::grammar::engine::GrammarEngine>>()
+        {
+            engine.set_dialect_override(config.dialect);
+        }
+        // Switching workspaces lands here too, which is what drops the
+        // previous workspace's fonts.
+        let font_dirs = config.font_dirs_in(root);
+        if font_dirs != self.world.project_font_dirs() {
+            self.set_project_fonts(font_dirs);
+        }
+
+        *self.project.write() = config;
+        count
+    }
+
+    /// Rescan the workspace's own fonts after the watcher saw them change.
+    pub(crate) fn reload_project_fonts(&self) {
+        let Some(root) = self.root.read().clone() else {
+            return;
+        };
+        let font_dirs = self.project.read().font_dirs_in(&root);
+        self.set_project_fonts(font_dirs);
+        self.pipeline.request_compile(CompileReason::Explicit);
+    }
+
+    fn set_project_fonts(&self, font_dirs: Vec<PathBuf>) {
+        // A handful of files, not a system scan: done in line so the next
+        // compile already sees them.
+        self.world.set_project_fonts(font_dirs);
+        if let Err(err) = self.app_handle.emit("app:fonts-loaded", ()) {
+            error!("WorkspaceState::set_project_fonts: emit failed err=\"{err}\"");
+        }
+    }
+
     // ─── Zoom / scale ──────────────────────────────────────────────────────
 
     pub fn set_zoom(&self, scale: f32) {
@@ -350,7 +552,7 @@ impl WorkspaceState {
             None => return,
         };
 
-        let doc = match self.pipeline.last_document.lock().clone() {
+        let doc = match self.pipeline.document() {
             Some(d) => d,
             None => return,
         };
@@ -511,6 +713,31 @@ impl WorkspaceState {
         }
     }
 
+    /// Copy the open workspace into the user templates directory so new
+    /// workspaces can be created from it. Its main file becomes the
+    /// template's main file and its preview thumbnail the gallery image.
+    pub fn save_as_template(
+        &self,
+        request: templates::SaveTemplateRequest,
+    ) -> Result<templates::TemplateSummary, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let main = self
+            .main_file
+            .read()
+            .as_ref()
+            .and_then(|main| main.strip_prefix(&root).ok().map(Path::to_path_buf))
+            .ok_or("Set a main file before saving the workspace as a template")?;
+        let main = main.to_string_lossy().replace('\\', "/");
+        let user_dir = templates::user_templates_dir(&self.app_handle)?;
+        templates::save_workspace_as_template(
+            &user_dir,
+            &root,
+            &main,
+            store::read_thumbnail(&root),
+            request,
+        )
+    }
+
     /// Replace the project snippet file, creating `.typwriter/` if needed.
     ///
     /// Written even when the set is empty, so deleting the last project
@@ -559,7 +786,7 @@ impl WorkspaceState {
 
     /// Filesystem accessor for the current workspace root. Every structural
     /// file op routes its disk work through this [`WorkingTreeFs`].
-    fn working_fs(&self) -> Result<Box<dyn WorkingTreeFs>, String> {
+    pub(crate) fn working_fs(&self) -> Result<Box<dyn WorkingTreeFs>, String> {
         let root = self.root.read().clone().ok_or("No workspace open")?;
         Ok(self.vcs.working_tree_fs_for(&root))
     }
@@ -572,22 +799,170 @@ impl WorkspaceState {
     /// recoverable prior state lives in the preceding restore point.
     ///
     /// Failures are logged and swallowed — versioning must never block file
-    /// management, mirroring the rest of the VCS integration.
-    pub(crate) fn snapshot_file_op(&self, message: &str) {
+    /// management, mirroring the rest of the VCS integration. Returns the
+    /// snapshot's id, or `None` when there was nothing new to record.
+    pub(crate) fn snapshot_file_op(&self, message: &str) -> Option<String> {
         match self.vcs.commit_if_changed(CommitTrigger::FileOp, message) {
             Ok(Some(id)) => {
                 let short = &id[..id.len().min(8)];
                 info!("WorkspaceState::snapshot_file_op: {short} — {message}");
+                Some(id)
             }
             Ok(None) => {
                 info!("WorkspaceState::snapshot_file_op: no change to snapshot — {message}");
+                None
             }
             Err(err) => {
                 warn!("WorkspaceState::snapshot_file_op: failed err=\"{err}\" — {message}");
+                None
+            }
+        }
+    }
+
+    // ─── Crash recovery ────────────────────────────────────────────────────
+
+    /// Journal the unsaved buffer of `abs` after the editor pushed a new
+    /// version of it.
+    pub fn journal_buffer(&self, abs: &Path) {
+        let Some(id) = self.world.path_to_id(abs) else {
+            return;
+        };
+        if let Some(text) = self.world.shadow_text(id) {
+            self.recovery.record(id.vpath().get_without_slash(), &text);
+        }
+    }
+
+    /// Drop the journaled buffer of `abs`, once it was saved or discarded.
+    pub fn forget_buffer(&self, abs: &Path) {
+        if let Some(id) = self.world.path_to_id(abs) {
+            self.recovery.forget(id.vpath().get_without_slash());
+        }
+    }
+
+    /// Journaled buffers that differ from disk — what a crash left unsaved.
+    pub fn recovered_buffers(&self) -> Result<Vec<RecoveredBuffer>, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        Ok(recovery::pending(&root))
+    }
+
+    /// The journaled text of `path`, for the frontend to open as an unsaved
+    /// buffer. The entry stays until that buffer is saved or discarded, so a
+    /// second crash before then loses nothing either.
+    pub fn restore_recovered_buffer(&self, path: &str) -> Result<String, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let rel = self.rel_path(path)?;
+        recovery::recovered_text(&root, &rel)
+    }
+
+    /// Throw away the journaled buffer of `path`.
+    pub fn discard_recovered_buffer(&self, path: &str) -> Result<(), String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let rel = self.rel_path(path)?;
+        recovery::discard(&root, &rel)
+    }
+
+    /// Workspace-relative, forward-slash form of a path the frontend gave,
+    /// absolute or relative.
+    fn rel_path(&self, path: &str) -> Result<String, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let abs = self.resolve_any(path)?;
+        let rel = abs
+            .strip_prefix(&root)
+            .map_err(|_| format!("{path} is not inside the workspace"))?;
+        Ok(rel.to_string_lossy().replace('\\', "/"))
+    }
+
+    // ─── External changes to open buffers ──────────────────────────────────
+
+    /// Remember `text` as what `abs` holds on disk, after the editor read the
+    /// file or saved it.
+    pub fn set_merge_base(&self, abs: &Path, text: String) {
+        if let Some(id) = self.world.path_to_id(abs) {
+            self.merge_bases
+                .lock()
+                .insert(id.vpath().get_without_slash().to_string(), text);
+        }
+    }
+
+    /// Merge what another program changed on disk into the unsaved buffers
+    /// of the files it touched, and send each result to the frontend on
+    /// `workspace:external-merge`. Files without unsaved edits need nothing:
+    /// the editor reloads them.
+    pub(crate) fn merge_external_changes(&self, changes: &[watcher::FileChange]) {
+        for change in changes {
+            let target = match change.kind {
+                watcher::ChangeKind::Created | watcher::ChangeKind::Modified => &change.path,
+                // An atomic save renames a temporary file over the original.
+                watcher::ChangeKind::Renamed => match &change.to {
+                    Some(to) => to,
+                    None => continue,
+                },
+                watcher::ChangeKind::Removed => continue,
+            };
+            if change.is_dir {
+                continue;
+            }
+            let Some(outcome) = self.merge_external_change(Path::new(target)) else {
+                continue;
+            };
+            info!(
+                "WorkspaceState::merge_external_changes: path={target:?} clean={}",
+                matches!(outcome, MergeOutcome::Clean { .. })
+            );
+            let payload = ExternalMerge {
+                path: target.clone(),
+                outcome,
+            };
+            if let Err(err) = self.app_handle.emit("workspace:external-merge", payload) {
+                warn!("WorkspaceState::merge_external_changes: failed to emit workspace:external-merge err=\"{err}\"");
             }
         }
     }
 
+    /// Catch the world and the open buffers up with text files the editor
+    /// rewrote on disk itself. The watcher skips those writes as self-writes,
+    /// so this does its part for them: files without unsaved edits drop their
+    /// cached copy, and unsaved buffers get the change merged in as if another
+    /// program had made it.
+    pub(crate) fn refresh_rewritten(&self, paths: &[PathBuf]) {
+        let mut changes = Vec::with_capacity(paths.len());
+        for path in paths {
+            if let Some(id) = self.world.path_to_id(path) {
+                if !self.world.has_shadow(id) {
+                    self.world.invalidate_file(id);
+                }
+            }
+            changes.push(watcher::FileChange {
+                path: path.to_string_lossy().into_owned(),
+                kind: watcher::ChangeKind::Modified,
+                to: None,
+                is_dir: false,
+            });
+        }
+        self.merge_external_changes(&changes);
+    }
+
+    /// Three-way merge of `abs`'s unsaved buffer, its text on disk, and the
+    /// base they share. `None` when the file has no unsaved edits, no known
+    /// base, or isn't text.
+    ///
+    /// The disk text becomes the new base right away. Whatever the user makes
+    /// of the result — applying the merge, keeping their buffer, reloading —
+    /// they have now seen this version of the file, so the next external
+    /// change is measured against it.
+    fn merge_external_change(&self, abs: &Path) -> Option<MergeOutcome> {
+        let id = self.world.path_to_id(abs)?;
+        let buffer = self.world.shadow_text(id)?;
+        let bytes = self.working_fs().ok()?.read_file(abs).ok()?;
+        let (disk, _) = encoding::decode(&bytes, true)?;
+        let rel = id.vpath().get_without_slash().to_string();
+        let Some(base) = self.merge_bases.lock().insert(rel, disk.clone()) else {
+            warn!("WorkspaceState::merge_external_change: no base for {abs:?}, leaving it to the user");
+            return None;
+        };
+        Some(merge::merge(&base, &buffer, &disk))
+    }
+
     // ─── FS operations ─────────────────────────────────────────────────────
 
     /// Create an empty file at `path`.
@@ -637,13 +1012,14 @@ impl WorkspaceState {
         Ok(())
     }
 
-    /// Delete a single file and evict it from the EditorWorld caches.
+    /// Move a single file to the trash and evict it from the EditorWorld
+    /// caches.
     pub fn delete_file(&self, path: &str) -> Result<(), String> {
         let t = Instant::now();
         let abs = self.resolve(path)?;
         info!("WorkspaceState::delete_file: abs={abs:?}");
         self.note_self_write(&abs);
-        self.working_fs()?.remove_file(&abs).map_err(|e| {
+        self.move_to_trash(&abs).map_err(|e| {
             error!("WorkspaceState::delete_file: failed abs={abs:?} err=\"{e}\"");
             e
         })?;
@@ -668,6 +1044,39 @@ impl WorkspaceState {
         Ok(())
     }
 
+    /// Rewrite a text file as UTF-8 without a byte order mark and with `\n`
+    /// line breaks, which saving keeps from then on. Returns the format the
+    /// file had.
+    pub fn convert_to_utf8_lf(&self, path: &str) -> Result<TextFormat, String> {
+        let t = Instant::now();
+        let abs = self.resolve_any(path)?;
+        let fs = self.working_fs()?;
+        info!("WorkspaceState::convert_to_utf8_lf: abs={abs:?}");
+        let bytes = fs.read_file(&abs)?;
+        let (text, format) = encoding::decode(&bytes, true)
+            .ok_or_else(|| format!("{path} is not text in any supported encoding"))?;
+        if format == TextFormat::UTF8_LF {
+            return Ok(format);
+        }
+
+        self.note_self_write(&abs);
+        fs.write_file(&abs, text.as_bytes()).map_err(|e| {
+            error!("WorkspaceState::convert_to_utf8_lf: failed abs={abs:?} err=\"{e}\"");
+            e
+        })?;
+        // The editor already holds this text; only the world's cached copy of
+        // the old bytes is stale.
+        if let Some(id) = self.world.path_to_id(&abs) {
+            self.world.invalidate_file(id);
+        }
+        self.snapshot_file_op(&format!("Converted {} to UTF-8 / LF", basename(path)));
+        info!(
+            "WorkspaceState::convert_to_utf8_lf: ok from={format:?} ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        );
+        Ok(format)
+    }
+
     /// Rename (or move within the same filesystem) a single file.
     pub fn rename_file(&self, src: &str, dst: &str) -> Result<(), String> {
         let t = Instant::now();
@@ -706,7 +1115,7 @@ impl WorkspaceState {
         Ok(())
     }
 
-    /// Recursively delete a directory.
+    /// Move a directory and everything in it to the trash.
     /// The confirmation dialog is handled on the frontend; this method simply
     /// executes the deletion once called.
     pub fn delete_folder(&self, path: &str) -> Result<(), String> {
@@ -732,7 +1141,7 @@ impl WorkspaceState {
             }
         }
 
-        fs.remove_dir_all(&abs).map_err(|e| {
+        self.move_to_trash(&abs).map_err(|e| {
             error!("WorkspaceState::delete_folder: failed abs={abs:?} err=\"{e}\"");
             e
         })?;
@@ -753,17 +1162,248 @@ impl WorkspaceState {
         Ok(())
     }
 
+    /// Move the file or folder at `abs` into the workspace's [`trash`], then
+    /// purge whatever that pushes past the trash's limits.
+    fn move_to_trash(&self, abs: &Path) -> Result<(), String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let fs = self.working_fs()?;
+        let rel = abs
+            .strip_prefix(&root)
+            .map_err(|_| format!("{} is not inside the workspace", abs.display()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        // Purge before the item goes in, so the limits never evict it.
+        let policy = crate::commands::settings::trash_policy_from_handle(&self.app_handle);
+        let (entry, purged) =
+            Trash::new(&root).put_within(fs.as_ref(), abs, &rel, policy, crate::vcs::now_ms())?;
+        info!(
+            "WorkspaceState::move_to_trash: {rel} -> id={} size={}",
+            entry.id, entry.size
+        );
+        log_purged(&purged);
+        Ok(())
+    }
+
+    /// Delete trash entries past the age and size limits in the settings.
+    fn purge_trash(&self, fs: &dyn WorkingTreeFs, root: &Path) {
+        let policy = crate::commands::settings::trash_policy_from_handle(&self.app_handle);
+        log_purged(&Trash::new(root).purge(fs, policy, crate::vcs::now_ms(), 0));
+    }
+
+    /// Everything in the workspace's trash, most recently deleted first.
+    pub fn list_trash(&self) -> Result<Vec<TrashEntry>, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let fs = self.working_fs()?;
+        Ok(Trash::new(&root).list(fs.as_ref()))
+    }
+
+    /// Put trash entry `id` back where it was deleted from — or next to it,
+    /// with a ` (n)` suffix, when that path has been taken since.
+    ///
+    /// The restore is deliberately not claimed as a self-write: the watcher
+    /// reports the file appearing like any other, which refreshes the file
+    /// tree and the world's caches and recompiles, exactly as if the file
+    /// had come back from outside the app.
+    ///
+    /// Returns the workspace-relative path the item was restored to.
+    pub fn restore_from_trash(&self, id: &str) -> Result<String, String> {
+        let t = Instant::now();
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let fs = self.working_fs()?;
+        let trash = Trash::new(&root);
+        let entry = trash.entry(fs.as_ref(), id)?;
+        info!(
+            "WorkspaceState::restore_from_trash: id={id} original={:?}",
+            entry.original_path
+        );
+
+        let original = self.resolve(&entry.original_path)?;
+        let dir = dirname(&entry.original_path);
+        let name = free_name(
+            fs.as_ref(),
+            original.parent().unwrap_or(&root),
+            basename(&entry.original_path),
+            &[],
+        );
+        let rel = join_rel(dir, &name);
+        let dest = self.resolve(&rel)?;
+        trash.take(fs.as_ref(), id, &dest).map_err(|e| {
+            error!("WorkspaceState::restore_from_trash: failed id={id} err=\"{e}\"");
+            e
+        })?;
+
+        self.snapshot_file_op(&format!("Restored {name} from the trash"));
+        info!(
+            "WorkspaceState::restore_from_trash: ok -> {rel} ({:.1}ms)",
+            t.elapsed().as_secs_f64() * 1000.0
+        );
+        Ok(rel)
+    }
+
+    /// Delete trash entry `id` for good.
+    pub fn delete_from_trash(&self, id: &str) -> Result<(), String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let fs = self.working_fs()?;
+        Trash::new(&root).remove(fs.as_ref(), id)
+    }
+
+    /// Delete everything in the trash for good. Returns how many entries
+    /// went.
+    pub fn empty_trash(&self) -> Result<usize, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let fs = self.working_fs()?;
+        let trash = Trash::new(&root);
+        let entries = trash.list(fs.as_ref());
+        for entry in &entries {
+            trash.remove(fs.as_ref(), &entry.id)?;
+        }
+        Ok(entries.len())
+    }
+
+    /// Workspace files the document does not use: assets no compile read
+    /// and `.typ` files no main file reaches. See [`unused`].
+    pub fn unused_files(&self) -> Result<UnusedFilesReport, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let reads = self
+            .pipeline
+            .file_reads()
+            .ok_or("The main file has not been compiled yet")?;
+        let mains: Vec<_> = self
+            .project
+            .read()
+            .main_files
+            .iter()
+            .filter_map(|main| local_file_id(Path::new(main)))
+            .collect();
+        let fs = self.working_fs()?;
+        unused::find_unused(
+            fs.as_ref(),
+            &root,
+            &reads,
+            &mains,
+            &self.world.project_font_dirs(),
+        )
+    }
+
+    /// Move files into the workspace's archive folder, `.typwriter/archive/`,
+    /// keeping their folder structure so each can be put back where it came
+    /// from. A name already taken in the archive gets a ` (n)` suffix. The
+    /// batch is recorded as a single restore point, including when it stops
+    /// early on an error.
+    ///
+    /// Returns the workspace-relative path each file was moved to.
+    pub fn archive_files(&self, paths: &[String]) -> Result<Vec<String>, String> {
+        let t = Instant::now();
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let fs = self.working_fs()?;
+        info!("WorkspaceState::archive_files: count={}", paths.len());
+
+        let mut archived = Vec::with_capacity(paths.len());
+        let mut result = Ok(());
+        for path in paths {
+            match self.archive_one(fs.as_ref(), &root, path) {
+                Ok(dst) => archived.push(dst),
+                Err(e) => {
+                    error!("WorkspaceState::archive_files: path={path:?} err=\"{e}\"");
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        if !archived.is_empty() {
+            let count = archived.len();
+            self.snapshot_file_op(&format!(
+                "Archived {count} unused file{}",
+                if count == 1 { "" } else { "s" }
+            ));
+        }
+        info!(
+            "WorkspaceState::archive_files: moved {} file(s) ({:.1}ms)",
+            archived.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        );
+        result.map(|()| archived)
+    }
+
+    /// Move one file under [`ARCHIVE_DIR`]; see [`Self::archive_files`].
+    fn archive_one(
+        &self,
+        fs: &dyn WorkingTreeFs,
+        root: &Path,
+        path: &str,
+    ) -> Result<String, String> {
+        let src_abs = self.resolve(path)?;
+        let rel = src_abs
+            .strip_prefix(root)
+            .map_err(|_| format!("{path} is not inside the workspace"))?;
+        let archive = Path::new(store::TYPWRITER_DIR).join(ARCHIVE_DIR);
+        if rel.starts_with(&archive) {
+            return Err(format!("{path} is already archived"));
+        }
+        let name = rel
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("Cannot determine file name for {path}"))?;
+        let dest_dir = root
+            .join(archive)
+            .join(rel.parent().unwrap_or(Path::new("")));
+        let dst_abs = dest_dir.join(free_name(fs, &dest_dir, name, &[]));
+
+        self.note_self_write(&src_abs);
+        self.note_self_write(&dst_abs);
+        fs.create_dir_all(&dest_dir)?;
+        fs.rename(&src_abs, &dst_abs)?;
+        if let Some(id) = self.world.path_to_id(&src_abs) {
+            self.world.shadow_remove(id);
+            self.world.invalidate_file(id);
+        }
+        self.update_main_file_path(&src_abs, &dst_abs, false)?;
+
+        let dst_rel = dst_abs.strip_prefix(root).unwrap_or(&dst_abs);
+        Ok(dst_rel.to_string_lossy().replace('\\', "/"))
+    }
+
     /// Move a single file to a new location.
     pub fn move_file(&self, src: &str, dst: &str) -> Result<(), String> {
         info!("WorkspaceState::move_file: src={src:?} dst={dst:?} (delegates to rename_file)");
         self.rename_file(src, dst)
     }
 
+    /// The de-duplication index for an import, when processing is enabled.
+    fn image_index(
+        &self,
+        fs: &dyn WorkingTreeFs,
+        config: &ImageImportConfig,
+    ) -> Result<Option<ImageIndex>, String> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        Ok(Some(ImageIndex::scan(fs, &root)))
+    }
+
+    /// Tell the frontend about skipped duplicates and processing notes.
+    fn emit_import_report(&self, report: ImportReport) {
+        if report.duplicates.is_empty() && report.notes.is_empty() {
+            return;
+        }
+        if let Err(err) = self.app_handle.emit("workspace:import-report", report) {
+            warn!("WorkspaceState: failed to emit workspace:import-report err=\"{err}\"");
+        }
+    }
+
     /// Import (copy) one or more external files into a workspace directory.
+    /// Images go through [`image_import`] processing when it is enabled.
     pub fn import_files(&self, sources: &[String], dest_dir: &str) -> Result<(), String> {
         let t = Instant::now();
         let dest = self.resolve(dest_dir)?;
         let fs = self.working_fs()?;
+        let config = crate::commands::settings::image_import_config_from_handle(&self.app_handle);
+        let mut images = self.image_index(fs.as_ref(), &config)?;
+        let dest_prefix = dest_dir.trim_end_matches(['/', '\\']);
+        let mut report = ImportReport::default();
+        let mut written = 0usize;
         info!(
             "WorkspaceState::import_files: dest={dest:?} count={}",
             sources.len()
@@ -785,26 +1425,44 @@ impl WorkspaceState {
                 error!("WorkspaceState::import_files: err=\"{e}\"");
                 return Err(e);
             }
-            let file_name = src_path.as_path().file_name().ok_or_else(|| {
-                let e = format!(
-                    "Cannot determine file name for {}",
-                    src_path.as_path().display()
-                );
-                error!("WorkspaceState::import_files: err=\"{e}\"");
-                e
+            let file_name = src_path
+                .as_path()
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| {
+                    let e = format!(
+                        "Cannot determine file name for {}",
+                        src_path.as_path().display()
+                    );
+                    error!("WorkspaceState::import_files: err=\"{e}\"");
+                    e
+                })?;
+            let bytes = std::fs::read(src_path.as_path()).map_err(|e| {
+                error!("WorkspaceState::import_files: read failed src={src_path:?} err=\"{e}\"");
+                e.to_string()
             })?;
-            let dst_path = dest.join(file_name);
+            let processed = image_import::process(file_name, &bytes, &config);
+            let ws_rel = join_rel(dest_prefix, &processed.name);
+            if let Some(existing) = images
+                .as_mut()
+                .filter(|_| processed.is_image)
+                .and_then(|index| index.find(&processed.bytes))
+            {
+                info!("WorkspaceState::import_files: {src_path:?} is already at {existing:?}");
+                report.duplicates.push(DuplicateImport {
+                    name: ws_rel,
+                    existing,
+                });
+                continue;
+            }
+            let dst_path = dest.join(&processed.name);
             if fs.exists(&dst_path) {
                 let e = format!("File already exists: {}", dst_path.display());
                 error!("WorkspaceState::import_files: err=\"{e}\"");
                 return Err(e);
             }
             self.note_self_write(&dst_path);
-            let bytes = std::fs::read(src_path.as_path()).map_err(|e| {
-                error!("WorkspaceState::import_files: read failed src={src_path:?} err=\"{e}\"");
-                e.to_string()
-            })?;
-            fs.write_file(&dst_path, &bytes).map_err(|e| {
+            fs.write_file(&dst_path, &processed.bytes).map_err(|e| {
                 error!("WorkspaceState::import_files: write failed dst={dst_path:?} err=\"{e}\"");
                 e
             })?;
@@ -812,9 +1470,20 @@ impl WorkspaceState {
                 "WorkspaceState::import_files: copied {:?} -> {:?}",
                 src_path, dst_path
             );
+            if let Some(index) = images.as_mut().filter(|_| processed.is_image) {
+                index.add(&processed.bytes, dst_path);
+            }
+            report
+                .notes
+                .extend(processed.notes.into_iter().map(|message| ImportNote {
+                    path: ws_rel.clone(),
+                    message,
+                }));
+            written += 1;
         }
+        self.emit_import_report(report);
 
-        let count = sources.len();
+        let count = written;
         self.snapshot_file_op(&format!(
             "Imported {count} file{} into {}",
             if count == 1 { "" } else { "s" },
@@ -838,7 +1507,9 @@ impl WorkspaceState {
     /// Names that would collide with something already in `dest_dir` get a
     /// ` (n)` suffix instead of failing the drop. Only the *first* segment of
     /// each entry is ever renamed, so a dropped folder keeps its internal
-    /// structure and is de-duplicated as a unit.
+    /// structure and is de-duplicated as a unit. Images go through
+    /// [`image_import`] processing when it is enabled, which can change their
+    /// extension and skip ones already in the workspace.
     ///
     /// Returns the workspace-relative path of every file written.
     pub fn import_dropped(
@@ -873,16 +1544,37 @@ impl WorkspaceState {
             return Err(e);
         }
 
-        // Resolve every destination first: a collision on a dropped folder has
-        // to rename the folder once, not once per file inside it.
-        let mut renamed_roots: HashMap<String, String> = HashMap::new();
-        let mut claimed: Vec<String> = Vec::new();
-        let mut plan: Vec<(String, usize)> = Vec::with_capacity(files.len());
+        let dest_prefix = dest_dir.trim_end_matches(['/', '\\']);
+        let config = crate::commands::settings::image_import_config_from_handle(&self.app_handle);
+        let mut images = self.image_index(fs.as_ref(), &config)?;
+        let mut report = ImportReport::default();
+
+        // Process every file before naming any: a conversion changes the
+        // extension, and the collision pass below has to see final names.
+        // Duplicates are only looked for when writing, so that one found
+        // earlier in the same drop is reported under the name it got.
+        let mut processed = Vec::with_capacity(files.len());
+        let mut offset = 0usize;
         for file in files {
-            let segments = drop_path_segments(&file.path).map_err(|e| {
+            let mut segments = drop_path_segments(&file.path).map_err(|e| {
                 error!("WorkspaceState::import_dropped: err=\"{e}\"");
                 e
             })?;
+            let bytes = &payload[offset..offset + file.len];
+            offset += file.len;
+
+            let name = segments.last_mut().expect("segments is never empty");
+            let result = image_import::process(name, bytes, &config);
+            *name = result.name;
+            processed.push((segments, result.bytes, result.notes, result.is_image));
+        }
+
+        // Resolve every destination first: a collision on a dropped folder has
+        // to rename the folder once, not once per file inside it.
+        let mut renamed_roots: HashMap<String, String> = HashMap::new();
+        let mut claimed: Vec<String> = Vec::new();
+        let mut plan = Vec::with_capacity(processed.len());
+        for (segments, bytes, notes, is_image) in processed {
             let (first, rest) = segments.split_first().expect("segments is never empty");
             let root_name = match renamed_roots.get(first) {
                 Some(name) => name.clone(),
@@ -898,21 +1590,23 @@ impl WorkspaceState {
                 rel.push('/');
                 rel.push_str(segment);
             }
-            plan.push((rel, file.len));
+            plan.push((rel, bytes, notes, is_image));
         }
 
-        let dest_prefix = dest_dir.trim_end_matches(['/', '\\']);
         let mut written = Vec::with_capacity(plan.len());
-        let mut offset = 0usize;
-        for (rel, len) in plan {
-            let bytes = &payload[offset..offset + len];
-            offset += len;
-
-            let ws_rel = if dest_prefix.is_empty() {
-                rel
-            } else {
-                format!("{dest_prefix}/{rel}")
-            };
+        for (rel, bytes, notes, is_image) in plan {
+            let ws_rel = join_rel(dest_prefix, &rel);
+            if let Some(existing) = images
+                .as_mut()
+                .filter(|_| is_image)
+                .and_then(|index| index.find(&bytes))
+            {
+                report.duplicates.push(DuplicateImport {
+                    name: ws_rel,
+                    existing,
+                });
+                continue;
+            }
             // Route through `resolve` so a crafted entry path can't escape the
             // workspace root, even though the segments were validated above.
             let abs = self.resolve(&ws_rel)?;
@@ -923,12 +1617,22 @@ impl WorkspaceState {
                     e
                 })?;
             }
-            fs.write_file(&abs, bytes).map_err(|e| {
+            fs.write_file(&abs, &bytes).map_err(|e| {
                 error!("WorkspaceState::import_dropped: write failed dst={abs:?} err=\"{e}\"");
                 e
             })?;
+            if let Some(index) = images.as_mut().filter(|_| is_image) {
+                index.add(&bytes, abs);
+            }
+            report
+                .notes
+                .extend(notes.into_iter().map(|message| ImportNote {
+                    path: ws_rel.clone(),
+                    message,
+                }));
             written.push(ws_rel);
         }
+        self.emit_import_report(report);
 
         let count = written.len();
         let destination = if dest_prefix.is_empty() {
@@ -1021,7 +1725,39 @@ impl WorkspaceState {
             format!("Cannot read workspace folder {}: {e}", root.display())
         })?;
 
-        Ok(read_dir_recursive(fs.as_ref(), &root, &root))
+        let rules = IgnoreRules::load(fs.as_ref(), &root);
+        // Git status is decoration: a repository it can't read leaves the
+        // tree plain rather than failing it.
+        let git_status = match GitRepo::discover(&root) {
+            Some(repo) => repo.status(&root, fs.as_ref()).unwrap_or_else(|e| {
+                warn!("WorkspaceState::get_file_tree: git status failed err=\"{e}\"");
+                BTreeMap::new()
+            }),
+            None => BTreeMap::new(),
+        };
+        Ok(read_dir_recursive(
+            fs.as_ref(),
+            &rules,
+            &git_status,
+            &root,
+            &root,
+        ))
+    }
+
+    // ─── Git ───────────────────────────────────────────────────────────────
+
+    /// Branch and HEAD of the git repository the workspace is in, or `None`
+    /// when it isn't in one.
+    pub fn git_info(&self) -> Result<Option<GitInfo>, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        GitRepo::discover(&root).map(|repo| repo.info()).transpose()
+    }
+
+    /// Diff the workspace's files against the git HEAD commit.
+    pub fn git_diff_vs_head(&self) -> Result<WorkspaceDiff, String> {
+        let root = self.root.read().clone().ok_or("No workspace open")?;
+        let repo = GitRepo::discover(&root).ok_or("The workspace is not in a git repository")?;
+        repo.diff_vs_head(&root, self.working_fs()?.as_ref())
     }
 }
 
@@ -1071,16 +1807,25 @@ impl WorkspaceState {
     }
 }
 
-// ─── Directory reading ────────────────────────────────────────────────────────
+fn log_purged(purged: &[TrashEntry]) {
+    if !purged.is_empty() {
+        info!(
+            "WorkspaceState::purge_trash: purged {} item(s), {} bytes",
+            purged.len(),
+            purged.iter().map(|entry| entry.size).sum::<u64>()
+        );
+    }
+}
 
-/// Directories never shown in the file tree and never descended into. Mirrors
-/// the watcher's ignore list (`watcher::IGNORED_DIRS`) so the tree walk doesn't
-/// spend time (and IPC payload) recursing through huge generated/dependency
-/// folders. Dot-prefixed names (`.git`, `.typwriter`, `.svelte-kit`) are already
-/// filtered separately; these are the non-dotfile cases.
-const IGNORED_TREE_DIRS: &[&str] = &["node_modules", "target", "dist"];
+// ─── Directory reading ────────────────────────────────────────────────────────
 
-fn read_dir_recursive(fs: &dyn WorkingTreeFs, root: &Path, dir: &Path) -> Vec<FileTreeEntry> {
+fn read_dir_recursive(
+    fs: &dyn WorkingTreeFs,
+    rules: &IgnoreRules,
+    git_status: &BTreeMap<String, GitFileStatus>,
+    root: &Path,
+    dir: &Path,
+) -> Vec<FileTreeEntry> {
     let entries = match fs.read_dir(dir) {
         Ok(entries) => entries,
         Err(err) => {
@@ -1098,14 +1843,15 @@ fn read_dir_recursive(fs: &dyn WorkingTreeFs, root: &Path, dir: &Path) -> Vec<Fi
                 return None;
             }
             let is_dir = entry.is_dir;
-            // Don't surface or descend into generated/dependency directories.
-            if is_dir && IGNORED_TREE_DIRS.contains(&name.as_str()) {
-                return None;
-            }
             let rel = entry.path.strip_prefix(root).ok()?;
             let path_str = rel.to_str()?.to_string();
+            let rel_slash = path_str.replace('\\', "/");
+            // Don't surface or descend into anything the workspace ignores.
+            if rules.is_ignored(&rel_slash, is_dir) {
+                return None;
+            }
             let children = if is_dir {
-                read_dir_recursive(fs, root, &entry.path)
+                read_dir_recursive(fs, rules, git_status, root, &entry.path)
             } else {
                 vec![]
             };
@@ -1114,6 +1860,7 @@ fn read_dir_recursive(fs: &dyn WorkingTreeFs, root: &Path, dir: &Path) -> Vec<Fi
                 path: path_str,
                 is_dir,
                 children,
+                git_status: git_status.get(&rel_slash).copied(),
             })
         })
         .collect();
@@ -1220,6 +1967,15 @@ fn free_name(fs: &dyn WorkingTreeFs, dest: &Path, name: &str, claimed: &[String]
     }
 }
 
+/// `rel` inside the workspace-relative directory `dir`, where `""` is the root.
+fn join_rel(dir: &str, rel: &str) -> String {
+    if dir.is_empty() {
+        rel.to_string()
+    } else {
+        format!("{dir}/{rel}")
+    }
+}
+
 /// Last path segment of a workspace-relative (forward- or back-slash) path,
 /// used to build human-readable restore-point messages.
 fn basename(path: &str) -> &str {
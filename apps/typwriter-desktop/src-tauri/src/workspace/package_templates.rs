@@ -0,0 +1,339 @@
+// Starting a workspace from a Typst Universe template package, the way
+// `typst init` does.
+//
+// A package that ships a template says so in its `typst.toml`:
+//
+//     [package]
+//     name = "charged-ieee"
+//     version = "0.1.0"
+//     entrypoint = "lib.typ"
+//
+//     [template]
+//     path = "template"
+//     entrypoint = "main.typ"
+//
+// The `template.path` folder is copied into the new workspace and its
+// `entrypoint` becomes the main file. Templates written against a checkout of
+// the package often import it by relative path (`"../lib.typ"`, `"/lib.typ"`);
+// once copied out of the package those paths point nowhere, so any import
+// that resolves to the package entrypoint is rewritten to the versioned
+// `@preview/name:version` form.
+
+use std::path::{Component, Path};
+
+use log::warn;
+use tauri::AppHandle;
+use typst::syntax::{
+    ast,
+    package::{PackageManifest, PackageSpec},
+    LinkedNode, SyntaxKind,
+};
+
+use super::ignore::{walk_files, IgnoreRules, Unreadable};
+use super::templates::safe_relative;
+use crate::vcs::fs::LocalWorkingTreeFs;
+
+/// Manifest file name at the root of every package.
+const PACKAGE_MANIFEST: &str = "typst.toml";
+
+/// Create a workspace's files from the template package in `package_dir`
+/// and make its entrypoint the workspace's main file.
+pub fn create_from_package(
+    app: &AppHandle,
+    root: &Path,
+    package_dir: &Path,
+    spec: &PackageSpec,
+) -> Result<String, String> {
+    let main = instantiate_package(package_dir, spec, root)?;
+    super::store::set_workspace_main_file(app, root, &root.join(&main));
+    Ok(main)
+}
+
+/// Copy the template of the package in `package_dir` (resolved from `spec`)
+/// into the workspace at `root`. Returns the main file, workspace-relative.
+pub fn instantiate_package(
+    package_dir: &Path,
+    spec: &PackageSpec,
+    root: &Path,
+) -> Result<String, String> {
+    let text = std::fs::read_to_string(package_dir.join(PACKAGE_MANIFEST))
+        .map_err(|e| format!("Failed to read {PACKAGE_MANIFEST} of {spec}: {e}"))?;
+    let manifest: PackageManifest =
+        toml::from_str(&text).map_err(|e| format!("invalid {PACKAGE_MANIFEST} of {spec}: {e}"))?;
+    let template = manifest
+        .template
+        .as_ref()
+        .ok_or_else(|| format!("{spec} is not a template package"))?;
+    let template_rel = safe_relative(template.path.as_str())
+        .ok_or_else(|| format!("{spec}: invalid template path {:?}", template.path))?;
+    let main = safe_relative(template.entrypoint.as_str()).ok_or_else(|| {
+        format!(
+            "{spec}: invalid template entrypoint {:?}",
+            template.entrypoint
+        )
+    })?;
+    let template_dir = package_dir.join(template_rel);
+    if !template_dir.join(main).is_file() {
+        return Err(format!(
+            "{spec}: template entrypoint {} does not exist",
+            template.entrypoint
+        ));
+    }
+
+    // Package-relative, forward-slashed: the form import paths resolve in.
+    let template_prefix = to_slashes(template_rel);
+    let entrypoint = normalize("", manifest.package.entrypoint.as_str());
+    let import = format!("\"{spec}\"");
+
+    // Nothing in a package is the user's to ignore; the walker still skips
+    // `.git` and symlinks.
+    let fs = LocalWorkingTreeFs;
+    for file in walk_files(
+        &fs,
+        &template_dir,
+        &IgnoreRules::parse([]),
+        Unreadable::Fail,
+    )? {
+        let target = root.join(&file.rel);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        let mut bytes =
+            std::fs::read(&file.path).map_err(|e| format!("Failed to read {}: {e}", file.rel))?;
+        if file.rel.ends_with(".typ") {
+            if let Ok(text) = std::str::from_utf8(&bytes) {
+                let in_package = match template_prefix.as_str() {
+                    "" => file.rel.clone(),
+                    prefix => format!("{prefix}/{}", file.rel),
+                };
+                let rewritten = rewrite_package_imports(
+                    text,
+                    &in_package,
+                    &template_prefix,
+                    entrypoint.as_deref(),
+                    &import,
+                );
+                bytes = rewritten.into_bytes();
+            }
+        }
+        std::fs::write(&target, bytes).map_err(|e| format!("Failed to write {}: {e}", file.rel))?;
+    }
+
+    Ok(to_slashes(main))
+}
+
+/// Rewrite the imports in `text` (the file at package-relative `path`) that
+/// resolve to the package `entrypoint` so they import `spec_literal` instead.
+/// Relative imports of other package files outside `template_dir` are left
+/// alone, with a warning: a package can only be imported through its
+/// entrypoint.
+fn rewrite_package_imports(
+    text: &str,
+    path: &str,
+    template_dir: &str,
+    entrypoint: Option<&str>,
+    spec_literal: &str,
+) -> String {
+    let root = typst::syntax::parse(text);
+    let mut edits = Vec::new();
+    let scope = ImportScope {
+        path,
+        template_dir,
+        entrypoint,
+    };
+    collect_import_edits(&LinkedNode::new(&root), &scope, &mut edits);
+
+    let mut out = text.to_string();
+    // Back to front, so earlier offsets stay valid.
+    for (start, end) in edits.into_iter().rev() {
+        out.replace_range(start..end, spec_literal);
+    }
+    out
+}
+
+/// Where the file being rewritten sits in its package.
+struct ImportScope<'a> {
+    path: &'a str,
+    template_dir: &'a str,
+    entrypoint: Option<&'a str>,
+}
+
+fn collect_import_edits(node: &LinkedNode, scope: &ImportScope, edits: &mut Vec<(usize, usize)>) {
+    if let Some(import) = node.cast::<ast::ModuleImport>() {
+        if let ast::Expr::Str(source) = import.source() {
+            let source = source.get();
+            // Package specs are not relative imports.
+            if !source.starts_with('@') {
+                let dir = scope.path.rsplit_once('/').map_or("", |(dir, _)| dir);
+                match normalize(dir, source.as_str()) {
+                    Some(resolved) if Some(resolved.as_str()) == scope.entrypoint => {
+                        if let Some(literal) = node.children().find(|c| c.kind() == SyntaxKind::Str)
+                        {
+                            edits.push((literal.offset(), literal.offset() + literal.len()));
+                        }
+                    }
+                    Some(resolved) if !is_within(&resolved, scope.template_dir) => warn!(
+                        "package template: {} imports {source:?} from outside the template",
+                        scope.path
+                    ),
+                    _ => {}
+                }
+            }
+        }
+        return;
+    }
+    for child in node.children() {
+        collect_import_edits(&child, scope, edits);
+    }
+}
+
+/// Whether package-relative `path` lies inside directory `dir`.
+fn is_within(path: &str, dir: &str) -> bool {
+    dir.is_empty()
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Resolve import `target` against package-relative directory `dir`, the way
+/// Typst does: a leading `/` is the package root. `None` when the path climbs
+/// out of the package.
+fn normalize(dir: &str, target: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    let (base, target) = match target.strip_prefix('/') {
+        Some(rest) => ("", rest),
+        None => (dir, target),
+    };
+    for part in base.split('/').chain(target.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+fn to_slashes(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn spec() -> PackageSpec {
+        "@preview/demo:0.2.1".parse().expect("valid spec")
+    }
+
+    fn package(template: &str) -> TempDir {
+        let dir = TempDir::new("pkg");
+        dir.write(
+            "typst.toml",
+            &format!(
+                "[package]\nname = \"demo\"\nversion = \"0.2.1\"\nentrypoint = \"src/lib.typ\"\n\n{template}"
+            ),
+        );
+        dir.write("src/lib.typ", "#let conf(doc) = doc\n");
+        dir
+    }
+
+    #[test]
+    fn template_is_copied_with_imports_rewritten() {
+        let pkg = package("[template]\npath = \"template\"\nentrypoint = \"main.typ\"\n");
+        pkg.write(
+            "template/main.typ",
+            "#import \"../src/lib.typ\": conf\n#show: conf\n#include \"chapters/one.typ\"\n",
+        );
+        pkg.write(
+            "template/chapters/one.typ",
+            "#import \"/src/lib.typ\"\n#import \"../helpers.typ\": x\n= One\n",
+        );
+        pkg.write("template/helpers.typ", "#let x = 1\n");
+        pkg.write(
+            "template/refs.bib",
+            "@book{a, title = {\"../src/lib.typ\"}}\n",
+        );
+        let root = TempDir::new("ws");
+
+        let main = instantiate_package(&pkg.0, &spec(), &root.0).expect("instantiated");
+
+        assert_eq!(main, "main.typ");
+        assert_eq!(
+            root.read("main.typ"),
+            "#import \"@preview/demo:0.2.1\": conf\n#show: conf\n#include \"chapters/one.typ\"\n"
+        );
+        assert_eq!(
+            root.read("chapters/one.typ"),
+            "#import \"@preview/demo:0.2.1\"\n#import \"../helpers.typ\": x\n= One\n"
+        );
+        // Only Typst imports are touched.
+        assert_eq!(
+            root.read("refs.bib"),
+            "@book{a, title = {\"../src/lib.typ\"}}\n"
+        );
+        assert!(!root.0.join("typst.toml").exists());
+        assert!(!root.0.join("src").exists());
+    }
+
+    #[test]
+    fn versioned_imports_are_left_alone() {
+        let pkg = package("[template]\npath = \"template\"\nentrypoint = \"main.typ\"\n");
+        pkg.write(
+            "template/main.typ",
+            "#import \"@preview/demo:0.2.1\": conf\n",
+        );
+        let root = TempDir::new("ws");
+
+        instantiate_package(&pkg.0, &spec(), &root.0).expect("instantiated");
+
+        assert_eq!(
+            root.read("main.typ"),
+            "#import \"@preview/demo:0.2.1\": conf\n"
+        );
+    }
+
+    #[test]
+    fn packages_without_a_usable_template_are_rejected() {
+        let root = TempDir::new("ws");
+
+        let plain = package("");
+        let err = instantiate_package(&plain.0, &spec(), &root.0).unwrap_err();
+        assert!(err.contains("not a template package"), "{err}");
+
+        let escaping = package("[template]\npath = \"../elsewhere\"\nentrypoint = \"main.typ\"\n");
+        let err = instantiate_package(&escaping.0, &spec(), &root.0).unwrap_err();
+        assert!(err.contains("invalid template path"), "{err}");
+
+        let missing = package("[template]\npath = \"template\"\nentrypoint = \"main.typ\"\n");
+        let err = instantiate_package(&missing.0, &spec(), &root.0).unwrap_err();
+        assert!(err.contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn import_paths_resolve_like_typst() {
+        assert_eq!(
+            normalize("template", "../lib.typ").as_deref(),
+            Some("lib.typ")
+        );
+        assert_eq!(
+            normalize("template/a", "/src/lib.typ").as_deref(),
+            Some("src/lib.typ")
+        );
+        assert_eq!(
+            normalize("template", "./x/../y.typ").as_deref(),
+            Some("template/y.typ")
+        );
+        assert_eq!(normalize("", "../../lib.typ"), None);
+    }
+}
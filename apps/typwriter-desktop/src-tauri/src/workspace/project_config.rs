@@ -0,0 +1,611 @@
+// Per-project configuration: `typwriter.toml` at the workspace root.
+//
+// Settings follow the user from project to project, but some choices belong
+// to the project and should travel with its sources: which file is the
+// document, the `sys.inputs` it is compiled with, the fonts it ships, how it
+// is formatted, which English it is written in, what is not part of it and
+// how a single chapter is previewed.
+//
+//     main = "thesis.typ"            # or a list; the first is the default
+//     font-dirs = ["brand/fonts"]    # besides `fonts/`, relative to the root
+//     ignore = ["drafts/", "*.bak"]  # .gitignore syntax
+//     standalone-wrapper = "preview.typ"  # around a chapter previewed alone
+//
+//     [inputs]                       # sys.inputs
+//     edition = "print"
+//
+//     [format]                       # the typstyle options from the settings
+//     max-width = 100
+//
+//     [grammar]
+//     dialect = "british"
+//
+// Whatever the file declares wins over the user's settings while the
+// workspace is open. Unknown keys and wrong types are errors rather than
+// being skipped, so a typo never quietly falls back to the settings. Problems
+// are reported as diagnostics on `typwriter.toml`: a file that does not match
+// the schema is ignored as a whole, while a single bad value (a main file that
+// does not exist, an out-of-range width) only drops that value.
+
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Deserialize;
+use toml::Spanned;
+use typstyle_core::Config;
+
+use crate::{
+    compiler::{DiagnosticRange, SerializedDiagnostic},
+    grammar::engine::GrammarDialect,
+    vcs::WorkingTreeFs,
+    world::local_file_id,
+};
+
+/// The project file, at the workspace root.
+pub const PROJECT_CONFIG_FILE: &str = "typwriter.toml";
+
+/// Folder whose fonts a workspace gets without any configuration.
+pub const FONTS_DIR: &str = "fonts";
+
+/// The validated contents of `typwriter.toml`. The default — what a
+/// workspace without the file gets — overrides nothing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProjectConfig {
+    /// Workspace-relative `.typ` files that may be the main file, the
+    /// default first. Empty leaves the choice to the user.
+    pub main_files: Vec<String>,
+    /// Exposed to documents as `sys.inputs`.
+    pub inputs: BTreeMap<String, String>,
+    /// Absolute font directories besides [`FONTS_DIR`]. See
+    /// [`Self::font_dirs_in`].
+    pub font_dirs: Vec<PathBuf>,
+    pub format: FormatOverrides,
+    pub dialect: Option<GrammarDialect>,
+    /// Extra ignore patterns, applied after the ignore files.
+    pub ignore: Vec<String>,
+    /// Workspace-relative `.typ` file holding the template import and rules
+    /// a chapter previewed on its own is wrapped in.
+    pub standalone_wrapper: Option<String>,
+}
+
+impl ProjectConfig {
+    /// Which main file the project wants, given the one currently set: the
+    /// current one if the project lists it, the project's default otherwise.
+    /// `None` when the project leaves the choice to the user.
+    pub fn choose_main<'a>(&'a self, current: Option<&'a str>) -> Option<&'a str> {
+        match current {
+            Some(current) if self.main_files.iter().any(|m| m == current) => Some(current),
+            _ => self.main_files.first().map(String::as_str),
+        }
+    }
+
+    /// Where the workspace's own fonts are read from: its [`FONTS_DIR`] when
+    /// there is one, then the directories the file names.
+    pub fn font_dirs_in(&self, root: &Path) -> Vec<PathBuf> {
+        let default = root.join(FONTS_DIR);
+        let mut dirs: Vec<PathBuf> = default.is_dir().then_some(default).into_iter().collect();
+        for dir in &self.font_dirs {
+            if !dirs.contains(dir) {
+                dirs.push(dir.clone());
+            }
+        }
+        dirs
+    }
+}
+
+/// Formatter options the project pins; `None` keeps the user's setting.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormatOverrides {
+    pub tab_spaces: Option<usize>,
+    pub max_width: Option<usize>,
+    pub blank_lines_upper_bound: Option<usize>,
+    pub collapse_markup_spaces: Option<bool>,
+    pub reorder_import_items: Option<bool>,
+    pub wrap_text: Option<bool>,
+}
+
+impl FormatOverrides {
+    /// Overlay the pinned options on the user's formatter config.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(v) = self.tab_spaces {
+            config.tab_spaces = v;
+        }
+        if let Some(v) = self.max_width {
+            config.max_width = v;
+        }
+        if let Some(v) = self.blank_lines_upper_bound {
+            config.blank_lines_upper_bound = v;
+        }
+        if let Some(v) = self.collapse_markup_spaces {
+            config.collapse_markup_spaces = v;
+        }
+        if let Some(v) = self.reorder_import_items {
+            config.reorder_import_items = v;
+        }
+        if let Some(v) = self.wrap_text {
+            config.wrap_text = v;
+            // Same coupling as the settings: wrapping implies collapsing.
+            config.collapse_markup_spaces |= v;
+        }
+    }
+}
+
+// ─── Schema ─────────────────────────────────────────────────────────────────
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct RawConfig {
+    main: Option<Spanned<MainFiles>>,
+    inputs: BTreeMap<String, String>,
+    font_dirs: Vec<Spanned<String>>,
+    format: RawFormat,
+    grammar: RawGrammar,
+    ignore: Vec<String>,
+    standalone_wrapper: Option<Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MainFiles {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct RawFormat {
+    tab_spaces: Option<Spanned<usize>>,
+    max_width: Option<Spanned<usize>>,
+    blank_lines_upper_bound: Option<Spanned<usize>>,
+    collapse_markup_spaces: Option<bool>,
+    reorder_import_items: Option<bool>,
+    wrap_text: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawGrammar {
+    dialect: Option<GrammarDialect>,
+}
+
+// ─── Loading ────────────────────────────────────────────────────────────────
+
+/// Read and validate the workspace's `typwriter.toml`, returning the config
+/// and the problems found on the way. A missing file is not a problem.
+pub fn load(fs: &dyn WorkingTreeFs, root: &Path) -> (ProjectConfig, Vec<SerializedDiagnostic>) {
+    let path = root.join(PROJECT_CONFIG_FILE);
+    if !path.is_file() {
+        return (ProjectConfig::default(), Vec::new());
+    }
+    let text = match fs.read_file(&path) {
+        Ok(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => {
+                let message = format!("{PROJECT_CONFIG_FILE} is not valid UTF-8");
+                return (
+                    ProjectConfig::default(),
+                    vec![problem(ERROR, message, "", None)],
+                );
+            }
+        },
+        Err(e) => {
+            let message = format!("cannot read {PROJECT_CONFIG_FILE}: {e}");
+            return (
+                ProjectConfig::default(),
+                vec![problem(ERROR, message, "", None)],
+            );
+        }
+    };
+    parse(&text, root)
+}
+
+/// The extra ignore patterns `typwriter.toml` declares, for
+/// [`super::ignore::IgnoreRules`]. Empty when the file is missing or invalid.
+pub fn ignore_patterns(fs: &dyn WorkingTreeFs, root: &Path) -> Vec<String> {
+    let Ok(bytes) = fs.read_file(&root.join(PROJECT_CONFIG_FILE)) else {
+        return Vec::new();
+    };
+    std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| toml::from_str::<RawConfig>(text).ok())
+        .map(|raw| raw.ignore)
+        .unwrap_or_default()
+}
+
+const ERROR: &str = "error";
+const WARNING: &str = "warning";
+
+fn parse(text: &str, root: &Path) -> (ProjectConfig, Vec<SerializedDiagnostic>) {
+    let raw: RawConfig = match toml::from_str(text) {
+        Ok(raw) => raw,
+        Err(e) => {
+            let message = format!("invalid {PROJECT_CONFIG_FILE}: {}", e.message());
+            return (
+                ProjectConfig::default(),
+                vec![problem(ERROR, message, text, e.span())],
+            );
+        }
+    };
+
+    let mut problems = Vec::new();
+    let mut report = |severity: &str, message: String, span: Range<usize>| {
+        problems.push(problem(severity, message, text, Some(span)));
+    };
+
+    let mut main_files = Vec::new();
+    if let Some(main) = raw.main {
+        let span = main.span();
+        let listed = match main.into_inner() {
+            MainFiles::One(file) => vec![file],
+            MainFiles::Many(files) => files,
+        };
+        for file in listed {
+            let Some(rel) =
+                inside_root(&file).filter(|rel| local_file_id(Path::new(rel)).is_some())
+            else {
+                report(
+                    ERROR,
+                    format!("main file {file:?} must be inside the workspace"),
+                    span.clone(),
+                );
+                continue;
+            };
+            if !rel.ends_with(".typ") {
+                report(
+                    ERROR,
+                    format!("main file {file:?} is not a .typ file"),
+                    span.clone(),
+                );
+            } else if !root.join(&rel).is_file() {
+                report(
+                    WARNING,
+                    format!("main file {file:?} does not exist"),
+                    span.clone(),
+                );
+            } else if !main_files.contains(&rel) {
+                main_files.push(rel);
+            }
+        }
+    }
+
+    let mut font_dirs = Vec::new();
+    for dir in raw.font_dirs {
+        let span = dir.span();
+        let Some(rel) = inside_root(dir.get_ref()) else {
+            report(
+                ERROR,
+                format!(
+                    "font directory {:?} must be inside the workspace",
+                    dir.get_ref()
+                ),
+                span,
+            );
+            continue;
+        };
+        let path = root.join(rel);
+        if path.is_dir() {
+            font_dirs.push(path);
+        } else {
+            report(
+                WARNING,
+                format!("font directory {:?} does not exist", dir.get_ref()),
+                span,
+            );
+        }
+    }
+
+    let standalone_wrapper = raw.standalone_wrapper.and_then(|wrapper| {
+        let span = wrapper.span();
+        let file = wrapper.into_inner();
+        let Some(rel) = inside_root(&file).filter(|rel| local_file_id(Path::new(rel)).is_some())
+        else {
+            report(
+                ERROR,
+                format!("standalone wrapper {file:?} must be inside the workspace"),
+                span,
+            );
+            return None;
+        };
+        if !rel.ends_with(".typ") {
+            report(
+                ERROR,
+                format!("standalone wrapper {file:?} is not a .typ file"),
+                span,
+            );
+            None
+        } else if !root.join(&rel).is_file() {
+            report(
+                WARNING,
+                format!("standalone wrapper {file:?} does not exist"),
+                span,
+            );
+            None
+        } else {
+            Some(rel)
+        }
+    });
+
+    let mut bounded = |option: Option<Spanned<usize>>, key: &str, range: Range<usize>| {
+        let option = option?;
+        let value = *option.get_ref();
+        if range.contains(&value) {
+            Some(value)
+        } else {
+            let message = format!(
+                "format.{key} must be between {} and {}",
+                range.start,
+                range.end - 1
+            );
+            report(ERROR, message, option.span());
+            None
+        }
+    };
+    // The same bounds the settings dialog enforces.
+    let format = FormatOverrides {
+        tab_spaces: bounded(raw.format.tab_spaces, "tab-spaces", 1..9),
+        max_width: bounded(raw.format.max_width, "max-width", 20..241),
+        blank_lines_upper_bound: bounded(
+            raw.format.blank_lines_upper_bound,
+            "blank-lines-upper-bound",
+            0..9,
+        ),
+        collapse_markup_spaces: raw.format.collapse_markup_spaces,
+        reorder_import_items: raw.format.reorder_import_items,
+        wrap_text: raw.format.wrap_text,
+    };
+
+    let config = ProjectConfig {
+        main_files,
+        inputs: raw.inputs,
+        font_dirs,
+        format,
+        dialect: raw.grammar.dialect,
+        ignore: raw.ignore,
+        standalone_wrapper,
+    };
+    (config, problems)
+}
+
+/// `path` as a forward-slash path relative to the workspace root, or `None`
+/// when it is absolute or climbs out with `..`.
+fn inside_root(path: &str) -> Option<String> {
+    let rel = path.trim_start_matches("./").replace('\\', "/");
+    Path::new(&rel)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then_some(rel)
+}
+
+fn problem(
+    severity: &str,
+    message: String,
+    text: &str,
+    span: Option<Range<usize>>,
+) -> SerializedDiagnostic {
+    SerializedDiagnostic {
+        severity: severity.to_string(),
+        message,
+        hints: Vec::new(),
+        file_path: Some(PROJECT_CONFIG_FILE.to_string()),
+        range: span.map(|span| {
+            let (start_line, start_col) = line_col(text, span.start);
+            let (end_line, end_col) = line_col(text, span.end);
+            DiagnosticRange {
+                start_line,
+                start_col,
+                end_line,
+                end_col,
+            }
+        }),
+        trace: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+/// Zero-based line and character column of byte `offset`, as the compiler's
+/// own diagnostics count them.
+fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new("ws");
+        std::fs::write(dir.0.join("thesis.typ"), "= Thesis\n").expect("write");
+        std::fs::write(dir.0.join("slides.typ"), "= Slides\n").expect("write");
+        std::fs::write(dir.0.join("preview.typ"), "#set page(\"a5\")\n").expect("write");
+        std::fs::create_dir_all(dir.0.join("fonts")).expect("mkdir");
+        dir
+    }
+
+    #[test]
+    fn a_full_config_is_read() {
+        let ws = workspace();
+        let (config, problems) = parse(
+            r#"
+main = ["thesis.typ", "./slides.typ"]
+font-dirs = ["fonts"]
+ignore = ["drafts/"]
+standalone-wrapper = "./preview.typ"
+
+[inputs]
+edition = "print"
+
+[format]
+max-width = 100
+wrap-text = true
+
+[grammar]
+dialect = "british"
+"#,
+            &ws.0,
+        );
+
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(config.main_files, vec!["thesis.typ", "slides.typ"]);
+        assert_eq!(
+            config.inputs.get("edition").map(String::as_str),
+            Some("print")
+        );
+        assert_eq!(config.font_dirs, vec![ws.0.join("fonts")]);
+        assert_eq!(config.ignore, vec!["drafts/"]);
+        assert_eq!(config.standalone_wrapper.as_deref(), Some("preview.typ"));
+        assert_eq!(config.format.max_width, Some(100));
+        assert_eq!(config.dialect, Some(GrammarDialect::British));
+    }
+
+    #[test]
+    fn a_single_main_file_may_be_a_string() {
+        let ws = workspace();
+        let (config, problems) = parse("main = \"slides.typ\"\n", &ws.0);
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(config.main_files, vec!["slides.typ"]);
+    }
+
+    #[test]
+    fn schema_errors_drop_the_file_and_point_at_the_key() {
+        let ws = workspace();
+        let (config, problems) = parse("main = \"thesis.typ\"\nmain-file = \"x.typ\"\n", &ws.0);
+
+        assert_eq!(config, ProjectConfig::default());
+        assert_eq!(problems.len(), 1);
+        let problem = &problems[0];
+        assert_eq!(problem.severity, "error");
+        assert_eq!(problem.file_path.as_deref(), Some(PROJECT_CONFIG_FILE));
+        assert!(problem.message.contains("main-file"), "{}", problem.message);
+        assert_eq!(problem.range.as_ref().map(|r| r.start_line), Some(1));
+    }
+
+    #[test]
+    fn font_dirs_outside_the_workspace_are_rejected() {
+        let ws = workspace();
+        let outside = if cfg!(windows) {
+            "C:/Windows/Fonts"
+        } else {
+            "/usr/share/fonts"
+        };
+        let (config, problems) = parse(
+            &format!("font-dirs = [\"../shared\", \"{outside}\", \"fonts\"]\n"),
+            &ws.0,
+        );
+
+        assert_eq!(config.font_dirs, vec![ws.0.join("fonts")]);
+        assert!(problems.iter().all(|p| p.severity == "error"));
+        let messages: Vec<&str> = problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "font directory \"../shared\" must be inside the workspace",
+                format!("font directory {outside:?} must be inside the workspace").as_str(),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_values_only_drop_themselves() {
+        let ws = workspace();
+        let (config, problems) = parse(
+            "main = [\"missing.typ\", \"../outside.typ\", \"thesis.typ\"]\n\
+             font-dirs = [\"nope\"]\n\
+             [format]\ntab-spaces = 0\nmax-width = 80\n",
+            &ws.0,
+        );
+
+        assert_eq!(config.main_files, vec!["thesis.typ"]);
+        assert!(config.font_dirs.is_empty());
+        assert_eq!(config.format.tab_spaces, None);
+        assert_eq!(config.format.max_width, Some(80));
+
+        let found: Vec<(&str, usize)> = problems
+            .iter()
+            .map(|p| {
+                (
+                    p.severity.as_str(),
+                    p.range.as_ref().map_or(0, |r| r.start_line),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![("warning", 0), ("error", 0), ("warning", 1), ("error", 3)]
+        );
+    }
+
+    #[test]
+    fn the_current_main_file_survives_if_the_project_lists_it() {
+        let config = ProjectConfig {
+            main_files: vec!["thesis.typ".into(), "slides.typ".into()],
+            ..ProjectConfig::default()
+        };
+        assert_eq!(config.choose_main(Some("slides.typ")), Some("slides.typ"));
+        assert_eq!(config.choose_main(Some("notes.typ")), Some("thesis.typ"));
+        assert_eq!(config.choose_main(None), Some("thesis.typ"));
+        assert_eq!(
+            ProjectConfig::default().choose_main(Some("notes.typ")),
+            None
+        );
+    }
+
+    #[test]
+    fn the_fonts_folder_is_used_without_configuration() {
+        let ws = workspace();
+        std::fs::create_dir_all(ws.0.join("brand")).expect("mkdir");
+
+        assert_eq!(
+            ProjectConfig::default().font_dirs_in(&ws.0),
+            vec![ws.0.join("fonts")]
+        );
+        let config = ProjectConfig {
+            font_dirs: vec![ws.0.join("brand"), ws.0.join("fonts")],
+            ..ProjectConfig::default()
+        };
+        assert_eq!(
+            config.font_dirs_in(&ws.0),
+            vec![ws.0.join("fonts"), ws.0.join("brand")]
+        );
+
+        std::fs::remove_dir(ws.0.join("fonts")).expect("rmdir");
+        assert!(ProjectConfig::default().font_dirs_in(&ws.0).is_empty());
+    }
+
+    #[test]
+    fn format_overrides_win_over_the_settings() {
+        let mut config = Config {
+            max_width: 80,
+            wrap_text: false,
+            collapse_markup_spaces: false,
+            ..Config::default()
+        };
+        let overrides = FormatOverrides {
+            max_width: Some(120),
+            wrap_text: Some(true),
+            ..FormatOverrides::default()
+        };
+        overrides.apply(&mut config);
+        assert_eq!(config.max_width, 120);
+        assert!(config.wrap_text);
+        assert!(config.collapse_markup_spaces);
+    }
+
+    #[test]
+    fn line_and_column_count_characters() {
+        let text = "a = \"é\"\nbc";
+        assert_eq!(line_col(text, 0), (0, 0));
+        assert_eq!(line_col(text, text.find('\n').unwrap() + 2), (1, 1));
+        assert_eq!(line_col(text, 7), (0, 6));
+    }
+}
@@ -0,0 +1,318 @@
+// Crash recovery for unsaved editor buffers.
+//
+// The world's shadow buffers live only in memory, so a crash or power loss
+// takes every edit since the last save with it. The journal copies each
+// buffer to `.typwriter/recovery/` whenever the editor pushes a new version,
+// and sweeps all buffers on a timer as a backstop. A save or a discard drops
+// the file's entry; the sweep drops entries for buffers that went away
+// another way.
+//
+// On the next open, every entry whose text differs from the file on disk is
+// offered back (see [`pending`]); the frontend shows the two side by side
+// and lets the user restore or discard each one.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::encoding::decode;
+use super::store::TYPWRITER_DIR;
+use crate::world::EditorWorld;
+
+const RECOVERY_DIR: &str = "recovery";
+
+/// How often every open buffer is swept into the journal.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// One journaled buffer, as stored on disk.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct JournalEntry {
+    /// Workspace-relative, forward-slash.
+    path: String,
+    saved_at_ms: i64,
+    content: String,
+}
+
+/// A journaled buffer that doesn't match the file on disk. Both sides are
+/// sent whole; the frontend diffs them, as it does for restore points.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredBuffer {
+    /// Workspace-relative, forward-slash.
+    pub path: String,
+    /// When the buffer was last journaled.
+    pub journaled_at_ms: i64,
+    /// The file as it is on disk; `None` when it no longer exists.
+    pub disk: Option<String>,
+    /// The unsaved text.
+    pub buffer: String,
+}
+
+/// Journals the shadow buffers of the open workspace.
+pub struct RecoveryJournal {
+    world: Arc<EditorWorld>,
+    state: Mutex<JournalState>,
+}
+
+#[derive(Default)]
+struct JournalState {
+    root: Option<PathBuf>,
+    /// Hash of what was last journaled per file this session. Entries left
+    /// by an earlier session aren't in here, so the sweep never drops one
+    /// the user hasn't answered yet.
+    written: HashMap<String, u128>,
+}
+
+impl RecoveryJournal {
+    pub fn new(world: Arc<EditorWorld>) -> Self {
+        Self {
+            world,
+            state: Mutex::new(JournalState::default()),
+        }
+    }
+
+    /// Start the periodic sweep.
+    pub fn start(self: &Arc<Self>) {
+        let journal = Arc::clone(self);
+        thread::spawn(move || loop {
+            thread::sleep(SWEEP_INTERVAL);
+            journal.sweep();
+        });
+    }
+
+    /// Journal into the workspace at `root` from now on.
+    pub fn attach(&self, root: &Path) {
+        let mut state = self.state.lock();
+        state.root = Some(root.to_path_buf());
+        state.written.clear();
+    }
+
+    /// Journal the buffer of `rel` after the editor pushed a new version.
+    pub fn record(&self, rel: &str, content: &str) {
+        let mut state = self.state.lock();
+        let Some(root) = state.root.clone() else {
+            return;
+        };
+        let hash = typst::utils::hash128(content);
+        if state.written.get(rel) == Some(&hash) {
+            return;
+        }
+        match write_entry(&root, rel, content, crate::vcs::now_ms()) {
+            Ok(()) => {
+                state.written.insert(rel.to_string(), hash);
+            }
+            Err(e) => warn!("RecoveryJournal::record: path={rel:?} err=\"{e}\""),
+        }
+    }
+
+    /// Drop the entry for `rel`, once its buffer was saved or discarded.
+    pub fn forget(&self, rel: &str) {
+        let mut state = self.state.lock();
+        let Some(root) = state.root.clone() else {
+            return;
+        };
+        state.written.remove(rel);
+        if let Err(e) = remove_entry(&root, rel) {
+            warn!("RecoveryJournal::forget: path={rel:?} err=\"{e}\"");
+        }
+    }
+
+    /// Journal every buffer that changed since it was last journaled, and
+    /// drop this session's entries for buffers that are gone.
+    pub fn sweep(&self) {
+        let buffers: HashMap<String, String> = self.world.shadow_entries().into_iter().collect();
+        for (rel, content) in &buffers {
+            self.record(rel, content);
+        }
+
+        let mut state = self.state.lock();
+        let Some(root) = state.root.clone() else {
+            return;
+        };
+        let gone: Vec<String> = state
+            .written
+            .keys()
+            .filter(|rel| !buffers.contains_key(*rel))
+            .cloned()
+            .collect();
+        for rel in gone {
+            state.written.remove(&rel);
+            if let Err(e) = remove_entry(&root, &rel) {
+                warn!("RecoveryJournal::sweep: path={rel:?} err=\"{e}\"");
+            }
+        }
+    }
+}
+
+fn journal_dir(root: &Path) -> PathBuf {
+    root.join(TYPWRITER_DIR).join(RECOVERY_DIR)
+}
+
+/// Entry file for `rel`: named by a hash of the path, so nested paths and
+/// odd characters need no escaping.
+fn entry_path(root: &Path, rel: &str) -> PathBuf {
+    let digest = Sha256::digest(rel.as_bytes());
+    let name: String = digest[..12].iter().map(|b| format!("{b:02x}")).collect();
+    journal_dir(root).join(format!("{name}.json"))
+}
+
+/// Write the entry through a temporary file, so a crash mid-write leaves the
+/// previous entry rather than a torn one.
+fn write_entry(root: &Path, rel: &str, content: &str, now_ms: i64) -> Result<(), String> {
+    let path = entry_path(root, rel);
+    std::fs::create_dir_all(journal_dir(root)).map_err(|e| e.to_string())?;
+    let entry = JournalEntry {
+        path: rel.to_string(),
+        saved_at_ms: now_ms,
+        content: content.to_string(),
+    };
+    let json = serde_json::to_vec(&entry).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+fn remove_entry(root: &Path, rel: &str) -> Result<(), String> {
+    match std::fs::remove_file(entry_path(root, rel)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn read_entry(root: &Path, rel: &str) -> Result<JournalEntry, String> {
+    let bytes = std::fs::read(entry_path(root, rel))
+        .map_err(|_| format!("No recovered buffer for {rel}"))?;
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+/// Journaled buffers under `root` that differ from disk, sorted by path.
+/// Entries that match disk are stale — the buffer was saved without the
+/// journal hearing of it — and are removed.
+pub fn pending(root: &Path) -> Vec<RecoveredBuffer> {
+    let Ok(dir) = std::fs::read_dir(journal_dir(root)) else {
+        return Vec::new();
+    };
+    let mut buffers = Vec::new();
+    for file in dir.flatten() {
+        let path = file.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let entry = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                serde_json::from_slice::<JournalEntry>(&bytes).map_err(|e| e.to_string())
+            });
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("recovery::pending: unreadable entry {path:?} err=\"{e}\"");
+                continue;
+            }
+        };
+        // Decoded the way the editor reads it, so a CRLF or Latin-1 file
+        // that was saved compares equal to its buffer.
+        let disk = std::fs::read(root.join(&entry.path)).ok().map(|bytes| {
+            decode(&bytes, true).map_or_else(
+                || String::from_utf8_lossy(&bytes).into_owned(),
+                |(text, _)| text,
+            )
+        });
+        if disk.as_deref() == Some(entry.content.as_str()) {
+            let _ = std::fs::remove_file(&path);
+            continue;
+        }
+        buffers.push(RecoveredBuffer {
+            path: entry.path,
+            journaled_at_ms: entry.saved_at_ms,
+            disk,
+            buffer: entry.content,
+        });
+    }
+    buffers.sort_by(|a, b| a.path.cmp(&b.path));
+    if !buffers.is_empty() {
+        info!(
+            "recovery::pending: {} buffer(s) to recover in {root:?}",
+            buffers.len()
+        );
+    }
+    buffers
+}
+
+/// The journaled text of `rel`, for restoring it into the editor.
+pub fn recovered_text(root: &Path, rel: &str) -> Result<String, String> {
+    read_entry(root, rel).map(|entry| entry.content)
+}
+
+/// Drop the journaled buffer of `rel` unrestored.
+pub fn discard(root: &Path, rel: &str) -> Result<(), String> {
+    remove_entry(root, rel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn buffers_that_differ_from_disk_are_offered_back() {
+        let dir = TempDir::new("pending");
+        dir.write("main.typ", "= Saved\n");
+        dir.write("ch/one.typ", "= One\n");
+        write_entry(&dir.0, "main.typ", "= Saved\nand more\n", 7).expect("journal");
+        write_entry(&dir.0, "ch/one.typ", "= One\n", 8).expect("journal");
+        write_entry(&dir.0, "gone.typ", "= Gone\n", 9).expect("journal");
+
+        let buffers = pending(&dir.0);
+        assert_eq!(
+            buffers,
+            [
+                RecoveredBuffer {
+                    path: "gone.typ".into(),
+                    journaled_at_ms: 9,
+                    disk: None,
+                    buffer: "= Gone\n".into(),
+                },
+                RecoveredBuffer {
+                    path: "main.typ".into(),
+                    journaled_at_ms: 7,
+                    disk: Some("= Saved\n".into()),
+                    buffer: "= Saved\nand more\n".into(),
+                },
+            ]
+        );
+        // The entry that matched disk was cleaned up.
+        assert!(!entry_path(&dir.0, "ch/one.typ").exists());
+    }
+
+    #[test]
+    fn a_newer_journal_write_replaces_the_entry() {
+        let dir = TempDir::new("replace");
+        write_entry(&dir.0, "main.typ", "first", 1).expect("journal");
+        write_entry(&dir.0, "main.typ", "second", 2).expect("journal");
+        assert_eq!(recovered_text(&dir.0, "main.typ").expect("entry"), "second");
+        let files = std::fs::read_dir(journal_dir(&dir.0)).expect("dir").count();
+        assert_eq!(files, 1);
+    }
+
+    #[test]
+    fn discarding_removes_the_entry() {
+        let dir = TempDir::new("discard");
+        write_entry(&dir.0, "main.typ", "draft", 1).expect("journal");
+        discard(&dir.0, "main.typ").expect("discard");
+        assert!(pending(&dir.0).is_empty());
+        assert!(recovered_text(&dir.0, "main.typ").is_err());
+        // Discarding again is harmless.
+        discard(&dir.0, "main.typ").expect("discard twice");
+    }
+}
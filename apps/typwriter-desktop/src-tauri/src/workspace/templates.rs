@@ -0,0 +1,818 @@
+// Project templates for new workspaces.
+//
+// A template is a folder of files plus a `template.toml` manifest:
+//
+//     name = "Report"
+//     description = "A multi-chapter report with a bibliography."
+//     main = "main.typ"
+//     thumbnail = "thumbnail.png"      # optional, never copied
+//
+//     [[variables]]
+//     key = "title"
+//     label = "Title"
+//     default = "Untitled Report"
+//
+// Text files may contain `{{key}}` placeholders, which are replaced with the
+// values the user entered (or the variable's default) when a workspace is
+// created. `{{workspace}}` (the new folder's name) and `{{date}}` (today,
+// `YYYY-MM-DD`) are always available. A placeholder names how its value is
+// to be escaped for where it sits:
+//
+//   * `{{title|str}}` inside a Typst string literal — `\` and `"` escaped,
+//     line breaks written as `\n`;
+//   * `{{title|markup}}` in markup — characters with markup meaning (`#`,
+//     `*`, `_`, `$`, `[`, …) escaped, so a title reads as text rather than
+//     running as code;
+//   * `{{title}}` as entered, for plain-text files and templates written
+//     before escaping existed.
+//
+// Templates come from three places, listed in this order:
+//
+//   * built in — bundled with the app from `assets/templates/`;
+//   * the user templates directory, `<app data>/templates/<id>/`, where
+//     templates can also be dropped by hand;
+//   * workspaces saved as templates, which are copied into the same user
+//     directory and remember the folder they came from.
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use base64::Engine;
+use log::warn;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::ignore::{walk_files, IgnoreRules, Unreadable};
+use super::text_files::is_text_extension;
+use crate::vcs::fs::LocalWorkingTreeFs;
+
+/// Manifest file name inside a template folder.
+pub const MANIFEST_FILE: &str = "template.toml";
+
+/// Prefix of built-in template ids; user templates are `user:<folder>`.
+const BUILTIN_PREFIX: &str = "builtin:";
+const USER_PREFIX: &str = "user:";
+
+/// Skip files larger than this when saving a workspace as a template. A
+/// template is a starting point, not an archive of someone's media library.
+const MAX_TEMPLATE_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TemplateVariable {
+    pub key: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub default: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TemplateManifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub main: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<TemplateVariable>,
+    /// Workspace the template was saved from, for templates made with
+    /// [`save_workspace_as_template`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_from: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateSource {
+    Builtin,
+    User,
+    Workspace,
+}
+
+/// A template as the gallery shows it.
+#[derive(Serialize, Clone, Debug)]
+pub struct TemplateSummary {
+    pub id: String,
+    pub source: TemplateSource,
+    pub name: String,
+    pub description: String,
+    pub main: String,
+    pub variables: Vec<TemplateVariable>,
+    /// Base64-encoded image, if the template ships one.
+    pub thumbnail: Option<String>,
+}
+
+/// The user's choice in the gallery, passed to `create_workspace`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TemplateChoice {
+    pub id: String,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+}
+
+// ─── Built-in templates ─────────────────────────────────────────────────────
+
+struct BuiltinTemplate {
+    id: &'static str,
+    manifest: &'static str,
+    files: &'static [(&'static str, &'static [u8])],
+}
+
+macro_rules! template_file {
+    ($template:literal, $path:literal) => {
+        (
+            $path,
+            include_bytes!(concat!("../../assets/templates/", $template, "/", $path)) as &[u8],
+        )
+    };
+}
+
+const BUILTIN_TEMPLATES: &[BuiltinTemplate] = &[
+    BuiltinTemplate {
+        id: "article",
+        manifest: include_str!("../../assets/templates/article/template.toml"),
+        files: &[template_file!("article", "main.typ")],
+    },
+    BuiltinTemplate {
+        id: "report",
+        manifest: include_str!("../../assets/templates/report/template.toml"),
+        files: &[
+            template_file!("report", "main.typ"),
+            template_file!("report", "template.typ"),
+            template_file!("report", "chapters/introduction.typ"),
+            template_file!("report", "chapters/background.typ"),
+            template_file!("report", "refs.bib"),
+        ],
+    },
+    BuiltinTemplate {
+        id: "letter",
+        manifest: include_str!("../../assets/templates/letter/template.toml"),
+        files: &[template_file!("letter", "letter.typ")],
+    },
+];
+
+// ─── Listing ────────────────────────────────────────────────────────────────
+
+/// `<app data>/templates/`.
+pub fn user_templates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("templates"))
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))
+}
+
+fn parse_manifest(text: &str) -> Result<TemplateManifest, String> {
+    let manifest: TemplateManifest =
+        toml::from_str(text).map_err(|e| format!("invalid {MANIFEST_FILE}: {e}"))?;
+    if manifest.name.trim().is_empty() {
+        return Err(format!("invalid {MANIFEST_FILE}: name must not be empty"));
+    }
+    safe_relative(&manifest.main)
+        .ok_or_else(|| format!("invalid {MANIFEST_FILE}: bad main file {:?}", manifest.main))?;
+    Ok(manifest)
+}
+
+/// Every template: built-ins first, then the user directory by name.
+/// Folders with a broken manifest are logged and left out.
+pub fn list_templates(user_dir: &Path) -> Vec<TemplateSummary> {
+    let mut out: Vec<TemplateSummary> = BUILTIN_TEMPLATES
+        .iter()
+        .filter_map(|builtin| match parse_manifest(builtin.manifest) {
+            Ok(manifest) => Some(summary(
+                format!("{BUILTIN_PREFIX}{}", builtin.id),
+                TemplateSource::Builtin,
+                manifest,
+                None,
+            )),
+            Err(err) => {
+                warn!("templates: built-in {} skipped: {err}", builtin.id);
+                None
+            }
+        })
+        .collect();
+
+    let mut user: Vec<TemplateSummary> = std::fs::read_dir(user_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let folder = entry.file_name().to_str()?.to_string();
+            let dir = entry.path();
+            let manifest = match read_user_manifest(&dir) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    warn!("templates: {dir:?} skipped: {err}");
+                    return None;
+                }
+            };
+            let source = if manifest.saved_from.is_some() {
+                TemplateSource::Workspace
+            } else {
+                TemplateSource::User
+            };
+            let thumbnail = manifest
+                .thumbnail
+                .as_deref()
+                .and_then(safe_relative)
+                .and_then(|rel| std::fs::read(dir.join(rel)).ok());
+            Some(summary(
+                format!("{USER_PREFIX}{folder}"),
+                source,
+                manifest,
+                thumbnail,
+            ))
+        })
+        .collect();
+    user.sort_by_key(|t| t.name.to_lowercase());
+    out.extend(user);
+    out
+}
+
+fn read_user_manifest(dir: &Path) -> Result<TemplateManifest, String> {
+    let text = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("cannot read {MANIFEST_FILE}: {e}"))?;
+    parse_manifest(&text)
+}
+
+fn summary(
+    id: String,
+    source: TemplateSource,
+    manifest: TemplateManifest,
+    thumbnail: Option<Vec<u8>>,
+) -> TemplateSummary {
+    TemplateSummary {
+        id,
+        source,
+        name: manifest.name,
+        description: manifest.description,
+        main: manifest.main,
+        variables: manifest.variables,
+        thumbnail: thumbnail.map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes)),
+    }
+}
+
+// ─── Instantiating ──────────────────────────────────────────────────────────
+
+/// A template's manifest and files, wherever it came from.
+struct LoadedTemplate {
+    manifest: TemplateManifest,
+    files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+fn load_template(user_dir: &Path, id: &str) -> Result<LoadedTemplate, String> {
+    if let Some(name) = id.strip_prefix(BUILTIN_PREFIX) {
+        let builtin = BUILTIN_TEMPLATES
+            .iter()
+            .find(|t| t.id == name)
+            .ok_or_else(|| format!("Unknown template {id:?}"))?;
+        return Ok(LoadedTemplate {
+            manifest: parse_manifest(builtin.manifest)?,
+            files: builtin
+                .files
+                .iter()
+                .map(|(path, bytes)| (PathBuf::from(path), bytes.to_vec()))
+                .collect(),
+        });
+    }
+
+    let folder = id
+        .strip_prefix(USER_PREFIX)
+        .and_then(safe_relative)
+        .filter(|rel| rel.components().count() == 1)
+        .ok_or_else(|| format!("Unknown template {id:?}"))?;
+    let dir = user_dir.join(folder);
+    let manifest = read_user_manifest(&dir)?;
+
+    let fs = LocalWorkingTreeFs;
+    let rules = IgnoreRules::load(&fs, &dir);
+    let thumbnail = manifest.thumbnail.as_deref().and_then(safe_relative);
+    let mut files = Vec::new();
+    for file in walk_files(&fs, &dir, &rules, Unreadable::Fail)? {
+        let rel = PathBuf::from(&file.rel);
+        if rel == Path::new(MANIFEST_FILE) || thumbnail == Some(rel.as_path()) {
+            continue;
+        }
+        let bytes =
+            std::fs::read(&file.path).map_err(|e| format!("Failed to read {}: {e}", file.rel))?;
+        files.push((rel, bytes));
+    }
+    Ok(LoadedTemplate { manifest, files })
+}
+
+/// Create a workspace's files from `choice` and make the template's main
+/// file the workspace's main file, so opening it compiles straight away.
+pub fn create_from_template(
+    app: &AppHandle,
+    root: &Path,
+    choice: &TemplateChoice,
+) -> Result<String, String> {
+    let user_dir = user_templates_dir(app)?;
+    let main = instantiate(&user_dir, &choice.id, root, &choice.values)?;
+    super::store::set_workspace_main_file(app, root, &root.join(&main));
+    Ok(main)
+}
+
+/// Write the template `id` into the (new, empty) workspace at `root`,
+/// substituting placeholders. Returns the main file, workspace-relative.
+pub fn instantiate(
+    user_dir: &Path,
+    id: &str,
+    root: &Path,
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    let template = load_template(user_dir, id)?;
+    let values = resolve_values(&template.manifest, root, values);
+
+    for (rel, bytes) in template.files {
+        let target = root.join(&rel);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        let bytes = map_text(&rel, bytes, |text| substitute(text, &values));
+        std::fs::write(&target, bytes)
+            .map_err(|e| format!("Failed to write {}: {e}", rel.display()))?;
+    }
+
+    Ok(template.manifest.main)
+}
+
+/// The values to substitute: built-ins, then manifest defaults, then what
+/// the user entered. Blank entries fall back to the default.
+fn resolve_values(
+    manifest: &TemplateManifest,
+    root: &Path,
+    entered: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let folder = root.file_name().map(|n| n.to_string_lossy().into_owned());
+    values.insert("workspace".to_string(), folder.unwrap_or_default());
+    values.insert(
+        "date".to_string(),
+        chrono::Local::now().format("%Y-%m-%d").to_string(),
+    );
+    for variable in &manifest.variables {
+        let value = entered
+            .get(&variable.key)
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or(&variable.default);
+        values.insert(variable.key.clone(), value.clone());
+    }
+    values
+}
+
+/// Whether `key` can be written as a `{{key}}` placeholder.
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn placeholder_regex() -> Regex {
+    Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*(?:\|\s*([a-z]+)\s*)?\}\}")
+        .expect("valid placeholder regex")
+}
+
+/// Replace `{{key}}` and `{{key|escape}}` placeholders. Unknown keys and
+/// escapes are left as written, so a literal `{{` in a template survives.
+fn substitute(text: &str, values: &HashMap<String, String>) -> String {
+    placeholder_regex()
+        .replace_all(text, |caps: &Captures| {
+            let value = values.get(&caps[1]);
+            let escaped = match (value, caps.get(2).map(|m| m.as_str())) {
+                (Some(value), None) => Some(value.clone()),
+                (Some(value), Some("str")) => Some(escape_str(value)),
+                (Some(value), Some("markup")) => Some(escape_markup(value)),
+                _ => None,
+            };
+            escaped.unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// `value` as the inside of a Typst string literal.
+fn escape_str(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `value` as Typst markup that reads as the text itself. Line breaks become
+/// spaces: a blank line would end the paragraph, and a value is one line.
+fn escape_markup(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | '#' | '*' | '_' | '$' | '@' | '<' | '>' | '[' | ']' | '`' | '~' | '=' | '-'
+            | '+' | '/' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\r' | '\n' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Apply `f` to the contents of a UTF-8 text file; other files pass through.
+fn map_text(path: &Path, bytes: Vec<u8>, f: impl FnOnce(&str) -> String) -> Vec<u8> {
+    let is_text = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| is_text_extension(&ext.to_ascii_lowercase()));
+    if !is_text {
+        return bytes;
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => f(&text).into_bytes(),
+        Err(err) => err.into_bytes(),
+    }
+}
+
+/// `path` as a relative path that cannot leave the folder it is joined to.
+pub(super) fn safe_relative(path: &str) -> Option<&Path> {
+    let path = Path::new(path);
+    let normal = path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    (normal && path.components().next().is_some()).then_some(path)
+}
+
+// ─── Saving a workspace ─────────────────────────────────────────────────────
+
+/// What the user fills in when saving a workspace as a template. Each
+/// variable's `default` is the text it currently has in the workspace;
+/// occurrences of it in text files become `{{key}}`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct SaveTemplateRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+}
+
+/// Copy the workspace at `root` into a new folder under `user_dir`, honouring
+/// its ignore rules, and write its manifest. `main` is workspace-relative;
+/// `thumbnail` is the workspace's last preview thumbnail, if any.
+pub fn save_workspace_as_template(
+    user_dir: &Path,
+    root: &Path,
+    main: &str,
+    thumbnail: Option<Vec<u8>>,
+    request: SaveTemplateRequest,
+) -> Result<TemplateSummary, String> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name must not be empty".into());
+    }
+    let variables: Vec<TemplateVariable> = request
+        .variables
+        .into_iter()
+        .filter(|v| is_valid_key(&v.key))
+        .collect();
+
+    let folder = free_folder(user_dir, &slug(&name));
+    let dir = user_dir.join(&folder);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create template: {e}"))?;
+
+    let fs = LocalWorkingTreeFs;
+    let rules = IgnoreRules::load(&fs, root);
+    let result = (|| {
+        for file in walk_files(&fs, root, &rules, Unreadable::Fail)? {
+            let rel = PathBuf::from(&file.rel);
+            if rel == Path::new(MANIFEST_FILE) {
+                continue;
+            }
+            let len = std::fs::metadata(&file.path).map(|m| m.len()).unwrap_or(0);
+            if len > MAX_TEMPLATE_FILE_BYTES {
+                warn!("templates: skipped large file {} ({len} bytes)", file.rel);
+                continue;
+            }
+            let bytes = std::fs::read(&file.path)
+                .map_err(|e| format!("Failed to read {}: {e}", file.rel))?;
+            let bytes = map_text(&rel, bytes, |text| templatize(text, &variables));
+            let target = dir.join(&rel);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&target, bytes)
+                .map_err(|e| format!("Failed to write {}: {e}", file.rel))?;
+        }
+
+        let thumbnail_file = match &thumbnail {
+            Some(png) => {
+                // Not a workspace file: a workspace may well hold its own
+                // `thumbnail.png`, which was copied above.
+                let rel = ".thumbnail.png";
+                std::fs::write(dir.join(rel), png).map_err(|e| e.to_string())?;
+                Some(rel.to_string())
+            }
+            None => None,
+        };
+        let manifest = TemplateManifest {
+            name: name.clone(),
+            description: request.description.trim().to_string(),
+            main: main.to_string(),
+            thumbnail: thumbnail_file,
+            variables,
+            saved_from: Some(root.to_string_lossy().into_owned()),
+        };
+        let text = toml::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(MANIFEST_FILE), text).map_err(|e| e.to_string())?;
+        Ok::<_, String>(summary(
+            format!("{USER_PREFIX}{folder}"),
+            TemplateSource::Workspace,
+            manifest,
+            thumbnail,
+        ))
+    })();
+
+    if result.is_err() {
+        // Leave no half-written template behind in the gallery.
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    result
+}
+
+/// Replace each variable's current value with its placeholder, longest
+/// value first so `Jane Doe` wins over `Jane`.
+fn templatize(text: &str, variables: &[TemplateVariable]) -> String {
+    let mut by_length: Vec<&TemplateVariable> = variables
+        .iter()
+        .filter(|v| !v.default.trim().is_empty())
+        .collect();
+    by_length.sort_by_key(|v| std::cmp::Reverse(v.default.len()));
+
+    // One pass over the text, so a placeholder inserted for one variable is
+    // never rewritten by another whose value it happens to contain.
+    let pattern = by_length
+        .iter()
+        .map(|v| regex::escape(&v.default))
+        .collect::<Vec<_>>()
+        .join("|");
+    if pattern.is_empty() {
+        return text.to_string();
+    }
+    let Ok(regex) = Regex::new(&pattern) else {
+        return text.to_string();
+    };
+    regex
+        .replace_all(text, |caps: &Captures| {
+            by_length
+                .iter()
+                .find(|v| v.default == caps[0])
+                .map(|v| format!("{{{{{}}}}}", v.key))
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// Delete a user template. Built-ins cannot be deleted.
+pub fn delete_template(user_dir: &Path, id: &str) -> Result<(), String> {
+    let folder = id
+        .strip_prefix(USER_PREFIX)
+        .and_then(safe_relative)
+        .filter(|rel| rel.components().count() == 1)
+        .ok_or_else(|| format!("Template {id:?} cannot be deleted"))?;
+    std::fs::remove_dir_all(user_dir.join(folder))
+        .map_err(|e| format!("Failed to delete template: {e}"))
+}
+
+/// A folder name for `name`: lowercase ASCII words joined by dashes.
+fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out = out.trim_end_matches('-');
+    if out.is_empty() {
+        "template".to_string()
+    } else {
+        out.to_string()
+    }
+}
+
+/// `base`, or `base-2`, `base-3`, … — the first name not taken in `dir`.
+fn free_folder(dir: &Path, base: &str) -> String {
+    std::iter::once(base.to_string())
+        .chain((2..).map(|n| format!("{base}-{n}")))
+        .find(|name| !dir.join(name).exists())
+        .expect("an unbounded sequence has a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn builtin_templates_are_valid() {
+        for builtin in BUILTIN_TEMPLATES {
+            let manifest = parse_manifest(builtin.manifest).expect("valid manifest");
+            assert!(
+                builtin.files.iter().any(|(path, _)| *path == manifest.main),
+                "{}: main file {} is not bundled",
+                builtin.id,
+                manifest.main
+            );
+        }
+        let empty = TempDir::new("builtins");
+        let listed = list_templates(&empty.0);
+        assert_eq!(listed.len(), BUILTIN_TEMPLATES.len());
+        assert!(listed.iter().all(|t| t.source == TemplateSource::Builtin));
+    }
+
+    #[test]
+    fn placeholders_are_substituted_and_unknown_ones_kept() {
+        let text = "#set document(title: \"{{ title }}\")\n{{author}} {{unknown}}";
+        assert_eq!(
+            substitute(text, &values(&[("title", "Notes"), ("author", "Ada")])),
+            "#set document(title: \"Notes\")\nAda {{unknown}}"
+        );
+    }
+
+    #[test]
+    fn escaped_placeholders_keep_values_inert() {
+        let text = "#set document(title: \"{{title|str}}\")\n*{{ title | markup }}*";
+        assert_eq!(
+            substitute(text, &values(&[("title", "A \"#quote\" \\ *not* bold")])),
+            "#set document(title: \"A \\\"#quote\\\" \\\\ *not* bold\")\n\
+             *A \"\\#quote\" \\\\ \\*not\\* bold*"
+        );
+        // An escape the app doesn't know leaves the placeholder alone.
+        assert_eq!(
+            substitute("{{title|html}}", &values(&[("title", "x")])),
+            "{{title|html}}"
+        );
+    }
+
+    #[test]
+    fn blank_entries_fall_back_to_the_default() {
+        let manifest = parse_manifest(
+            "name = \"T\"\nmain = \"main.typ\"\n[[variables]]\nkey = \"title\"\ndefault = \"Draft\"\n",
+        )
+        .unwrap();
+        let resolved = resolve_values(&manifest, Path::new("/x/book"), &values(&[("title", " ")]));
+        assert_eq!(resolved["title"], "Draft");
+        assert_eq!(resolved["workspace"], "book");
+        assert!(resolved.contains_key("date"));
+    }
+
+    #[test]
+    fn manifests_are_validated() {
+        assert!(parse_manifest("name = \"\"\nmain = \"main.typ\"").is_err());
+        assert!(parse_manifest("name = \"T\"\nmain = \"../main.typ\"").is_err());
+        assert!(parse_manifest("name = \"T\"").is_err());
+        assert!(parse_manifest("name = \"T\"\nmain = \"src/main.typ\"").is_ok());
+    }
+
+    #[test]
+    fn a_builtin_template_creates_a_workspace() {
+        let user = TempDir::new("user-empty");
+        let root = TempDir::new("ws-builtin");
+        let main = instantiate(
+            &user.0,
+            "builtin:report",
+            &root.0,
+            &values(&[("title", "Annual Report"), ("author", "Ada")]),
+        )
+        .unwrap();
+        assert_eq!(main, "main.typ");
+        let text = root.read("main.typ");
+        assert!(text.contains("title: \"Annual Report\""));
+        assert!(text.contains("author: \"Ada\""));
+        assert!(root.0.join("chapters/introduction.typ").is_file());
+        assert!(!root.0.join(MANIFEST_FILE).exists());
+    }
+
+    #[test]
+    fn a_saved_workspace_round_trips_through_the_gallery() {
+        let user = TempDir::new("user");
+        let ws = TempDir::new("ws-source");
+        ws.write(
+            "main.typ",
+            "#set document(author: \"Jane Doe\")\n= Jane's Thesis\n",
+        );
+        ws.write("notes.md", "by Jane Doe");
+        ws.write(".typwriter/history/HEAD", "abc");
+        ws.write("secret.env", "TOKEN=1");
+        ws.write(".gitignore", "*.env\n");
+
+        let saved = save_workspace_as_template(
+            &user.0,
+            &ws.0,
+            "main.typ",
+            Some(vec![0x89, b'P', b'N', b'G']),
+            SaveTemplateRequest {
+                name: "My Thesis!".into(),
+                description: String::new(),
+                variables: vec![
+                    TemplateVariable {
+                        key: "author".into(),
+                        label: "Author".into(),
+                        default: "Jane Doe".into(),
+                    },
+                    TemplateVariable {
+                        key: "title".into(),
+                        label: "Title".into(),
+                        default: "Jane's Thesis".into(),
+                    },
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(saved.id, "user:my-thesis");
+        assert_eq!(saved.source, TemplateSource::Workspace);
+        assert!(saved.thumbnail.is_some());
+
+        let listed = list_templates(&user.0);
+        assert!(listed.iter().any(|t| t.id == "user:my-thesis"));
+
+        let target = TempDir::new("ws-target");
+        let main = instantiate(
+            &user.0,
+            "user:my-thesis",
+            &target.0,
+            &values(&[("author", "Max"), ("title", "Max's Thesis")]),
+        )
+        .unwrap();
+        assert_eq!(main, "main.typ");
+        assert_eq!(
+            target.read("main.typ"),
+            "#set document(author: \"Max\")\n= Max's Thesis\n"
+        );
+        assert_eq!(target.read("notes.md"), "by Max");
+        assert!(!target.0.join("secret.env").exists());
+        assert!(!target.0.join(".typwriter").exists());
+        assert!(!target.0.join(".thumbnail.png").exists());
+
+        delete_template(&user.0, "user:my-thesis").unwrap();
+        assert!(delete_template(&user.0, "builtin:article").is_err());
+    }
+
+    #[test]
+    fn templatize_prefers_the_longest_value() {
+        let variables = vec![
+            TemplateVariable {
+                key: "first".into(),
+                label: String::new(),
+                default: "Jane".into(),
+            },
+            TemplateVariable {
+                key: "full".into(),
+                label: String::new(),
+                default: "Jane Doe".into(),
+            },
+        ];
+        assert_eq!(
+            templatize("Jane Doe, or Jane", &variables),
+            "{{full}}, or {{first}}"
+        );
+    }
+
+    #[test]
+    fn template_ids_cannot_escape_the_user_directory() {
+        let user = TempDir::new("escape");
+        assert!(instantiate(&user.0, "user:../x", &user.0, &HashMap::new()).is_err());
+        assert!(delete_template(&user.0, "user:a/b").is_err());
+        assert!(instantiate(&user.0, "builtin:nope", &user.0, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn slugs_and_free_folders() {
+        assert_eq!(slug("My Thesis!"), "my-thesis");
+        assert_eq!(slug("  --  "), "template");
+        let dir = TempDir::new("free");
+        std::fs::create_dir(dir.0.join("thesis")).unwrap();
+        assert_eq!(free_folder(&dir.0, "thesis"), "thesis-2");
+    }
+}
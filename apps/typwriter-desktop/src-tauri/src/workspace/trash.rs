@@ -0,0 +1,404 @@
+// App-managed trash for deleted files and folders.
+//
+// `delete_file` and `delete_folder` move their target here instead of
+// removing it, so one deletion can be undone on its own — restoring a
+// FileOp restore point would roll back the whole workspace. The OS trash
+// isn't used: restoring needs the original workspace path, which the
+// platform trash APIs don't reliably give back, and the trash should travel
+// with the project rather than the machine.
+//
+// Each deletion is one folder under `.typwriter/trash/`:
+//
+//   .typwriter/trash/<id>/item        the file or folder, as it was
+//   .typwriter/trash/<id>/entry.json  where it came from, and when
+//
+// `.typwriter` is never watched, so the watcher sees a deletion as the
+// source disappearing and a restore as the file appearing — the same
+// events an external tool would produce.
+//
+// Before each deletion, entries older than the configured age are purged,
+// then the oldest go until the new item fits the size limit alongside the
+// rest. An item bigger than the limit on its own is refused — it would
+// otherwise be deleted for good the moment it arrived.
+
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::store::TYPWRITER_DIR;
+use crate::commands::settings::AppSettings;
+use crate::vcs::WorkingTreeFs;
+
+const TRASH_DIR: &str = "trash";
+const ITEM: &str = "item";
+const ENTRY_FILE: &str = "entry.json";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    /// Name of the entry's folder in the trash.
+    pub id: String,
+    /// Workspace-relative, forward-slash path the item was deleted from.
+    pub original_path: String,
+    pub is_dir: bool,
+    /// Total size of the item's files.
+    pub size: u64,
+    pub deleted_at_ms: i64,
+}
+
+/// Limits automatic purging applies. `0` disables a limit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrashPolicy {
+    pub max_age_days: u32,
+    pub max_bytes: u64,
+}
+
+impl TrashPolicy {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            max_age_days: settings.trash_max_age_days,
+            max_bytes: u64::from(settings.trash_max_size_mb) * 1024 * 1024,
+        }
+    }
+}
+
+/// The trash of the workspace at `root`.
+pub struct Trash {
+    dir: PathBuf,
+}
+
+impl Trash {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            dir: root.join(TYPWRITER_DIR).join(TRASH_DIR),
+        }
+    }
+
+    /// Move `src` — the workspace file or folder at `original_path` — into
+    /// the trash.
+    pub fn put(
+        &self,
+        fs: &dyn WorkingTreeFs,
+        src: &Path,
+        original_path: &str,
+        now_ms: i64,
+    ) -> Result<TrashEntry, String> {
+        let is_dir = fs.read_dir(src).is_ok();
+        let size = disk_size(fs, src);
+
+        let mut id = now_ms.to_string();
+        let mut n = 1;
+        while fs.exists(&self.dir.join(&id)) {
+            id = format!("{now_ms}-{n}");
+            n += 1;
+        }
+        let entry = TrashEntry {
+            id,
+            original_path: original_path.to_string(),
+            is_dir,
+            size,
+            deleted_at_ms: now_ms,
+        };
+
+        let entry_dir = self.dir.join(&entry.id);
+        fs.create_dir_all(&entry_dir)?;
+        let json = serde_json::to_vec_pretty(&entry).map_err(|e| e.to_string())?;
+        fs.write_file(&entry_dir.join(ENTRY_FILE), &json)?;
+        if let Err(e) = fs.rename(src, &entry_dir.join(ITEM)) {
+            let _ = fs.remove_dir_all(&entry_dir);
+            return Err(e);
+        }
+        Ok(entry)
+    }
+
+    /// [`Self::put`] under `policy`: purge what the policy no longer allows,
+    /// making room for `src`, then move it in. Returns the new entry and the
+    /// purged ones. Refuses an item larger than the size limit by itself.
+    pub fn put_within(
+        &self,
+        fs: &dyn WorkingTreeFs,
+        src: &Path,
+        original_path: &str,
+        policy: TrashPolicy,
+        now_ms: i64,
+    ) -> Result<(TrashEntry, Vec<TrashEntry>), String> {
+        let size = disk_size(fs, src);
+        if policy.max_bytes > 0 && size > policy.max_bytes {
+            return Err(format!(
+                "{original_path} ({} MB) is larger than the trash limit ({} MB), so it could \
+                 not be restored once deleted. Raise the limit in Settings to delete it here.",
+                size.div_ceil(1024 * 1024),
+                policy.max_bytes / (1024 * 1024)
+            ));
+        }
+        let purged = self.purge(fs, policy, now_ms, size);
+        let entry = self.put(fs, src, original_path, now_ms)?;
+        Ok((entry, purged))
+    }
+
+    /// Everything in the trash, most recently deleted first. Entries whose
+    /// record is unreadable are skipped.
+    pub fn list(&self, fs: &dyn WorkingTreeFs) -> Vec<TrashEntry> {
+        let Ok(dirs) = fs.read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<TrashEntry> = dirs
+            .into_iter()
+            .filter(|dir| dir.is_dir)
+            .filter_map(|dir| match self.entry(fs, &dir.name) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Trash::list: skipping {:?} err=\"{e}\"", dir.path);
+                    None
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.deleted_at_ms
+                .cmp(&a.deleted_at_ms)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+        entries
+    }
+
+    pub fn entry(&self, fs: &dyn WorkingTreeFs, id: &str) -> Result<TrashEntry, String> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+            return Err(format!("Invalid trash entry: {id}"));
+        }
+        let bytes = fs
+            .read_file(&self.dir.join(id).join(ENTRY_FILE))
+            .map_err(|_| format!("No such item in the trash: {id}"))?;
+        let mut entry: TrashEntry = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+        // The folder name is what identifies the entry, whatever the record
+        // says.
+        entry.id = id.to_string();
+        Ok(entry)
+    }
+
+    /// Move entry `id`'s item to `dest` and drop the entry.
+    pub fn take(&self, fs: &dyn WorkingTreeFs, id: &str, dest: &Path) -> Result<(), String> {
+        self.entry(fs, id)?;
+        let entry_dir = self.dir.join(id);
+        if let Some(parent) = dest.parent() {
+            fs.create_dir_all(parent)?;
+        }
+        fs.rename(&entry_dir.join(ITEM), dest)?;
+        fs.remove_dir_all(&entry_dir)
+    }
+
+    /// Delete entry `id` for good.
+    pub fn remove(&self, fs: &dyn WorkingTreeFs, id: &str) -> Result<(), String> {
+        self.entry(fs, id)?;
+        fs.remove_dir_all(&self.dir.join(id))
+    }
+
+    /// Delete the entries `policy` no longer allows: those older than its
+    /// age, then the oldest until the rest, plus `incoming` bytes about to be
+    /// added, fit its size. Returns what was deleted.
+    pub fn purge(
+        &self,
+        fs: &dyn WorkingTreeFs,
+        policy: TrashPolicy,
+        now_ms: i64,
+        incoming: u64,
+    ) -> Vec<TrashEntry> {
+        let mut entries = self.list(fs);
+        let mut doomed = Vec::new();
+        if policy.max_age_days > 0 {
+            let cutoff = now_ms - i64::from(policy.max_age_days) * DAY_MS;
+            let (old, kept): (Vec<_>, Vec<_>) = entries
+                .into_iter()
+                .partition(|entry| entry.deleted_at_ms < cutoff);
+            doomed.extend(old);
+            entries = kept;
+        }
+        if policy.max_bytes > 0 {
+            let mut total: u64 = incoming + entries.iter().map(|entry| entry.size).sum::<u64>();
+            // Newest first, so the oldest are at the end.
+            while total > policy.max_bytes {
+                let Some(entry) = entries.pop() else { break };
+                total -= entry.size;
+                doomed.push(entry);
+            }
+        }
+
+        doomed
+            .into_iter()
+            .filter(|entry| match fs.remove_dir_all(&self.dir.join(&entry.id)) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Trash::purge: failed id={} err=\"{e}\"", entry.id);
+                    false
+                }
+            })
+            .collect()
+    }
+}
+
+/// Size of the file at `path`, or of all files beneath the folder at `path`.
+fn disk_size(fs: &dyn WorkingTreeFs, path: &Path) -> u64 {
+    match fs.read_dir(path) {
+        Ok(children) => children
+            .iter()
+            .map(|child| disk_size(fs, &child.path))
+            .sum(),
+        Err(_) => std::fs::metadata(path).map_or(0, |meta| meta.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use crate::vcs::fs::LocalWorkingTreeFs;
+
+    fn ids(entries: &[TrashEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.id.as_str()).collect()
+    }
+
+    #[test]
+    fn trashed_items_are_listed_and_restored() {
+        let dir = TempDir::new("restore");
+        dir.write("notes.typ", "= Notes\n");
+        dir.write("fig/a.png", "aaaa");
+        dir.write("fig/b.png", "bb");
+        let fs = LocalWorkingTreeFs;
+        let trash = Trash::new(&dir.0);
+
+        let file = trash
+            .put(&fs, &dir.0.join("notes.typ"), "notes.typ", 1_000)
+            .expect("trash file");
+        let folder = trash
+            .put(&fs, &dir.0.join("fig"), "fig", 2_000)
+            .expect("trash folder");
+        assert!(!dir.0.join("notes.typ").exists());
+        assert!(!dir.0.join("fig").exists());
+        assert!(!file.is_dir);
+        assert!(folder.is_dir);
+        assert_eq!(folder.size, 6);
+        assert_eq!(trash.list(&fs), [folder.clone(), file.clone()]);
+
+        trash
+            .take(&fs, &folder.id, &dir.0.join("fig"))
+            .expect("restore");
+        assert_eq!(
+            std::fs::read_to_string(dir.0.join("fig/a.png")).expect("restored"),
+            "aaaa"
+        );
+        assert_eq!(ids(&trash.list(&fs)), [file.id.as_str()]);
+    }
+
+    #[test]
+    fn deletions_in_the_same_millisecond_get_distinct_entries() {
+        let dir = TempDir::new("same-ms");
+        dir.write("a.typ", "a");
+        dir.write("b.typ", "b");
+        let fs = LocalWorkingTreeFs;
+        let trash = Trash::new(&dir.0);
+        let a = trash.put(&fs, &dir.0.join("a.typ"), "a.typ", 5).expect("a");
+        let b = trash.put(&fs, &dir.0.join("b.typ"), "b.typ", 5).expect("b");
+        assert_eq!((a.id.as_str(), b.id.as_str()), ("5", "5-1"));
+    }
+
+    #[test]
+    fn entry_ids_cannot_leave_the_trash() {
+        let dir = TempDir::new("ids");
+        let fs = LocalWorkingTreeFs;
+        let trash = Trash::new(&dir.0);
+        assert!(trash.entry(&fs, "../history").is_err());
+        assert!(trash.remove(&fs, "..").is_err());
+        assert!(trash.entry(&fs, "").is_err());
+    }
+
+    #[test]
+    fn purge_drops_old_entries_then_the_oldest_over_the_size_limit() {
+        let dir = TempDir::new("purge");
+        dir.write("old.txt", "0123456789");
+        dir.write("mid.txt", "0123456789");
+        dir.write("new.txt", "0123456789");
+        dir.write("newest.txt", "0123456789");
+        let fs = LocalWorkingTreeFs;
+        let trash = Trash::new(&dir.0);
+        let now = 100 * DAY_MS;
+        for (name, age_days) in [("old", 40), ("mid", 3), ("new", 2), ("newest", 1)] {
+            let rel = format!("{name}.txt");
+            trash
+                .put(&fs, &dir.0.join(&rel), &rel, now - age_days * DAY_MS)
+                .expect("trash");
+        }
+
+        let purged = trash.purge(
+            &fs,
+            TrashPolicy {
+                max_age_days: 30,
+                max_bytes: 20,
+            },
+            now,
+            0,
+        );
+        let purged: Vec<_> = purged.iter().map(|e| e.original_path.as_str()).collect();
+        assert_eq!(purged, ["old.txt", "mid.txt"]);
+        let left: Vec<_> = trash
+            .list(&fs)
+            .into_iter()
+            .map(|e| e.original_path)
+            .collect();
+        assert_eq!(left, ["newest.txt", "new.txt"]);
+
+        let unlimited = TrashPolicy {
+            max_age_days: 0,
+            max_bytes: 0,
+        };
+        assert!(trash.purge(&fs, unlimited, now * 2, 0).is_empty());
+    }
+
+    #[test]
+    fn putting_makes_room_first_and_keeps_the_new_item() {
+        let dir = TempDir::new("room");
+        dir.write("a.txt", "0123456789");
+        dir.write("b.txt", "0123456789");
+        dir.write("c.txt", "0123456789");
+        let fs = LocalWorkingTreeFs;
+        let trash = Trash::new(&dir.0);
+        let policy = TrashPolicy {
+            max_age_days: 0,
+            max_bytes: 20,
+        };
+        for (n, name) in ["a.txt", "b.txt", "c.txt"].into_iter().enumerate() {
+            trash
+                .put_within(&fs, &dir.0.join(name), name, policy, n as i64)
+                .expect("trash");
+        }
+        let left: Vec<_> = trash
+            .list(&fs)
+            .into_iter()
+            .map(|e| e.original_path)
+            .collect();
+        assert_eq!(left, ["c.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn an_item_over_the_size_limit_is_refused() {
+        let dir = TempDir::new("too-big");
+        dir.write("small.txt", "0123");
+        dir.write("huge.txt", "0123456789");
+        let fs = LocalWorkingTreeFs;
+        let trash = Trash::new(&dir.0);
+        let policy = TrashPolicy {
+            max_age_days: 0,
+            max_bytes: 8,
+        };
+        trash
+            .put_within(&fs, &dir.0.join("small.txt"), "small.txt", policy, 1)
+            .expect("trash");
+
+        assert!(trash
+            .put_within(&fs, &dir.0.join("huge.txt"), "huge.txt", policy, 2)
+            .is_err());
+        assert!(dir.0.join("huge.txt").is_file());
+        assert_eq!(ids(&trash.list(&fs)), ["1"]);
+    }
+}
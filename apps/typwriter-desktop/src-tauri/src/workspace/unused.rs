@@ -0,0 +1,238 @@
+// Unused assets and orphaned `.typ` files.
+//
+// The compiler records every file it reads through `World::source` and
+// `World::file` (see `FileReads`), so comparing that with the workspace tree
+// says what the document does not use — without guessing at paths built at
+// runtime, like `image("figures/" + name)`. Two kinds of leftovers come out:
+//
+//   * assets — non-`.typ` files no compile read, neither the full document
+//     nor any file compiled on its own for workspace diagnostics;
+//   * orphans — `.typ` files no main file reaches.
+//
+// Archiving moves them under `.typwriter/archive/`, out of the walked tree
+// like the rest of the app's own folder, so the report never sees them again.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use typst::syntax::FileId;
+
+use super::ignore::{walk_files, IgnoreRules, Unreadable};
+use crate::compiler::FileReads;
+use crate::vcs::WorkingTreeFs;
+use crate::world::local_file_id;
+
+/// Folder under `.typwriter/` that archived files are moved into.
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// Files that belong to the project although no compile reads them.
+const PROJECT_FILES: &[&str] = &["typst.toml"];
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnusedFile {
+    /// Workspace-relative, forward-slash.
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnusedFilesReport {
+    pub assets: Vec<UnusedFile>,
+    pub orphans: Vec<UnusedFile>,
+    /// Combined size of everything listed.
+    pub total_bytes: u64,
+    /// The last compile failed and may have stopped before reading all the
+    /// document uses, so some listed files could be in use after all.
+    pub incomplete: bool,
+}
+
+/// Compare `reads` with the files under `root`. `mains` are the entry points
+/// that count as the document besides the one the last full compile entered
+/// at — the other main files `typwriter.toml` declares. Files inside
+/// `font_dirs` are loaded as fonts rather than read, and never reported.
+pub fn find_unused(
+    fs: &dyn WorkingTreeFs,
+    root: &Path,
+    reads: &FileReads,
+    mains: &[FileId],
+    font_dirs: &[PathBuf],
+) -> Result<UnusedFilesReport, String> {
+    let reachable: HashSet<FileId> = reads
+        .main
+        .iter()
+        .chain(
+            mains
+                .iter()
+                .filter_map(|id| reads.entries.get(id))
+                .flatten(),
+        )
+        .copied()
+        .collect();
+    let read: HashSet<FileId> = reachable
+        .iter()
+        .chain(reads.entries.values().flatten())
+        .copied()
+        .collect();
+
+    let rules = IgnoreRules::load(fs, root);
+    let mut report = UnusedFilesReport {
+        incomplete: reads.main_failed,
+        ..UnusedFilesReport::default()
+    };
+    for file in walk_files(fs, root, &rules, Unreadable::Skip)? {
+        let rel = Path::new(&file.rel);
+        if is_exempt(rel) || font_dirs.iter().any(|dir| file.path.starts_with(dir)) {
+            continue;
+        }
+        let Some(id) = local_file_id(rel) else {
+            continue;
+        };
+        let is_typ = rel.extension().is_some_and(|ext| ext == "typ");
+        let used = if is_typ {
+            reachable.contains(&id)
+        } else {
+            read.contains(&id)
+        };
+        if used {
+            continue;
+        }
+
+        let size = std::fs::metadata(&file.path).map_or(0, |meta| meta.len());
+        report.total_bytes += size;
+        let entry = UnusedFile {
+            path: file.rel,
+            size,
+        };
+        if is_typ {
+            report.orphans.push(entry);
+        } else {
+            report.assets.push(entry);
+        }
+    }
+    Ok(report)
+}
+
+/// Files never reported: configuration the app or typst reads outside of a
+/// compile.
+fn is_exempt(rel: &Path) -> bool {
+    IgnoreRules::is_ignore_file(rel) || PROJECT_FILES.iter().any(|name| rel == Path::new(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use crate::vcs::fs::LocalWorkingTreeFs;
+    use std::collections::HashMap;
+
+    fn id(rel: &str) -> FileId {
+        local_file_id(Path::new(rel)).expect("file id")
+    }
+
+    fn paths(files: &[UnusedFile]) -> Vec<&str> {
+        files.iter().map(|file| file.path.as_str()).collect()
+    }
+
+    /// A book whose main file includes one chapter and one figure, with a
+    /// draft chapter that reads its own data file.
+    fn book() -> TempDir {
+        let dir = TempDir::new("book");
+        dir.write(
+            "main.typ",
+            "#include \"ch1.typ\"\n#image(\"fig/used.png\")\n",
+        );
+        dir.write("ch1.typ", "= One\n");
+        dir.write("draft.typ", "#csv(\"data.csv\")\n");
+        dir.write("data.csv", "a,b\n");
+        dir.write("fig/used.png", "png");
+        dir.write("fig/old.png", "old figure");
+        dir.write("fonts/Brand.otf", "font");
+        dir.write(".typwriter/archive/gone.png", "archived");
+        dir.write("typwriter.toml", "");
+        dir
+    }
+
+    fn reads() -> FileReads {
+        FileReads {
+            main: ["main.typ", "ch1.typ", "fig/used.png"]
+                .into_iter()
+                .map(id)
+                .collect(),
+            main_failed: false,
+            entries: HashMap::from([
+                (id("ch1.typ"), vec![id("ch1.typ")]),
+                (id("draft.typ"), vec![id("draft.typ"), id("data.csv")]),
+            ]),
+        }
+    }
+
+    #[test]
+    fn unread_assets_and_unreachable_sources_are_reported() {
+        let dir = book();
+        let report = find_unused(
+            &LocalWorkingTreeFs,
+            &dir.0,
+            &reads(),
+            &[],
+            &[dir.0.join("fonts")],
+        )
+        .expect("report");
+
+        // `data.csv` is read by a workspace-diagnostics compile of the draft,
+        // so only the draft itself is left over.
+        assert_eq!(paths(&report.assets), ["fig/old.png"]);
+        assert_eq!(paths(&report.orphans), ["draft.typ"]);
+        assert_eq!(report.assets[0].size, "old figure".len() as u64);
+        assert_eq!(
+            report.total_bytes,
+            ("old figure".len() + "#csv(\"data.csv\")\n".len()) as u64
+        );
+        assert!(!report.incomplete);
+    }
+
+    #[test]
+    fn other_declared_main_files_reach_their_own_sources() {
+        let dir = book();
+        let report = find_unused(
+            &LocalWorkingTreeFs,
+            &dir.0,
+            &reads(),
+            &[id("draft.typ")],
+            &[dir.0.join("fonts")],
+        )
+        .expect("report");
+        assert!(report.orphans.is_empty());
+    }
+
+    #[test]
+    fn a_failed_compile_marks_the_report_incomplete() {
+        let dir = book();
+        let mut reads = reads();
+        reads.main_failed = true;
+        let report = find_unused(&LocalWorkingTreeFs, &dir.0, &reads, &[], &[]).expect("report");
+        assert!(report.incomplete);
+        // Without the fonts folder configured, its fonts count as assets.
+        assert!(paths(&report.assets).contains(&"fonts/Brand.otf"));
+    }
+
+    #[test]
+    fn a_projects_own_archive_folder_is_still_reported() {
+        let dir = book();
+        dir.write("archive/old-logo.png", "logo");
+        let report = find_unused(
+            &LocalWorkingTreeFs,
+            &dir.0,
+            &reads(),
+            &[],
+            &[dir.0.join("fonts")],
+        )
+        .expect("report");
+        assert_eq!(
+            paths(&report.assets),
+            ["archive/old-logo.png", "fig/old.png"]
+        );
+    }
+}
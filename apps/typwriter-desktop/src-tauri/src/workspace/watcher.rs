@@ -15,7 +15,7 @@
 
 use std::{
     collections::{BTreeMap, BTreeSet},
-    path::{Component, Path, PathBuf},
+    path::{Path, PathBuf},
     sync::mpsc,
     sync::Arc,
     thread,
@@ -32,8 +32,13 @@ use tauri::{AppHandle, Emitter, Manager};
 
 use crate::{
     compiler::{CompileReason, PreviewPipeline},
-    workspace::self_writes::SelfWriteLog,
-    world::EditorWorld,
+    vcs::fs::LocalWorkingTreeFs,
+    workspace::{
+        ignore::IgnoreRules,
+        project_config::{FONTS_DIR, PROJECT_CONFIG_FILE},
+        self_writes::SelfWriteLog,
+    },
+    world::{is_font_file, EditorWorld},
 };
 
 /// What happened to a path between two quiet moments on disk.
@@ -70,15 +75,6 @@ struct FilesChangedPayload {
     changes: Vec<FileChange>,
 }
 
-const IGNORED_DIRS: &[&str] = &[
-    ".typwriter",
-    ".git",
-    "node_modules",
-    "target",
-    "dist",
-    ".svelte-kit",
-];
-
 pub fn start_watcher(
     root: PathBuf,
     world: Arc<EditorWorld>,
@@ -114,6 +110,7 @@ fn dispatch_loop(
     self_writes: Arc<SelfWriteLog>,
 ) {
     let debounce = Duration::from_millis(100);
+    let mut rules = IgnoreRules::load(&LocalWorkingTreeFs, &root);
 
     loop {
         let first = match rx.recv() {
@@ -142,6 +139,34 @@ fn dispatch_loop(
         // after the debounce rather than per event.
         let changes = classify(events, &probe_disk);
 
+        // An edited ignore file changes what is in scope, starting with this
+        // batch. The change itself is still reported: the tree has to refresh.
+        if touches_ignore_file(&root, &changes) {
+            info!("watcher: ignore rules changed, reloading");
+            rules = IgnoreRules::load(&LocalWorkingTreeFs, &root);
+        }
+
+        // Likewise `typwriter.toml`, and even when the editor wrote it: saving
+        // it from the editor is the usual way to change it.
+        if touches_project_config(&root, &changes) {
+            info!("watcher: {PROJECT_CONFIG_FILE} changed, reloading");
+            if let Some(state) = app_handle.try_state::<Arc<crate::workspace::WorkspaceState>>() {
+                state.reload_project_config();
+            }
+        }
+
+        // A font added to (or removed from) the workspace's own font folders
+        // rescans just those folders. `fonts/` counts before it exists, so
+        // creating it is noticed.
+        let mut font_dirs = world.project_font_dirs();
+        font_dirs.push(root.join(FONTS_DIR));
+        if touches_project_fonts(&font_dirs, &changes) {
+            info!("watcher: workspace fonts changed, rescanning");
+            if let Some(state) = app_handle.try_state::<Arc<crate::workspace::WorkspaceState>>() {
+                state.reload_project_fonts();
+            }
+        }
+
         // Drop changes the editor itself caused. A save cannot change the shape
         // of the tree, the world cache already holds exactly those bytes (see
         // `EditorWorld::shadow_commit`), and every in-app file operation has
@@ -149,7 +174,7 @@ fn dispatch_loop(
         // discard a good parse tree and make the app re-walk and re-read a
         // workspace that is already in the state it expects. External edits to
         // other files in the same batch survive.
-        let changes = scope_changes(&root, changes, |path| self_writes.is_recent(path));
+        let changes = scope_changes(&root, &rules, changes, |path| self_writes.is_recent(path));
 
         if changes.is_empty() {
             continue;
@@ -172,8 +197,11 @@ fn dispatch_loop(
         // Before the frontend hears about it: the compiler's own main-file
         // pointer has to survive a rename or delete done by another program,
         // and the compile queued at the bottom of this loop runs against it.
+        // Unsaved edits to a changed file are merged with the change here, so
+        // the result reaches the frontend ahead of the change itself.
         if let Some(state) = app_handle.try_state::<Arc<crate::workspace::WorkspaceState>>() {
             state.reconcile_main_file(&changes);
+            state.merge_external_changes(&changes);
         }
 
         let count = changes.len();
@@ -350,21 +378,23 @@ fn display(path: &Path) -> String {
 /// moved out of it has appeared.
 fn scope_changes(
     root: &Path,
+    rules: &IgnoreRules,
     changes: Vec<FileChange>,
     is_self_write: impl Fn(&Path) -> bool,
 ) -> Vec<FileChange> {
-    let out_of_scope =
-        |path: &Path| is_ignored_path(root, path) || is_self_write(path);
+    let out_of_scope = |path: &Path, is_dir: bool| {
+        is_ignored_path(root, rules, path, is_dir) || is_self_write(path)
+    };
 
     changes
         .into_iter()
         .filter_map(|change| {
             let from = PathBuf::from(&change.path);
-            let from_out = out_of_scope(&from);
+            let from_out = out_of_scope(&from, change.is_dir);
             let Some(to) = change.to.as_deref().map(PathBuf::from) else {
                 return (!from_out).then_some(change);
             };
-            match (from_out, out_of_scope(&to)) {
+            match (from_out, out_of_scope(&to, change.is_dir)) {
                 (true, true) => None,
                 (false, false) => Some(change),
                 (true, false) => Some(FileChange {
@@ -379,28 +409,51 @@ fn scope_changes(
         .collect()
 }
 
-fn is_ignored_path(root: &Path, path: &Path) -> bool {
+fn is_ignored_path(root: &Path, rules: &IgnoreRules, path: &Path, is_dir: bool) -> bool {
     // notify only fires for paths under what we watch, so strip_prefix should
     // always succeed. If it doesn't, the event is outside our scope — treat
-    // it as ignored rather than scanning unrelated parent components for
-    // accidental name matches (e.g. a user's "~/node_modules").
+    // it as ignored rather than matching unrelated parent components by
+    // accident (e.g. a user's "~/node_modules").
     let Ok(rel) = path.strip_prefix(root) else {
         return true;
     };
-    rel.components().any(|component| match component {
-        Component::Normal(name) => {
-            let name = name.to_string_lossy();
-            IGNORED_DIRS
-                .iter()
-                .any(|ignored| name.eq_ignore_ascii_case(ignored))
-        }
-        _ => false,
+    rules.is_ignored_path(rel, is_dir)
+}
+
+/// Whether a change touched one of the workspace's ignore files.
+fn touches_ignore_file(root: &Path, changes: &[FileChange]) -> bool {
+    changes.iter().flat_map(change_paths).any(|path| {
+        path.strip_prefix(root)
+            .is_ok_and(IgnoreRules::is_ignore_file)
     })
 }
 
+/// Whether a change touched a font file inside one of `dirs`, or one of the
+/// directories themselves.
+fn touches_project_fonts(dirs: &[PathBuf], changes: &[FileChange]) -> bool {
+    changes.iter().any(|change| {
+        change_paths(change).any(|path| {
+            dirs.iter().any(|dir| path.starts_with(dir)) && (change.is_dir || is_font_file(path))
+        })
+    })
+}
+
+/// Whether a change touched the workspace's `typwriter.toml`.
+fn touches_project_config(root: &Path, changes: &[FileChange]) -> bool {
+    let config = root.join(PROJECT_CONFIG_FILE);
+    changes
+        .iter()
+        .flat_map(change_paths)
+        .any(|path| path == config)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{classify, scope_changes, ChangeKind, FileChange, Probe};
+    use super::{
+        classify, scope_changes, touches_ignore_file, touches_project_config,
+        touches_project_fonts, ChangeKind, FileChange, Probe,
+    };
+    use crate::workspace::ignore::IgnoreRules;
     use notify::event::{
         CreateKind, DataChange, EventAttributes, EventKind, ModifyKind, RemoveKind, RenameMode,
     };
@@ -647,7 +700,7 @@ mod tests {
             change("/w/.typwriter/history/x", ChangeKind::Created, None),
             change("/w/main.typ", ChangeKind::Modified, None),
         ];
-        let kept = scope_changes(Path::new("/w"), changes, |_| false);
+        let kept = scope_changes(Path::new("/w"), &IgnoreRules::parse([]), changes, |_| false);
         assert_eq!(kept.len(), 1);
         assert_eq!(kept[0].path, "/w/main.typ");
     }
@@ -660,7 +713,7 @@ mod tests {
             change("/w/mine.typ", ChangeKind::Modified, None),
             change("/w/theirs.typ", ChangeKind::Modified, None),
         ];
-        let kept = scope_changes(Path::new("/w"), changes, |p| {
+        let kept = scope_changes(Path::new("/w"), &IgnoreRules::parse([]), changes, |p| {
             p == Path::new("/w/mine.typ")
         });
         assert_eq!(kept.len(), 1);
@@ -674,7 +727,7 @@ mod tests {
             ChangeKind::Renamed,
             Some("/w/node_modules/main.typ"),
         )];
-        let kept = scope_changes(Path::new("/w"), changes, |_| false);
+        let kept = scope_changes(Path::new("/w"), &IgnoreRules::parse([]), changes, |_| false);
         assert_eq!(kept.len(), 1);
         assert_eq!(kept[0].kind, ChangeKind::Removed);
         assert_eq!(kept[0].path, "/w/main.typ");
@@ -688,7 +741,7 @@ mod tests {
             ChangeKind::Renamed,
             Some("/w/out.typ"),
         )];
-        let kept = scope_changes(Path::new("/w"), changes, |_| false);
+        let kept = scope_changes(Path::new("/w"), &IgnoreRules::parse([]), changes, |_| false);
         assert_eq!(kept.len(), 1);
         assert_eq!(kept[0].kind, ChangeKind::Created);
         assert_eq!(kept[0].path, "/w/out.typ");
@@ -701,12 +754,66 @@ mod tests {
             ChangeKind::Renamed,
             Some("/w/dist/b.js"),
         )];
-        assert!(scope_changes(Path::new("/w"), changes, |_| false).is_empty());
+        assert!(
+            scope_changes(Path::new("/w"), &IgnoreRules::parse([]), changes, |_| false).is_empty()
+        );
+    }
+
+    #[test]
+    fn workspace_ignore_patterns_are_out_of_scope() {
+        let rules = IgnoreRules::parse(["*.aux\n/exports/\n"]);
+        let changes = vec![
+            change("/w/main.aux", ChangeKind::Modified, None),
+            change("/w/exports/book.pdf", ChangeKind::Created, None),
+            change("/w/chapters/exports/a.typ", ChangeKind::Created, None),
+        ];
+        let kept = scope_changes(Path::new("/w"), &rules, changes, |_| false);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].path, "/w/chapters/exports/a.typ");
+    }
+
+    #[test]
+    fn only_root_ignore_files_trigger_a_reload() {
+        let root = Path::new("/w");
+        let edited = |path: &str| [change(path, ChangeKind::Modified, None)];
+        assert!(touches_ignore_file(root, &edited("/w/.gitignore")));
+        assert!(touches_ignore_file(root, &edited("/w/.typwriterignore")));
+        assert!(!touches_ignore_file(root, &edited("/w/sub/.gitignore")));
+        assert!(!touches_ignore_file(root, &edited("/w/main.typ")));
+    }
+
+    #[test]
+    fn font_files_in_the_workspace_font_folders_trigger_a_rescan() {
+        let dirs = [PathBuf::from("/w/fonts")];
+        let added =
+            |path: &str| touches_project_fonts(&dirs, &[change(path, ChangeKind::Created, None)]);
+        assert!(added("/w/fonts/Brand-Bold.otf"));
+        assert!(added("/w/fonts/sub/Brand.TTF"));
+        assert!(!added("/w/fonts/LICENSE.txt"));
+        assert!(!added("/w/assets/Brand.otf"));
+
+        let mut folder = change("/w/fonts", ChangeKind::Created, None);
+        folder.is_dir = true;
+        assert!(touches_project_fonts(&dirs, &[folder]));
+    }
+
+    #[test]
+    fn only_the_root_project_config_triggers_a_reload() {
+        let root = Path::new("/w");
+        let edited = |path: &str| [change(path, ChangeKind::Modified, None)];
+        assert!(touches_project_config(root, &edited("/w/typwriter.toml")));
+        assert!(!touches_project_config(
+            root,
+            &edited("/w/sub/typwriter.toml")
+        ));
+        assert!(!touches_project_config(root, &edited("/w/typst.toml")));
     }
 
     #[test]
     fn a_path_outside_the_root_is_ignored() {
         let changes = vec![change("/elsewhere/main.typ", ChangeKind::Modified, None)];
-        assert!(scope_changes(Path::new("/w"), changes, |_| false).is_empty());
+        assert!(
+            scope_changes(Path::new("/w"), &IgnoreRules::parse([]), changes, |_| false).is_empty()
+        );
     }
 }
@@ -0,0 +1,141 @@
+// Where each loaded font face came from.
+//
+// `FontStore` answers "which face renders this family and variant" but not
+// "which file is it". The font usage report needs the latter for licence
+// audits, so the origin of every face is recorded as the store is built.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use typst::text::{FontInfo, FontVariant};
+
+/// Depth cap for [`locate_in_dir`], matching the other directory walks.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FontOrigin {
+    /// Bundled with typst itself.
+    Embedded,
+    /// Installed on the operating system.
+    System,
+    /// Found in this user-configured font directory.
+    Directory(PathBuf),
+    /// Found in this font directory of the open workspace.
+    Workspace(PathBuf),
+}
+
+#[derive(Default)]
+pub struct FontOrigins {
+    by_face: HashMap<(String, FontVariant), FontOrigin>,
+}
+
+impl FontOrigins {
+    /// Note where `info` came from. The first origin recorded for a face wins:
+    /// when two sources provide the same family and variant, the book resolves
+    /// to the one added first, so that is the file actually rendered.
+    pub fn record(&mut self, info: &FontInfo, origin: &FontOrigin) {
+        self.by_face
+            .entry((info.family.to_lowercase(), info.variant))
+            .or_insert_with(|| origin.clone());
+    }
+
+    pub fn lookup(&self, info: &FontInfo) -> Option<&FontOrigin> {
+        self.by_face
+            .get(&(info.family.to_lowercase(), info.variant))
+    }
+}
+
+/// Find the file under `dir` that provides `info`'s face.
+///
+/// Only called for the handful of faces a report actually lists, so reading
+/// candidate files here is cheaper than keeping a path for every face of
+/// every scanned directory.
+pub fn locate_in_dir(dir: &Path, info: &FontInfo) -> Option<PathBuf> {
+    let mut stack = vec![(dir.to_path_buf(), 0usize)];
+    while let Some((dir, depth)) = stack.pop() {
+        if depth > MAX_DEPTH {
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push((path, depth + 1));
+                continue;
+            }
+            if !is_font_file(&path) {
+                continue;
+            }
+            let Ok(data) = std::fs::read(&path) else {
+                continue;
+            };
+            let found = FontInfo::iter(&data).any(|face| {
+                face.family.eq_ignore_ascii_case(&info.family) && face.variant == info.variant
+            });
+            if found {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+/// Whether `path` names a font file, by extension.
+pub fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "ttf" | "otf" | "ttc" | "otc"
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use typst_kit::fonts;
+
+    fn embedded_infos() -> Vec<FontInfo> {
+        fonts::embedded().map(|(_, info)| info).collect()
+    }
+
+    #[test]
+    fn the_first_recorded_origin_wins() {
+        let info = embedded_infos().remove(0);
+        let mut origins = FontOrigins::default();
+        origins.record(&info, &FontOrigin::Embedded);
+        origins.record(&info, &FontOrigin::Directory(PathBuf::from("/fonts")));
+        assert_eq!(origins.lookup(&info), Some(&FontOrigin::Embedded));
+    }
+
+    #[test]
+    fn lookup_ignores_family_case() {
+        let info = embedded_infos().remove(0);
+        let mut origins = FontOrigins::default();
+        origins.record(&info, &FontOrigin::System);
+
+        let mut shouted = info.clone();
+        shouted.family = info.family.to_uppercase();
+        assert_eq!(origins.lookup(&shouted), Some(&FontOrigin::System));
+    }
+
+    #[test]
+    fn unrecorded_faces_have_no_origin() {
+        let origins = FontOrigins::default();
+        assert!(origins.lookup(&embedded_infos().remove(0)).is_none());
+    }
+
+    #[test]
+    fn only_font_extensions_are_read() {
+        assert!(is_font_file(Path::new("a/Inter.TTF")));
+        assert!(is_font_file(Path::new("b.otc")));
+        assert!(!is_font_file(Path::new("README.md")));
+        assert!(!is_font_file(Path::new("noext")));
+    }
+}
@@ -0,0 +1,252 @@
+// A `World` that needs no running app.
+//
+// `EditorWorld` is tied to the Tauri `AppHandle` (progress events, the
+// working-tree provider, package downloads). Compiles that run outside the
+// app — the isolation worker process and the headless diagnostics export —
+// read the workspace straight from disk instead, optionally overlaid with
+// unsaved buffers, and resolve packages from the local package directories
+// only. Nothing is downloaded here: a package that isn't installed is reported
+// as not found and recorded in `missing_packages`, so the caller can fetch it
+// (from Typst Universe or a custom registry) and compile again.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use parking_lot::Mutex;
+use typst::{
+    diag::{FileError, FileResult},
+    foundations::{Bytes, Datetime, Dict, Duration, Str, Value},
+    syntax::{package::PackageSpec, FileId, Source, VirtualRoot},
+    text::{Font, FontBook},
+    utils::LazyHash,
+    Feature, Features, Library, LibraryExt, World,
+};
+use typst_kit::{
+    downloader::{Downloader, SystemDownloader},
+    fonts::{self, FontStore},
+    packages::{FsPackages, SystemPackages},
+};
+
+use super::{local_file_id, registries, today_with_offset, PackageRegistry};
+
+/// The standard library as the editor configures it, with `inputs` as
+/// `sys.inputs`. The experimental HTML target is enabled so `export_html` can
+/// compile an `HtmlDocument`; the paged targets are unaffected.
+pub fn headless_library(inputs: &BTreeMap<String, String>) -> LazyHash<Library> {
+    let inputs: Dict = inputs
+        .iter()
+        .map(|(key, value)| (Str::from(key.as_str()), Value::Str(value.as_str().into())))
+        .collect();
+    LazyHash::new(
+        Library::builder()
+            .with_features(Features::from_iter([Feature::Html]))
+            .with_inputs(inputs)
+            .build(),
+    )
+}
+
+/// Local package directories (data dir first, then the download cache), in
+/// the order typst searches them.
+pub fn local_package_dirs() -> Vec<PathBuf> {
+    [FsPackages::system_data(), FsPackages::system_cache()]
+        .into_iter()
+        .flatten()
+        .map(|packages| packages.path().to_path_buf())
+        .collect()
+}
+
+/// Installs packages for compiles that run without the app: from a custom
+/// registry when one claims the namespace, from Typst Universe otherwise, into
+/// the same data/cache directories the app uses. Nothing reports progress.
+pub struct HeadlessPackages {
+    packages: SystemPackages,
+    registries: Vec<PackageRegistry>,
+    user_agent: String,
+}
+
+impl HeadlessPackages {
+    pub fn new(registries: Vec<PackageRegistry>) -> Self {
+        let user_agent = format!("typwriter/{}", env!("CARGO_PKG_VERSION"));
+        Self {
+            packages: SystemPackages::new(SystemDownloader::new(user_agent.clone())),
+            registries,
+            user_agent,
+        }
+    }
+
+    /// Install each of `specs`. True if at least one of them is now local;
+    /// failures are reported on stderr and the compile reports the package as
+    /// not found.
+    pub fn obtain(&self, specs: &[PackageSpec]) -> bool {
+        let mut obtained = false;
+        for spec in specs {
+            match self.obtain_one(spec) {
+                Ok(()) => obtained = true,
+                Err(e) => eprintln!("cannot obtain {spec}: {e}"),
+            }
+        }
+        obtained
+    }
+
+    fn obtain_one(&self, spec: &PackageSpec) -> Result<(), String> {
+        if let Some(registry) = registries::registry_for(&self.registries, &spec.namespace) {
+            let cache = self
+                .packages
+                .cache()
+                .ok_or("no package cache directory on this system")?;
+            let downloader = SystemDownloader::new(self.user_agent.clone());
+            let download = |url: &str| {
+                downloader
+                    .download(spec, url)
+                    .map_err(|e| format!("Failed to download {url}: {e}"))
+            };
+            return registries::install(registry, spec, cache.path(), download).map(drop);
+        }
+        self.packages
+            .obtain(spec)
+            .map(drop)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Fonts from `dirs`, in the order given, then the embedded and system
+/// fonts. A family found in several wins where it is found first, so a
+/// project's own fonts shadow installed faces of the same name, as they do
+/// in the editor.
+pub fn headless_fonts(dirs: &[PathBuf]) -> FontStore {
+    let mut store = FontStore::new();
+    for dir in dirs {
+        store.extend(fonts::scan(dir));
+    }
+    store.extend(fonts::embedded());
+    store.extend(fonts::system());
+    store
+}
+
+pub struct HeadlessWorld<'a> {
+    root: &'a Path,
+    main: FileId,
+    shadows: HashMap<FileId, &'a str>,
+    library: &'a LazyHash<Library>,
+    fonts: &'a FontStore,
+    packages: &'a [PathBuf],
+    sources: Mutex<HashMap<FileId, Source>>,
+    /// Packages a lookup found in none of `packages`.
+    missing: Mutex<HashSet<PackageSpec>>,
+}
+
+impl<'a> HeadlessWorld<'a> {
+    /// A world over the workspace at `root`, entered at `main`. `shadows`
+    /// are (workspace-relative path, content) pairs read in place of disk.
+    pub fn new(
+        root: &'a Path,
+        main: FileId,
+        shadows: &'a [(String, String)],
+        library: &'a LazyHash<Library>,
+        fonts: &'a FontStore,
+        packages: &'a [PathBuf],
+    ) -> Self {
+        let shadows = shadows
+            .iter()
+            .filter_map(|(path, text)| Some((local_file_id(Path::new(path))?, text.as_str())))
+            .collect();
+        Self {
+            root,
+            main,
+            shadows,
+            library,
+            fonts,
+            packages,
+            sources: Mutex::new(HashMap::new()),
+            missing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Packages the compile asked for that aren't installed locally.
+    pub fn missing_packages(&self) -> Vec<PackageSpec> {
+        self.missing.lock().iter().cloned().collect()
+    }
+
+    pub fn root(&self) -> &Path {
+        self.root
+    }
+
+    /// On-disk location of `id`: under the workspace root for project files,
+    /// under the first local package directory holding the package otherwise.
+    pub fn path_of(&self, id: FileId) -> FileResult<PathBuf> {
+        let rel = id.vpath().get_without_slash();
+        match id.root() {
+            VirtualRoot::Project => Ok(self.root.join(rel)),
+            VirtualRoot::Package(spec) => self
+                .packages
+                .iter()
+                .map(|dir| {
+                    dir.join(spec.namespace.as_str())
+                        .join(spec.name.as_str())
+                        .join(spec.version.to_string())
+                })
+                .find(|dir| dir.is_dir())
+                .map(|dir| dir.join(rel))
+                .ok_or_else(|| {
+                    self.missing.lock().insert(spec.clone());
+                    FileError::NotFound(PathBuf::from(spec.to_string()))
+                }),
+        }
+    }
+
+    fn read(&self, id: FileId) -> FileResult<Vec<u8>> {
+        let path = self.path_of(id)?;
+        std::fs::read(&path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                FileError::NotFound(path)
+            } else {
+                FileError::AccessDenied
+            }
+        })
+    }
+}
+
+impl World for HeadlessWorld<'_> {
+    fn library(&self) -> &LazyHash<Library> {
+        self.library
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        self.fonts.book()
+    }
+
+    fn main(&self) -> FileId {
+        self.main
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if let Some(source) = self.sources.lock().get(&id) {
+            return Ok(source.clone());
+        }
+        // Decoded like the editor decodes it, so spans line up with what the
+        // user sees in a file saved as UTF-16 or with a BOM.
+        let text = match self.shadows.get(&id) {
+            Some(text) => text.to_string(),
+            None => crate::workspace::encoding::decode(&self.read(id)?, true)
+                .map(|(text, _)| text)
+                .ok_or(FileError::AccessDenied)?,
+        };
+        let source = Source::new(id, text);
+        self.sources.lock().insert(id, source.clone());
+        Ok(source)
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.read(id).map(Bytes::new)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.fonts.font(index)
+    }
+
+    fn today(&self, offset: Option<Duration>) -> Option<Datetime> {
+        today_with_offset(chrono::Utc::now(), offset)
+    }
+}
@@ -1,29 +1,37 @@
+mod font_origin;
+mod headless;
 mod progress;
+mod registries;
+pub use font_origin::{is_font_file, locate_in_dir, FontOrigin, FontOrigins};
+pub use headless::{
+    headless_fonts, headless_library, local_package_dirs, HeadlessPackages, HeadlessWorld,
+};
 pub use progress::TauriProgress;
+pub use registries::PackageRegistry;
 
 use chrono::Datelike;
 use ecow::EcoString;
-use log::{error, info};
+use log::{error, info, warn};
 use parking_lot::{Condvar, Mutex, RwLock};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     ops::Range,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, OnceLock,
+        Arc,
     },
     time::Instant,
 };
 use tauri::{AppHandle, Emitter};
 use typst::{
-    diag::{FileError, FileResult},
+    diag::{FileError, FileResult, PackageError},
     foundations::{Bytes, Datetime, Duration},
     syntax::package::PackageSpec,
     syntax::{FileId, RootedPath, Source, VirtualPath, VirtualRoot},
-    text::{Font, FontBook},
+    text::{Font, FontBook, FontInfo},
     utils::LazyHash,
-    Feature, Features, Library, LibraryExt, World,
+    Library, World,
 };
 use typst_ide::IdeWorld;
 use typst_kit::{
@@ -32,6 +40,9 @@ use typst_kit::{
     packages::{FsPackages, SystemPackages, UniversePackages},
 };
 
+/// A package in the index, with its description.
+type PackageEntry = (PackageSpec, Option<EcoString>);
+
 pub struct EditorWorld {
     /// Workspace root on disk — updatable when the user opens a new folder.
     root: RwLock<PathBuf>,
@@ -46,8 +57,13 @@ pub struct EditorWorld {
     /// collide with a real file in the workspace.
     main: RwLock<Option<FileId>>,
 
-    /// Typst standard library — built lazily on first compile, not at startup
-    library: OnceLock<LazyHash<Library>>,
+    /// Typst standard library — built lazily on first compile, not at startup,
+    /// and again whenever `inputs` change. Leaked for the same reason as
+    /// `font_store`; rebuilds happen only when `typwriter.toml` is edited.
+    library: RwLock<Option<&'static LazyHash<Library>>>,
+
+    /// `sys.inputs` of the open workspace, from its `typwriter.toml`.
+    inputs: RwLock<BTreeMap<String, String>>,
 
     /// Active font set, behind a lock so settings changes can swap fonts at
     /// runtime. `FontStore` (typst-kit 0.15) owns its `LazyHash<FontBook>` and
@@ -60,6 +76,13 @@ pub struct EditorWorld {
     /// font reloads happen at human cadence.
     font_store: RwLock<Option<&'static FontStore>>,
 
+    /// Where each face in `font_store` came from. Swapped together with it.
+    font_origins: RwLock<FontOrigins>,
+
+    /// The open workspace's own fonts, layered in front of `font_store`. See
+    /// [`ProjectFonts`].
+    project_fonts: RwLock<ProjectFonts>,
+
     /// Empty fallback for `World::book()` / `World::font()` before fonts arrive.
     empty_store: FontStore,
 
@@ -109,9 +132,75 @@ pub struct EditorWorld {
     /// not expose it).
     index_downloader: SystemDownloader,
 
-    /// Lazily cached list of all available packages from the Typst registry.
-    /// Populated on the first call to `IdeWorld::packages()`.
-    package_index: OnceLock<Vec<(PackageSpec, Option<EcoString>)>>,
+    /// User agent for downloads made outside `SystemPackages` (custom
+    /// registries), matching the one it sends.
+    user_agent: String,
+
+    /// Extra registries from the settings, each serving one namespace. See
+    /// [`registries`].
+    registries: RwLock<Vec<PackageRegistry>>,
+
+    /// Lazily cached list of all available packages from Typst Universe and
+    /// the custom registries. Populated on the first call to
+    /// `IdeWorld::packages()` and cleared when the registries change. Leaked
+    /// for the same reason as `font_store`: `packages()` hands out a slice
+    /// with the `&self` lifetime, which must survive a reset. Resets happen
+    /// only when the user edits the registry list.
+    package_index: Mutex<Option<&'static [PackageEntry]>>,
+}
+
+/// The open workspace's own fonts: its `fonts/` folder and the directories its
+/// `typwriter.toml` names. Kept apart from the global set so switching
+/// workspaces, or adding a font to the project, rescans only these folders;
+/// and layered in front of it — the book lists these faces first, and the
+/// first face of a family and variant is the one that renders — so a project
+/// font wins over an installed one of the same name.
+#[derive(Default)]
+struct ProjectFonts {
+    dirs: Vec<PathBuf>,
+    /// `None` when the directories hold no faces.
+    store: Option<&'static FontStore>,
+    /// Number of faces in `store`.
+    len: usize,
+    origins: FontOrigins,
+    /// The global set the layer was last put over, and the book listing
+    /// `store`'s faces followed by its. Leaked like `font_store`.
+    layered: Option<(&'static FontStore, &'static LazyHash<FontBook>)>,
+}
+
+impl ProjectFonts {
+    fn new(dirs: Vec<PathBuf>, store: FontStore, origins: FontOrigins) -> Self {
+        let len = (0..).map_while(|i| store.book().info(i)).count();
+        let store: Option<&'static FontStore> = if len > 0 {
+            Some(Box::leak(Box::new(store)))
+        } else {
+            None
+        };
+        Self {
+            dirs,
+            store,
+            len,
+            origins,
+            layered: None,
+        }
+    }
+
+    /// Rebuild the layered book over `base`. Only face metadata is copied;
+    /// no font file is read again.
+    fn layer_over(&mut self, base: &'static FontStore) {
+        let Some(store) = self.store else {
+            self.layered = None;
+            return;
+        };
+        let mut book = FontBook::new();
+        for source in [store.book(), base.book()] {
+            for info in (0..).map_while(|i| source.info(i)) {
+                book.push(info.clone());
+            }
+        }
+        let book: &'static LazyHash<FontBook> = Box::leak(Box::new(LazyHash::new(book)));
+        self.layered = Some((base, book));
+    }
 }
 
 impl EditorWorld {
@@ -129,6 +218,16 @@ impl EditorWorld {
         (FsPackages::system_data(), FsPackages::system_cache())
     }
 
+    /// The package (data, cache) directories as paths, for tooling that
+    /// inspects what is installed. Same locations `packages` resolves from.
+    pub fn package_storage_dirs(&self) -> (Option<PathBuf>, Option<PathBuf>) {
+        let (data, cache) = Self::packages_dirs(&self.app_handle);
+        (
+            data.as_ref().map(|d| d.path().to_path_buf()),
+            cache.as_ref().map(|c| c.path().to_path_buf()),
+        )
+    }
+
     pub fn new(root: PathBuf, app_handle: AppHandle, vcs: Arc<crate::vcs::VcsState>) -> Self {
         let pkg = app_handle.package_info();
         let user_agent = format!("{}/{}", pkg.name, pkg.version);
@@ -159,8 +258,11 @@ impl EditorWorld {
             root: RwLock::new(root),
             vcs,
             main: RwLock::new(None),
-            library: OnceLock::new(),
+            library: RwLock::new(None),
+            inputs: RwLock::new(BTreeMap::new()),
             font_store: RwLock::new(None),
+            font_origins: RwLock::new(FontOrigins::default()),
+            project_fonts: RwLock::new(ProjectFonts::default()),
             empty_store: FontStore::new(),
             font_load_started: AtomicBool::new(false),
             fonts_ready: Mutex::new(false),
@@ -171,32 +273,44 @@ impl EditorWorld {
             shadow_versions: RwLock::new(HashMap::new()),
             app_handle,
             packages,
-            index_downloader: SystemDownloader::new(user_agent),
-            package_index: OnceLock::new(),
+            index_downloader: SystemDownloader::new(user_agent.clone()),
+            user_agent,
+            registries: RwLock::new(Vec::new()),
+            package_index: Mutex::new(None),
         }
     }
 
     /// Build a [`FontStore`] from the embedded fonts, optionally the system
     /// fonts, and the given extra directories. Mirrors the typst-cli 0.15 font
-    /// discovery pattern (`FontStore::new()` + `extend`).
-    fn build_font_store(extra_dirs: &[PathBuf], include_system: bool) -> FontStore {
+    /// discovery pattern (`FontStore::new()` + `extend`), recording where each
+    /// face came from on the way past.
+    fn build_font_store(extra_dirs: &[PathBuf], include_system: bool) -> (FontStore, FontOrigins) {
         let mut store = FontStore::new();
-        store.extend(fonts::embedded());
+        let mut origins = FontOrigins::default();
+        store.extend(
+            fonts::embedded().inspect(|(_, info)| origins.record(info, &FontOrigin::Embedded)),
+        );
         if include_system {
-            store.extend(fonts::system());
+            store.extend(
+                fonts::system().inspect(|(_, info)| origins.record(info, &FontOrigin::System)),
+            );
         }
         for dir in extra_dirs {
-            store.extend(fonts::scan(dir));
+            let origin = FontOrigin::Directory(dir.clone());
+            store.extend(fonts::scan(dir).inspect(|(_, info)| origins.record(info, &origin)));
         }
-        store
+        (store, origins)
     }
 
     /// Install a font set, replacing any existing one. Previous allocations are
     /// leaked so any outstanding `&LazyHash<FontBook>` / `Font` borrows returned
     /// from `World::book` / `World::font` remain valid.
-    pub fn load_fonts(&self, store: FontStore) {
+    pub fn load_fonts(&self, store: FontStore, origins: FontOrigins) {
         let store: &'static FontStore = Box::leak(Box::new(store));
         *self.font_store.write() = Some(store);
+        *self.font_origins.write() = origins;
+        // The workspace's faces keep their place in front of the new set.
+        self.project_fonts.write().layer_over(store);
         // Mark ready and wake any compile worker blocked in
         // `wait_until_fonts_loaded`. Keeping this in lockstep with `font_store`
         // means "ready" always implies a usable font set is installed — true
@@ -205,6 +319,15 @@ impl EditorWorld {
         self.fonts_cv.notify_all();
     }
 
+    /// Where the face described by `info` was loaded from, if it is part of
+    /// the current font set.
+    pub fn font_origin(&self, info: &FontInfo) -> Option<FontOrigin> {
+        if let Some(origin) = self.project_fonts.read().origins.lookup(info) {
+            return Some(origin.clone());
+        }
+        self.font_origins.read().lookup(info).cloned()
+    }
+
     /// Whether a font set has been installed yet.
     pub fn fonts_ready(&self) -> bool {
         *self.fonts_ready.lock()
@@ -240,10 +363,11 @@ impl EditorWorld {
                 Self::build_font_store(&extra_dirs, true)
             }));
             match searched {
-                Ok(store) => world.load_fonts(store),
+                Ok((store, origins)) => world.load_fonts(store, origins),
                 Err(_) => {
                     error!("ensure_fonts_loading: font search panicked; falling back to embedded fonts only");
-                    world.load_fonts(Self::build_font_store(&[], false));
+                    let (store, origins) = Self::build_font_store(&[], false);
+                    world.load_fonts(store, origins);
                 }
             }
             if let Err(err) = world.app_handle.emit("app:fonts-loaded", ()) {
@@ -252,20 +376,86 @@ impl EditorWorld {
         });
     }
 
-    /// Run a font search (system + embedded + the given extra directories)
-    /// and replace the current font set. Intended to be called from a
-    /// background thread since `fontdb`'s system scan can be slow.
-    pub fn reload_fonts_with(&self, extra_dirs: Vec<PathBuf>) {
-        self.load_fonts(Self::build_font_store(&extra_dirs, true));
+    /// Run a font search (system + embedded + the font directories from the
+    /// settings) and replace the global font set. Intended to be called from
+    /// a background thread since `fontdb`'s system scan can be slow.
+    pub fn reload_fonts(&self) {
+        let extra_dirs = crate::commands::settings::load_font_directories(&self.app_handle);
+        let (store, origins) = Self::build_font_store(&extra_dirs, true);
+        self.load_fonts(store, origins);
+    }
+
+    /// Every extra font directory: the workspace's own, then the user's.
+    pub fn font_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = self.project_fonts.read().dirs.clone();
+        for dir in crate::commands::settings::load_font_directories(&self.app_handle) {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    }
+
+    /// The directories the workspace's own fonts were scanned from.
+    pub fn project_font_dirs(&self) -> Vec<PathBuf> {
+        self.project_fonts.read().dirs.clone()
+    }
+
+    /// Scan `dirs` as the workspace's own fonts, replacing the previous
+    /// workspace layer; an empty list drops it. Only these directories are
+    /// read — the global set is reused as it is.
+    pub fn set_project_fonts(&self, dirs: Vec<PathBuf>) {
+        let t = Instant::now();
+        let mut store = FontStore::new();
+        let mut origins = FontOrigins::default();
+        for dir in &dirs {
+            let origin = FontOrigin::Workspace(dir.clone());
+            store.extend(fonts::scan(dir).inspect(|(_, info)| origins.record(info, &origin)));
+        }
+        let layer = ProjectFonts::new(dirs, store, origins);
+        info!(
+            "set_project_fonts: {} face(s) from {} dir(s) ({:.1}ms)",
+            layer.len,
+            layer.dirs.len(),
+            t.elapsed().as_secs_f64() * 1000.0
+        );
+
+        let base: Option<&'static FontStore> = *self.font_store.read();
+        let mut project = self.project_fonts.write();
+        *project = layer;
+        if let Some(base) = base {
+            project.layer_over(base);
+        }
+    }
+
+    /// The workspace's `sys.inputs`.
+    pub fn inputs(&self) -> BTreeMap<String, String> {
+        self.inputs.read().clone()
+    }
+
+    /// Replace `sys.inputs`. Returns whether they changed; the library is
+    /// rebuilt on next use if so.
+    pub fn set_inputs(&self, inputs: BTreeMap<String, String>) -> bool {
+        {
+            let mut current = self.inputs.write();
+            if *current == inputs {
+                return false;
+            }
+            *current = inputs;
+        }
+        // Not under the `inputs` lock: `library()` takes the two the other
+        // way round.
+        *self.library.write() = None;
+        true
     }
 
     /// Snapshot of the currently loaded font families (deduplicated, sorted).
     /// Used by the settings UI to populate the editor/UI font pickers.
     pub fn font_families(&self) -> Vec<String> {
-        let Some(store) = *self.font_store.read() else {
+        if self.font_store.read().is_none() {
             return Vec::new();
-        };
-        let mut families: Vec<String> = store
+        }
+        let mut families: Vec<String> = self
             .book()
             .families()
             .map(|(name, _)| name.to_string())
@@ -338,6 +528,22 @@ impl EditorWorld {
         self.shadow.read().contains_key(&id)
     }
 
+    /// The unsaved buffer of `id`, if it has one.
+    pub fn shadow_text(&self, id: FileId) -> Option<String> {
+        self.shadow.read().get(&id).cloned()
+    }
+
+    /// Every unsaved project buffer as (workspace-relative path, content).
+    /// Sent to the isolated compile worker, which otherwise only sees disk.
+    pub fn shadow_entries(&self) -> Vec<(String, String)> {
+        self.shadow
+            .read()
+            .iter()
+            .filter(|(id, _)| matches!(id.root(), VirtualRoot::Project))
+            .map(|(id, text)| (id.vpath().get_without_slash().to_string(), text.clone()))
+            .collect()
+    }
+
     /// Called on every keystroke from the editor.
     ///
     /// Updates the cached [`Source`] **in place** rather than dropping it. This
@@ -396,6 +602,19 @@ impl EditorWorld {
         self.file_cache.lock().remove(&id);
     }
 
+    /// Drop the cached files of every version of `@namespace/name`, after its
+    /// contents changed on disk (a local package published or deleted).
+    pub fn invalidate_package(&self, namespace: &str, name: &str) {
+        let stale = |id: &FileId| match id.root() {
+            VirtualRoot::Package(spec) => {
+                spec.namespace.as_str() == namespace && spec.name.as_str() == name
+            }
+            VirtualRoot::Project => false,
+        };
+        self.source_cache.lock().retain(|id, _| !stale(id));
+        self.file_cache.lock().retain(|id, _| !stale(id));
+    }
+
     /// Read a file's raw bytes for the compiler, routing workspace-local files
     /// through the [`WorkingTreeFs`] accessor. Package files live in the
     /// app-private cache — reachable with `std::fs` and outside the workspace
@@ -436,6 +655,10 @@ impl EditorWorld {
         let vpath = id.vpath();
         match id.root() {
             VirtualRoot::Package(spec) => {
+                // A custom registry's packages are unpacked into the cache
+                // dir first, where `obtain` then finds them.
+                self.install_from_registry(spec)
+                    .map_err(|e| FileError::Package(PackageError::Other(Some(e.into()))))?;
                 // `obtain` resolves the package from the data/cache dirs,
                 // downloading it from Typst Universe if missing. Download
                 // progress is reported automatically by the wrapped
@@ -446,24 +669,88 @@ impl EditorWorld {
             VirtualRoot::Project => Ok(self.root.read().join(vpath.get_without_slash())),
         }
     }
+
+    /// Replace the custom package registries. The package index is rebuilt
+    /// on next use, so completion and the browser pick the change up.
+    pub fn set_package_registries(&self, registries: Vec<PackageRegistry>) {
+        info!(
+            "EditorWorld: {} custom package registr{}",
+            registries.len(),
+            if registries.len() == 1 { "y" } else { "ies" }
+        );
+        *self.registries.write() = registries;
+        *self.package_index.lock() = None;
+    }
+
+    /// If `spec`'s namespace is served by a custom registry, make sure the
+    /// package is in the cache dir, unpacking it from the registry if needed.
+    /// A copy in the data dir wins, as it does for `SystemPackages`.
+    fn install_from_registry(&self, spec: &PackageSpec) -> Result<(), String> {
+        let Some(registry) =
+            registries::registry_for(&self.registries.read(), &spec.namespace).cloned()
+        else {
+            return Ok(());
+        };
+        let (data_dir, cache_dir) = self.package_storage_dirs();
+        let in_data = data_dir.is_some_and(|dir| {
+            dir.join(spec.namespace.as_str())
+                .join(spec.name.as_str())
+                .join(spec.version.to_string())
+                .is_dir()
+        });
+        if in_data {
+            return Ok(());
+        }
+        let cache_dir = cache_dir.ok_or("no package cache directory on this system")?;
+        let label = spec.to_string();
+        let download = |url: &str| {
+            let handle = self.app_handle.clone();
+            let progress_label = label.clone();
+            ProgressDownloader::new(
+                SystemDownloader::new(self.user_agent.clone()),
+                move |_: &dyn std::any::Any| {
+                    TauriProgress::new(handle.clone(), progress_label.clone())
+                },
+            )
+            .download(spec, url)
+            .map(|data| data.to_vec())
+            .map_err(|e| format!("Failed to download {url}: {e}"))
+        };
+        registries::install(&registry, spec, &cache_dir, download).map(drop)
+    }
+
+    /// The directory holding package `spec`, installing it from its custom
+    /// registry or downloading it from Typst Universe first if it is in
+    /// neither the data nor the cache dir.
+    pub fn obtain_package(&self, spec: &PackageSpec) -> Result<PathBuf, String> {
+        self.install_from_registry(spec)?;
+        self.packages
+            .obtain(spec)
+            .map(|root| root.path().to_path_buf())
+            .map_err(|e| format!("Failed to obtain {spec}: {e}"))
+    }
 }
 
 impl World for EditorWorld {
     fn library(&self) -> &LazyHash<Library> {
-        self.library.get_or_init(|| {
-            // Enable the experimental HTML target so `export_html` can compile
-            // an `HtmlDocument`; without this feature the compiler rejects the
-            // `html` export pass. The paged preview/PDF/PNG/SVG paths are
-            // unaffected.
-            LazyHash::new(
-                Library::builder()
-                    .with_features(Features::from_iter([Feature::Html]))
-                    .build(),
-            )
-        })
+        if let Some(library) = *self.library.read() {
+            return library;
+        }
+        let mut slot = self.library.write();
+        // Built by another thread while we waited for the lock.
+        if let Some(library) = *slot {
+            return library;
+        }
+        let library: &'static LazyHash<Library> =
+            Box::leak(Box::new(headless_library(&self.inputs.read())));
+        *slot = Some(library);
+        library
     }
 
     fn book(&self) -> &LazyHash<FontBook> {
+        if let Some((_, book)) = self.project_fonts.read().layered {
+            return book;
+        }
         // `*self.font_store.read()` copies the `Option<&'static FontStore>` out
         // of the guard so we can return a reference whose lifetime is tied to
         // `&self` (the static reference outlives any caller-chosen lifetime).
@@ -488,9 +775,13 @@ impl World for EditorWorld {
         let text = if let Some(content) = self.shadow.read().get(&id) {
             content.clone()
         } else {
-            // 3. Fall back to disk (may trigger a package download)
+            // 3. Fall back to disk (may trigger a package download), decoded
+            //    the way `read_file` hands it to the editor so spans line up
+            //    with what the user sees.
             let bytes = self.read_file_bytes(id)?;
-            String::from_utf8(bytes).map_err(|_| FileError::AccessDenied)?
+            crate::workspace::encoding::decode(&bytes, true)
+                .map(|(text, _)| text)
+                .ok_or(FileError::AccessDenied)?
         };
 
         let source = Source::new(id, text);
@@ -508,6 +799,17 @@ impl World for EditorWorld {
     }
 
     fn font(&self, index: usize) -> Option<Font> {
+        {
+            let project = self.project_fonts.read();
+            if let (Some(store), Some((base, _))) = (project.store, project.layered) {
+                // Indices follow the layered book: the workspace's faces,
+                // then the global set it was layered over.
+                return match index.checked_sub(project.len) {
+                    None => store.font(index),
+                    Some(index) => base.font(index),
+                };
+            }
+        }
         let opt: Option<&'static FontStore> = *self.font_store.read();
         opt.and_then(|store| store.font(index))
     }
@@ -614,9 +916,14 @@ impl IdeWorld for EditorWorld {
     /// The index is fetched lazily on first call and cached for the app
     /// lifetime. Returns an empty slice if the network is unavailable.
     fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
-        self.package_index
-            .get_or_init(|| fetch_package_index(&self.index_downloader))
-            .as_slice()
+        let mut index = self.package_index.lock();
+        if let Some(packages) = *index {
+            return packages;
+        }
+        let registries = self.registries.read().clone();
+        let fetched: &'static [_] = fetch_package_index(&self.index_downloader, &registries).leak();
+        *index = Some(fetched);
+        fetched
     }
 
     /// Returns all file IDs currently known to the world (cached or shadowed).
@@ -629,68 +936,49 @@ impl IdeWorld for EditorWorld {
     }
 }
 
-/// Download and parse the Typst preview package index from the registry.
+/// Download and parse the Typst preview package index from the registry, and
+/// the index of every custom registry.
 ///
 /// Returns a `Vec<(PackageSpec, Option<EcoString>)>` suitable for
-/// [`IdeWorld::packages`]. Returns an empty vec on any network or parse error.
-fn fetch_package_index(downloader: &SystemDownloader) -> Vec<(PackageSpec, Option<EcoString>)> {
+/// [`IdeWorld::packages`]. A registry that can't be reached or parsed
+/// contributes nothing, as does Universe when offline. A registry mapped to
+/// `preview` stands in for Universe, which is then not contacted at all.
+fn fetch_package_index(
+    downloader: &SystemDownloader,
+    registries: &[PackageRegistry],
+) -> Vec<(PackageSpec, Option<EcoString>)> {
     const INDEX_URL: &str = "https://packages.typst.org/preview/index.json";
     let t = Instant::now();
 
     // The `&dyn Any` download key (`&"package index"`) matches the convention
     // typst-kit uses for the index; it lets a progress wrapper skip it. This
     // downloader has no wrapper, so the key is irrelevant here.
-    let data = match downloader.download(&"package index", INDEX_URL) {
-        Ok(d) => d,
-        Err(_) => {
-            info!(
-                "package_index: network error ({:.1}ms)",
-                t.elapsed().as_secs_f64() * 1000.0
-            );
-            return vec![];
-        }
+    let download = |url: &str| {
+        downloader
+            .download(&"package index", url)
+            .map(|data| data.to_vec())
+            .map_err(|e| format!("Failed to download {url}: {e}"))
     };
 
-    let json: serde_json::Value = match serde_json::from_slice(&data) {
-        Ok(v) => v,
-        Err(_) => {
-            info!(
-                "package_index: parse error ({:.1}ms)",
-                t.elapsed().as_secs_f64() * 1000.0
-            );
-            return vec![];
+    let mut packages = Vec::new();
+    if registries::registry_for(registries, "preview").is_none() {
+        match download(INDEX_URL) {
+            Ok(data) => match registries::parse_index("preview", &data) {
+                Some(universe) => packages.extend(universe),
+                None => info!("package_index: invalid Universe index"),
+            },
+            Err(_) => info!("package_index: network error"),
         }
-    };
-
-    let array = match json.as_array() {
-        Some(a) => a,
-        None => {
-            info!(
-                "package_index: invalid format ({:.1}ms)",
-                t.elapsed().as_secs_f64() * 1000.0
-            );
-            return vec![];
+    }
+    for registry in registries {
+        match registries::fetch_index(registry, download) {
+            Ok(index) => packages.extend(index),
+            Err(err) => warn!(
+                "package_index: registry @{} unavailable err=\"{err}\"",
+                registry.namespace
+            ),
         }
-    };
-
-    let packages: Vec<(PackageSpec, Option<EcoString>)> = array
-        .iter()
-        .filter_map(|entry| {
-            let name = entry.get("name")?.as_str()?;
-            let version_str = entry.get("version")?.as_str()?;
-            let version: typst::syntax::package::PackageVersion = version_str.parse().ok()?;
-            let description = entry
-                .get("description")
-                .and_then(|d| d.as_str())
-                .map(EcoString::from);
-            let spec = PackageSpec {
-                namespace: EcoString::from("preview"),
-                name: EcoString::from(name),
-                version,
-            };
-            Some((spec, description))
-        })
-        .collect();
+    }
 
     info!(
         "package_index: fetched {} packages ({:.1}ms)",
@@ -702,11 +990,15 @@ fn fetch_package_index(downloader: &SystemDownloader) -> Vec<(PackageSpec, Optio
 
 #[cfg(test)]
 mod tests {
-    use super::{apply_edit_to_cache, claim_write_version, local_file_id, today_from_secs};
+    use super::{
+        apply_edit_to_cache, claim_write_version, local_file_id, today_from_secs, FontOrigins,
+        ProjectFonts,
+    };
     use chrono::{TimeZone, Utc};
     use std::collections::HashMap;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
     use typst::syntax::{FileId, Source, SyntaxKind, SyntaxNode};
+    use typst_kit::fonts::{self, FontStore};
 
     const HOUR: i32 = 3600;
 
@@ -1009,4 +1301,39 @@ mod tests {
         // Exact date depends on the host's local zone; just assert it resolves.
         assert!(today_from_secs(now, None).is_some());
     }
+
+    // ─── Workspace fonts ────────────────────────────────────────────────────
+
+    fn embedded_store(skip: usize, take: usize) -> FontStore {
+        let mut store = FontStore::new();
+        store.extend(fonts::embedded().skip(skip).take(take));
+        store
+    }
+
+    #[test]
+    fn workspace_fonts_are_listed_ahead_of_the_global_set() {
+        let base: &'static FontStore = Box::leak(Box::new(embedded_store(0, usize::MAX)));
+        let base_len = (0..).map_while(|i| base.book().info(i)).count();
+
+        let dirs = vec![PathBuf::from("/w/fonts")];
+        let mut layer = ProjectFonts::new(dirs, embedded_store(1, 1), FontOrigins::default());
+        assert_eq!(layer.len, 1);
+        layer.layer_over(base);
+
+        let (layered_over, book) = layer.layered.expect("layered");
+        assert!(std::ptr::eq(layered_over, base));
+        assert_eq!(book.info(0), base.book().info(1));
+        assert_eq!(book.info(1), base.book().info(0));
+        assert_eq!(book.info(base_len), base.book().info(base_len - 1));
+        assert!(book.info(base_len + 1).is_none());
+    }
+
+    #[test]
+    fn an_empty_workspace_layer_leaves_the_global_set_alone() {
+        let base: &'static FontStore = Box::leak(Box::new(embedded_store(0, usize::MAX)));
+        let mut layer = ProjectFonts::new(Vec::new(), FontStore::new(), FontOrigins::default());
+        layer.layer_over(base);
+        assert!(layer.store.is_none());
+        assert!(layer.layered.is_none());
+    }
 }
@@ -0,0 +1,308 @@
+// Custom package registries.
+//
+// Typst Universe serves each namespace as a flat folder:
+//
+//     <base>/index.json                  [{ "name", "version", "description", … }]
+//     <base>/<name>-<version>.tar.gz
+//
+// A registry here is any place laid out the same way — a mirror directory
+// (an air-gapped copy of Universe, a shared drive) or an HTTP base URL — mapped
+// to one namespace. Its packages are unpacked into the package cache dir, where
+// typst-kit's `SystemPackages` then finds them like any downloaded package;
+// its index feeds completion and the package browser. Mapping `preview` to a
+// mirror replaces Universe for that namespace entirely.
+
+use std::path::{Path, PathBuf};
+
+use ecow::EcoString;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use typst::syntax::package::{PackageSpec, PackageVersion};
+
+/// File listing a registry's packages, next to the archives.
+const INDEX_FILE: &str = "index.json";
+
+/// Namespaces a registry may not claim: `local` is this machine's own.
+const RESERVED_NAMESPACES: &[&str] = &["local"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageRegistry {
+    /// The namespace imports use, `@<namespace>/name:version`.
+    pub namespace: String,
+    /// A mirror directory, or an `http://` / `https://` base URL.
+    pub location: String,
+}
+
+impl PackageRegistry {
+    fn url(&self) -> Option<&str> {
+        let location = self.location.trim();
+        (location.starts_with("http://") || location.starts_with("https://"))
+            .then(|| location.trim_end_matches('/'))
+    }
+
+    /// Read `file` from the registry: from disk for a mirror directory,
+    /// through `download` (given a URL) otherwise.
+    pub fn fetch(
+        &self,
+        file: &str,
+        download: impl FnOnce(&str) -> Result<Vec<u8>, String>,
+    ) -> Result<Vec<u8>, String> {
+        match self.url() {
+            Some(base) => download(&format!("{base}/{file}")),
+            None => {
+                let path = Path::new(self.location.trim()).join(file);
+                std::fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))
+            }
+        }
+    }
+
+    /// Check the registry can be saved: a usable namespace, and a location
+    /// that is a URL or an existing directory.
+    pub fn validate(&self) -> Result<(), String> {
+        let probe = format!("@{}/probe:0.0.0", self.namespace);
+        if probe.parse::<PackageSpec>().is_err() {
+            return Err(format!("invalid namespace {:?}", self.namespace));
+        }
+        if RESERVED_NAMESPACES.contains(&self.namespace.as_str()) {
+            return Err(format!("the {:?} namespace is reserved", self.namespace));
+        }
+        if self.url().is_none() && !Path::new(self.location.trim()).is_dir() {
+            return Err(format!(
+                "{:?} is neither a URL nor a directory",
+                self.location
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The registry serving `namespace`, if one is configured.
+pub fn registry_for<'a>(
+    registries: &'a [PackageRegistry],
+    namespace: &str,
+) -> Option<&'a PackageRegistry> {
+    registries.iter().find(|r| r.namespace == namespace)
+}
+
+/// Parse an `index.json` into `(spec, description)` rows for `namespace`.
+/// Malformed entries are skipped; a malformed file yields `None`.
+pub fn parse_index(namespace: &str, data: &[u8]) -> Option<Vec<(PackageSpec, Option<EcoString>)>> {
+    let json: serde_json::Value = serde_json::from_slice(data).ok()?;
+    let packages = json
+        .as_array()?
+        .iter()
+        .filter_map(|entry| {
+            let name = entry.get("name")?.as_str()?;
+            let version: PackageVersion = entry.get("version")?.as_str()?.parse().ok()?;
+            let description = entry
+                .get("description")
+                .and_then(|d| d.as_str())
+                .map(EcoString::from);
+            let spec = PackageSpec {
+                namespace: EcoString::from(namespace),
+                name: EcoString::from(name),
+                version,
+            };
+            Some((spec, description))
+        })
+        .collect();
+    Some(packages)
+}
+
+/// The registry's index, or an error when it cannot be read or parsed.
+pub fn fetch_index(
+    registry: &PackageRegistry,
+    download: impl FnOnce(&str) -> Result<Vec<u8>, String>,
+) -> Result<Vec<(PackageSpec, Option<EcoString>)>, String> {
+    let data = registry.fetch(INDEX_FILE, download)?;
+    parse_index(&registry.namespace, &data)
+        .ok_or_else(|| format!("{}: invalid {INDEX_FILE}", registry.location))
+}
+
+/// Make sure `spec` is unpacked under `cache_dir`, fetching its archive from
+/// `registry` if it is not there yet. Returns the package directory.
+pub fn install(
+    registry: &PackageRegistry,
+    spec: &PackageSpec,
+    cache_dir: &Path,
+    download: impl FnOnce(&str) -> Result<Vec<u8>, String>,
+) -> Result<PathBuf, String> {
+    let package_dir = cache_dir
+        .join(spec.namespace.as_str())
+        .join(spec.name.as_str());
+    let target = package_dir.join(spec.version.to_string());
+    if target.is_dir() {
+        return Ok(target);
+    }
+
+    let archive = registry.fetch(&format!("{}-{}.tar.gz", spec.name, spec.version), download)?;
+
+    // Unpack next to the target and rename into place, so an interrupted
+    // unpack never leaves a half-written package that later compiles trust.
+    let staging = package_dir.join(format!(".{}.partial", spec.version));
+    let _ = std::fs::remove_dir_all(&staging);
+    let unpacked = std::fs::create_dir_all(&staging)
+        .map_err(|e| e.to_string())
+        .and_then(|()| {
+            tar::Archive::new(GzDecoder::new(archive.as_slice()))
+                .unpack(&staging)
+                .map_err(|e| format!("invalid archive: {e}"))
+        })
+        .and_then(|()| std::fs::rename(&staging, &target).map_err(|e| e.to_string()));
+    if let Err(e) = unpacked {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(format!(
+            "Failed to install {spec} from {}: {e}",
+            registry.location
+        ));
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// A mirror directory serving `@acme/brand:1.2.0`.
+    fn mirror() -> TempDir {
+        let dir = TempDir::new("mirror");
+        std::fs::write(
+            dir.0.join(INDEX_FILE),
+            r#"[
+                {"name": "brand", "version": "1.2.0", "description": "House style"},
+                {"name": "brand", "version": "1.1.0"},
+                {"name": "broken"}
+            ]"#,
+        )
+        .expect("write index");
+
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (path, contents) in [
+            (
+                "typst.toml",
+                "[package]\nname = \"brand\"\nversion = \"1.2.0\"\nentrypoint = \"lib.typ\"\n",
+            ),
+            ("lib.typ", "#let brand = \"acme\"\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .expect("append");
+        }
+        let archive = builder
+            .into_inner()
+            .expect("finish tar")
+            .finish()
+            .expect("finish gzip");
+        std::fs::write(dir.0.join("brand-1.2.0.tar.gz"), archive).expect("write archive");
+        dir
+    }
+
+    fn registry(location: &Path) -> PackageRegistry {
+        PackageRegistry {
+            namespace: "acme".into(),
+            location: location.to_string_lossy().into_owned(),
+        }
+    }
+
+    fn spec(s: &str) -> PackageSpec {
+        s.parse().expect("valid spec")
+    }
+
+    fn no_network(url: &str) -> Result<Vec<u8>, String> {
+        panic!("unexpected download of {url}")
+    }
+
+    #[test]
+    fn a_mirror_index_is_read_into_its_namespace() {
+        let mirror = mirror();
+        let index = fetch_index(&registry(&mirror.0), no_network).expect("index");
+        let rows: Vec<(String, Option<&str>)> = index
+            .iter()
+            .map(|(spec, d)| (spec.to_string(), d.as_deref()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("@acme/brand:1.2.0".to_string(), Some("House style")),
+                ("@acme/brand:1.1.0".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn packages_are_unpacked_into_the_cache_once() {
+        let mirror = mirror();
+        let cache = TempDir::new("cache");
+        let registry = registry(&mirror.0);
+
+        let dir = install(&registry, &spec("@acme/brand:1.2.0"), &cache.0, no_network)
+            .expect("installed");
+
+        assert_eq!(dir, cache.0.join("acme/brand/1.2.0"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lib.typ")).expect("read"),
+            "#let brand = \"acme\"\n"
+        );
+        // Already there: the mirror is not consulted again.
+        std::fs::remove_file(mirror.0.join("brand-1.2.0.tar.gz")).expect("remove");
+        install(&registry, &spec("@acme/brand:1.2.0"), &cache.0, no_network).expect("cached");
+    }
+
+    #[test]
+    fn a_missing_archive_leaves_nothing_behind() {
+        let mirror = mirror();
+        let cache = TempDir::new("cache");
+
+        let err = install(
+            &registry(&mirror.0),
+            &spec("@acme/brand:9.9.9"),
+            &cache.0,
+            no_network,
+        )
+        .unwrap_err();
+
+        assert!(err.contains("brand-9.9.9.tar.gz"), "{err}");
+        assert!(!cache.0.join("acme/brand/9.9.9").exists());
+        assert!(!cache.0.join("acme/brand/.9.9.9.partial").exists());
+    }
+
+    #[test]
+    fn url_registries_fetch_through_the_downloader() {
+        let registry = PackageRegistry {
+            namespace: "acme".into(),
+            location: "https://packages.example.com/acme/".into(),
+        };
+        let data = registry
+            .fetch(INDEX_FILE, |url| {
+                assert_eq!(url, "https://packages.example.com/acme/index.json");
+                Ok(b"[]".to_vec())
+            })
+            .expect("fetched");
+        assert_eq!(data, b"[]");
+    }
+
+    #[test]
+    fn registries_are_validated() {
+        let mirror = mirror();
+        assert!(registry(&mirror.0).validate().is_ok());
+
+        let mut bad = registry(&mirror.0);
+        bad.namespace = "local".into();
+        assert!(bad.validate().is_err());
+
+        bad.namespace = "not a namespace".into();
+        assert!(bad.validate().is_err());
+
+        let missing = registry(&mirror.0.join("missing"));
+        assert!(missing.validate().is_err());
+    }
+}
@@ -0,0 +1,62 @@
+#!/bin/sh
+# Rebuilds the git fixture repositories the `git` module's tests unpack.
+# Needs a real git; the code under test never runs one.
+#
+#   loose.tar.gz   branch `main`, loose objects, index v2, and one working
+#                  tree change of every kind the status reports.
+#   packed.tar.gz  branch `feature/long` in packed-refs, everything packed
+#                  (with deltas), index v4.
+set -eu
+
+here=$(cd "$(dirname "$0")" && pwd)
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+export GIT_AUTHOR_NAME="Ada Writer" GIT_AUTHOR_EMAIL="ada@example.com"
+export GIT_COMMITTER_NAME="Ada Writer" GIT_COMMITTER_EMAIL="ada@example.com"
+export GIT_CONFIG_NOSYSTEM=1 HOME="$work"
+
+commit() {
+  GIT_AUTHOR_DATE="$1 +0000" GIT_COMMITTER_DATE="$1 +0000" git commit -q -m "$2"
+}
+
+# ── loose ────────────────────────────────────────────────────────────────
+mkdir "$work/loose" && cd "$work/loose"
+git init -q -b main .
+git config gc.auto 0
+printf '= Thesis\n\n#include "chapters/intro.typ"\n' > main.typ
+mkdir chapters && printf '== Intro\n\nHello.\n' > chapters/intro.typ
+printf 'todo\n' > notes.txt
+printf 'old\n' > gone.typ
+printf 'build/\n' > .gitignore
+git add -A && commit 1700000000 "Start the thesis"
+printf '= Thesis\n\n#include "chapters/intro.typ"\n#include "chapters/two.typ"\n' > main.typ
+git add -A && commit 1700003600 "Add chapter two"
+
+printf '== Intro\n\nHello, world.\n' > chapters/intro.typ
+printf 'todo\nstaged\n' > notes.txt && git add notes.txt
+printf 'todo\nstaged\nand not\n' > notes.txt
+printf '= New\n' > new.typ && git add new.typ
+printf '= Draft\n' > draft.typ
+mkdir build && printf 'pdf' > build/out.pdf
+rm gone.typ
+rm -rf .git/hooks
+tar czf "$here/loose.tar.gz" .
+
+# ── packed ───────────────────────────────────────────────────────────────
+mkdir "$work/packed" && cd "$work/packed"
+git init -q -b feature/long .
+git config gc.auto 0
+i=1; while [ $i -le 200 ]; do echo "Line $i of the long chapter."; i=$((i + 1)); done > long.typ
+printf '= Long\n' > main.typ
+git add -A && commit 1700000000 "Long chapter"
+sed -i 's/^Line 50 of/Line fifty of/' long.typ
+git add -A && commit 1700086400 "Spell out fifty"
+sed -i 's/^Line 150 of/Line one hundred fifty of/' long.typ
+git add -A && commit 1700172800 "Spell out one hundred fifty"
+git gc -q --aggressive
+git pack-refs --all
+git update-index --index-version 4
+sed -i 's/^Line 1 of/Line one of/' long.typ
+rm -rf .git/hooks
+tar czf "$here/packed.tar.gz" .
@@ -108,6 +108,8 @@ export function toSerializedDiagnostics(lspDiags: LspDiagnostic[]): SerializedDi
                 end_line: d.range.end.line,
                 end_col: d.range.end.character,
             },
+            trace: [],
+            fixes: [],
         }));
 }
 
@@ -197,6 +197,33 @@ export interface SerializedDiagnostic {
     /** Workspace-relative path, if the span resolves to a local file. */
     file_path: string | null;
     range: DiagnosticRange | null;
+    /** Call chain typst recorded, innermost frame first. */
+    trace: TraceEntry[];
+    /** Edits that would resolve the error, best first. */
+    fixes: QuickFix[];
+}
+
+export interface QuickFix {
+    title: string;
+    edits: TextEdit[];
+}
+
+/** One replacement; offsets are UTF-16 code units. */
+export interface TextEdit {
+    /** Absolute path of the file to edit. */
+    path: string;
+    start: number;
+    end: number;
+    text: string;
+}
+
+export interface TraceEntry {
+    message: string;
+    /** Workspace-relative, or the on-disk path for package files. */
+    file_path: string | null;
+    /** `@namespace/name:version` when the frame lies inside a package. */
+    package: string | null;
+    range: DiagnosticRange | null;
 }
 
 // ─── Event payloads ───────────────────────────────────────────────────────────